@@ -16,6 +16,10 @@ pub enum AssetSource {
     Symlink(PathBuf),
     /// Write data to destination as-is.
     Data(Vec<u8>),
+    /// A symlink pointing to the path, like from `debian/<package>.links`
+    SymlinkTo(PathBuf),
+    /// An empty directory, like from `debian/<package>.dirs`
+    Directory,
 }
 
 impl AssetSource {
@@ -38,7 +42,7 @@ impl AssetSource {
         match self {
             AssetSource::Symlink(ref p) |
             AssetSource::Path(ref p) => Some(p),
-            AssetSource::Data(_) => None,
+            AssetSource::Data(_) | AssetSource::SymlinkTo(_) | AssetSource::Directory => None,
         }
     }
 
@@ -47,13 +51,13 @@ impl AssetSource {
         match self {
             AssetSource::Symlink(p) |
             AssetSource::Path(p) => Some(p),
-            AssetSource::Data(_) => None,
+            AssetSource::Data(_) | AssetSource::SymlinkTo(_) | AssetSource::Directory => None,
         }
     }
 
     #[must_use]
     pub fn archive_as_symlink_only(&self) -> bool {
-        matches!(self, AssetSource::Symlink(_))
+        matches!(self, AssetSource::Symlink(_) | AssetSource::SymlinkTo(_))
     }
 
     #[must_use]
@@ -62,7 +66,7 @@ impl AssetSource {
             // FIXME: may not be accurate if the executable is not stripped yet?
             AssetSource::Path(ref p) => fs::metadata(p).ok().map(|m| m.len()),
             AssetSource::Data(ref d) => Some(d.len() as u64),
-            AssetSource::Symlink(_) => None,
+            AssetSource::Symlink(_) | AssetSource::SymlinkTo(_) | AssetSource::Directory => None,
         }
     }
 
@@ -74,7 +78,8 @@ impl AssetSource {
                 Cow::Owned(data)
             },
             AssetSource::Data(d) => Cow::Borrowed(d),
-            AssetSource::Symlink(_) => return Err(CargoDebError::Str("Symlink unexpectedly used to read file data")),
+            AssetSource::Symlink(_) | AssetSource::SymlinkTo(_) => return Err(CargoDebError::Str("Symlink unexpectedly used to read file data")),
+            AssetSource::Directory => return Err(CargoDebError::Str("Directory unexpectedly used to read file data")),
         })
     }
 }
@@ -90,6 +95,7 @@ pub(crate) struct RawAsset {
     pub source_path: PathBuf,
     pub target_path: PathBuf,
     pub chmod: u32,
+    pub separate_debug_symbols: Option<bool>,
 }
 
 impl Assets {
@@ -133,13 +139,13 @@ impl UnresolvedAsset {
     pub(crate) fn new(source_path: PathBuf, target_path: PathBuf, chmod: u32, is_built: IsBuilt, is_example: bool) -> Self {
         Self {
             source_path,
-            c: AssetCommon { target_path, chmod, is_example, is_built },
+            c: AssetCommon { target_path, chmod, is_example, is_built, separate_debug_symbols: None },
         }
     }
 
     /// Convert `source_path` (with glob or dir) to actual path
     pub fn resolve(self, preserve_symlinks: bool) -> CDResult<Vec<Asset>> {
-        let Self { source_path, c: AssetCommon { target_path, chmod, is_built, is_example } } = self;
+        let Self { source_path, c: AssetCommon { target_path, chmod, is_built, is_example, separate_debug_symbols } } = self;
         let source_prefix = is_glob_pattern(&source_path).then(|| {
             source_path.iter()
                 .take_while(|&part| !is_glob_pattern(part.as_ref()))
@@ -159,13 +165,14 @@ impl UnresolvedAsset {
                         target_path.clone()
                     };
                     log::debug!("asset {} -> {} {} {:o}", source_file.display(), target_file.display(), if is_built != IsBuilt::No {"copy"} else {"build"}, chmod);
-                    let asset = Asset::new(
+                    let mut asset = Asset::new(
                         AssetSource::from_path(source_file, preserve_symlinks),
                         target_file,
                         chmod,
                         is_built,
                         is_example,
                     );
+                    asset.c.separate_debug_symbols = separate_debug_symbols;
                     if source_prefix.is_some() {
                         asset.processed("glob", None)
                     } else {
@@ -188,6 +195,8 @@ pub struct AssetCommon {
     pub chmod: u32,
     pub(crate) is_example: bool,
     is_built: IsBuilt,
+    /// Overrides the package-wide setting
+    pub(crate) separate_debug_symbols: Option<bool>,
 }
 
 #[derive(Debug, Clone)]
@@ -220,7 +229,7 @@ impl Asset {
         Self {
             source,
             processed_from: None,
-            c: AssetCommon { target_path, chmod, is_example, is_built },
+            c: AssetCommon { target_path, chmod, is_example, is_built, separate_debug_symbols: None },
         }
     }
 
@@ -279,7 +288,7 @@ fn debug_filename(path: &Path) -> PathBuf {
 pub(crate) fn is_dynamic_library_filename(path: &Path) -> bool {
     path.file_name()
         .and_then(|f| f.to_str())
-        .map_or(false, |f| f.ends_with(DLL_SUFFIX))
+        .is_some_and(|f| f.ends_with(DLL_SUFFIX))
 }
 
 /// Compress man pages and other assets per Debian Policy.
@@ -299,8 +308,35 @@ pub fn compress_assets(package_deb: &mut PackageConfig, listener: &dyn Listener)
                 (path.starts_with("usr/share/info/") && path.ends_with(".info")))
     }
 
+    for asset in &mut package_deb.assets.resolved {
+        if !asset.c.target_path.starts_with("usr/share/man/") || matches!(asset.source, AssetSource::Directory) {
+            continue;
+        }
+        match man_page_path(&asset.c.target_path) {
+            Some(path) if path != asset.c.target_path => {
+                listener.warning(format!("Man page '{}' is not in the directory of its section. Moved to '{}'", asset.c.target_path.display(), path.display()));
+                asset.c.target_path = path;
+            },
+            Some(_) => {},
+            None => listener.warning(format!("Man page '{}' doesn't have a section number in its file extension, like '.1'", asset.c.target_path.display())),
+        }
+        // links need to point to the compressed pages
+        if let AssetSource::SymlinkTo(link_to) = &mut asset.source {
+            if !asset.c.target_path.as_os_str().to_string_lossy().ends_with(".gz") {
+                let mut path = asset.c.target_path.as_os_str().to_owned();
+                path.push(".gz");
+                asset.c.target_path = path.into();
+                if !link_to.as_os_str().to_string_lossy().ends_with(".gz") {
+                    let mut link = link_to.as_os_str().to_owned();
+                    link.push(".gz");
+                    *link_to = link.into();
+                }
+            }
+        }
+    }
+
     for (idx, orig_asset) in package_deb.assets.resolved.iter().enumerate() {
-        if !orig_asset.c.target_path.starts_with("usr") {
+        if !orig_asset.c.target_path.starts_with("usr") || matches!(orig_asset.source, AssetSource::SymlinkTo(_) | AssetSource::Directory) {
             continue;
         }
         let target_path_str = orig_asset.c.target_path.to_string_lossy();
@@ -309,9 +345,17 @@ pub fn compress_assets(package_deb: &mut PackageConfig, listener: &dyn Listener)
 
             let mut new_path = target_path_str.into_owned();
             new_path.push_str(".gz");
-            listener.info(format!("Compressing '{new_path}'"));
+            let data = orig_asset.source.data()?;
+            // pre-compressed files only need the suffix
+            let source = if data.starts_with(&[0x1f, 0x8b]) {
+                listener.info(format!("Renaming compressed file to '{new_path}'"));
+                orig_asset.source.clone()
+            } else {
+                listener.info(format!("Compressing '{new_path}'"));
+                AssetSource::Data(gzipped(&data)?)
+            };
             new_assets.push(Asset::new(
-                crate::assets::AssetSource::Data(gzipped(&orig_asset.source.data()?)?),
+                source,
                 new_path.into(),
                 orig_asset.c.chmod,
                 IsBuilt::No,
@@ -332,6 +376,29 @@ pub fn compress_assets(package_deb: &mut PackageConfig, listener: &dyn Listener)
     Ok(())
 }
 
+/// The path in the `man<section>` directory for the section in the file extension of the page, like `usr/share/man/man3/foo.3pm`.
+///
+/// Pages of other languages stay in the directory of their locale.
+/// Returns `None` if the file extension isn't a section.
+pub(crate) fn man_page_path(target_path: &Path) -> Option<PathBuf> {
+    let relative = target_path.strip_prefix("usr/share/man").ok()?;
+    let file_name = relative.file_name()?.to_str()?;
+    let (_, section) = file_name.trim_end_matches(".gz").rsplit_once('.')?;
+    let section_dir = match section.chars().next()? {
+        c @ '1'..='9' => format!("man{c}"),
+        _ => return None,
+    };
+    let mut dirs = relative.parent()?.components().map(|c| c.as_os_str()).collect::<Vec<_>>();
+    if dirs.last().and_then(|d| d.to_str()).is_some_and(|d| d.starts_with("man")) {
+        dirs.pop();
+    }
+    let mut path = PathBuf::from("usr/share/man");
+    path.extend(dirs);
+    path.push(section_dir);
+    path.push(file_name);
+    Some(path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,6 +431,44 @@ mod tests {
         assert!(a.c.is_built == IsBuilt::No);
     }
 
+    #[test]
+    fn man_page_sections() {
+        assert_eq!(man_page_path(Path::new("usr/share/man/man1/foo.1")).unwrap(), Path::new("usr/share/man/man1/foo.1"));
+        assert_eq!(man_page_path(Path::new("usr/share/man/man8/foo.1.gz")).unwrap(), Path::new("usr/share/man/man1/foo.1.gz"));
+        assert_eq!(man_page_path(Path::new("usr/share/man/foo.3pm")).unwrap(), Path::new("usr/share/man/man3/foo.3pm"));
+        assert_eq!(man_page_path(Path::new("usr/share/man/de/man5/foo.conf.5")).unwrap(), Path::new("usr/share/man/de/man5/foo.conf.5"));
+        assert_eq!(man_page_path(Path::new("usr/share/man/de/foo.5")).unwrap(), Path::new("usr/share/man/de/man5/foo.5"));
+        assert!(man_page_path(Path::new("usr/share/man/man1/foo.md")).is_none());
+        assert!(man_page_path(Path::new("usr/share/man/man1/foo")).is_none());
+    }
+
+    #[test]
+    fn compresses_man_pages() {
+        let mut listener = crate::listener::MockListener::new();
+        listener.expect_info().return_const(());
+        listener.expect_warning().return_const(());
+        let (_, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &listener).unwrap();
+        package_deb.assets.resolved = vec![
+            Asset::new(AssetSource::Data(b".TH FOO 1".to_vec()), "usr/share/man/man8/foo.1".into(), 0o644, IsBuilt::No, false),
+            Asset::new(AssetSource::Data(gzipped(b".TH BAR 1").unwrap()), "usr/share/man/man1/bar.1".into(), 0o644, IsBuilt::No, false),
+            Asset::new(AssetSource::SymlinkTo("foo.1".into()), "usr/share/man/man1/baz.1".into(), 0o777, IsBuilt::No, false),
+        ];
+        compress_assets(&mut package_deb, &listener).unwrap();
+        let mut paths = package_deb.assets.resolved.iter().map(|a| a.c.target_path.to_str().unwrap()).collect::<Vec<_>>();
+        paths.sort_unstable();
+        assert_eq!(paths, ["usr/share/man/man1/bar.1.gz", "usr/share/man/man1/baz.1.gz", "usr/share/man/man1/foo.1.gz"]);
+        for asset in &package_deb.assets.resolved {
+            match &asset.source {
+                AssetSource::SymlinkTo(link) => assert_eq!(link, Path::new("foo.1.gz")),
+                source => {
+                    let mut page = String::new();
+                    std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&source.data().unwrap()[..]), &mut page).unwrap();
+                    assert!(page.starts_with(".TH "), "{page}");
+                },
+            }
+        }
+    }
+
     /// Tests that getting the debug filename from a path returns the same path
     /// with ".debug" appended
     #[test]
@@ -417,7 +522,7 @@ mod tests {
         // supply a systemd unit file as if it were available on disk
         let _g = add_test_fs_paths(&[to_canon_static_str("cargo-deb.service")]);
 
-        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
         config.prepare_assets_before_build(&mut package_deb).unwrap();
 
         let num_unit_assets = package_deb.assets.resolved.iter()
@@ -435,7 +540,7 @@ mod tests {
         // supply a systemd unit file as if it were available on disk
         let _g = add_test_fs_paths(&[to_canon_static_str("cargo-deb.service")]);
 
-        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
 
         package_deb.systemd_units.get_or_insert(vec![SystemdUnitsConfig::default()]);
         package_deb.maintainer_scripts_rel_path.get_or_insert(PathBuf::new());
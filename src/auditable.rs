@@ -0,0 +1,100 @@
+//! Builds with [cargo-auditable](https://github.com/rust-secure-code/cargo-auditable), which embeds the dependency tree
+//! in the binaries, so that `cargo audit bin` and other scanners can check the installed files for vulnerable crates.
+//!
+//! The data is zlib-compressed JSON in the `.dep-v0` section of the binaries.
+use crate::config::PackageConfig;
+use crate::error::{CDResult, CargoDebError};
+use crate::listener::Listener;
+use std::fs;
+use std::io::Read;
+use std::process::Command;
+
+/// Name of the linker section with the audit data
+const SECTION: &str = ".dep-v0";
+
+/// `auditable build` for the `build` subcommand
+pub(crate) fn build_command(build_command: &str) -> String {
+    if build_command.split(' ').next() == Some("auditable") {
+        build_command.to_owned()
+    } else {
+        format!("auditable {build_command}")
+    }
+}
+
+/// Fails early if `cargo auditable` isn't installed, instead of a confusing build error
+pub(crate) fn check_installed() -> CDResult<()> {
+    let output = Command::new("cargo").arg("--list").output()
+        .map_err(|e| CargoDebError::CommandFailed(e, "cargo"))?;
+    if has_subcommand(&String::from_utf8_lossy(&output.stdout)) {
+        return Ok(());
+    }
+    Err("auditable = true needs cargo-auditable to build the binaries\nnote: install it with `cargo install cargo-auditable`".into())
+}
+
+/// Whether the output of `cargo --list` has the `auditable` command
+fn has_subcommand(cargo_list: &str) -> bool {
+    cargo_list.lines().any(|line| line.split_whitespace().next() == Some("auditable"))
+}
+
+/// Checks that the executables in the package have the audit data, which is easy to lose with a custom build command
+pub(crate) fn check_binaries(package_deb: &mut PackageConfig, listener: &dyn Listener) -> CDResult<()> {
+    for asset in package_deb.built_binaries_mut() {
+        let Some(path) = asset.source.path().filter(|_| !asset.c.is_dynamic_library() && !asset.source.archive_as_symlink_only()) else { continue };
+        let data = fs::read(path).map_err(|e| CargoDebError::IoFile("unable to read binary", e, path.to_owned()))?;
+        let section = match elf_section(&data, SECTION) {
+            Ok(section) => section,
+            Err(e) => {
+                listener.warning(format!("unable to check audit data of '{}': {e}", path.display()));
+                continue;
+            },
+        };
+        match section.as_deref().and_then(packages_count) {
+            Some(count) => log::debug!("{} has audit data of {count} crates", path.display()),
+            // stripped binaries are temporary copies
+            None => {
+                let original = asset.processed_from.as_ref().and_then(|p| p.original_path.as_deref()).unwrap_or(path);
+                return Err(CargoDebError::AuditDataMissing(original.to_owned()));
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Number of crates in valid audit data
+fn packages_count(compressed: &[u8]) -> Option<usize> {
+    let mut json = Vec::new();
+    flate2::read::ZlibDecoder::new(compressed).read_to_end(&mut json).ok()?;
+    let data: serde_json::Value = serde_json::from_slice(&json).ok()?;
+    Some(data.get("packages")?.as_array()?.len())
+}
+
+#[cfg(not(feature = "debug-id"))]
+fn elf_section(_: &[u8], _: &str) -> std::io::Result<Option<Vec<u8>>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "cargo-deb was built without ELF support (the debug-id feature)"))
+}
+
+#[cfg(feature = "debug-id")]
+fn elf_section(data: &[u8], name: &str) -> Result<Option<Vec<u8>>, elf::ParseError> {
+    use elf::endian::AnyEndian;
+    use elf::ElfBytes;
+
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let Some(shdr) = elf.section_header_by_name(name)? else { return Ok(None) };
+    let (section, compression) = elf.section_data(&shdr)?;
+    Ok(Some(section.to_vec()).filter(|_| compression.is_none()))
+}
+
+#[test]
+fn audit_data() {
+    use std::io::Write;
+
+    assert_eq!(build_command("build"), "auditable build");
+    assert_eq!(build_command("auditable build"), "auditable build");
+    assert!(has_subcommand("Installed Commands:\n    add                  Add dependencies\n    auditable\n    build                Compile a local package\n"));
+    assert!(!has_subcommand("Installed Commands:\n    build                Compile a local package\n"));
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(br#"{"packages":[{"name":"a","version":"1.0.0","source":"local","root":true},{"name":"b","version":"1.0.0","source":"crates.io"}]}"#).unwrap();
+    assert_eq!(packages_count(&encoder.finish().unwrap()), Some(2));
+    assert_eq!(packages_count(b"not zlib"), None);
+}
@@ -0,0 +1,464 @@
+//! Debian changelogs, read from the `changelog` file, converted from a `CHANGELOG.md`, or generated from the git history.
+//!
+//! See [the changelog format](https://www.debian.org/doc/debian-policy/ch-source.html#debian-changelog-debian-changelog).
+use crate::config::{check_debian_version, upstream_version, Config, PackageConfig};
+use crate::deb::changes::read_changelog;
+use crate::error::{CDResult, CargoDebError};
+use crate::parse::manifest::GitChangelog;
+use crate::util::rfc2822_date;
+use std::path::Path;
+use std::process::Command;
+
+/// Commit types that aren't interesting to users of the package
+const HIDDEN_COMMIT_TYPES: [&str; 7] = ["build", "chore", "ci", "docs", "refactor", "style", "test"];
+
+/// The `changelog` file takes priority over `git-changelog`
+pub(crate) fn read_debian_changelog(config: &Config, package_deb: &PackageConfig) -> CDResult<Option<String>> {
+    let package = ChangelogPackage {
+        name: &package_deb.deb_name,
+        deb_version: &package_deb.deb_version,
+        cargo_version: &package_deb.cargo_version,
+        distribution: package_deb.distribution.as_deref().unwrap_or("unstable"),
+        urgency: package_deb.urgency.as_deref().unwrap_or("medium"),
+        maintainer: &package_deb.maintainer,
+        default_timestamp: package_deb.default_timestamp,
+    };
+    if let Some(path) = &package_deb.changelog {
+        let path = config.path_in_package(path);
+        let changelog = read_changelog(&path)?;
+        if is_markdown(&path) {
+            return Ok(Some(changelog_from_markdown(&changelog, &package)));
+        }
+        return Ok(Some(changelog));
+    }
+    let Some(style) = package_deb.git_changelog else { return Ok(None) };
+    let commits = git_log(&config.package_manifest_dir)?;
+    Ok(Some(changelog_from_commits(&commits, &package, style == GitChangelog::ConventionalCommits)))
+}
+
+/// Takes the version and distribution from the top entry of the `changelog`, or `debian/changelog`.
+///
+/// The package name and the upstream part of the version must match `Cargo.toml`.
+pub(crate) fn use_changelog_version(config: &Config, package_deb: &mut PackageConfig) -> CDResult<()> {
+    let rel_path = package_deb.changelog.clone().filter(|path| !is_markdown(Path::new(path))).unwrap_or_else(|| "debian/changelog".into());
+    let path = config.path_in_package(&rel_path);
+    let changelog = read_changelog(&path)?;
+    let top = top_entry(&changelog).map_err(|msg| CargoDebError::InvalidChangelog(msg, path.clone()))?;
+
+    // the Debian revision can differ
+    let has_revision = changelog_has_revision(top.version, package_deb.upstream_version()).filter(|_| top.name == package_deb.deb_name);
+    let Some(has_revision) = has_revision else {
+        return Err(CargoDebError::InvalidChangelog(format!("the top entry doesn't match Cargo.toml\n-{rel_path}: {} ({})\n+Cargo.toml: {} ({})",
+            top.name, top.version, package_deb.deb_name, package_deb.deb_version), path));
+    };
+    package_deb.deb_version = top.version.to_owned();
+    package_deb.has_revision = has_revision;
+    package_deb.distribution.get_or_insert_with(|| top.distribution.to_owned());
+    package_deb.changelog = Some(rel_path);
+    Ok(())
+}
+
+#[derive(Debug)]
+struct TopEntry<'a> {
+    name: &'a str,
+    version: &'a str,
+    distribution: &'a str,
+}
+
+/// Checks the syntax of the first entry, like `dpkg-parsechangelog`
+fn top_entry(changelog: &str) -> Result<TopEntry<'_>, String> {
+    let mut lines = changelog.lines().skip_while(|l| l.trim().is_empty());
+    let header = lines.next().ok_or("the changelog is empty")?;
+    let bad_header = || format!("the first line must be like `name (version) distribution; urgency=medium`, not `{header}`");
+    let (name, rest) = header.split_once(" (").ok_or_else(bad_header)?;
+    let (version, rest) = rest.split_once(')').ok_or_else(bad_header)?;
+    let (distribution, params) = rest.split_once(';').ok_or_else(bad_header)?;
+    let distribution = distribution.trim();
+    if name.is_empty() || !name.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || b"+-.".contains(&c)) || distribution.is_empty() || !params.contains("urgency=") {
+        return Err(bad_header());
+    }
+    check_debian_version(version).map_err(|e| format!("version '{version}' is invalid: {e}"))?;
+
+    let trailer = lines.find(|l| !l.is_empty() && !l.starts_with(' ') && !l.starts_with('\t') || l.starts_with(" -- "))
+        .filter(|l| l.starts_with(" -- "))
+        .ok_or_else(|| format!("the entry for {version} has no ` -- maintainer <email>  date` line"))?;
+    let (maintainer, date) = trailer[4..].split_once(">  ").ok_or_else(|| format!("the ` -- ` line must be like ` -- Name <email>  Mon, 01 Jan 2024 00:00:00 +0000`, not `{trailer}`"))?;
+    if !maintainer.contains('<') || date.trim().is_empty() {
+        return Err(format!("the ` -- ` line must have the maintainer's email and the date, not `{trailer}`"));
+    }
+    Ok(TopEntry { name, version, distribution: distribution.split_whitespace().next().unwrap_or(distribution) })
+}
+
+/// Whether the changelog's version has a Debian revision, or `None` if its upstream part isn't the package's `upstream` version.
+/// A native version can have a `-` too.
+fn changelog_has_revision(version: &str, upstream: &str) -> Option<bool> {
+    if upstream_version(version, false) == upstream {
+        return Some(false);
+    }
+    (version.contains('-') && upstream_version(version, true) == upstream).then_some(true)
+}
+
+/// `NEWS.Debian` has the same format as changelogs, and the latest entry is checked
+pub(crate) fn read_news(path: &Path) -> CDResult<String> {
+    let news = read_changelog(path)?;
+    top_entry(&news).map_err(|msg| CargoDebError::InvalidChangelog(msg, path.to_owned()))?;
+    Ok(news)
+}
+
+/// `CHANGELOG.md` needs to be converted
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("md"))
+}
+
+struct ChangelogPackage<'a> {
+    name: &'a str,
+    deb_version: &'a str,
+    cargo_version: &'a str,
+    distribution: &'a str,
+    urgency: &'a str,
+    maintainer: &'a str,
+    default_timestamp: u64,
+}
+
+struct Commit {
+    timestamp: u64,
+    is_merge: bool,
+    tags: Vec<String>,
+    subject: String,
+}
+
+/// Commits reachable from `HEAD`, newest first
+fn git_log(dir: &Path) -> CDResult<Vec<Commit>> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(["log", "--decorate-refs=refs/tags/", "--format=%ct%x1f%P%x1f%D%x1f%s"])
+        .output()
+        .map_err(|e| CargoDebError::CommandFailed(e, "git"))?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("git log for the changelog failed", dir.display().to_string(), output.stderr));
+    }
+    Ok(parse_git_log(std::str::from_utf8(&output.stdout)?))
+}
+
+fn parse_git_log(log: &str) -> Vec<Commit> {
+    log.lines().filter_map(|line| {
+        let mut fields = line.splitn(4, '\x1f');
+        let timestamp = fields.next()?.parse().ok()?;
+        let is_merge = fields.next()?.contains(' ');
+        let tags = fields.next()?.split(", ").filter_map(|r| r.strip_prefix("tag: ")).map(String::from).collect();
+        Some(Commit { timestamp, is_merge, tags, subject: fields.next()?.trim().to_owned() })
+    }).collect()
+}
+
+#[derive(Default)]
+struct Entry {
+    deb_version: String,
+    timestamp: Option<u64>,
+    items: Vec<String>,
+    /// Items under headings like "Added"
+    sections: Vec<(String, Vec<String>)>,
+}
+
+/// An entry for every version tag, and one for the package's version if it's not tagged yet
+fn changelog_from_commits(commits: &[Commit], package: &ChangelogPackage<'_>, conventional: bool) -> String {
+    let mut entries = Vec::new();
+    let mut current = Entry { deb_version: package.deb_version.to_owned(), ..Entry::default() };
+    for commit in commits {
+        if let Some(version) = commit.tags.iter().find_map(|tag| tag_version(tag, package.name)) {
+            // commits after the tag of the current version are in its entry
+            let deb_version = if version == package.cargo_version { package.deb_version.to_owned() } else { tagged_deb_version(version, package.deb_version) };
+            if deb_version != current.deb_version {
+                let previous = std::mem::replace(&mut current, Entry { deb_version, ..Entry::default() });
+                if previous.timestamp.is_some() {
+                    entries.push(previous);
+                }
+            }
+        }
+        current.timestamp.get_or_insert(commit.timestamp);
+        if commit.is_merge {
+            continue;
+        }
+        if let Some(item) = changelog_item(&commit.subject, conventional) {
+            current.items.push(item);
+        }
+    }
+    if current.timestamp.is_some() || entries.is_empty() {
+        entries.push(current);
+    }
+    format_entries(&entries, package)
+}
+
+/// Converts [keep a changelog](https://keepachangelog.com) style, with `## [1.2.3] - 2024-01-31` headings for versions.
+///
+/// The `Unreleased` section is skipped.
+fn changelog_from_markdown(markdown: &str, package: &ChangelogPackage<'_>) -> String {
+    let mut entries = Vec::<Entry>::new();
+    let mut in_release = false;
+    let mut in_item = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("## ") {
+            in_release = false;
+            in_item = false;
+            let version = heading.split_whitespace().next().unwrap_or_default().trim_matches(['[', ']']);
+            let version = version.strip_prefix('v').unwrap_or(version);
+            if version.starts_with(|c: char| c.is_ascii_digit()) {
+                let deb_version = if version == package.cargo_version { package.deb_version.to_owned() } else { tagged_deb_version(version, package.deb_version) };
+                entries.push(Entry { deb_version, timestamp: heading_date(heading), ..Entry::default() });
+                in_release = true;
+            }
+            continue;
+        }
+        let Some(entry) = entries.last_mut().filter(|_| in_release) else { continue };
+        if let Some(heading) = trimmed.strip_prefix("### ") {
+            entry.sections.push((heading.trim().to_owned(), Vec::new()));
+            in_item = false;
+            continue;
+        }
+        // link reference definitions, like `[1.2.3]: https://…`
+        if trimmed.is_empty() || trimmed.starts_with('#') || (trimmed.starts_with('[') && trimmed.contains("]: ")) {
+            in_item = false;
+            continue;
+        }
+        let items = match entry.sections.last_mut() {
+            Some((_, items)) => items,
+            None => &mut entry.items,
+        };
+        if let Some(text) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            items.push(text.trim().to_owned());
+            in_item = true;
+        } else if let Some(item) = items.last_mut().filter(|_| in_item) {
+            item.push(' ');
+            item.push_str(trimmed);
+        } else {
+            items.push(trimmed.to_owned());
+            in_item = true;
+        }
+    }
+    format_entries(&entries, package)
+}
+
+/// The first `YYYY-MM-DD` date in the heading, at midnight UTC
+fn heading_date(heading: &str) -> Option<u64> {
+    let bytes = heading.as_bytes();
+    (0..bytes.len().saturating_sub(9)).find_map(|start| {
+        let date = heading.get(start..start + 10)?;
+        let mut parts = date.split('-');
+        let (y, m, d) = (parts.next()?, parts.next()?, parts.next()?);
+        if y.len() != 4 || m.len() != 2 || d.len() != 2 {
+            return None;
+        }
+        days_since_epoch(y.parse().ok()?, m.parse().ok()?, d.parse().ok()?).map(|days| days * 86400)
+    })
+}
+
+/// Days from 1970-01-01 in the proleptic Gregorian calendar
+fn days_since_epoch(year: u64, month: u64, day: u64) -> Option<u64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    // years starting in March put the leap day at the end
+    let year = if month <= 2 { year - 1 } else { year };
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year;
+    // the same formula for 1970-01-01
+    Some(days - 719_468)
+}
+
+fn format_entries(entries: &[Entry], package: &ChangelogPackage<'_>) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!("{} ({}) {}; urgency={}\n\n", package.name, entry.deb_version, package.distribution, package.urgency));
+        if entry.items.is_empty() && entry.sections.is_empty() {
+            push_wrapped_item(&mut out, "  * ", &format!("Release {}", entry.deb_version));
+        }
+        for item in &entry.items {
+            push_wrapped_item(&mut out, "  * ", item);
+        }
+        for (heading, items) in &entry.sections {
+            push_wrapped_item(&mut out, "  * ", heading);
+            for item in items {
+                push_wrapped_item(&mut out, "    - ", item);
+            }
+        }
+        out.push_str(&format!("\n -- {}  {}\n\n", package.maintainer, rfc2822_date(entry.timestamp.unwrap_or(package.default_timestamp))));
+    }
+    out.pop();
+    out
+}
+
+/// `v1.2.3`, `1.2.3`, `name-v1.2.3` or `name-1.2.3`
+fn tag_version<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let version = tag.strip_prefix(name).and_then(|t| t.strip_prefix('-')).unwrap_or(tag);
+    let version = version.strip_prefix('v').unwrap_or(version);
+    version.starts_with(|c: char| c.is_ascii_digit()).then_some(version)
+}
+
+/// Releases before the current one get the first Debian revision
+fn tagged_deb_version(version: &str, current_deb_version: &str) -> String {
+    // like semver, prerelease versions sort before releases
+    let version = version.replacen('-', "~", 1);
+    if current_deb_version.contains('-') { format!("{version}-1") } else { version }
+}
+
+/// Text of the changelog bullet point, or `None` if it's a conventional commit that isn't listed
+fn changelog_item(subject: &str, conventional: bool) -> Option<String> {
+    if subject.is_empty() {
+        return None;
+    }
+    if !conventional {
+        return Some(subject.to_owned());
+    }
+    let Some((kind, scope, breaking, description)) = conventional_commit(subject) else {
+        return Some(subject.to_owned());
+    };
+    if !breaking && HIDDEN_COMMIT_TYPES.contains(&kind) {
+        return None;
+    }
+    let mut item = String::new();
+    if breaking {
+        item.push_str("Breaking change: ");
+    }
+    if let Some(scope) = scope {
+        item.push_str(scope);
+        item.push_str(": ");
+    }
+    let mut chars = description.chars();
+    item.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+    item.push_str(chars.as_str());
+    Some(item)
+}
+
+/// `type(scope)!: description`, see [conventional commits](https://www.conventionalcommits.org)
+fn conventional_commit(subject: &str) -> Option<(&str, Option<&str>, bool, &str)> {
+    let (prefix, description) = subject.split_once(": ")?;
+    let (prefix, breaking) = prefix.strip_suffix('!').map_or((prefix, false), |p| (p, true));
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.bytes().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((kind, scope, breaking, description.trim()))
+}
+
+/// A bullet point wrapped to 80 columns, with continuation lines aligned after the bullet
+fn push_wrapped_item(out: &mut String, bullet: &str, text: &str) {
+    out.push_str(bullet);
+    let mut line_len = bullet.len();
+    for word in text.split_whitespace() {
+        if line_len > bullet.len() {
+            if line_len + 1 + word.len() > 79 {
+                out.push('\n');
+                out.extend(std::iter::repeat(' ').take(bullet.len()));
+                line_len = bullet.len();
+            } else {
+                out.push(' ');
+                line_len += 1;
+            }
+        }
+        out.push_str(word);
+        line_len += word.len();
+    }
+    out.push('\n');
+}
+
+#[test]
+fn changelog_from_git_log() {
+    let log = "1700000300\x1fa\x1f\x1ffeat(cli): add --frobnicate\n\
+        1700000200\x1fb c\x1f\x1fMerge branch 'fix'\n\
+        1700000150\x1fd\x1f\x1fchore: bump dependencies\n\
+        1700000100\x1fe\x1ftag: v1.0.0\x1ffix!: remove the deprecated config format\n\
+        1700000000\x1ff\x1ftag: example-v0.9.0-beta.1, tag: latest\x1fInitial commit\n";
+    let package = ChangelogPackage {
+        name: "example",
+        deb_version: "1.1.0-1",
+        cargo_version: "1.1.0",
+        distribution: "unstable",
+        urgency: "medium",
+        maintainer: "Me <me@example.com>",
+        default_timestamp: 0,
+    };
+    let commits = parse_git_log(log);
+    assert_eq!(commits[4].tags, ["example-v0.9.0-beta.1", "latest"]);
+
+    assert_eq!(changelog_from_commits(&commits, &package, false), "\
+example (1.1.0-1) unstable; urgency=medium
+
+  * feat(cli): add --frobnicate
+  * chore: bump dependencies
+
+ -- Me <me@example.com>  Tue, 14 Nov 2023 22:18:20 +0000
+
+example (1.0.0-1) unstable; urgency=medium
+
+  * fix!: remove the deprecated config format
+
+ -- Me <me@example.com>  Tue, 14 Nov 2023 22:15:00 +0000
+
+example (0.9.0~beta.1-1) unstable; urgency=medium
+
+  * Initial commit
+
+ -- Me <me@example.com>  Tue, 14 Nov 2023 22:13:20 +0000
+");
+    let conventional = changelog_from_commits(&commits, &package, true);
+    assert!(conventional.starts_with("example (1.1.0-1) unstable; urgency=medium\n\n  * cli: Add --frobnicate\n\n"), "{conventional}");
+    assert!(conventional.contains("\n  * Breaking change: Remove the deprecated config format\n"));
+}
+
+#[test]
+fn wraps_long_items() {
+    let mut out = String::new();
+    push_wrapped_item(&mut out, "  * ", &"word ".repeat(20));
+    assert_eq!(out, "  * word word word word word word word word word word word word word word word\n    word word word word word\n");
+}
+
+#[test]
+fn changelog_from_keep_a_changelog() {
+    let markdown = "# Changelog\n\nAll notable changes.\n\n## [Unreleased]\n\n- Not yet\n\n\
+        ## [1.1.0] - 2023-11-14\n### Added\n- A `--frobnicate` flag, which is described\n  over two lines\n\n### Fixed\n* Crash on start\n\n\
+        ## 1.0.0\nFirst release\n\n[1.1.0]: https://example.com/compare/v1.0.0...v1.1.0\n";
+    let package = ChangelogPackage {
+        name: "example",
+        deb_version: "1.1.0-2",
+        cargo_version: "1.1.0",
+        distribution: "unstable",
+        urgency: "low",
+        maintainer: "Me <me@example.com>",
+        default_timestamp: 0,
+    };
+    assert_eq!(changelog_from_markdown(markdown, &package), "\
+example (1.1.0-2) unstable; urgency=low
+
+  * Added
+    - A `--frobnicate` flag, which is described over two lines
+  * Fixed
+    - Crash on start
+
+ -- Me <me@example.com>  Tue, 14 Nov 2023 00:00:00 +0000
+
+example (1.0.0-1) unstable; urgency=low
+
+  * First release
+
+ -- Me <me@example.com>  Thu, 01 Jan 1970 00:00:00 +0000
+");
+    assert_eq!(days_since_epoch(2000, 3, 1), Some(11_017));
+}
+
+#[test]
+fn validates_top_entry() {
+    let top = top_entry("example (1:1.2.0-3) bookworm; urgency=low\n\n  * Fixed\n\n -- Me <me@example.com>  Mon, 01 Jan 2024 00:00:00 +0000\n").unwrap();
+    assert_eq!((top.name, top.version, top.distribution), ("example", "1:1.2.0-3", "bookworm"));
+    assert_eq!(changelog_has_revision(top.version, "1.2.0"), Some(true));
+    assert_eq!(changelog_has_revision("1.2.0", "1.2.0"), Some(false));
+    assert_eq!(changelog_has_revision("1.2.0-beta", "1.2.0-beta"), Some(false));
+    assert_eq!(changelog_has_revision("1.2.0-beta-2", "1.2.0-beta"), Some(true));
+    assert_eq!(changelog_has_revision("1.2.0-beta", "1.2.0"), Some(true));
+    assert_eq!(changelog_has_revision("1.3.0-1", "1.2.0"), None);
+    assert!(top_entry("example 1.2.0 unstable\n").unwrap_err().contains("the first line must be"));
+    assert!(top_entry("example (1.2.0) unstable; urgency=low\n\n  * Fixed\n\nexample (1.1.0) unstable; urgency=low\n").unwrap_err().contains("no ` -- maintainer"));
+    assert!(top_entry("example (1.2.0) unstable; urgency=low\n\n -- Me  Mon, 01 Jan 2024\n").unwrap_err().contains("must be like"));
+}
@@ -0,0 +1,84 @@
+//! Shell completions, installed where bash-completion, zsh, and fish look for completions of packages.
+//!
+//! `clap_complete` in `build.rs` conventionally writes them to `OUT_DIR`, named `<binary>.bash`, `_<binary>`, and `<binary>.fish`.
+//!
+//! See [clap_complete](https://docs.rs/clap_complete) and [dh_bash-completion(1)](https://manpages.debian.org/dh_bash-completion.1).
+use crate::assets::{Asset, AssetSource, IsBuilt};
+use crate::config::{Config, PackageConfig};
+use crate::listener::Listener;
+use crate::manpages::{built_binaries, find_build_script_output};
+use crate::CDResult;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub(crate) const ALL: [Self; 3] = [Self::Bash, Self::Zsh, Self::Fish];
+
+    /// Name of the completions file that `clap_complete` generates for the command
+    pub(crate) fn generated_file_name(self, command: &str) -> String {
+        match self {
+            Self::Bash => format!("{command}.bash"),
+            Self::Zsh => format!("_{command}"),
+            Self::Fish => format!("{command}.fish"),
+        }
+    }
+
+    /// bash-completion loads completions named after the command, zsh from files named `_<command>`,
+    /// and fish from `<command>.fish`
+    pub(crate) fn target_path(self, command: &str) -> PathBuf {
+        match self {
+            Self::Bash => Path::new("usr/share/bash-completion/completions").join(command),
+            Self::Zsh => Path::new("usr/share/zsh/vendor-completions").join(format!("_{command}")),
+            Self::Fish => Path::new("usr/share/fish/vendor_completions.d").join(format!("{command}.fish")),
+        }
+    }
+}
+
+/// The command that completions are for, from the name of the file, like `foo` for `foo.bash`, `_foo`, or `foo.fish`
+pub(crate) fn command_name(shell: Shell, file_name: &str) -> &str {
+    match shell {
+        Shell::Bash => file_name.strip_suffix(".bash").unwrap_or(file_name),
+        Shell::Zsh => file_name.strip_prefix('_').unwrap_or(file_name),
+        Shell::Fish => file_name.strip_suffix(".fish").unwrap_or(file_name),
+    }
+}
+
+/// Adds completions that the build scripts generated for built binaries in `/usr/bin` that have none
+pub(crate) fn add_generated_completions(config: &Config, package_deb: &mut PackageConfig, listener: &dyn Listener) -> CDResult<()> {
+    let mut completions = Vec::new();
+    for (name, _) in built_binaries(package_deb, "usr/bin") {
+        let mut found = false;
+        for shell in Shell::ALL {
+            let target_path = shell.target_path(name);
+            if package_deb.assets.resolved.iter().any(|a| a.c.target_path == target_path) {
+                found = true;
+                continue;
+            }
+            if let Some(path) = find_build_script_output(config, package_deb, &shell.generated_file_name(name)) {
+                found = true;
+                listener.info(format!("Adding completions /{}", target_path.display()));
+                completions.push(Asset::new(AssetSource::Path(path), target_path, 0o644, IsBuilt::No, false));
+            }
+        }
+        if !found {
+            listener.warning(format!("/usr/bin/{name} has no shell completions. Generate them with clap_complete in OUT_DIR of build.rs"));
+        }
+    }
+    package_deb.assets.resolved.extend(completions);
+    Ok(())
+}
+
+#[test]
+fn completion_paths() {
+    assert_eq!(Shell::Zsh.target_path(command_name(Shell::Zsh, "_mycmd")), Path::new("usr/share/zsh/vendor-completions/_mycmd"));
+    assert_eq!(Shell::Bash.target_path(command_name(Shell::Bash, "mycmd.bash")), Path::new("usr/share/bash-completion/completions/mycmd"));
+    assert_eq!(Shell::Bash.target_path(command_name(Shell::Bash, "mycmd")), Path::new("usr/share/bash-completion/completions/mycmd"));
+    assert_eq!(Shell::Fish.target_path(command_name(Shell::Fish, "mycmd.fish")), Path::new("usr/share/fish/vendor_completions.d/mycmd.fish"));
+    assert_eq!(Shell::Fish.generated_file_name("mycmd"), "mycmd.fish");
+}
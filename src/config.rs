@@ -1,16 +1,32 @@
 use crate::assets::is_dynamic_library_filename;
 use crate::assets::{Asset, AssetSource, Assets, IsBuilt, UnresolvedAsset, RawAsset};
-use crate::util::compress::gzipped;
+use crate::util::compress::{gzipped, Format, Level};
 use crate::{debian_architecture_from_rust_triple, CargoLockingFlags};
-use crate::dependencies::resolve;
-use crate::dh::dh_installsystemd;
+use crate::changelog::{is_markdown, read_debian_changelog, read_news, use_changelog_version};
+use crate::dependencies::{has_dpkg_shlibdeps, installed_source_versions, locked_dependencies, resolve, resolve_from_sonames, OwnLibraries, ShlibsDependencies, static_built_using, vendored_c_libraries};
+use crate::dh::{dh_apparmor, dh_appstream, dh_dkms, dh_icons, dh_install, dh_installdbus, dh_installlogrotate, dh_installpolkit, dh_installsystemd, dh_installsysusers, dh_installtmpfiles, dh_installudev, dh_selinux};
+use crate::dh::dh_install::normalized;
+use crate::dh::dh_installdebconf::DEBCONF_DEPENDS;
+use crate::dh::dh_installalternatives::Alternative;
+use crate::dh::dh_dkms::DkmsModule;
+use crate::dh::dh_statoverride::StatOverride;
+use crate::deb::buildinfo::rustc_version;
+use crate::deb::control::format_extended_description;
 use crate::error::{CDResult, CargoDebError};
+use crate::librust::CrateSources;
 use crate::listener::Listener;
 use crate::parse::cargo::CargoConfig;
-use crate::parse::manifest::{cargo_metadata, manifest_debug_flag, manifest_version_string, LicenseFile};
-use crate::parse::manifest::{CargoDeb, CargoMetadataTarget, CargoPackageMetadata, ManifestFound};
-use crate::parse::manifest::{DependencyList, SystemUnitsSingleOrMultiple, SystemdUnitsConfig};
+use crate::parse::manifest::{cargo_metadata, expand_git_placeholders, manifest_debug_flag, manifest_version_string, GitChangelog, LicenseFile, Multiarch};
+use crate::parse::manifest::{AlternativeEntry, CargoDeb, CompletionsEntry, CronFiles, DbusFiles, DesktopEntry, DkmsConfig, DocsEntry, ExampleBinaryEntry, LicensePolicy, SbomConfig, TranslationsConfig, MetainfoEntry, StatOverrideEntry, CargoMetadataTarget, CargoPackageMetadata, ManifestFound};
+use crate::parse::manifest::{AssetEntry, AssetList, DependencyList, MaintscriptEntry, SystemUnitsSingleOrMultiple, SystemdUnitsConfig, KeyringConfig, UploadTarget};
+use crate::completions::{self, Shell};
+use crate::licenses::{self, CrateLicense};
+use crate::sbom::Sbom;
+use crate::symbols::{self, InstalledLibraries};
+use crate::translations;
+use crate::util::{is_path_file, read_file_to_bytes};
 use crate::util::ok_or::OkOrThen;
+use itertools::Itertools;
 use crate::util::pathbytes::AsUnixPathBytes;
 use crate::util::wordsplit::WordSplit;
 use rayon::prelude::*;
@@ -86,6 +102,9 @@ fn match_architecture(spec: ArchSpec, target_arch: &str) -> CDResult<bool> {
     }
 }
 
+/// Same as `dh_strip`
+const DEFAULT_STRIP_FLAGS: &[&str] = &["--strip-unneeded", "--remove-section=.comment", "--remove-section=.note"];
+
 #[derive(Debug)]
 #[non_exhaustive]
 /// Cargo deb configuration read from the manifest and cargo metadata
@@ -103,6 +122,20 @@ pub struct Config {
     pub default_features: bool,
     /// Should the binary be stripped from debug symbols?
     pub debug_symbols: DebugSymbols,
+    /// Compression format set in `Cargo.toml`. Command-line option takes precedence.
+    pub compress_type: Option<Format>,
+    /// Compression format of `control.tar` set in `Cargo.toml`. Same as `compress_type` if `None`.
+    pub control_compress_type: Option<Format>,
+    /// Compression level of `data.tar` set in `Cargo.toml`
+    pub compress_level: Option<Level>,
+    /// Compression level of `control.tar` set in `Cargo.toml`
+    pub control_compress_level: Option<Level>,
+    /// Arguments for `strip`, from `Cargo.toml` or the defaults
+    pub strip_flags: Vec<String>,
+    /// ELF sections that `strip` must not remove
+    pub strip_keep_sections: Vec<String>,
+    /// Remove RPATH and RUNPATH from built binaries, instead of warning about them
+    pub remove_rpath: bool,
 
     /// "release" if None
     build_profile_override: Option<String>,
@@ -129,6 +162,10 @@ pub struct PackageConfig {
     pub deb_name: String,
     /// The version to give the Debian package; usually the same as the Cargo version
     pub deb_version: String,
+    /// Whether `deb_version` ends with a Debian revision; native packages don't have one
+    pub(crate) has_revision: bool,
+    /// The version in `Cargo.toml`
+    pub(crate) cargo_version: String,
     /// The software license of the project (SPDX format).
     pub license: Option<String>,
     /// The location of the license file
@@ -139,12 +176,32 @@ pub struct PackageConfig {
     /// (Debian's `copyright` file contents).
     pub copyright: Option<String>,
     pub changelog: Option<String>,
+    /// Generates the changelog from git tags and commits, if there's no `changelog` file
+    pub(crate) git_changelog: Option<GitChangelog>,
+    /// Upgrade notes for `apt-listchanges`, in the changelog format
+    pub news: Option<String>,
+    /// The changelog of the project, if it's not one of `UPSTREAM_CHANGELOG_FILES`. Empty to not install any.
+    pub upstream_changelog: Option<String>,
+    /// `Distribution` of the `.changes` file. Taken from the changelog if `None`.
+    pub distribution: Option<String>,
+    /// `Urgency` of the `.changes` file. Taken from the changelog if `None`.
+    pub urgency: Option<String>,
     /// The homepage URL of the project.
     pub homepage: Option<String>,
     /// Documentation URL from `Cargo.toml`. Fallback if `homepage` is missing.
     pub documentation: Option<String>,
     /// The URL of the software repository.
     pub repository: Option<String>,
+    /// `Vcs-Git` set in the metadata, instead of the one guessed from `repository`
+    pub vcs_git: Option<String>,
+    /// `Vcs-Browser` set in the metadata, instead of the one guessed from `repository`
+    pub vcs_browser: Option<String>,
+    /// `Origin` control field, the name of the distribution or vendor
+    pub origin: Option<String>,
+    /// `Bugs` control field, the URL of the bug tracker
+    pub bugs: Option<String>,
+    /// `Tag` control field, with [debtags](https://wiki.debian.org/Debtags)
+    pub tag: Option<String>,
     /// A short description of the project.
     pub description: String,
     /// An extended description of the project.
@@ -156,8 +213,10 @@ pub struct PackageConfig {
     pub wildcard_depends: String,
     /// The Debian dependencies required to run the project.
     pub resolved_depends: Option<String>,
-    /// The Debian pre-dependencies.
+    /// The Debian pre-dependencies. `$auto` is replaced in `resolve_binary_dependencies`.
     pub pre_depends: Option<String>,
+    /// `Build-Depends` of the source package built with `--source`
+    pub build_depends: Option<String>,
     /// The Debian recommended dependencies.
     pub recommends: Option<String>,
     /// The Debian suggested dependencies.
@@ -185,9 +244,25 @@ pub struct PackageConfig {
     ///
     /// See [PackageTransition](https://wiki.debian.org/PackageTransition).
     pub provides: Option<String>,
+    /// `Built-Using` Debian control field. `$auto` is replaced in `Config::from_manifest`.
+    pub built_using: Option<String>,
+    /// `Static-Built-Using` Debian control field, with Rust crates linked into the binaries
+    pub static_built_using: Option<String>,
 
     /// The Debian architecture of the target system.
     pub architecture: String,
+    /// `Multi-Arch` control field
+    pub(crate) multiarch: Option<Multiarch>,
+    /// `Essential: yes`, for packages that must never be removed
+    pub essential: bool,
+    /// `Protected: yes`, for packages that are needed to boot
+    pub protected: bool,
+    /// `Important: yes`, for packages that are removed only after a confirmation
+    pub important: bool,
+    /// Adds `X-Cargo-Rustc-Version` and other fields about the build to the control file
+    pub provenance_fields: bool,
+    /// File name of the `.deb` with `{name}`, `{version}`, `{arch}` and `{distro}` placeholders
+    pub output_template: Option<String>,
     /// A list of configuration files installed by the package.
     /// Automatically includes all files in `/etc`
     pub conf_files: Vec<String>,
@@ -199,14 +274,86 @@ pub struct PackageConfig {
     pub readme_rel_path: Option<PathBuf>,
     /// The location of the triggers file
     pub triggers_file_rel_path: Option<PathBuf>,
+    /// Trigger directives added after the triggers file
+    pub triggers: Vec<String>,
+    /// Arguments of `dpkg-maintscript-helper` calls
+    pub maintscript: Vec<String>,
+    /// SysV init script, installed as `/etc/init.d/<package>` and registered with `update-rc.d`
+    pub init_script: Option<String>,
+    /// logrotate config, installed as `/etc/logrotate.d/<package>`
+    pub logrotate: Option<String>,
+    /// Files for `/etc/cron.d` and `/etc/cron.<period>`
+    pub(crate) cron: CronFiles,
+    /// udev rules, installed as `/usr/lib/udev/rules.d/60-<package>.rules`
+    pub udev_rules: Option<String>,
+    /// D-Bus service activation files and the bus policy
+    pub(crate) dbus: DbusFiles,
+    /// polkit `.policy` and `.rules` files
+    pub polkit: Vec<String>,
+    /// AppArmor profiles, installed in `/etc/apparmor.d`
+    pub apparmor_profiles: Vec<String>,
+    /// SELinux policy modules, installed in `/usr/share/selinux/<package>`
+    pub selinux_modules: Vec<String>,
+    /// initramfs-tools hook, installed as `/usr/share/initramfs-tools/hooks/<package>`
+    pub initramfs_hook: Option<String>,
+    /// Sources of a kernel module to build with DKMS
+    pub(crate) dkms: Option<DkmsConfig>,
+    /// `.desktop` files and their icons
+    pub(crate) desktop_entries: Vec<DesktopEntry>,
+    /// AppStream metainfo file, or the id of the generated one
+    pub(crate) metainfo: Option<MetainfoEntry>,
+    /// Add man pages generated by build scripts or `help2man` for binaries that have none
+    pub generate_man_pages: bool,
+    /// Shell completions files, and whether to add the ones generated by build scripts
+    pub(crate) completions: Completions,
+    /// Files to install in `/usr/share/doc/<package>/`
+    pub(crate) docs: Option<DocsEntry>,
+    /// Install sources of examples in `/usr/share/doc/<package>/examples/`
+    pub examples: bool,
+    /// Examples to build, and install like binaries
+    pub(crate) example_binaries: Vec<ExampleBinaryEntry>,
+    /// Gettext catalogs to install in `/usr/share/locale/`
+    pub(crate) translations: Option<TranslationsConfig>,
+    /// Add copyright and licenses of the dependencies to the copyright file
+    pub dependency_copyright: bool,
+    /// Install the license files of the dependencies in `/usr/share/doc/<package>/licenses/`
+    pub license_texts: bool,
+    /// Licenses the dependencies may or must not have
+    pub(crate) license_policy: Option<LicensePolicy>,
+    /// Statically linked crates, if `dependency_copyright`, `license_texts`, or `license_policy`
+    pub(crate) dependency_licenses: Vec<CrateLicense>,
+    /// The `sbom` setting, with the statically linked crates
+    pub(crate) sbom: Option<Sbom>,
+    /// Build with `cargo auditable`, and check that the binaries have its dependency list
+    pub auditable: bool,
+    /// Root of the target system, with its dpkg database, for `$auto` dependencies of cross-compiled binaries
+    pub sysroot: Option<PathBuf>,
+    /// Absolute paths of configuration files managed by `ucf`, and their reference copies
+    pub ucf_files: Vec<(PathBuf, PathBuf)>,
+    /// Assets in `update-alternatives` groups
+    pub(crate) alternatives: Vec<Alternative>,
+    /// Absolute paths of assets, and the file capabilities that the `postinst` sets
+    pub capabilities: Vec<(String, String)>,
+    /// Ownership and modes that the `postinst` sets with `dpkg-statoverride`
+    pub(crate) stat_overrides: Vec<StatOverride>,
     /// The path where possible maintainer scripts live
     pub maintainer_scripts_rel_path: Option<PathBuf>,
+    /// The directory with `debian/<package>.install`-like files to add to the assets
+    pub debhelper_files_rel_path: Option<PathBuf>,
     /// Should symlinks be preserved in the assets
     pub preserve_symlinks: bool,
     /// Details of how to install any systemd units
     pub(crate) systemd_units: Option<Vec<SystemdUnitsConfig>>,
+    /// Repositories that `--upload` can publish to, by name
+    pub(crate) upload_targets: HashMap<String, UploadTarget>,
+    /// Repository for the package built with `--keyring`
+    pub(crate) keyring: Option<KeyringConfig>,
+    /// This is the package built with `--keyring`
+    pub(crate) is_keyring: bool,
     /// unix timestamp for generated files
     pub default_timestamp: u64,
+    /// This is an automatically generated package with debug symbols of another package
+    pub dbgsym: Option<DbgsymFormat>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -217,31 +364,59 @@ pub enum DebugSymbols {
     Separate {
         /// Should the debug symbols be compressed
         compress: bool,
+        /// Put the debug symbols in a `-dbgsym` package instead of the main package
+        dbgsym: Option<DbgsymFormat>,
+        /// Should `dwz` deduplicate the debug info
+        dwz: bool,
     },
 }
 
+/// How the `-dbgsym` package is made
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DbgsymFormat {
+    /// Regular `.deb`, as used by Debian
+    Deb,
+    /// `.ddeb` with `Package-Type: ddeb`, as expected by Ubuntu
+    Ddeb,
+}
+
 /// Replace config values via command-line
 #[derive(Debug, Clone, Default)]
 pub struct DebConfigOverrides {
     pub deb_version: Option<String>,
     pub deb_revision: Option<String>,
+    /// Use the version of the top entry of `debian/changelog`
+    pub version_from_changelog: bool,
     pub maintainer: Option<String>,
+    /// Fail instead of warning about invalid `Section` and `Priority` values
+    pub strict: bool,
+    /// Overrides `output-template` from `Cargo.toml`
+    pub output_template: Option<String>,
+    /// Write the SBOM next to the `.deb`, even without the `sbom` setting
+    pub sbom: bool,
+    /// Overrides `sysroot` from `Cargo.toml`
+    pub sysroot: Option<PathBuf>,
 }
 
 impl Config {
     /// Makes a new config from `Cargo.toml` in the `manifest_path`
     ///
     /// `None` target means the host machine's architecture.
+    // each argument is a command-line option that the binary passes through
+    #[allow(clippy::too_many_arguments)]
     pub fn from_manifest(
         root_manifest_path: Option<&Path>,
         selected_package_name: Option<&str>,
         deb_output_path: Option<String>,
         target: Option<&str>,
         variant: Option<&str>,
-        overrides: DebConfigOverrides,
+        mut overrides: DebConfigOverrides,
         build_profile_override: Option<String>,
         separate_debug_symbols: Option<bool>,
         compress_debug_symbols: Option<bool>,
+        generate_dbgsym_package: Option<bool>,
+        ddeb: Option<bool>,
+        dwz: Option<bool>,
         cargo_locking_flags: CargoLockingFlags,
         listener: &dyn Listener,
     ) -> CDResult<(Self, PackageConfig)> {
@@ -253,7 +428,10 @@ impl Config {
             root_manifest,
             mut manifest_path,
             mut target_dir,
+            lockfile_path,
             mut manifest,
+            other_packages,
+            linked_packages,
         } = cargo_metadata(root_manifest_path, selected_package_name, cargo_locking_flags)?;
 
         let default_timestamp = if let Ok(source_date_epoch) = std::env::var("SOURCE_DATE_EPOCH") {
@@ -279,7 +457,12 @@ impl Config {
             .or_else(move || manifest_debug_flag(root_manifest.as_ref()?, selected_profile))
             .unwrap_or(false);
 
+        let names_of = |deps: &cargo_toml::DepsSet| deps.iter().map(|(name, dep)| dep.package().unwrap_or(name).to_owned()).collect::<Vec<_>>();
+        let dependency_names = [names_of(&manifest.dependencies), names_of(&manifest.build_dependencies)].concat();
+        let dev_dependencies = names_of(&manifest.dev_dependencies).into_iter().filter(|name| !dependency_names.contains(name)).collect::<Vec<_>>();
+
         let cargo_package = manifest.package.as_mut().ok_or("bad package")?;
+        let locked_name_version = (cargo_package.name.clone(), cargo_package.version().to_owned());
 
         // If we build against a variant use that config and change the package name
         let mut deb = if let Some(variant) = variant {
@@ -296,14 +479,31 @@ impl Config {
             cargo_package.metadata.take().and_then(|m| m.deb).unwrap_or_default()
         };
 
-        let separate_debug_symbols = separate_debug_symbols.unwrap_or_else(|| deb.separate_debug_symbols.unwrap_or(false));
+        if let Some(template) = deb.version.as_mut().filter(|_| overrides.deb_version.is_none()) {
+            *template = expand_git_placeholders(template, &manifest_dir)?;
+        }
+
+        let ddeb = ddeb.unwrap_or_else(|| deb.ddeb.unwrap_or(false));
+        let generate_dbgsym_package = generate_dbgsym_package.unwrap_or_else(|| deb.dbgsym.unwrap_or(ddeb));
+        let separate_debug_symbols = separate_debug_symbols.unwrap_or_else(|| deb.separate_debug_symbols.unwrap_or(generate_dbgsym_package));
         let compress_debug_symbols = compress_debug_symbols.unwrap_or_else(|| deb.compress_debug_symbols.unwrap_or(false));
 
+        let dwz = dwz.unwrap_or_else(|| deb.dwz.unwrap_or(false));
+        if generate_dbgsym_package && !separate_debug_symbols {
+            log::warn!("separate-debug-symbols required to make a dbgsym package");
+        }
+        if dwz && !separate_debug_symbols {
+            log::warn!("separate-debug-symbols required to run dwz");
+        }
         let debug_symbols = if separate_debug_symbols {
             if !debug_enabled {
                 log::warn!("separate-debug-symbols implies strip");
             }
-            DebugSymbols::Separate { compress: compress_debug_symbols }
+            DebugSymbols::Separate {
+                compress: compress_debug_symbols,
+                dbgsym: generate_dbgsym_package.then_some(if ddeb { DbgsymFormat::Ddeb } else { DbgsymFormat::Deb }),
+                dwz,
+            }
         } else if debug_enabled {
             if compress_debug_symbols {
                 log::warn!("separate-debug-symbols required to compress");
@@ -313,6 +513,8 @@ impl Config {
             DebugSymbols::Strip
         };
 
+        let strip_flags = resolve_strip_flags(&mut deb, target);
+
         let config = Self {
             package_manifest_dir: manifest_dir,
             deb_output_path,
@@ -321,12 +523,80 @@ impl Config {
             features: deb.features.take().unwrap_or_default(),
             default_features: deb.default_features.unwrap_or(true),
             debug_symbols,
+            compress_type: deb.compression.as_deref().map(str::parse).transpose()?,
+            control_compress_type: deb.control_compression.as_deref().map(str::parse).transpose()?,
+            compress_level: deb.compression_level.as_ref().map(|l| l.parse()).transpose()?,
+            control_compress_level: deb.control_compression_level.as_ref().map(|l| l.parse()).transpose()?,
+            strip_flags,
+            strip_keep_sections: deb.strip_keep_sections.take().unwrap_or_default(),
+            remove_rpath: deb.remove_rpath.unwrap_or(false),
             build_profile_override,
             build_targets,
             cargo_locking_flags,
         };
 
-        let package_deb = PackageConfig::new(deb, cargo_package, listener, default_timestamp, overrides, target)?;
+        let version_from_changelog = overrides.version_from_changelog;
+        let auto_revision = overrides.deb_version.is_none() && overrides.deb_revision.as_deref().or(deb.revision.as_deref()) == Some("auto");
+        if auto_revision {
+            overrides.deb_revision = Some("1".into());
+        }
+        let sysroot_override = overrides.sysroot.take();
+        let mut package_deb = PackageConfig::new(deb, cargo_package, listener, default_timestamp, overrides, target)?;
+        // the command-line path is relative to the current dir, like other paths given on the command line
+        package_deb.sysroot = sysroot_override.or_else(|| package_deb.sysroot.take().map(|path| config.path_in_package(path)));
+        if version_from_changelog {
+            use_changelog_version(&config, &mut package_deb)?;
+        } else if auto_revision {
+            let revision = config.next_revision(&package_deb)?;
+            if let Some((upstream, _)) = package_deb.deb_version.rsplit_once('-') {
+                package_deb.deb_version = format!("{upstream}-{revision}");
+            }
+        }
+        let auto_built_using = package_deb.built_using.as_deref().is_some_and(|b| b.contains("$auto"));
+        if auto_built_using || package_deb.static_built_using.is_some() || package_deb.dependency_copyright || package_deb.license_texts || package_deb.license_policy.is_some() || package_deb.sbom.is_some() {
+            let dev_dependencies = dev_dependencies.iter().map(String::as_str).collect::<Vec<_>>();
+            let locked = locked_dependencies(&lockfile_path, &locked_name_version.0, &locked_name_version.1, &dev_dependencies)?;
+            if auto_built_using {
+                let mut vendored = Vec::new();
+                for library in vendored_c_libraries(&locked, &installed_source_versions()) {
+                    match library {
+                        Ok(built_using) => vendored.push(built_using),
+                        Err(library) => listener.warning(format!("{library} is vendored, but left out of Built-Using, because no Debian source package of that version is installed")),
+                    }
+                }
+                package_deb.built_using = replace_auto(package_deb.built_using.as_deref().unwrap_or_default(), &vendored);
+            }
+            if package_deb.static_built_using.is_some() {
+                package_deb.static_built_using = replace_auto("$auto", &static_built_using(&locked));
+            }
+            if package_deb.dependency_copyright || package_deb.license_texts || package_deb.license_policy.is_some() || package_deb.sbom.is_some() {
+                // build scripts and proc macros aren't linked into the binaries
+                let linked = locked.into_iter()
+                    .filter(|p| linked_packages.iter().any(|(name, version)| *name == p.name && *version == p.version))
+                    .collect::<Vec<_>>();
+                let normal_dependencies = names_of(&manifest.dependencies);
+                package_deb.dependency_licenses = licenses::dependency_licenses(&other_packages, &linked);
+                if let Some(sbom) = &mut package_deb.sbom {
+                    let target_dependencies = manifest.target.values().flat_map(|t| names_of(&t.dependencies));
+                    let direct = normal_dependencies.iter().cloned().chain(target_dependencies).collect::<Vec<_>>();
+                    sbom.set_components(&linked, &package_deb.dependency_licenses, &direct);
+                }
+                if let Some(policy) = &package_deb.license_policy {
+                    let violations = licenses::policy_violations(&package_deb.dependency_licenses, policy);
+                    if !violations.is_empty() {
+                        let crates = violations.iter().map(|&(name, version, license)| {
+                            format!("{name} {version} ({})", if license.is_empty() { "no license" } else { license })
+                        }).collect::<Vec<_>>();
+                        return Err(CargoDebError::LicensePolicy(crates.join(", ")));
+                    }
+                }
+                if package_deb.dependency_copyright {
+                    for (id, crates) in licenses::missing_texts(&package_deb.dependency_licenses) {
+                        listener.warning(format!("The copyright file has no text of the {id} license of {}, because the crate has no LICENSE-* file", crates.join(", ")));
+                    }
+                }
+            }
+        }
 
         Ok((config, package_deb))
     }
@@ -337,11 +607,41 @@ impl Config {
         } else {
             self.implicit_assets(&package_deb.deb_name, package_deb.readme_rel_path.as_deref())?
         };
+        if let Some(dir) = &package_deb.debhelper_files_rel_path {
+            let (raw_assets, assets) = dh_install::read_debhelper_files(&self.path_in_package(dir), &self.package_manifest_dir, &package_deb.deb_name)?;
+            let mut debhelper_assets = self.explicit_assets(raw_assets)?;
+            for asset in debhelper_assets.unresolved.iter_mut().filter(|a| a.c.is_built()) {
+                asset.c.chmod = 0o755;
+            }
+            package_deb.assets.unresolved.append(&mut debhelper_assets.unresolved);
+            package_deb.assets.resolved.extend(assets);
+        }
+        self.add_example_binary_assets(package_deb)?;
         self.add_copyright_asset(package_deb)?;
+        self.add_license_text_assets(package_deb)?;
+        self.add_sbom_asset(package_deb);
         self.add_changelog_asset(package_deb)?;
+        self.add_news_asset(package_deb)?;
+        self.add_upstream_changelog_asset(package_deb)?;
+        self.add_doc_assets(package_deb);
+        self.add_example_assets(package_deb);
+        self.add_translation_assets(package_deb)?;
         self.add_systemd_assets(package_deb)?;
-
-        self.reset_deb_temp_directory(&package_deb)?;
+        self.add_init_script_asset(package_deb);
+        self.add_logrotate_asset(package_deb);
+        self.add_cron_assets(package_deb);
+        self.add_udev_rules_asset(package_deb);
+        self.add_dbus_assets(package_deb)?;
+        self.add_polkit_assets(package_deb)?;
+        self.add_apparmor_assets(package_deb);
+        self.add_selinux_assets(package_deb)?;
+        self.add_initramfs_hook_asset(package_deb);
+        self.add_dkms_assets(package_deb);
+        self.add_desktop_entry_assets(package_deb)?;
+        self.add_metainfo_asset(package_deb)?;
+        self.add_completion_assets(package_deb);
+
+        self.reset_deb_temp_directory(package_deb)?;
         Ok(())
     }
 
@@ -357,6 +657,13 @@ impl Config {
             if is_glob_pattern(&a.source_path) {
                 log::debug!("building entire workspace because of glob {}", a.source_path.display());
                 flags.push("--workspace".into());
+                // selecting examples would skip the default targets
+                if package_deb.assets.resolved.iter().map(|a| &a.c).chain(package_deb.assets.unresolved.iter().map(|a| &a.c)).any(|c| c.is_example && c.is_built()) {
+                    if self.build_targets.iter().any(|t| t.kind.iter().any(|k| k.ends_with("lib"))) {
+                        flags.push("--lib".into());
+                    }
+                    flags.extend(["--bins".into(), "--examples".into()]);
+                }
                 return;
             }
         }
@@ -372,7 +679,7 @@ impl Config {
                 log::debug!("building workspace because {} is from another package", source_path.unwrap_or(&asset_target.target_path).display());
                 same_package = false;
             }
-            if asset_target.is_dynamic_library() || source_path.map_or(false, is_dynamic_library_filename) {
+            if asset_target.is_dynamic_library() || source_path.is_some_and(is_dynamic_library_filename) {
                 log::debug!("building libs for {}", source_path.unwrap_or(&asset_target.target_path).display());
                 build_libs = true;
             } else if asset_target.is_executable() {
@@ -418,7 +725,7 @@ impl Config {
     }
 
     /// Generates the copyright file from the license file and adds that to the tar archive.
-    fn generate_copyright_asset(&self, package_deb: &PackageConfig) -> CDResult<(PathBuf, Vec<u8>)> {
+    pub(crate) fn generate_copyright_asset(&self, package_deb: &PackageConfig) -> CDResult<(PathBuf, Vec<u8>)> {
         let mut copyright: Vec<u8> = Vec::new();
         let source_path;
         if let Some(path) = &package_deb.license_file_rel_path {
@@ -443,29 +750,200 @@ impl Config {
             source_path = "Cargo.toml".into();
             package_deb.append_copyright_metadata(&mut copyright)?;
         }
+        // without a license file, the copyright file would have no license texts
+        let own_license = package_deb.license.as_deref().filter(|_| package_deb.license_file_rel_path.is_none());
+        let crates = if package_deb.dependency_copyright { &package_deb.dependency_licenses[..] } else { &[] };
+        if own_license.is_some() || package_deb.dependency_copyright {
+            licenses::append_dep5_paragraphs(&mut copyright, own_license, crates)?;
+        }
 
         Ok((source_path, copyright))
     }
 
+    fn add_license_text_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        if !package_deb.license_texts {
+            return Ok(());
+        }
+        let licenses_dir = Path::new("usr/share/doc").join(&package_deb.deb_name).join("licenses");
+        let assets = licenses::license_file_assets(&package_deb.dependency_licenses, &licenses_dir)?;
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    fn add_sbom_asset(&self, package_deb: &mut PackageConfig) {
+        let Some(sbom) = package_deb.sbom.as_ref().filter(|s| s.install) else { return };
+        let target_path = Path::new("usr/share/doc").join(&package_deb.deb_name).join(sbom.file_name());
+        let asset = Asset::new(AssetSource::Data(sbom.generate(package_deb)), target_path, 0o644, IsBuilt::No, false)
+            .processed("generated", PathBuf::from("Cargo.lock"));
+        package_deb.assets.resolved.push(asset);
+    }
+
     fn add_changelog_asset(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
-        if package_deb.changelog.is_some() {
-            if let Some((source_path, changelog_file)) = self.generate_changelog_asset(package_deb)? {
-                log::debug!("added changelog via {}", source_path.display());
-                package_deb.assets.resolved.push(Asset::new(
-                    AssetSource::Data(changelog_file),
-                    Path::new("usr/share/doc").join(&package_deb.deb_name).join("changelog.Debian.gz"),
-                    0o644,
-                    IsBuilt::No,
-                    false,
-                ).processed("generated", source_path));
+        if let Some((source_path, changelog_file)) = self.generate_changelog_asset(package_deb)? {
+            log::debug!("added changelog via {}", source_path.as_deref().unwrap_or(Path::new("git")).display());
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Data(changelog_file),
+                Path::new("usr/share/doc").join(&package_deb.deb_name).join("changelog.Debian.gz"),
+                0o644,
+                IsBuilt::No,
+                false,
+            ).processed("generated", source_path));
+        }
+        Ok(())
+    }
+
+    /// `NEWS.Debian.gz`, which `dh_installchangelogs` would install from `debian/NEWS`
+    fn add_news_asset(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        if let Some(path) = &package_deb.news {
+            let source_path = self.path_in_package(path);
+            let news = read_news(&source_path)?;
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Data(gzipped(news.as_bytes())?),
+                Path::new("usr/share/doc").join(&package_deb.deb_name).join("NEWS.Debian.gz"),
+                0o644,
+                IsBuilt::No,
+                false,
+            ).processed("generated", source_path));
+        }
+        Ok(())
+    }
+
+    /// The `docs` in `/usr/share/doc/<package>/`.
+    /// Like `dh_installdocs`, directories are copied with their contents
+    fn add_doc_assets(&self, package_deb: &mut PackageConfig) {
+        let sources = match &package_deb.docs {
+            Some(DocsEntry::Auto(true)) => package_deb.readme_rel_path.iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .chain(UPSTREAM_DOC_FILES.iter().map(|&name| name.to_owned()))
+                .filter(|name| is_path_file(&self.path_in_package(name)))
+                .collect(),
+            Some(DocsEntry::Files(files)) => files.clone(),
+            Some(DocsEntry::Auto(false)) | None => return,
+        };
+        let doc_dir = Path::new("usr/share/doc").join(&package_deb.deb_name);
+        for source in sources {
+            let source = source.trim_end_matches('/');
+            let source_path = self.path_in_package(source);
+            let Some(file_name) = Path::new(source).file_name() else { continue };
+            let target_path = doc_dir.join(file_name);
+            if package_deb.assets.resolved.iter().map(|a| &a.c).chain(package_deb.assets.unresolved.iter().map(|a| &a.c)).any(|c| c.target_path == target_path) {
+                log::debug!("{} is already in the assets", target_path.display());
+                continue;
+            }
+            let asset = if is_glob_pattern(&source_path) {
+                UnresolvedAsset::new(source_path, doc_dir.clone(), 0o644, IsBuilt::No, false)
+            } else if source_path.is_dir() {
+                UnresolvedAsset::new(source_path.join("**/*"), target_path, 0o644, IsBuilt::No, false)
+            } else {
+                UnresolvedAsset::new(source_path, target_path, 0o644, IsBuilt::No, false)
+            };
+            package_deb.assets.unresolved.push(asset);
+        }
+    }
+
+    /// Assets of `target/release/examples/<name>`, which makes them built with `--example`
+    fn add_example_binary_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let raw_assets = package_deb.example_binaries.iter().map(|entry| {
+            let name = entry.name();
+            if !self.build_targets.iter().any(|t| t.name == name && t.kind.iter().any(|k| k == "example")) {
+                return Err(CargoDebError::InvalidField("example-binaries", name.into(), "the package has no example with this name"));
+            }
+            Ok(RawAsset {
+                source_path: Path::new("target/release/examples").join(format!("{name}{EXE_SUFFIX}")),
+                target_path: PathBuf::from(entry.dest().trim_start_matches('/')),
+                chmod: 0o755,
+                separate_debug_symbols: None,
+            })
+        }).collect::<CDResult<Vec<_>>>()?;
+        if !raw_assets.is_empty() {
+            let mut assets = self.explicit_assets(raw_assets)?;
+            package_deb.assets.unresolved.append(&mut assets.unresolved);
+        }
+        Ok(())
+    }
+
+    /// Compiles `.po` files, so that the package doesn't need to build them in `build.rs`
+    fn add_translation_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let Some(config) = &package_deb.translations else { return Ok(()) };
+        let dir = self.path_in_package(config.dir.as_deref().unwrap_or("po"));
+        let domain = config.domain.as_deref().unwrap_or(&package_deb.name);
+        let catalogs = translations::catalogs(&dir, config.languages.as_deref())?;
+        if catalogs.is_empty() {
+            return Err(CargoDebError::InvalidField("translations", dir.display().to_string(), "the dir has no <language>.po or .mo files"));
+        }
+        if let Some(missing) = config.languages.iter().flatten().find(|l| !catalogs.contains_key(*l)) {
+            return Err(CargoDebError::InvalidField("translations languages", missing.clone(), "the dir has no catalog for this language"));
+        }
+        let assets = catalogs.iter().map(|(language, path)| {
+            log::debug!("adding translation {}", path.display());
+            let target_path = translations::target_path(language, domain);
+            Ok(if path.extension().is_some_and(|e| e == "mo") {
+                Asset::new(AssetSource::Path(path.clone()), target_path, 0o644, IsBuilt::No, false)
+            } else {
+                Asset::new(AssetSource::Data(translations::msgfmt(path)?), target_path, 0o644, IsBuilt::No, false).processed("msgfmt", path.clone())
+            })
+        }).collect::<CDResult<Vec<_>>>()?;
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    /// The `examples/` directories, and sources of `[[example]]` targets outside of them
+    fn add_example_assets(&self, package_deb: &mut PackageConfig) {
+        if !package_deb.examples {
+            return;
+        }
+        let target_dir = Path::new("usr/share/doc").join(&package_deb.deb_name).join("examples");
+        let dirs = EXAMPLE_DIRS.iter().map(|dir| self.path_in_package(dir)).filter(|dir| dir.is_dir()).collect::<Vec<_>>();
+        for dir in &dirs {
+            log::debug!("adding examples from {}", dir.display());
+            package_deb.assets.unresolved.push(UnresolvedAsset::new(dir.join("**/*"), target_dir.clone(), 0o644, IsBuilt::No, false));
+        }
+        for target in self.build_targets.iter().filter(|t| t.kind.iter().any(|k| k == "example")) {
+            if !target.src_path.starts_with(&self.package_manifest_dir) || dirs.iter().any(|dir| target.src_path.starts_with(dir)) {
+                continue;
             }
+            let Some(file_name) = target.src_path.file_name() else { continue };
+            package_deb.assets.resolved.push(Asset::new(AssetSource::Path(target.src_path.clone()), target_dir.join(file_name), 0o644, IsBuilt::No, false));
         }
+    }
+
+    /// `changelog.gz`, or `changelog.html.gz` for HTML, as required by Debian Policy 12.7
+    fn add_upstream_changelog_asset(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let source_path = match package_deb.upstream_changelog.as_deref() {
+            Some("") => return Ok(()),
+            Some(path) => self.path_in_package(path),
+            None => {
+                let debian_changelog = package_deb.changelog.as_ref().filter(|path| !is_markdown(Path::new(path))).map(|path| self.path_in_package(path));
+                let found = UPSTREAM_CHANGELOG_FILES.iter()
+                    .map(|name| self.path_in_package(name))
+                    .find(|path| path.is_file() && Some(path) != debian_changelog.as_ref());
+                let Some(path) = found else { return Ok(()) };
+                path
+            },
+        };
+        let is_html = source_path.extension().is_some_and(|e| e == "html" || e == "htm");
+        let target_path = Path::new("usr/share/doc").join(&package_deb.deb_name).join(if is_html { "changelog.html.gz" } else { "changelog.gz" });
+        if package_deb.assets.resolved.iter().map(|a| &a.c).chain(package_deb.assets.unresolved.iter().map(|a| &a.c)).any(|c| c.target_path == target_path) {
+            log::debug!("{} is already in the assets", target_path.display());
+            return Ok(());
+        }
+        let changelog = fs::read(&source_path)
+            .and_then(|content| if source_path.extension().is_some_and(|e| e == "gz") { Ok(content) } else { gzipped(&content) })
+            .map_err(|e| CargoDebError::IoFile("unable to read upstream changelog file", e, source_path.clone()))?;
+        log::debug!("added upstream changelog via {}", source_path.display());
+        package_deb.assets.resolved.push(Asset::new(
+            AssetSource::Data(changelog),
+            target_path,
+            0o644,
+            IsBuilt::No,
+            false,
+        ).processed("compressed", source_path));
         Ok(())
     }
 
     /// Generates compressed changelog file
-    fn generate_changelog_asset(&self, package_deb: &PackageConfig) -> CDResult<Option<(PathBuf, Vec<u8>)>> {
-        if let Some(ref path) = package_deb.changelog {
+    fn generate_changelog_asset(&self, package_deb: &PackageConfig) -> CDResult<Option<(Option<PathBuf>, Vec<u8>)>> {
+        if let Some(path) = package_deb.changelog.as_ref().filter(|path| !is_markdown(Path::new(path))) {
             let source_path = self.path_in_package(path);
             let changelog = fs::read(&source_path)
                 .and_then(|content| {
@@ -477,7 +955,10 @@ impl Config {
                     gzipped(&content)
                 })
                 .map_err(|e| CargoDebError::IoFile("unable to read changelog file", e, source_path.clone()))?;
-            Ok(Some((source_path, changelog)))
+            Ok(Some((Some(source_path), changelog)))
+        } else if let Some(changelog) = read_debian_changelog(self, package_deb)? {
+            let source_path = package_deb.changelog.as_ref().map(|path| self.path_in_package(path));
+            Ok(Some((source_path, gzipped(changelog.as_bytes())?)))
         } else {
             Ok(None)
         }
@@ -486,24 +967,16 @@ impl Config {
     fn add_systemd_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
         if let Some(ref config_vec) = package_deb.systemd_units {
             for config in config_vec {
-                let units_dir_option = config.unit_scripts.as_ref()
-                    .or(package_deb.maintainer_scripts_rel_path.as_ref());
-                if let Some(unit_dir) = units_dir_option {
-                    let search_path = self.path_in_package(unit_dir);
-                    let package = &package_deb.name;
-                    let unit_name = config.unit_name.as_deref();
-
-                    let units = dh_installsystemd::find_units(&search_path, package, unit_name);
-
-                    for (source, target) in units {
-                        package_deb.assets.resolved.push(Asset::new(
-                            AssetSource::from_path(source, package_deb.preserve_symlinks), // should this even support symlinks at all?
-                            target.path,
-                            target.mode,
-                            IsBuilt::No,
-                            false,
-                        ));
-                    }
+                let units = self.systemd_unit_files(package_deb, config)?;
+
+                for (source, target) in units {
+                    package_deb.assets.resolved.push(Asset::new(
+                        AssetSource::from_path(source, package_deb.preserve_symlinks), // should this even support symlinks at all?
+                        target.path,
+                        target.mode,
+                        IsBuilt::No,
+                        false,
+                    ));
                 }
             }
         } else {
@@ -512,6 +985,243 @@ impl Config {
         Ok(())
     }
 
+    /// `/etc/init.d/<package>`, which `dh_installinit` would install from `debian/<package>.init`
+    fn add_init_script_asset(&self, package_deb: &mut PackageConfig) {
+        if let Some(path) = &package_deb.init_script {
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Path(self.path_in_package(path)),
+                Path::new("etc/init.d").join(&package_deb.deb_name),
+                0o755,
+                IsBuilt::No,
+                false,
+            ));
+        }
+    }
+
+    /// `/etc/logrotate.d/<package>`, which `dh_installlogrotate` would install from `debian/<package>.logrotate`
+    fn add_logrotate_asset(&self, package_deb: &mut PackageConfig) {
+        if let Some(path) = &package_deb.logrotate {
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Path(self.path_in_package(path)),
+                Path::new(dh_installlogrotate::LOGROTATE_DIR).join(&package_deb.deb_name),
+                0o644,
+                IsBuilt::No,
+                false,
+            ));
+        }
+    }
+
+    /// Activation files keep their names, because `dbus-daemon` looks them up by the bus name
+    fn add_dbus_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let dbus = &package_deb.dbus;
+        let mut assets = Vec::new();
+        for (dir, paths) in [(dh_installdbus::SYSTEM_SERVICES_DIR, &dbus.system_services), (dh_installdbus::SESSION_SERVICES_DIR, &dbus.session_services)] {
+            for path in paths.iter().flatten() {
+                let file_name = Path::new(path).file_name().ok_or("D-Bus service paths must be files")?;
+                assets.push(Asset::new(AssetSource::Path(self.path_in_package(path)), Path::new(dir).join(file_name), 0o644, IsBuilt::No, false));
+            }
+        }
+        if let Some(path) = &dbus.policy {
+            let target_path = Path::new(dh_installdbus::SYSTEM_POLICY_DIR).join(format!("{}.conf", package_deb.deb_name));
+            assets.push(Asset::new(AssetSource::Path(self.path_in_package(path)), target_path, 0o644, IsBuilt::No, false));
+        }
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    /// Profiles keep their names, which are conventionally the paths of the programs with dots, like `usr.bin.example`
+    fn add_apparmor_assets(&self, package_deb: &mut PackageConfig) {
+        let assets = package_deb.apparmor_profiles.iter().map(|path| Asset::new(
+            AssetSource::Path(self.path_in_package(path)),
+            Path::new(dh_apparmor::APPARMOR_DIR).join(Path::new(path).file_name().unwrap_or_default()),
+            0o644,
+            IsBuilt::No,
+            false,
+        )).collect::<Vec<_>>();
+        package_deb.assets.resolved.extend(assets);
+    }
+
+    /// `/usr/share/initramfs-tools/hooks/<package>`, which `dh_installinitramfs` would install from `debian/<package>.initramfs-hook`
+    fn add_initramfs_hook_asset(&self, package_deb: &mut PackageConfig) {
+        if let Some(path) = &package_deb.initramfs_hook {
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Path(self.path_in_package(path)),
+                Path::new(INITRAMFS_TOOLS_DIR).join("hooks").join(&package_deb.deb_name),
+                0o755,
+                IsBuilt::No,
+                false,
+            ));
+        }
+    }
+
+    /// Completions are named after the command in the name of their file, or the package
+    fn add_completion_assets(&self, package_deb: &mut PackageConfig) {
+        let assets = package_deb.completions.files.iter().map(|(shell, path)| {
+            let command = package_deb.completions.command(*shell, path, &package_deb.deb_name);
+            Asset::new(AssetSource::Path(self.path_in_package(path)), shell.target_path(command), 0o644, IsBuilt::No, false)
+        }).collect::<Vec<_>>();
+        package_deb.assets.resolved.extend(assets);
+    }
+
+    /// The generated metainfo describes a desktop application if the package has a desktop entry, and otherwise the console application of its binaries
+    fn add_metainfo_asset(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let asset = match &package_deb.metainfo {
+            None => return Ok(()),
+            Some(MetainfoEntry::Path(path)) => {
+                let file_name = Path::new(path).file_name().ok_or("metainfo must be a path to a file")?;
+                Asset::new(AssetSource::Path(self.path_in_package(path)), Path::new(dh_appstream::METAINFO_DIR).join(file_name), 0o644, IsBuilt::No, false)
+            },
+            Some(MetainfoEntry::Generate(generated)) => {
+                // unresolved assets may have only the target directory
+                let unresolved = package_deb.assets.unresolved.iter().map(|a| if a.c.target_path.to_string_lossy().ends_with('/') {
+                    a.c.target_path.join(a.source_path.file_name().unwrap_or_default())
+                } else {
+                    a.c.target_path.clone()
+                }).collect::<Vec<_>>();
+                let mut desktop_id = None;
+                let mut binaries = Vec::new();
+                for path in package_deb.assets.resolved.iter().map(|a| &a.c.target_path).chain(&unresolved) {
+                    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name().and_then(|f| f.to_str())) else { continue };
+                    if dir == Path::new(dh_icons::APPLICATIONS_DIR) && file_name.ends_with(".desktop") {
+                        desktop_id.get_or_insert(file_name);
+                    } else if dir == Path::new("usr/bin") {
+                        binaries.push(file_name);
+                    }
+                }
+                let component = dh_appstream::Component {
+                    id: &generated.id,
+                    metadata_license: generated.metadata_license.as_deref().unwrap_or("CC0-1.0"),
+                    project_license: package_deb.license.as_deref(),
+                    name: &package_deb.name,
+                    summary: &package_deb.description,
+                    description: dh_appstream::description_paragraphs(&package_deb.extended_description_lines(self)?),
+                    homepage: package_deb.homepage.as_deref(),
+                    desktop_id,
+                    binaries,
+                    version: package_deb.upstream_version(),
+                };
+                let target_path = Path::new(dh_appstream::METAINFO_DIR).join(format!("{}.metainfo.xml", generated.id));
+                Asset::new(AssetSource::Data(component.to_xml().into_bytes()), target_path, 0o644, IsBuilt::No, false)
+            },
+        };
+        package_deb.assets.resolved.push(asset);
+        Ok(())
+    }
+
+    /// Desktop files keep their names, and icons are installed in the hicolor theme with the name that the desktop file uses
+    fn add_desktop_entry_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let mut assets = Vec::new();
+        for entry in &package_deb.desktop_entries {
+            let desktop_path = self.path_in_package(&entry.file);
+            let file_name = desktop_path.file_name().and_then(|f| f.to_str()).filter(|f| f.ends_with(".desktop"))
+                .ok_or_else(|| CargoDebError::InvalidField("desktop-entries", entry.file.clone(), "the file must end with .desktop"))?;
+            let icons = entry.icons.as_deref().unwrap_or_default();
+            if !icons.is_empty() {
+                let desktop_file = read_file_to_bytes(&desktop_path).map_err(|e| CargoDebError::IoFile("unable to read desktop file", e, desktop_path.clone()))?;
+                let icon_name = match &entry.icon_name {
+                    Some(name) => name.clone(),
+                    None => dh_icons::desktop_icon_name(&String::from_utf8_lossy(&desktop_file))
+                        .ok_or_else(|| CargoDebError::InvalidField("desktop-entries", entry.file.clone(), "the desktop file has no Icon= name, so set icon-name"))?
+                        .to_owned(),
+                };
+                for icon in icons {
+                    let icon_path = self.path_in_package(icon);
+                    let data = read_file_to_bytes(&icon_path).map_err(|e| CargoDebError::IoFile("unable to read icon", e, icon_path.clone()))?;
+                    let target_path = dh_icons::icon_target_path(&icon_name, &icon_path, &data)
+                        .map_err(|msg| CargoDebError::InvalidField("desktop-entries icon", icon.clone(), msg))?;
+                    assets.push(Asset::new(AssetSource::Path(icon_path), target_path, 0o644, IsBuilt::No, false));
+                }
+            }
+            let target_path = Path::new(dh_icons::APPLICATIONS_DIR).join(file_name);
+            assets.push(Asset::new(AssetSource::Path(desktop_path), target_path, 0o644, IsBuilt::No, false));
+        }
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    /// The module sources keep their directory structure. Their own `dkms.conf` is used instead of the generated one.
+    fn add_dkms_assets(&self, package_deb: &mut PackageConfig) {
+        let Some(module) = package_deb.dkms_module() else { return };
+        let source_dir = self.path_in_package(&module.source);
+        package_deb.assets.unresolved.push(UnresolvedAsset::new(source_dir.join("**/*"), module.source_dir(), 0o644, IsBuilt::No, false));
+        if !is_path_file(&source_dir.join("dkms.conf")) {
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Data(module.dkms_conf().into_bytes()),
+                module.source_dir().join("dkms.conf"),
+                0o644,
+                IsBuilt::No,
+                false,
+            ));
+        }
+    }
+
+    /// Policy modules compiled to `.pp` files
+    fn add_selinux_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let dir = dh_selinux::modules_dir(&package_deb.deb_name);
+        let mut assets = Vec::with_capacity(package_deb.selinux_modules.len());
+        for path in &package_deb.selinux_modules {
+            if Path::new(path).extension().map_or(true, |ext| ext != "pp") {
+                return Err(CargoDebError::InvalidField("selinux-modules", path.clone(), "SELinux modules must be compiled .pp files"));
+            }
+            let file_name = Path::new(path).file_name().unwrap_or_default();
+            assets.push(Asset::new(AssetSource::Path(self.path_in_package(path)), dir.join(file_name), 0o644, IsBuilt::No, false));
+        }
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    /// The directory depends on the extension, and the files are only writable by root
+    fn add_polkit_assets(&self, package_deb: &mut PackageConfig) -> CDResult<()> {
+        let mut assets = Vec::with_capacity(package_deb.polkit.len());
+        for path in &package_deb.polkit {
+            let dir = dh_installpolkit::target_dir(path).ok_or_else(|| CargoDebError::InvalidField("polkit", path.clone(), "polkit files must end with .policy or .rules"))?;
+            let file_name = Path::new(path).file_name().unwrap_or_default();
+            assets.push(Asset::new(AssetSource::Path(self.path_in_package(path)), Path::new(dir).join(file_name), 0o644, IsBuilt::No, false));
+        }
+        package_deb.assets.resolved.extend(assets);
+        Ok(())
+    }
+
+    /// `/usr/lib/udev/rules.d/60-<package>.rules`, which `dh_installudev` would install from `debian/<package>.udev`
+    fn add_udev_rules_asset(&self, package_deb: &mut PackageConfig) {
+        if let Some(path) = &package_deb.udev_rules {
+            package_deb.assets.resolved.push(Asset::new(
+                AssetSource::Path(self.path_in_package(path)),
+                Path::new(dh_installudev::UDEV_RULES_DIR).join(format!("{}-{}.rules", dh_installudev::DEFAULT_PRIORITY, package_deb.deb_name)),
+                0o644,
+                IsBuilt::No,
+                false,
+            ));
+        }
+    }
+
+    /// Like `dh_installcron`, crontabs in `/etc/cron.d` aren't executable, and the scripts run by `run-parts` are
+    fn add_cron_assets(&self, package_deb: &mut PackageConfig) {
+        let name = cron_file_name(&package_deb.deb_name);
+        let assets = package_deb.cron.files().map(|(suffix, path)| Asset::new(
+            AssetSource::Path(self.path_in_package(path)),
+            Path::new(&format!("etc/cron.{suffix}")).join(&name),
+            if suffix == "d" { 0o644 } else { 0o755 },
+            IsBuilt::No,
+            false,
+        )).collect::<Vec<_>>();
+        package_deb.assets.resolved.extend(assets);
+    }
+
+    /// Unit files of one of the `systemd-units` entries, either listed in `unit-files`, or found in the `unit-scripts` directory
+    pub(crate) fn systemd_unit_files(&self, package_deb: &PackageConfig, config: &SystemdUnitsConfig) -> CDResult<dh_installsystemd::PackageUnitFiles> {
+        if let Some(unit_files) = &config.unit_files {
+            let paths = unit_files.iter().map(|p| self.path_in_package(p)).collect::<Vec<_>>();
+            return dh_installsystemd::explicit_units(&paths);
+        }
+        let units_dir_option = config.unit_scripts.as_ref()
+            .or(package_deb.maintainer_scripts_rel_path.as_ref());
+        Ok(match units_dir_option {
+            Some(unit_dir) => dh_installsystemd::find_units(&self.path_in_package(unit_dir), &package_deb.name, config.unit_name.as_deref()),
+            None => Default::default(),
+        })
+    }
+
     pub(crate) fn path_in_build<P: AsRef<Path>>(&self, rel_path: P) -> PathBuf {
         self.path_in_build_(rel_path.as_ref())
     }
@@ -539,12 +1249,15 @@ impl Config {
 
     /// Save final .deb here
     pub(crate) fn deb_output_path(&self, package_deb: &PackageConfig) -> PathBuf {
-        let filename = format!("{}_{}_{}.deb", package_deb.deb_name, package_deb.deb_version, package_deb.architecture);
+        let filename = package_deb.output_filename(&package_deb.deb_name, package_deb.version_without_epoch(), package_deb.file_extension());
 
         if let Some(ref path_str) = self.deb_output_path {
             let path = Path::new(path_str);
             if path_str.ends_with('/') || path.is_dir() {
                 path.join(filename)
+            } else if package_deb.dbgsym.is_some() || package_deb.is_keyring {
+                // the output path is for the main package
+                path.with_file_name(filename)
             } else {
                 path.to_owned()
             }
@@ -553,6 +1266,41 @@ impl Config {
         }
     }
 
+    /// One more than the highest revision of the packages of the same version in the output directory
+    fn next_revision(&self, package_deb: &PackageConfig) -> CDResult<u32> {
+        let output_path = self.deb_output_path(package_deb);
+        let dir = output_path.parent().ok_or("bad output path")?;
+        // the revision is between the prefix and a non-digit
+        let filename = package_deb.output_filename(&package_deb.deb_name, &format!("{}-\0", package_deb.upstream_version()), package_deb.file_extension());
+        let prefix = filename.split('\0').next().unwrap_or_default();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(1),
+            Err(e) => return Err(CargoDebError::IoFile("unable to read the output directory", e, dir.into())),
+        };
+        let highest = entries.filter_map(|e| {
+            let name = e.ok()?.file_name().into_string().ok()?;
+            let rest = name.strip_prefix(prefix)?;
+            rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())].parse::<u32>().ok()
+        }).max();
+        Ok(highest.map_or(1, |revision| revision + 1))
+    }
+
+    /// `X-Cargo-*` fields, for finding out how an installed package has been built
+    pub(crate) fn provenance_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(rustc_version) = rustc_version() {
+            fields.push(("X-Cargo-Rustc-Version", rustc_version));
+        }
+        fields.push(("X-Cargo-Profile", self.build_profile_override.clone().unwrap_or_else(|| "release".into())));
+        let features = self.default_features.then_some("default").into_iter().chain(self.features.iter().map(String::as_str)).collect::<Vec<_>>();
+        if !features.is_empty() {
+            fields.push(("X-Cargo-Features", features.join(", ")));
+        }
+        fields.push(("X-Cargo-Deb-Version", env!("CARGO_PKG_VERSION").into()));
+        fields
+    }
+
     pub(crate) fn default_deb_output_dir(&self) -> PathBuf {
         self.target_dir.join("debian")
     }
@@ -562,15 +1310,31 @@ impl Config {
     }
 
     /// Creates empty (removes files if needed) target/debian/foo directory so that we can start fresh.
-    fn reset_deb_temp_directory(&self, package_deb: &PackageConfig) -> io::Result<()> {
+    pub(crate) fn reset_deb_temp_directory(&self, package_deb: &PackageConfig) -> io::Result<()> {
         let deb_temp_dir = self.deb_temp_dir(package_deb);
         let _ = fs::remove_dir(&deb_temp_dir);
-        // Delete previous .deb from target/debian, but only other versions of the same package
-        let mut deb_dir = self.default_deb_output_dir();
-        deb_dir.push(format!("{}_*_{}.deb", package_deb.deb_name, package_deb.architecture));
-        if let Ok(old_files) = glob::glob(deb_dir.to_str().ok_or(io::ErrorKind::InvalidInput)?) {
-            for old_file in old_files.flatten() {
-                let _ = fs::remove_file(old_file);
+        // Delete previous .deb, .changes, .buildinfo and checksums from target/debian, but only other versions of the same package
+        let dbgsym_name = format!("{}-dbgsym", package_deb.deb_name);
+        let mut patterns = vec![
+            format!("{}_*_{}.changes", package_deb.deb_name, package_deb.architecture),
+            format!("{}_*_{}.buildinfo", package_deb.deb_name, package_deb.architecture),
+        ];
+        // a template without the name could match other packages
+        if package_deb.output_template.as_deref().map_or(true, |t| t.contains("{name}")) {
+            for (deb_name, ext) in [(&package_deb.deb_name, "deb"), (&dbgsym_name, "*deb"), (&package_deb.deb_name, "deb.sha*"), (&dbgsym_name, "*deb.sha*")] {
+                patterns.push(package_deb.output_filename(deb_name, "*", ext));
+            }
+        } else {
+            // the dbgsym package has the default name then
+            patterns.push(format!("{dbgsym_name}_*_{}.*deb", package_deb.architecture));
+            patterns.push(format!("{dbgsym_name}_*_{}.*deb.sha*", package_deb.architecture));
+        }
+        for pattern in patterns {
+            let deb_dir = self.default_deb_output_dir().join(pattern);
+            if let Ok(old_files) = glob::glob(deb_dir.to_str().ok_or(io::ErrorKind::InvalidInput)?) {
+                for old_file in old_files.flatten() {
+                    let _ = fs::remove_file(old_file);
+                }
             }
         }
         fs::create_dir_all(deb_temp_dir)
@@ -591,23 +1355,59 @@ impl PackageConfig {
             }
         }
 
-        let has_maintainer_override = overrides.maintainer.is_some();
-        let deb_version = overrides.deb_version.unwrap_or_else(|| manifest_version_string(cargo_package, overrides.deb_revision.or(deb.revision.take()).as_deref()).into_owned());
+        if deb.essential == Some(true) {
+            listener.warning("essential = true makes dpkg refuse to remove the package, and other packages may rely on it without declaring a dependency. Use it only for packages in the base system".into());
+        }
+        if deb.protected == Some(true) {
+            listener.warning("protected = true makes apt and dpkg refuse to remove the package without --allow-remove-essential. Use it only for packages needed to boot the system".into());
+        }
+        if deb.important == Some(true) && deb.essential != Some(true) {
+            listener.warning("important = true makes apt ask for confirmation before removing the package".into());
+        }
+
+        let invalid_section = deb.section.as_deref().and_then(|section| Some(("Section", section, check_section(section).err()?)));
+        let invalid_priority = deb.priority.as_deref().and_then(|priority| Some(("Priority", priority, check_priority(priority).err()?)));
+        for (field, value, why) in invalid_section.into_iter().chain(invalid_priority) {
+            if overrides.strict {
+                return Err(CargoDebError::InvalidField(field, value.into(), why));
+            }
+            listener.warning(format!("{field} '{value}' is invalid: {why}"));
+        }
+
+        // like in other Debian tools, when Cargo.toml doesn't say who the maintainer is
+        let env_maintainer = if overrides.maintainer.is_none() && deb.maintainer.is_none() && cargo_package.authors().is_empty() {
+            debian_maintainer(std::env::var("DEBFULLNAME").ok(), std::env::var("DEBEMAIL").ok())
+        } else {
+            None
+        };
+        let has_maintainer_override = overrides.maintainer.is_some() || env_maintainer.is_some();
+        let revision = overrides.deb_revision.or(deb.revision.take());
+        let has_revision = overrides.deb_version.as_deref().map_or(revision.as_deref() != Some(""), |v| v.contains('-'));
+        let deb_version = overrides.deb_version.unwrap_or_else(|| manifest_version_string(cargo_package, revision.as_deref(), deb.epoch, deb.prerelease_tilde.unwrap_or(true), deb.version.as_deref()).into_owned());
         if let Err(why) = check_debian_version(&deb_version) {
             return Err(CargoDebError::InvalidVersion(why, deb_version));
         }
+        let deb_name = deb.name.take().unwrap_or_else(|| debian_package_name(&cargo_package.name));
+        let mut asset_scripts = AssetScripts::default();
+        let raw_assets = deb.assets.take().map(|assets| Self::parse_assets(assets, &deb_name, &mut asset_scripts, listener)).transpose()?;
+        let mut sbom = deb.sbom.as_ref().map(Sbom::new);
+        if overrides.sbom {
+            sbom.get_or_insert_with(|| Sbom::new(&SbomConfig { install: Some(false), ..SbomConfig::default() })).output = true;
+        }
         Ok(Self {
             deb_version,
+            has_revision,
             default_timestamp,
-            raw_assets: deb.assets.take().map(|assets| Self::parse_assets(assets, listener)).transpose()?,
+            raw_assets,
             name: cargo_package.name.clone(),
-            deb_name: deb.name.take().unwrap_or_else(|| debian_package_name(&cargo_package.name)),
+            cargo_version: cargo_package.version().to_owned(),
+            deb_name,
             license,
             license_file_rel_path,
             license_file_skip_lines,
-            maintainer: overrides.maintainer.or_else(|| deb.maintainer.take()).ok_or_then(|| {
+            maintainer: overrides.maintainer.or_else(|| deb.maintainer.take()).or(env_maintainer).ok_or_then(|| {
                 Ok(cargo_package.authors().first()
-                    .ok_or("The package must have a maintainer specified (--maintainer works too) or have the authors property")?.to_owned())
+                    .ok_or("The package must have a maintainer specified (--maintainer and DEBEMAIL work too) or have the authors property")?.to_owned())
             })?,
             copyright: match deb.copyright.take() {
                 ok @ Some(_) => ok,
@@ -622,6 +1422,11 @@ impl PackageConfig {
             homepage: cargo_package.homepage().map(From::from),
             documentation: cargo_package.documentation().map(From::from),
             repository: cargo_package.repository.take().map(|v| v.unwrap()),
+            vcs_git: deb.vcs_git.take(),
+            vcs_browser: deb.vcs_browser.take(),
+            origin: deb.origin.take(),
+            bugs: deb.bugs.take(),
+            tag: deb.tag.take().map(DependencyList::into_depends_string),
             description: cargo_package.description.take().map_or_else(|| {
                 listener.warning("description field is missing in Cargo.toml".to_owned());
                 format!("[generated from Rust crate {}]", cargo_package.name)
@@ -635,7 +1440,7 @@ impl PackageConfig {
                 ExtendedDescription::String(desc)
             } else if let Some(readme_rel_path) = cargo_package.readme().as_path() {
                 if readme_rel_path.extension().is_some_and(|ext| ext == "md" || ext == "markdown") {
-                    listener.info(format!("extended-description field missing. Using {}, but markdown may not render well.", readme_rel_path.display()));
+                    listener.info(format!("extended-description field missing. Using {}, converted from markdown.", readme_rel_path.display()));
                 }
                 ExtendedDescription::ReadmeFallback(readme_rel_path.into())
             } else {
@@ -645,33 +1450,318 @@ impl PackageConfig {
             wildcard_depends: deb.depends.take().map_or_else(|| "$auto".to_owned(), DependencyList::into_depends_string),
             resolved_depends: None,
             pre_depends: deb.pre_depends.take().map(DependencyList::into_depends_string),
+            build_depends: deb.build_depends.take().map(DependencyList::into_depends_string),
             recommends: deb.recommends.take().map(DependencyList::into_depends_string),
             suggests: deb.suggests.take().map(DependencyList::into_depends_string),
-            enhances: deb.enhances.take(),
-            conflicts: deb.conflicts.take(),
-            breaks: deb.breaks.take(),
-            replaces: deb.replaces.take(),
-            provides: deb.provides.take(),
+            enhances: deb.enhances.take().map(DependencyList::into_depends_string),
+            conflicts: deb.conflicts.take().map(DependencyList::into_depends_string),
+            breaks: deb.breaks.take().map(DependencyList::into_depends_string),
+            replaces: deb.replaces.take().map(DependencyList::into_depends_string),
+            provides: deb.provides.take().map(DependencyList::into_depends_string).map(|provides| check_provides(&provides).map(|()| provides)).transpose()?,
+            built_using: deb.built_using.take().map(DependencyList::into_depends_string),
+            static_built_using: deb.static_built_using.unwrap_or(false).then(String::new),
             section: deb.section.take(),
             priority: deb.priority.take().unwrap_or_else(|| "optional".to_owned()),
             architecture: debian_architecture_from_rust_triple(target.unwrap_or(crate::DEFAULT_TARGET)).to_owned(),
+            multiarch: deb.multiarch,
+            essential: deb.essential.unwrap_or(false),
+            protected: deb.protected.unwrap_or(false),
+            important: deb.important.unwrap_or(false),
+            provenance_fields: deb.provenance_fields.unwrap_or(false),
+            output_template: overrides.output_template.or(deb.output_template.take()),
             conf_files: deb.conf_files.take().unwrap_or_default(),
             assets: Assets::new(),
             triggers_file_rel_path: deb.triggers_file.take().map(PathBuf::from),
+            triggers: deb.triggers.take().unwrap_or_default(),
+            maintscript: deb.maintscript.take().unwrap_or_default().iter().map(MaintscriptEntry::helper_args).collect::<CDResult<_>>()?,
+            init_script: deb.init_script.take(),
+            logrotate: deb.logrotate.take(),
+            cron: deb.cron.take().unwrap_or_default(),
+            udev_rules: deb.udev_rules.take(),
+            dbus: deb.dbus.take().unwrap_or_default(),
+            polkit: deb.polkit.take().unwrap_or_default(),
+            apparmor_profiles: deb.apparmor_profiles.take().unwrap_or_default(),
+            selinux_modules: deb.selinux_modules.take().unwrap_or_default(),
+            initramfs_hook: deb.initramfs_hook.take(),
+            dkms: deb.dkms.take(),
+            desktop_entries: deb.desktop_entries.take().unwrap_or_default(),
+            metainfo: deb.metainfo.take(),
+            generate_man_pages: deb.generate_man_pages.unwrap_or(false),
+            completions: deb.completions.take().map(Completions::from).unwrap_or_default(),
+            docs: deb.docs.take(),
+            examples: deb.examples.unwrap_or(false),
+            example_binaries: deb.example_binaries.take().unwrap_or_default(),
+            translations: deb.translations.take(),
+            dependency_copyright: deb.dependency_copyright.unwrap_or(false),
+            license_texts: deb.license_texts.unwrap_or(false),
+            license_policy: deb.license_policy.take(),
+            sbom,
+            auditable: deb.auditable.unwrap_or(false),
+            sysroot: deb.sysroot.take().map(PathBuf::from),
+            dependency_licenses: Vec::new(),
+            ucf_files: asset_scripts.ucf_files,
+            alternatives: asset_scripts.alternatives,
+            capabilities: asset_scripts.capabilities,
+            stat_overrides: asset_scripts.stat_overrides,
             changelog: deb.changelog.take(),
+            git_changelog: deb.git_changelog.take(),
+            news: deb.news.take(),
+            upstream_changelog: deb.upstream_changelog.take(),
+            distribution: deb.distribution.take(),
+            urgency: deb.urgency.take(),
             maintainer_scripts_rel_path: deb.maintainer_scripts.take().map(PathBuf::from),
+            debhelper_files_rel_path: deb.debhelper_files.take().map(PathBuf::from),
             preserve_symlinks: deb.preserve_symlinks.unwrap_or(false),
             systemd_units: match deb.systemd_units.take() {
                 None => None,
                 Some(SystemUnitsSingleOrMultiple::Single(s)) => Some(vec![s]),
                 Some(SystemUnitsSingleOrMultiple::Multi(v)) => Some(v),
             },
+            upload_targets: deb.upload.take().unwrap_or_default(),
+            keyring: deb.keyring.take(),
+            is_keyring: false,
+            dbgsym: None,
+        })
+    }
+
+    /// Moves files from `/usr/lib/debug` to a new `<name>-dbgsym` package, like `dh_strip` does.
+    ///
+    /// Returns `None` if there are no debug symbols to move.
+    pub fn split_dbgsym(&mut self, format: DbgsymFormat) -> Option<PackageConfig> {
+        let (debug_assets, assets) = self.assets.resolved.drain(..)
+            .partition::<Vec<_>, _>(|a| a.c.target_path.starts_with("usr/lib/debug"));
+        self.assets.resolved = assets;
+        if debug_assets.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            resolved_depends: Some(format!("{} (= {})", self.deb_name, self.deb_version)),
+            section: Some("debug".into()),
+            multiarch: self.multiarch.filter(|&m| m == Multiarch::Same),
+            preserve_symlinks: self.preserve_symlinks,
+            dbgsym: Some(format),
+            ..self.derived_package(format!("{}-dbgsym", self.deb_name), format!("debug symbols for {}", self.deb_name), debug_assets)
         })
     }
 
-    fn parse_assets(assets: Vec<Vec<String>>, listener: &dyn Listener) -> CDResult<Vec<RawAsset>> {
+    /// A package with the repository's key in `/usr/share/keyrings`, and an apt source that uses it.
+    ///
+    /// Returns `None` if there's no `[package.metadata.deb.keyring]`.
+    pub(crate) fn keyring_package(&self, config: &Config) -> CDResult<Option<PackageConfig>> {
+        let Some(keyring) = &self.keyring else { return Ok(None) };
+        let deb_name = keyring.name.clone().unwrap_or_else(|| format!("{}-archive-keyring", self.deb_name));
+        let key_path = config.path_in_package(&keyring.key_file);
+        let key = fs::read(&key_path).map_err(|e| CargoDebError::IoFile("unable to read keyring key-file", e, key_path.clone()))?;
+        // apt supports armored keys only with the .asc extension
+        let key_extension = if key.starts_with(b"-----BEGIN PGP") { "asc" } else { "gpg" };
+        let key_target = format!("usr/share/keyrings/{deb_name}.{key_extension}");
+
+        let mut sources = format!("Types: deb\nURIs: {}\n", keyring.uri);
+        sources.push_str(&format!("Suites: {}\n", keyring.suites.as_ref().map_or_else(|| "stable".into(), |s| s.join(" "))));
+        sources.push_str(&format!("Components: {}\n", keyring.components.as_ref().map_or_else(|| "main".into(), |c| c.join(" "))));
+        if let Some(architectures) = &keyring.architectures {
+            sources.push_str(&format!("Architectures: {}\n", architectures.join(" ")));
+        }
+        sources.push_str(&format!("Signed-By: /{key_target}\n"));
+        let sources_target = format!("etc/apt/sources.list.d/{deb_name}.sources");
+
+        let assets = vec![
+            Asset::new(AssetSource::Data(key), key_target.into(), 0o644, IsBuilt::No, false).processed("keyring", key_path),
+            Asset::new(AssetSource::Data(sources.into_bytes()), sources_target.clone().into(), 0o644, IsBuilt::No, false),
+        ];
+        let mut package = Self {
+            extended_description: ExtendedDescription::String(format!("Installs the key that signs the apt repository at {}, and adds the repository to apt's sources.", keyring.uri)),
+            section: Some("misc".into()),
+            architecture: "all".into(),
+            multiarch: Some(Multiarch::Foreign),
+            conf_files: vec![format!("/{sources_target}")],
+            is_keyring: true,
+            ..self.derived_package(deb_name, format!("archive keyring for the {} repository", self.deb_name), assets)
+        };
+        config.add_copyright_asset(&mut package)?;
+        Ok(Some(package))
+    }
+
+    /// A `librust-<name>-dev` package with the sources of the crate, for building other crates with dh-cargo
+    pub(crate) fn librust_package(&self, config: &Config, sources: &CrateSources) -> CDResult<PackageConfig> {
+        let registry_dir = sources.registry_dir();
+        let mut assets = sources.files.iter().map(|(path, data, executable)| {
+            Asset::new(AssetSource::Data(data.clone()), registry_dir.join(path), if *executable { 0o755 } else { 0o644 }, IsBuilt::No, false)
+        }).collect::<Vec<_>>();
+        assets.push(Asset::new(AssetSource::Data(sources.cargo_checksum_json().into_bytes()), registry_dir.join(".cargo-checksum.json"), 0o644, IsBuilt::No, false));
+        let (depends, suggests, provides) = sources.relationships(&self.deb_version);
+
+        let mut package = Self {
+            license_file_rel_path: self.license_file_rel_path.clone(),
+            license_file_skip_lines: self.license_file_skip_lines,
+            changelog: self.changelog.clone(),
+            git_changelog: self.git_changelog,
+            extended_description: ExtendedDescription::String(format!("Source code for Debianized Rust crate \"{}\"", sources.name)),
+            resolved_depends: (!depends.is_empty()).then(|| depends.join(", ")),
+            suggests: (!suggests.is_empty()).then(|| suggests.join(", ")),
+            section: Some("rust".into()),
+            provides: Some(provides.join(", ")),
+            multiarch: Some(Multiarch::Same),
+            upload_targets: self.upload_targets.clone(),
+            ..self.derived_package(sources.deb_name(), format!("{} - Rust source code", self.description), assets)
+        };
+        config.add_copyright_asset(&mut package)?;
+        config.add_changelog_asset(&mut package)?;
+        Ok(package)
+    }
+
+    /// Another package made from this one, with its metadata, the given files, and none of its other settings
+    fn derived_package(&self, deb_name: String, description: String, assets: Vec<Asset>) -> Self {
+        Self {
+            name: self.name.clone(),
+            deb_name,
+            deb_version: self.deb_version.clone(),
+            has_revision: self.has_revision,
+            cargo_version: self.cargo_version.clone(),
+            license: self.license.clone(),
+            license_file_rel_path: None,
+            license_file_skip_lines: 0,
+            copyright: self.copyright.clone(),
+            changelog: None,
+            git_changelog: None,
+            news: None,
+            upstream_changelog: None,
+            distribution: self.distribution.clone(),
+            urgency: self.urgency.clone(),
+            homepage: self.homepage.clone(),
+            documentation: self.documentation.clone(),
+            repository: self.repository.clone(),
+            vcs_git: self.vcs_git.clone(),
+            vcs_browser: self.vcs_browser.clone(),
+            origin: self.origin.clone(),
+            bugs: self.bugs.clone(),
+            tag: None,
+            description,
+            extended_description: ExtendedDescription::None,
+            maintainer: self.maintainer.clone(),
+            wildcard_depends: String::new(),
+            resolved_depends: None,
+            pre_depends: None,
+            build_depends: None,
+            recommends: None,
+            suggests: None,
+            enhances: None,
+            section: None,
+            priority: "optional".into(),
+            conflicts: None,
+            breaks: None,
+            replaces: None,
+            provides: None,
+            built_using: None,
+            static_built_using: None,
+            architecture: self.architecture.clone(),
+            multiarch: None,
+            essential: false,
+            protected: false,
+            important: false,
+            provenance_fields: false,
+            // without `{name}`, the file name would be the same as this package's
+            output_template: self.output_template.clone().filter(|t| t.contains("{name}")),
+            conf_files: Vec::new(),
+            assets: Assets::with_resolved_assets(assets),
+            raw_assets: None,
+            readme_rel_path: None,
+            triggers_file_rel_path: None,
+            triggers: Vec::new(),
+            maintscript: Vec::new(),
+            init_script: None,
+            logrotate: None,
+            cron: CronFiles::default(),
+            udev_rules: None,
+            dbus: DbusFiles::default(),
+            polkit: Vec::new(),
+            apparmor_profiles: Vec::new(),
+            selinux_modules: Vec::new(),
+            initramfs_hook: None,
+            dkms: None,
+            desktop_entries: Vec::new(),
+            metainfo: None,
+            generate_man_pages: false,
+            completions: Completions::default(),
+            docs: None,
+            examples: false,
+            example_binaries: Vec::new(),
+            translations: None,
+            dependency_copyright: false,
+            license_texts: false,
+            license_policy: None,
+            sbom: None,
+            auditable: false,
+            sysroot: None,
+            dependency_licenses: Vec::new(),
+            ucf_files: Vec::new(),
+            alternatives: Vec::new(),
+            capabilities: Vec::new(),
+            stat_overrides: Vec::new(),
+            maintainer_scripts_rel_path: None,
+            debhelper_files_rel_path: None,
+            preserve_symlinks: false,
+            systemd_units: None,
+            upload_targets: HashMap::new(),
+            keyring: None,
+            is_keyring: false,
+            default_timestamp: self.default_timestamp,
+            dbgsym: None,
+        }
+    }
+
+    /// File names don't have the epoch, like in Debian's archive
+    pub(crate) fn version_without_epoch(&self) -> &str {
+        self.deb_version.split_once(':').map_or(&self.deb_version, |(_, v)| v)
+    }
+
+    /// Without the epoch and the Debian revision, for versions of the software rather than of the package
+    pub(crate) fn upstream_version(&self) -> &str {
+        upstream_version(&self.deb_version, self.has_revision)
+    }
+
+    /// `deb`, or `ddeb` for Ubuntu's debug packages
+    pub(crate) fn file_extension(&self) -> &'static str {
+        if self.dbgsym == Some(DbgsymFormat::Ddeb) { "ddeb" } else { "deb" }
+    }
+
+    /// An asset wants separate debug symbols, even if the package doesn't
+    pub(crate) fn has_separate_debug_symbols_override(&self) -> bool {
+        self.assets.resolved.iter().any(|a| a.c.separate_debug_symbols == Some(true))
+    }
+
+    /// GNU Build IDs of the debug files
+    fn build_ids(&self) -> Vec<String> {
+        self.assets.resolved.iter().filter_map(|a| build_id_from_debug_path(&a.c.target_path)).collect()
+    }
+
+    /// Settings of asset tables that need maintainer scripts are collected in `scripts`.
+    /// Assets with `ucf = true` are moved to their reference path.
+    fn parse_assets(assets: AssetList, deb_name: &str, scripts: &mut AssetScripts, listener: &dyn Listener) -> CDResult<Vec<RawAsset>> {
         // Treat all explicit assets as unresolved until after the build step
-        assets.into_iter().map(|mut asset_line| {
+        assets.into_iter().map(|asset| {
+            let (mut asset_line, separate_debug_symbols) = match asset {
+                AssetEntry::Array(line) => (line, None),
+                AssetEntry::Table(mut t) => {
+                    if t.ucf == Some(true) {
+                        t.dest = ucf_reference_path(&t.source, &t.dest, deb_name, &mut scripts.ucf_files)?;
+                    }
+                    if let Some(alternative) = t.alternative.take() {
+                        scripts.alternatives.push(asset_alternative(alternative, &t.source, &t.dest)?);
+                    }
+                    if let Some(caps) = t.capabilities.take() {
+                        if caps.is_empty() || !caps.chars().all(|c| c.is_ascii_alphanumeric() || "_,+-=".contains(c)) {
+                            return Err(CargoDebError::InvalidField("capabilities", caps, "use the format of setcap, like cap_net_bind_service+ep"));
+                        }
+                        scripts.capabilities.push((asset_file_path("capabilities", &t.source, &t.dest)?, caps));
+                    }
+                    if let Some(entry) = t.statoverride.take() {
+                        scripts.stat_overrides.push(asset_stat_override(entry, &t.source, &t.dest, &t.mode)?);
+                    }
+                    (vec![t.source, t.dest, t.mode], t.separate_debug_symbols)
+                },
+            };
             let mut asset_parts = asset_line.drain(..);
             let source_path = PathBuf::from(asset_parts.next()
                 .ok_or("missing path (first array entry) for asset in Cargo.toml")?);
@@ -686,6 +1776,7 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
                 target_path: PathBuf::from(asset_parts.next().ok_or("missing target (second array entry) for asset in Cargo.toml. Use something like \"usr/local/bin/\".")?),
                 chmod: u32::from_str_radix(&asset_parts.next().ok_or("missing chmod (third array entry) for asset in Cargo.toml. Use an octal string like \"777\".")?, 8)
                     .map_err(|e| CargoDebError::NumParse("unable to parse chmod argument", e))?,
+                separate_debug_symbols,
             })
         }).collect()
     }
@@ -721,38 +1812,96 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
 
     /// run dpkg/ldd to check deps of libs
     pub fn resolve_binary_dependencies(&mut self, target: Option<&str>, listener: &dyn Listener) -> CDResult<()> {
-        let mut deps = HashSet::new();
-        for word in self.wildcard_depends.split(',') {
+        let mut auto_deps = None;
+        let pre_depends = self.pre_depends.as_deref()
+            .map(|pre_depends| self.resolve_dependency_list(pre_depends, true, &mut auto_deps, target, listener))
+            .transpose()?;
+        let mut deps = self.resolve_dependency_list(&self.wildcard_depends, false, &mut auto_deps, target, listener)?;
+        if let Some(pre_depends) = pre_depends {
+            // no need to repeat them
+            deps.retain(|dep| !pre_depends.contains(dep));
+            self.pre_depends = Some(pre_depends.join(", "));
+        }
+        let auto_recommends = auto_deps.map(|auto| auto.recommends).unwrap_or_default().into_iter()
+            .filter(|dep| !deps.contains(dep))
+            .unique()
+            .collect::<Vec<_>>();
+        if !auto_recommends.is_empty() {
+            self.recommends = Some(self.recommends.iter().map(String::as_str).chain(auto_recommends.iter().map(String::as_str)).join(", "));
+        }
+        self.resolved_depends = Some(deps.join(", "));
+        Ok(())
+    }
+
+    /// Replaces `$auto`, and removes dependencies for other architectures
+    fn resolve_dependency_list(&self, list: &str, is_pre_depends: bool, auto_deps: &mut Option<ShlibsDependencies>, target: Option<&str>, listener: &dyn Listener) -> CDResult<Vec<String>> {
+        // Vec rather than a set to keep the order stable between builds
+        let mut deps = Vec::new();
+        for word in list.split(',') {
             let word = word.trim();
             if word == "$auto" {
-                let bin = self.all_binaries();
-                let resolved = bin.par_iter()
-                    .filter(|bin| !bin.archive_as_symlink_only())
-                    .filter_map(|p| p.path())
-                    .filter_map(|bname| match resolve(bname, target) {
-                        Ok(bindeps) => Some(bindeps),
-                        Err(err) => {
-                            listener.warning(format!("{} (no auto deps for {})", err, bname.display()));
-                            None
-                        },
-                    })
-                    .collect::<Vec<_>>();
-                for dep in resolved.into_iter().flat_map(|s| s.into_iter()) {
-                    deps.insert(dep);
+                if let Some(sysroot) = self.sysroot.as_deref().filter(|root| !root.join(symbols::DPKG_ADMIN_DIR).is_dir()) {
+                    return Err(CargoDebError::IoFile("sysroot has no dpkg database", io::ErrorKind::NotFound.into(), sysroot.join(symbols::DPKG_ADMIN_DIR)));
                 }
-            } else {
+                let auto_deps = auto_deps.get_or_insert_with(|| {
+                    // dpkg-shlibdeps only knows the host's packages
+                    let use_dpkg = self.sysroot.is_none() && has_dpkg_shlibdeps();
+                    if !use_dpkg && self.sysroot.is_none() {
+                        listener.warning("dpkg-shlibdeps isn't installed, so $auto dependencies are found from symbols files of the installed libraries, or guessed from their names".into());
+                    }
+                    let own_library_assets = self.assets.resolved.iter().filter(|a| a.c.is_dynamic_library()).collect::<Vec<_>>();
+                    let own_libraries = OwnLibraries {
+                        package: &self.deb_name,
+                        names: own_library_assets.iter().filter_map(|a| a.c.target_path.file_name()?.to_str()).collect(),
+                        dirs: own_library_assets.iter().filter_map(|a| a.source.path()?.parent()).unique().collect(),
+                    };
+                    // like `dh_shlibdeps -- -dPre-Depends`
+                    let pre_depends_auto = self.pre_depends.as_deref().is_some_and(|list| list.split(',').any(|w| w.trim() == "$auto"));
+                    let field = if pre_depends_auto { "Pre-Depends" } else { "Depends" };
+                    let installed = if use_dpkg { InstalledLibraries::default() } else { InstalledLibraries::read(self.sysroot.as_deref().unwrap_or(Path::new("/")), &self.architecture) };
+                    let bin = self.all_binaries();
+                    let resolved = bin.par_iter()
+                        .filter(|bin| !bin.archive_as_symlink_only())
+                        .filter_map(|p| p.path())
+                        .filter_map(|bname| {
+                            let bindeps = if use_dpkg {
+                                resolve(bname, target, field, &own_libraries)
+                            } else {
+                                resolve_from_sonames(bname, &own_libraries.names, &installed).map(|deps| match field {
+                                    "Pre-Depends" => ShlibsDependencies { pre_depends: deps, ..ShlibsDependencies::default() },
+                                    _ => ShlibsDependencies { depends: deps, ..ShlibsDependencies::default() },
+                                })
+                            };
+                            match bindeps {
+                                Ok(bindeps) => Some(bindeps),
+                                Err(err) => {
+                                    listener.warning(format!("{} (no auto deps for {})", err, bname.display()));
+                                    None
+                                },
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    let mut all = ShlibsDependencies::default();
+                    for bindeps in resolved {
+                        all.pre_depends.extend(bindeps.pre_depends);
+                        all.depends.extend(bindeps.depends);
+                        all.recommends.extend(bindeps.recommends);
+                    }
+                    all
+                });
+                deps.extend(if is_pre_depends { &auto_deps.pre_depends } else { &auto_deps.depends }.iter().cloned());
+            } else if !word.is_empty() {
                 let (dep, arch_spec) = get_architecture_specification(word)?;
                 if let Some(spec) = arch_spec {
                     if match_architecture(spec, &self.architecture)? {
-                        deps.insert(dep);
+                        deps.push(dep);
                     }
                 } else {
-                    deps.insert(dep);
+                    deps.push(dep);
                 }
             }
         }
-        self.resolved_depends = Some(deps.into_iter().collect::<Vec<_>>().join(", "));
-        Ok(())
+        Ok(deps.into_iter().unique().collect())
     }
 
     /// Executables AND dynamic libraries. May include symlinks.
@@ -807,18 +1956,62 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
         Ok(sha256sums)
     }
 
-    fn extended_description(&self, config: &Config) -> CDResult<Option<Cow<'_, str>>> {
+    pub(crate) fn extended_description(&self, config: &Config) -> CDResult<Option<Cow<'_, str>>> {
         let path = match &self.extended_description {
             ExtendedDescription::None => return Ok(None),
             ExtendedDescription::String(s) => return Ok(Some(s.as_str().into())),
-            ExtendedDescription::File(p) => Cow::Borrowed(p.as_path()),
-            ExtendedDescription::ReadmeFallback(p) => Cow::Owned(config.path_in_package(p)),
+            ExtendedDescription::File(p) | ExtendedDescription::ReadmeFallback(p) => config.path_in_package(p),
         };
         let desc = fs::read_to_string(&path)
-            .map_err(|err| CargoDebError::IoFile("unable to read extended description from file", err, path.into_owned()))?;
+            .map_err(|err| CargoDebError::IoFile("unable to read extended description from file", err, path))?;
         Ok(Some(desc.into()))
     }
 
+    /// Extended description formatted for the `Description` field, converted from markdown if it's from a `.md` file
+    pub(crate) fn extended_description_lines(&self, config: &Config) -> CDResult<Vec<String>> {
+        let markdown = match &self.extended_description {
+            ExtendedDescription::File(p) | ExtendedDescription::ReadmeFallback(p) => is_markdown(p) || p.extension().is_some_and(|e| e == "markdown"),
+            ExtendedDescription::None | ExtendedDescription::String(_) => false,
+        };
+        Ok(self.extended_description(config)?.map(|desc| format_extended_description(&desc, markdown)).unwrap_or_default())
+    }
+
+    /// In KiB, counted like `dpkg-gencontrol` does: files rounded up to whole KiB,
+    /// and 1 KiB for every symlink and directory, including the parent directories the archive creates implicitly.
+    pub(crate) fn installed_size(&self) -> u64 {
+        let mut directories = HashSet::new();
+        directories.insert(PathBuf::new());
+        let mut size = 0;
+        for asset in &self.assets.resolved {
+            let target_path = normalized(&asset.c.target_path);
+            match asset.source {
+                AssetSource::Directory => { directories.insert(target_path.clone()); },
+                AssetSource::Symlink(_) | AssetSource::SymlinkTo(_) => size += 1,
+                _ => size += asset.source.file_size().map_or(0, |len| (len + 1023) / 1024),
+            }
+            directories.extend(target_path.ancestors().skip(1).map(Path::to_path_buf));
+        }
+        size + directories.len() as u64
+    }
+
+    /// `<name>_<version>_<arch>.<extension>`, or the `output_template`. The `deb_name` is separate for the dbgsym package.
+    pub(crate) fn output_filename(&self, deb_name: &str, version: &str, extension: &str) -> String {
+        self.output_filename_for_arch(deb_name, version, &self.architecture, extension)
+    }
+
+    /// `output_filename` with another `arch`, like a variable in `debian/rules`
+    pub(crate) fn output_filename_for_arch(&self, deb_name: &str, version: &str, arch: &str, extension: &str) -> String {
+        let Some(template) = &self.output_template else {
+            return format!("{deb_name}_{version}_{arch}.{extension}");
+        };
+        let stem = template.strip_suffix(".deb").unwrap_or(template)
+            .replace("{name}", deb_name)
+            .replace("{version}", version)
+            .replace("{arch}", arch)
+            .replace("{distro}", self.distribution.as_deref().unwrap_or("unstable"));
+        format!("{stem}.{extension}")
+    }
+
     /// Generates the control file that obtains all the important information about the package.
     pub fn generate_control(&self, config: &Config) -> CDResult<Vec<u8>> {
         // Create and return the handle to the control file with write access.
@@ -828,32 +2021,40 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
         writeln!(&mut control, "Package: {}", self.deb_name)?;
         writeln!(&mut control, "Version: {}", self.deb_version)?;
         writeln!(&mut control, "Architecture: {}", self.architecture)?;
-        if let Some(ref repo) = self.repository {
-            if repo.starts_with("http") {
-                writeln!(&mut control, "Vcs-Browser: {repo}")?;
-            }
-            if let Some(kind) = self.repository_type() {
-                writeln!(&mut control, "Vcs-{kind}: {repo}")?;
-            }
+        if let Some(multiarch) = self.multiarch {
+            writeln!(&mut control, "Multi-Arch: {}", multiarch.as_str())?;
+        }
+        for (name, value) in self.vcs_fields() {
+            writeln!(&mut control, "{name}: {value}")?;
         }
-        if let Some(homepage) = self.homepage.as_ref().or(self.documentation.as_ref()) {
+        if let Some(homepage) = self.homepage_url() {
             writeln!(&mut control, "Homepage: {homepage}")?;
         }
+        for (name, value) in self.vendor_fields() {
+            writeln!(&mut control, "{name}: {value}")?;
+        }
         if let Some(ref section) = self.section {
             writeln!(&mut control, "Section: {section}")?;
         }
         writeln!(&mut control, "Priority: {}", self.priority)?;
+        for (field, enabled) in [("Essential", self.essential), ("Protected", self.protected), ("Important", self.important)] {
+            if enabled {
+                writeln!(&mut control, "{field}: yes")?;
+            }
+        }
         writeln!(&mut control, "Maintainer: {}", self.maintainer)?;
 
-        let installed_size = self.assets.resolved
-            .iter()
-            .map(|m| (m.source.file_size().unwrap_or(0) + 2047) / 1024) // assume 1KB of fs overhead per file
-            .sum::<u64>();
-
-        writeln!(&mut control, "Installed-Size: {installed_size}")?;
+        writeln!(&mut control, "Installed-Size: {}", self.installed_size())?;
 
-        if let Some(deps) = &self.resolved_depends {
-            writeln!(&mut control, "Depends: {deps}")?;
+        let misc_depends = self.misc_depends(config);
+        match self.resolved_depends.as_deref() {
+            Some(deps) if misc_depends.is_empty() => writeln!(&mut control, "Depends: {deps}")?,
+            deps => {
+                let deps = deps.into_iter().filter(|d| !d.trim().is_empty()).chain(misc_depends).join(", ");
+                if !deps.is_empty() {
+                    writeln!(&mut control, "Depends: {deps}")?;
+                }
+            },
         }
 
         if let Some(ref pre_depends) = self.pre_depends {
@@ -900,16 +2101,37 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
         if let Some(ref provides) = self.provides {
             writeln!(&mut control, "Provides: {provides}")?;
         }
+        if let Some(ref built_using) = self.built_using {
+            writeln!(&mut control, "Built-Using: {built_using}")?;
+        }
+        if let Some(ref static_built_using) = self.static_built_using {
+            writeln!(&mut control, "Static-Built-Using: {static_built_using}")?;
+        }
+
+        if self.provenance_fields {
+            for (name, value) in config.provenance_fields() {
+                writeln!(&mut control, "{name}: {value}")?;
+            }
+        }
+
+        if let Some(format) = self.dbgsym {
+            if format == DbgsymFormat::Ddeb {
+                writeln!(&mut control, "Package-Type: ddeb")?;
+            }
+            writeln!(&mut control, "Auto-Built-Package: debug-symbols")?;
+            let build_ids = self.build_ids();
+            if !build_ids.is_empty() {
+                writeln!(&mut control, "Build-Ids: {}", build_ids.join(" "))?;
+            }
+        }
 
         write!(&mut control, "Description:")?;
         for line in self.description.split_by_chars(79) {
             writeln!(&mut control, " {line}")?;
         }
 
-        if let Some(desc) = self.extended_description(config)? {
-            for line in desc.split_by_chars(79) {
-                writeln!(&mut control, " {line}")?;
-            }
+        for line in self.extended_description_lines(config)? {
+            writeln!(&mut control, " {line}")?;
         }
         control.push(b'\n');
 
@@ -943,6 +2165,39 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
         None
     }
 
+    /// `Vcs-Browser` and `Vcs-<type>` fields, from the metadata or guessed from `repository`
+    pub(crate) fn vcs_fields(&self) -> Vec<(String, String)> {
+        let repo = self.repository.as_deref().map(|repo| {
+            // Cargo allows URLs like git+https://, which are only for Cargo
+            repo.split_once('+').filter(|(scheme, _)| ["git", "hg", "svn", "cvs"].contains(scheme)).map_or(repo, |(_, url)| url)
+        });
+        let mut fields = Vec::new();
+        if let Some(browser) = self.vcs_browser.as_deref().map(String::from).or_else(|| repository_browser_url(repo?)) {
+            fields.push(("Vcs-Browser".into(), browser));
+        }
+        if let Some(git) = &self.vcs_git {
+            fields.push(("Vcs-Git".into(), git.clone()));
+        } else if let (Some(repo), Some(kind)) = (repo, self.repository_type()) {
+            fields.push((format!("Vcs-{kind}"), repo.into()));
+        }
+        fields
+    }
+
+    /// `Origin`, `Bugs` and `Tag` fields
+    pub(crate) fn vendor_fields(&self) -> Vec<(&'static str, &str)> {
+        [("Origin", &self.origin), ("Bugs", &self.bugs), ("Tag", &self.tag)].into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+            .collect()
+    }
+
+    /// `homepage`, or the repository's web page, or `documentation`
+    pub(crate) fn homepage_url(&self) -> Option<String> {
+        self.homepage.clone()
+            .or_else(|| self.vcs_browser.clone())
+            .or_else(|| repository_browser_url(self.repository.as_deref()?))
+            .or_else(|| self.documentation.clone())
+    }
+
     pub(crate) fn append_copyright_metadata(&self, copyright: &mut Vec<u8>) -> Result<(), CargoDebError> {
         writeln!(copyright, "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/")?;
         writeln!(copyright, "Upstream-Name: {}", self.name)?;
@@ -953,7 +2208,7 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
             writeln!(copyright, "Copyright: {c}")?;
         }
         if let Some(license) = self.license.as_deref() {
-            writeln!(copyright, "License: {license}")?;
+            writeln!(copyright, "License: {}", licenses::dep5_license_expression(license))?;
         }
         Ok(())
     }
@@ -964,6 +2219,249 @@ This will be hard error in a future release of cargo-deb.", source_path.display(
         }
         Some(format_conffiles(&self.conf_files))
     }
+
+    /// The `dkms` table, with the defaults from the package name and version
+    pub(crate) fn dkms_module(&self) -> Option<DkmsModule> {
+        Some(DkmsModule::new(self.dkms.as_ref()?, &self.deb_name, self.upstream_version()))
+    }
+
+    /// Dependencies of the generated maintainer scripts, which debhelper would put in `${misc:Depends}`
+    fn misc_depends(&self, config: &Config) -> Vec<&'static str> {
+        let mut depends = Vec::new();
+        if self.uses_debconf(config) {
+            depends.push(DEBCONF_DEPENDS);
+        }
+        if !self.ucf_files.is_empty() {
+            depends.push("ucf");
+        }
+        if dh_installsysusers::sysusers_files(&self.assets.resolved).next().is_some() {
+            depends.push(dh_installsysusers::SYSUSERS_DEPENDS);
+        }
+        if dh_installtmpfiles::tmpfiles_files(&self.assets.resolved).next().is_some() {
+            depends.push(dh_installtmpfiles::TMPFILES_DEPENDS);
+        }
+        if self.dkms.is_some() {
+            depends.push(dh_dkms::DKMS_DEPENDS);
+        }
+        // a dependency on one of the alternatives is enough, and adding the alternatives would repeat the package
+        let depended = self.resolved_depends.iter().flat_map(|d| d.split(','))
+            .filter(|relation| !relation.contains('|'))
+            .map(relation_package_name)
+            .collect::<HashSet<_>>();
+        depends.retain(|misc| !misc.split('|').any(|alternative| depended.contains(relation_package_name(alternative))));
+        depends
+    }
+
+    /// Has a debconf `templates` file in the `maintainer-scripts` directory
+    pub(crate) fn uses_debconf(&self, config: &Config) -> bool {
+        self.maintainer_scripts_rel_path.as_ref().is_some_and(|dir| is_path_file(&config.path_in_package(dir).join("templates")))
+    }
+
+    /// Contents of the triggers control file, from `triggers-file` and `triggers`
+    pub(crate) fn triggers(&self, config: &Config) -> CDResult<Option<String>> {
+        let mut triggers = String::new();
+        if let Some(rel_path) = &self.triggers_file_rel_path {
+            let path = config.path_in_package(rel_path);
+            triggers = fs::read_to_string(&path).map_err(|e| CargoDebError::IoFile("triggers file", e, path))?;
+            if !triggers.is_empty() && !triggers.ends_with('\n') {
+                triggers.push('\n');
+            }
+        }
+        for line in &self.triggers {
+            triggers.push_str(line.trim());
+            triggers.push('\n');
+        }
+        for line in triggers.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            check_trigger(line)?;
+        }
+        Ok(Some(triggers).filter(|t| !t.is_empty()))
+    }
+
+    /// Also makes dpkg run `ldconfig` after installing or removing shared libraries in its directories, like `dh_makeshlibs`,
+    /// `update-initramfs` for initramfs hooks and scripts, like `dh_installinitramfs`, and `update-icon-caches` for icons, like `dh_icons`.
+    /// debhelper adds them when building with the `debian/` directory.
+    pub(crate) fn archive_triggers(&self, config: &Config) -> CDResult<Option<String>> {
+        let mut triggers = self.triggers(config)?.unwrap_or_default();
+        let automatic = [
+            ("ldconfig", self.assets.resolved.iter().any(|a| is_ldconfig_library(&a.c.target_path))),
+            ("update-initramfs", self.assets.resolved.iter().any(|a| a.c.target_path.starts_with(INITRAMFS_TOOLS_DIR))),
+            ("update-icon-caches", self.assets.resolved.iter().any(|a| a.c.target_path.starts_with(dh_icons::ICONS_DIR))),
+        ];
+        for (name, needed) in automatic {
+            if needed && !triggers.lines().any(|l| l.split_whitespace().nth(1) == Some(name)) {
+                triggers.push_str("activate-noawait ");
+                triggers.push_str(name);
+                triggers.push('\n');
+            }
+        }
+        Ok(Some(triggers).filter(|t| !t.is_empty()))
+    }
+}
+
+/// Settings of asset tables that are implemented in maintainer scripts
+#[derive(Debug, Default)]
+struct AssetScripts {
+    ucf_files: Vec<(PathBuf, PathBuf)>,
+    alternatives: Vec<Alternative>,
+    capabilities: Vec<(String, String)>,
+    stat_overrides: Vec<StatOverride>,
+}
+
+/// Files managed by `ucf` are installed to `/usr/share/<package>/`, with their path in `/etc`
+fn ucf_reference_path(source: &str, dest: &str, deb_name: &str, ucf_files: &mut Vec<(PathBuf, PathBuf)>) -> CDResult<String> {
+    if dest.ends_with('/') || is_glob_pattern(Path::new(source)) {
+        return Err(CargoDebError::InvalidField("ucf asset", dest.into(), "it must be a single file, with its file name in `dest`"));
+    }
+    let conf_path = Path::new("/").join(dh_install::normalized(Path::new(dest)));
+    let rel_path = conf_path.strip_prefix("/etc").or_else(|_| conf_path.strip_prefix("/")).unwrap_or(&conf_path);
+    let reference = Path::new("usr/share").join(deb_name).join(rel_path);
+    ucf_files.push((conf_path, Path::new("/").join(&reference)));
+    reference.into_os_string().into_string().map_err(|_| CargoDebError::Str("ucf asset path is not UTF-8"))
+}
+
+/// Absolute path of a single-file asset, with the source's file name if the `dest` is a directory
+fn asset_file_path(setting: &'static str, source: &str, dest: &str) -> CDResult<String> {
+    let invalid = |value: &str, msg| CargoDebError::InvalidField(setting, value.into(), msg);
+    let mut path = Path::new("/").join(dh_install::normalized(Path::new(dest)));
+    if dest.ends_with('/') {
+        if is_glob_pattern(Path::new(source)) {
+            return Err(invalid(dest, "it must be a single file, with its file name in `dest`"));
+        }
+        path.push(Path::new(source).file_name().ok_or_else(|| invalid(source, "the source must be a file"))?);
+    }
+    let path = path.into_os_string().into_string().map_err(|_| invalid(dest, "the path is not UTF-8"))?;
+    if path.contains(|c: char| c.is_whitespace() || c == '"') {
+        return Err(invalid(dest, "the path can't have spaces or quotes"));
+    }
+    Ok(path)
+}
+
+fn asset_stat_override(entry: StatOverrideEntry, source: &str, dest: &str, asset_mode: &str) -> CDResult<StatOverride> {
+    let invalid = |value: &str, msg| CargoDebError::InvalidField("statoverride", value.into(), msg);
+    let mode_str = entry.mode.as_deref().unwrap_or(asset_mode);
+    let mode = u32::from_str_radix(mode_str, 8).ok().filter(|&m| m <= 0o7777).ok_or_else(|| invalid(mode_str, "the mode must be an octal number, like \"4755\""))?;
+    let owner = entry.owner.unwrap_or_else(|| "root".into());
+    let group = entry.group.unwrap_or_else(|| "root".into());
+    for name in [&owner, &group] {
+        // a name, or #id
+        if name.is_empty() || !name.trim_start_matches('#').chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c)) {
+            return Err(invalid(name, "the owner and group must be names or #ids"));
+        }
+    }
+    Ok(StatOverride { path: asset_file_path("statoverride", source, dest)?, owner, group, mode })
+}
+
+/// The asset's absolute path in the alternatives group
+fn asset_alternative(entry: AlternativeEntry, source: &str, dest: &str) -> CDResult<Alternative> {
+    let invalid = |value: &str, msg| CargoDebError::InvalidField("alternative", value.into(), msg);
+    let path = asset_file_path("alternative", source, dest)?;
+    let slaves = entry.slaves.unwrap_or_default().into_iter().map(|s| (s.link, s.name, s.path)).collect::<Vec<_>>();
+    for (link, name, slave_path) in std::iter::once((&entry.link, &entry.name, &path)).chain(slaves.iter().map(|(l, n, p)| (l, n, p))) {
+        if name.is_empty() || name.contains(|c: char| c == '/' || c.is_whitespace()) {
+            return Err(invalid(name, "the name can't be empty or have slashes or spaces"));
+        }
+        for p in [link, slave_path] {
+            if !p.starts_with('/') || p.contains(char::is_whitespace) {
+                return Err(invalid(p, "the paths must be absolute, without spaces"));
+            }
+        }
+    }
+    Ok(Alternative {
+        name: entry.name,
+        link: entry.link,
+        path,
+        priority: entry.priority,
+        slaves,
+    })
+}
+
+/// Without the epoch, and without the Debian revision after the last `-` if the version `has_revision`
+pub(crate) fn upstream_version(deb_version: &str, has_revision: bool) -> &str {
+    let version = deb_version.split_once(':').map_or(deb_version, |(_, v)| v);
+    if !has_revision {
+        return version;
+    }
+    version.rsplit_once('-').map_or(version, |(v, _)| v)
+}
+
+/// `foo` of `foo:any (>= 1.0) [amd64]`
+fn relation_package_name(relation: &str) -> &str {
+    relation.trim().split(|c: char| c.is_whitespace() || matches!(c, ':' | '(' | '[' | '<')).next().unwrap_or_default()
+}
+
+/// cron and `run-parts` ignore files with dots and other characters in their names
+pub(crate) fn cron_file_name(deb_name: &str) -> String {
+    deb_name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+/// The `completions` setting
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Completions {
+    pub files: Vec<(Shell, String)>,
+    /// The command of the `files`
+    pub command: Option<String>,
+    /// Add the completions generated by build scripts
+    pub generate: bool,
+}
+
+impl Completions {
+    /// The `command` setting, or the name of the file without the shell's prefix or extension, like `foo` for `_foo`.
+    /// Names that can't be a command, like `completions.zsh` or `example.zsh`, fall back to the package name.
+    pub(crate) fn command<'a>(&'a self, shell: Shell, path: &'a str, deb_name: &'a str) -> &'a str {
+        if let Some(command) = &self.command {
+            return command;
+        }
+        let file_name = Path::new(path).file_name().and_then(|f| f.to_str()).unwrap_or_default();
+        Some(completions::command_name(shell, file_name))
+            .filter(|c| !c.is_empty() && *c != "completions" && !c.contains('.'))
+            .unwrap_or(deb_name)
+    }
+}
+
+impl From<CompletionsEntry> for Completions {
+    fn from(entry: CompletionsEntry) -> Self {
+        match entry {
+            CompletionsEntry::Generate(generate) => Self { files: Vec::new(), command: None, generate },
+            CompletionsEntry::Files(files) => Self {
+                files: [(Shell::Bash, files.bash), (Shell::Zsh, files.zsh), (Shell::Fish, files.fish)].into_iter()
+                    .filter_map(|(shell, path)| Some((shell, path?)))
+                    .collect(),
+                command: files.command,
+                generate: files.generate.unwrap_or(false),
+            },
+        }
+    }
+}
+
+/// Hooks and scripts of packages for initramfs-tools
+pub(crate) const INITRAMFS_TOOLS_DIR: &str = "usr/share/initramfs-tools";
+
+/// Shared libraries with a versioned soname, like `libfoo.so.1`, in `/lib`, `/usr/lib`, their multiarch subdirectories, and `lib32`/`lib64` variants.
+/// ldconfig only makes links for sonames, so `libfoo.so` for linking or plugins doesn't need it.
+fn is_ldconfig_library(path: &Path) -> bool {
+    let is_library = path.file_name().and_then(|f| f.to_str())
+        .is_some_and(|f| f.starts_with("lib") && f.split_once(".so.").is_some_and(|(_, version)| version.starts_with(|c: char| c.is_ascii_digit())));
+    let is_lib_dir = |dir: &Path| ["lib", "lib32", "lib64", "libx32"].iter().any(|l| dir == Path::new(l) || dir == Path::new("usr").join(l));
+    is_library && path.parent().is_some_and(|dir| is_lib_dir(dir)
+        || dir.parent().is_some_and(is_lib_dir) && dir.file_name().and_then(|d| d.to_str()).is_some_and(|d| d.contains("-linux-")))
+}
+
+/// Directives that `deb-triggers(5)` allows
+const TRIGGER_DIRECTIVES: [&str; 6] = ["interest", "interest-await", "interest-noawait", "activate", "activate-await", "activate-noawait"];
+
+/// A directive and one trigger name, which is either an absolute path (file trigger) or a name without spaces
+fn check_trigger(line: &str) -> CDResult<()> {
+    let mut words = line.split_whitespace();
+    let (Some(directive), Some(name), None) = (words.next(), words.next(), words.next()) else {
+        return Err(CargoDebError::InvalidField("trigger", line.into(), "it must be a directive followed by one trigger name"));
+    };
+    if !TRIGGER_DIRECTIVES.contains(&directive) {
+        return Err(CargoDebError::InvalidField("trigger", line.into(), "the directive must be interest or activate, optionally with -await or -noawait"));
+    }
+    if !name.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(CargoDebError::InvalidField("trigger", line.into(), "trigger names must be printable ASCII"));
+    }
+    Ok(())
 }
 
 fn parse_license_file(package: &cargo_toml::Package<CargoPackageMetadata>, license_file: Option<&LicenseFile>) -> CDResult<(Option<PathBuf>, usize)> {
@@ -986,6 +2484,24 @@ fn has_copyright_metadata(file: &str) -> bool {
         .any(|l| ["Copyright: ", "License: ", "Source: ", "Upstream-Name: ", "Format: "].into_iter().any(|f| l.starts_with(f)))
 }
 
+/// Target-specific flags replace the package's flags, and extra flags of both are appended
+fn resolve_strip_flags(deb: &mut CargoDeb, target: Option<&str>) -> Vec<String> {
+    let target_deb = target.and_then(|t| deb.targets.as_mut()?.remove(t)).unwrap_or_default();
+    let mut strip_flags = target_deb.strip_flags.or(deb.strip_flags.take())
+        .unwrap_or_else(|| DEFAULT_STRIP_FLAGS.iter().map(|&f| f.into()).collect());
+    strip_flags.extend(deb.extra_strip_flags.take().into_iter().flatten());
+    strip_flags.extend(target_deb.extra_strip_flags.into_iter().flatten());
+    strip_flags
+}
+
+/// Build ID of `usr/lib/debug/.build-id/xx/yyyy.debug`
+pub(crate) fn build_id_from_debug_path(target_path: &Path) -> Option<String> {
+    let rel = target_path.strip_prefix("usr/lib/debug/.build-id").ok()?;
+    let dir = rel.parent()?.to_str()?;
+    let file = rel.file_name()?.to_str()?.strip_suffix(".debug")?;
+    Some(format!("{dir}{file}"))
+}
+
 /// Debian doesn't like `_` in names
 fn debian_package_name(crate_name: &str) -> String {
     // crate names are ASCII only
@@ -998,7 +2514,7 @@ impl Config {
     fn explicit_assets(&self, assets: Vec<RawAsset>) -> CDResult<Assets> {
         let custom_profile_target_dir = self.build_profile_override.as_deref().map(|profile| format!("target/{profile}"));
         // Treat all explicit assets as unresolved until after the build step
-        let unresolved_assets = assets.into_iter().map(|RawAsset { source_path, target_path, chmod }| {
+        let unresolved_assets = assets.into_iter().map(|RawAsset { source_path, target_path, chmod, separate_debug_symbols }| {
             // target/release is treated as a magic prefix that resolves to any profile
             let target_artifact_rel_path = source_path.strip_prefix("target/release").ok()
                 .or_else(|| source_path.strip_prefix(custom_profile_target_dir.as_ref()?).ok());
@@ -1009,7 +2525,9 @@ impl Config {
             } else {
                 (IsBuilt::No, self.path_in_package(&source_path), false)
             };
-            Ok(UnresolvedAsset::new(source_path, target_path, chmod, is_built, is_example))
+            let mut asset = UnresolvedAsset::new(source_path, target_path, chmod, is_built, is_example);
+            asset.c.separate_debug_symbols = separate_debug_symbols;
+            Ok(asset)
         }).collect::<CDResult<Vec<_>>>()?;
         Ok(Assets::with_unresolved_assets(unresolved_assets))
     }
@@ -1076,6 +2594,15 @@ impl Config {
     }
 }
 
+/// Directories of examples that `examples = true` installs
+const EXAMPLE_DIRS: [&str; 3] = ["examples", "docs/examples", "doc/examples"];
+
+/// Docs that `docs = true` installs, besides the readme. Licenses are in the `copyright` file instead.
+const UPSTREAM_DOC_FILES: [&str; 8] = ["README.md", "README", "AUTHORS", "AUTHORS.md", "CONTRIBUTORS", "CREDITS", "THANKS", "NOTICE"];
+
+/// Installed as the upstream changelog if `upstream-changelog` isn't set
+const UPSTREAM_CHANGELOG_FILES: [&str; 7] = ["CHANGELOG.md", "CHANGELOG", "CHANGELOG.txt", "ChangeLog", "CHANGES.md", "CHANGES", "HISTORY.md"];
+
 /// Format conffiles section, ensuring each path has a leading slash
 ///
 /// Starting with [dpkg 1.20.1](https://github.com/guillemj/dpkg/blob/68ab722604217d3ab836276acfc0ae1260b28f5f/debian/changelog#L393),
@@ -1093,7 +2620,83 @@ fn format_conffiles<S: AsRef<str>>(files: &[S]) -> String {
     })
 }
 
-fn check_debian_version(mut ver: &str) -> Result<(), &'static str> {
+/// Web page of the repository, if the URL is for a web browser, without `.git`
+fn repository_browser_url(repo: &str) -> Option<String> {
+    repo.starts_with("http").then(|| repo.trim_end_matches('/').trim_end_matches(".git").into())
+}
+
+/// Maintainer from `DEBFULLNAME` and `DEBEMAIL`, like in devscripts. `DEBEMAIL` can also be `Name <email>`.
+fn debian_maintainer(full_name: Option<String>, email: Option<String>) -> Option<String> {
+    let email = email.filter(|e| !e.trim().is_empty())?;
+    let email = email.trim();
+    if email.contains('<') {
+        return Some(email.into());
+    }
+    Some(format!("{} <{email}>", full_name.as_deref().map(str::trim).filter(|n| !n.is_empty())?))
+}
+
+/// Replaces `$auto` in a list of packages, and returns `None` if the list is empty
+fn replace_auto(list: &str, auto: &[String]) -> Option<String> {
+    let mut packages = Vec::new();
+    for word in list.split(',').map(str::trim).filter(|w| !w.is_empty()) {
+        if word == "$auto" {
+            packages.extend(auto.iter().map(String::as_str));
+        } else {
+            packages.push(word);
+        }
+    }
+    (!packages.is_empty()).then(|| packages.join(", "))
+}
+
+/// Debian Policy 7.5 allows only `(= version)` in `Provides`
+fn check_provides(provides: &str) -> CDResult<()> {
+    for provided in provides.split(',').map(str::trim) {
+        let (name, version) = provided.split_once('(').map_or((provided, None), |(name, version)| (name.trim_end(), Some(version)));
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '|') {
+            return Err(CargoDebError::InvalidRelationship("Provides must be a list of package names", provided.to_owned()));
+        }
+        if let Some(version) = version {
+            let version = version.trim().strip_prefix('=').and_then(|v| v.strip_suffix(')'))
+                .ok_or_else(|| CargoDebError::InvalidRelationship("versions in Provides must be exact, like `name (= 1.0)`", provided.to_owned()))?;
+            check_debian_version(version.trim()).map_err(|_| CargoDebError::InvalidRelationship("invalid version in Provides", provided.to_owned()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Sections of the Debian archive, from the Debian Policy 2.4
+const ARCHIVE_SECTIONS: [&str; 58] = [
+    "admin", "cli-mono", "comm", "database", "debian-installer", "debug", "devel", "doc", "editors", "education",
+    "electronics", "embedded", "fonts", "games", "gnome", "gnu-r", "gnustep", "graphics", "hamradio", "haskell",
+    "httpd", "interpreters", "introspection", "java", "javascript", "kde", "kernel", "libdevel", "libs", "lisp",
+    "localization", "mail", "math", "metapackages", "misc", "net", "news", "ocaml", "oldlibs", "otherosfs",
+    "perl", "php", "python", "ruby", "rust", "science", "shells", "sound", "tasks", "tex",
+    "text", "utils", "vcs", "video", "web", "x11", "xfce", "zope",
+];
+
+/// Archive areas of Debian and Ubuntu, which can prefix the section, like `contrib/net`
+const ARCHIVE_COMPONENTS: [&str; 7] = ["main", "contrib", "non-free", "non-free-firmware", "restricted", "universe", "multiverse"];
+
+fn check_section(section: &str) -> Result<(), &'static str> {
+    let (component, section) = section.rsplit_once('/').map_or((None, section), |(c, s)| (Some(c), s));
+    if component.is_some_and(|c| !ARCHIVE_COMPONENTS.contains(&c)) {
+        return Err("the area before / must be contrib, non-free, non-free-firmware, or one of Ubuntu's areas");
+    }
+    if !ARCHIVE_SECTIONS.contains(&section) {
+        return Err("it's not one of the archive's sections, like utils, net, or libs (https://www.debian.org/doc/debian-policy/ch-archive.html#sections)");
+    }
+    Ok(())
+}
+
+fn check_priority(priority: &str) -> Result<(), &'static str> {
+    match priority {
+        "required" | "important" | "standard" | "optional" => Ok(()),
+        "extra" => Err("extra is deprecated, use optional"),
+        _ => Err("it must be required, important, standard, or optional"),
+    }
+}
+
+pub(crate) fn check_debian_version(mut ver: &str) -> Result<(), &'static str> {
     if ver.trim_start().is_empty() {
         return Err("empty string");
     }
@@ -1121,6 +2724,19 @@ mod tests {
     use crate::parse::manifest::SystemdUnitsConfig;
     use crate::util::tests::add_test_fs_paths;
 
+    #[test]
+    fn strip_flags() {
+        let parse = |toml: &str| -> CargoDeb { toml::from_str(toml).unwrap() };
+        assert_eq!(resolve_strip_flags(&mut parse(""), None), DEFAULT_STRIP_FLAGS);
+        let mut deb = parse(r#"
+            extra-strip-flags = ["--keep-section=.note.ABI-tag"]
+            [targets.aarch64-unknown-linux-gnu]
+            strip-flags = ["--strip-debug"]
+        "#);
+        assert_eq!(resolve_strip_flags(&mut deb.clone(), Some("x86_64-unknown-linux-gnu")), [DEFAULT_STRIP_FLAGS, &["--keep-section=.note.ABI-tag"]].concat());
+        assert_eq!(resolve_strip_flags(&mut deb, Some("aarch64-unknown-linux-gnu")), ["--strip-debug", "--keep-section=.note.ABI-tag"]);
+    }
+
     #[test]
     fn match_arm_arch() {
         assert_eq!("armhf", debian_architecture_from_rust_triple("arm-unknown-linux-gnueabihf"));
@@ -1154,7 +2770,7 @@ mod tests {
         // supply a systemd unit file as if it were available on disk
         let _g = add_test_fs_paths(&[to_canon_static_str("cargo-deb.service")]);
 
-        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
         config.prepare_assets_before_build(&mut package_deb).unwrap();
 
         let num_unit_assets = package_deb.assets.resolved.iter()
@@ -1164,6 +2780,22 @@ mod tests {
         assert_eq!(0, num_unit_assets);
     }
 
+    #[test]
+    fn add_doc_assets_finds_docs() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let _g = add_test_fs_paths(&[to_canon_static_str("README.md"), to_canon_static_str("AUTHORS")]);
+
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        package_deb.docs = Some(DocsEntry::Auto(true));
+        config.add_doc_assets(&mut package_deb);
+        config.add_doc_assets(&mut package_deb);
+
+        let targets = package_deb.assets.unresolved.iter().map(|a| a.c.target_path.to_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(targets, ["usr/share/doc/cargo-deb/README.md", "usr/share/doc/cargo-deb/AUTHORS"]);
+        assert!(package_deb.assets.unresolved.iter().all(|a| a.c.chmod == 0o644));
+    }
+
     #[test]
     fn add_systemd_assets_with_config_adds_unit_assets() {
         let mut mock_listener = crate::listener::MockListener::new();
@@ -1172,7 +2804,7 @@ mod tests {
         // supply a systemd unit file as if it were available on disk
         let _g = add_test_fs_paths(&[to_canon_static_str("cargo-deb.service")]);
 
-        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
         config.prepare_assets_before_build(&mut package_deb).unwrap();
 
         package_deb.systemd_units.get_or_insert(vec![SystemdUnitsConfig::default()]);
@@ -1188,6 +2820,319 @@ mod tests {
         assert_eq!(1, num_unit_assets);
     }
 
+    #[test]
+    fn pre_depends_are_removed_from_depends() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let (_, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        package_deb.wildcard_depends = "$auto, bar, baz".into();
+        package_deb.pre_depends = Some("$auto, bar".into());
+        package_deb.resolve_binary_dependencies(None, &mock_listener).unwrap();
+        assert_eq!(package_deb.pre_depends.as_deref(), Some("bar"));
+        assert_eq!(package_deb.resolved_depends.as_deref(), Some("baz"));
+    }
+
+    #[test]
+    fn extra_control_fields() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        package_deb.essential = true;
+        package_deb.origin = Some("Example".into());
+        package_deb.provenance_fields = true;
+        let control = String::from_utf8(package_deb.generate_control(&config).unwrap()).unwrap();
+        assert!(control.contains("\nPriority: optional\nEssential: yes\n"), "{control}");
+        assert!(!control.contains("Protected:"));
+        assert!(control.contains("\nOrigin: Example\n"), "{control}");
+        assert!(control.contains("\nX-Cargo-Profile: release\nX-Cargo-Features: default\nX-Cargo-Deb-Version: "), "{control}");
+    }
+
+    #[test]
+    fn misc_depends() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+        let _g = crate::util::tests::add_test_fs_paths(&["/example/debian/templates"]);
+        package_deb.maintainer_scripts_rel_path = Some("/example/debian".into());
+
+        package_deb.resolved_depends = Some("libc6".into());
+        assert_eq!(package_deb.misc_depends(&config), [DEBCONF_DEPENDS]);
+        package_deb.resolved_depends = Some("libc6, debconf:any (>= 1.5.19)".into());
+        assert!(package_deb.misc_depends(&config).is_empty());
+        let control = String::from_utf8(package_deb.generate_control(&config).unwrap()).unwrap();
+        assert!(control.contains("\nDepends: libc6, debconf:any (>= 1.5.19)\n"), "{control}");
+        // doesn't require debconf itself
+        package_deb.resolved_depends = Some("cdebconf | debconf".into());
+        assert_eq!(package_deb.misc_depends(&config), [DEBCONF_DEPENDS]);
+    }
+
+    #[test]
+    fn installed_size() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let (_, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        package_deb.assets.resolved = vec![
+            Asset::new(AssetSource::Data(vec![0; 1024]), "usr/share/doc/example/copyright".into(), 0o644, IsBuilt::No, false),
+            Asset::new(AssetSource::Data(vec![0; 1025]), "usr/share/doc/example/changelog.gz".into(), 0o644, IsBuilt::No, false),
+            Asset::new(AssetSource::SymlinkTo("example".into()), "usr/bin/ex".into(), 0o777, IsBuilt::No, false),
+            Asset::new(AssetSource::Directory, "var/lib/example".into(), 0o755, IsBuilt::No, false),
+        ];
+        // 3 for the files, 1 for the link, and 9 directories including the root
+        assert_eq!(package_deb.installed_size(), 13);
+    }
+
+    #[test]
+    fn vcs_fields() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let (_, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        package_deb.homepage = None;
+        package_deb.repository = Some("https://github.com/example/tool.git".into());
+        assert_eq!(package_deb.vcs_fields(), [
+            ("Vcs-Browser".to_owned(), "https://github.com/example/tool".to_owned()),
+            ("Vcs-Git".to_owned(), "https://github.com/example/tool.git".to_owned()),
+        ]);
+        assert_eq!(package_deb.homepage_url().as_deref(), Some("https://github.com/example/tool"));
+
+        package_deb.repository = Some("git+https://example.com/tool".into());
+        package_deb.vcs_browser = Some("https://example.com/browse/tool".into());
+        assert_eq!(package_deb.vcs_fields(), [
+            ("Vcs-Browser".to_owned(), "https://example.com/browse/tool".to_owned()),
+            ("Vcs-Git".to_owned(), "https://example.com/tool".to_owned()),
+        ]);
+        package_deb.vcs_git = Some("https://example.com/tool.git -b main".into());
+        assert_eq!(package_deb.vcs_fields()[1].1, "https://example.com/tool.git -b main");
+    }
+
+    #[test]
+    fn auto_revision() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let out_dir = tempfile::tempdir().unwrap();
+        let overrides = || DebConfigOverrides { deb_revision: Some("auto".into()), ..DebConfigOverrides::default() };
+        let load = || Config::from_manifest(Some(Path::new("Cargo.toml")), None, Some(format!("{}/", out_dir.path().display())), None, None, overrides(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap().1;
+
+        let package_deb = load();
+        let version = package_deb.deb_version.strip_suffix("-1").unwrap().to_owned();
+        for revision in ["1", "3", "x"] {
+            fs::write(out_dir.path().join(format!("cargo-deb_{version}-{revision}_{}.deb", package_deb.architecture)), "").unwrap();
+        }
+        fs::write(out_dir.path().join(format!("cargo-deb_{version}.1-7_{}.deb", package_deb.architecture)), "").unwrap();
+        assert_eq!(load().deb_version, format!("{version}-4"));
+    }
+
+    #[test]
+    fn completions_command() {
+        let mut completions = Completions::default();
+        assert_eq!(completions.command(Shell::Bash, "completions/example.bash", "pkg"), "example");
+        assert_eq!(completions.command(Shell::Bash, "completions/example", "pkg"), "example");
+        assert_eq!(completions.command(Shell::Zsh, "completions/_example", "pkg"), "example");
+        assert_eq!(completions.command(Shell::Fish, "completions/example.fish", "pkg"), "example");
+        // not names of commands
+        assert_eq!(completions.command(Shell::Zsh, "completions/example.zsh", "pkg"), "pkg");
+        assert_eq!(completions.command(Shell::Bash, "bash/completions", "pkg"), "pkg");
+        assert_eq!(completions.command(Shell::Zsh, "completions/_", "pkg"), "pkg");
+
+        completions.command = Some("tool".into());
+        assert_eq!(completions.command(Shell::Zsh, "completions/example.zsh", "pkg"), "tool");
+        assert_eq!(completions.command(Shell::Bash, "completions/example.bash", "pkg"), "tool");
+    }
+
+    #[test]
+    fn upstream_version() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let load = |overrides| Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, overrides, None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap().1;
+
+        let package_deb = load(DebConfigOverrides::default());
+        assert_eq!(package_deb.upstream_version(), package_deb.cargo_version);
+
+        let package_deb = load(DebConfigOverrides { deb_version: Some("2:1.0.0-beta-3".into()), ..DebConfigOverrides::default() });
+        assert_eq!(package_deb.upstream_version(), "1.0.0-beta");
+
+        let mut package_deb = load(DebConfigOverrides { deb_revision: Some(String::new()), ..DebConfigOverrides::default() });
+        assert_eq!(package_deb.upstream_version(), package_deb.deb_version);
+        assert!(!package_deb.has_revision);
+        // a native package's version can still have a `-`
+        package_deb.deb_version = "1.0.0-beta".into();
+        assert_eq!(package_deb.upstream_version(), "1.0.0-beta");
+    }
+
+    #[test]
+    fn output_template() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let overrides = DebConfigOverrides { output_template: Some("{name}_{version}_{arch}_{distro}.deb".into()), ..DebConfigOverrides::default() };
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, overrides, None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        package_deb.distribution = Some("bookworm".into());
+        let filename = format!("cargo-deb_{}_{}_bookworm.deb", package_deb.version_without_epoch(), package_deb.architecture);
+        assert_eq!(config.deb_output_path(&package_deb), config.default_deb_output_dir().join(filename));
+        package_deb.dbgsym = Some(DbgsymFormat::Ddeb);
+        assert_eq!(package_deb.output_filename("cargo-deb-dbgsym", "*", package_deb.file_extension()), format!("cargo-deb-dbgsym_*_{}_bookworm.ddeb", package_deb.architecture));
+    }
+
+    #[test]
+    fn output_template_without_name() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        let overrides = DebConfigOverrides { output_template: Some("myapp_{version}_{arch}.deb".into()), ..DebConfigOverrides::default() };
+        let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, overrides, None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("key.asc");
+        fs::write(&key_file, "-----BEGIN PGP PUBLIC KEY BLOCK-----\n").unwrap();
+        package_deb.keyring = Some(KeyringConfig {
+            name: None, key_file: key_file.display().to_string(), uri: "https://example.com/apt".into(), suites: None, components: None, architectures: None,
+        });
+        package_deb.assets.resolved.push(Asset::new(AssetSource::Data(vec![]), "usr/lib/debug/.build-id/ab/cdef.debug".into(), 0o644, IsBuilt::No, false));
+        let dbgsym = package_deb.split_dbgsym(DbgsymFormat::Ddeb).unwrap();
+        let keyring = package_deb.keyring_package(&config).unwrap().unwrap();
+
+        let main_path = config.deb_output_path(&package_deb);
+        assert_eq!(main_path.file_name().unwrap().to_str().unwrap(), format!("myapp_{}_{}.deb", package_deb.version_without_epoch(), package_deb.architecture));
+        let paths = [main_path, config.deb_output_path(&dbgsym), config.deb_output_path(&keyring)];
+        assert_ne!(paths[0], paths[1]);
+        assert_ne!(paths[0], paths[2]);
+        assert_ne!(paths[1], paths[2]);
+        assert!(paths[1].ends_with(format!("cargo-deb-dbgsym_{}_{}.ddeb", package_deb.version_without_epoch(), package_deb.architecture)), "{}", paths[1].display());
+    }
+
+    #[test]
+    fn trigger_directives() {
+        assert!(check_trigger("activate-noawait update-icon-caches").is_ok());
+        assert!(check_trigger("interest-noawait /usr/share/icons/hicolor").is_ok());
+        assert!(check_trigger("interest").is_err());
+        assert!(check_trigger("activate foo bar").is_err());
+        assert!(check_trigger("trigger foo").is_err());
+    }
+
+    #[test]
+    fn ucf_assets() {
+        let assets: AssetList = toml::from_str::<CargoDeb>(r#"assets = [{ source = "example.conf", dest = "/etc/example/example.conf", mode = "644", ucf = true }, ["README.md", "usr/share/doc/example/", "644"]]"#).unwrap().assets.unwrap();
+        let mock_listener = crate::listener::MockListener::new();
+        let mut scripts = AssetScripts::default();
+        let raw = PackageConfig::parse_assets(assets, "example", &mut scripts, &mock_listener).unwrap();
+        assert_eq!(raw[0].target_path, Path::new("usr/share/example/example/example.conf"));
+        assert_eq!(raw[1].target_path, Path::new("usr/share/doc/example/"));
+        assert_eq!(scripts.ucf_files, [(PathBuf::from("/etc/example/example.conf"), PathBuf::from("/usr/share/example/example/example.conf"))]);
+        assert!(ucf_reference_path("*.conf", "etc/", "example", &mut scripts.ucf_files).is_err());
+    }
+
+    #[test]
+    fn alternative_assets() {
+        let assets: AssetList = toml::from_str::<CargoDeb>(r#"assets = [
+            { source = "target/release/foo", dest = "usr/bin/", mode = "755", alternative = { name = "editor", link = "/usr/bin/editor", priority = 40, slaves = [
+                { name = "editor.1.gz", link = "/usr/share/man/man1/editor.1.gz", path = "/usr/share/man/man1/foo.1.gz" },
+            ] } },
+            { source = "target/release/bar", dest = "usr/bin/bar-editor", mode = "755", alternative = { name = "editor", link = "/usr/bin/editor", priority = 20 } },
+        ]"#).unwrap().assets.unwrap();
+        let mock_listener = crate::listener::MockListener::new();
+        let mut scripts = AssetScripts::default();
+        PackageConfig::parse_assets(assets, "example", &mut scripts, &mock_listener).unwrap();
+        let alternatives = scripts.alternatives;
+        assert_eq!(alternatives.len(), 2);
+        assert_eq!(alternatives[0].path, "/usr/bin/foo");
+        assert_eq!(alternatives[0].slaves, [("/usr/share/man/man1/editor.1.gz".into(), "editor.1.gz".into(), "/usr/share/man/man1/foo.1.gz".into())]);
+        assert_eq!(alternatives[1].path, "/usr/bin/bar-editor");
+        assert_eq!(alternatives[1].priority, 20);
+        let bad = AlternativeEntry { name: "editor".into(), link: "usr/bin/editor".into(), priority: 1, slaves: None };
+        assert!(asset_alternative(bad, "foo", "/usr/bin/foo").is_err());
+    }
+
+    #[test]
+    fn capabilities_assets() {
+        let assets: AssetList = toml::from_str::<CargoDeb>(r#"assets = [{ source = "target/release/foo", dest = "usr/bin/", mode = "755", capabilities = "cap_net_bind_service+ep" }]"#).unwrap().assets.unwrap();
+        let mock_listener = crate::listener::MockListener::new();
+        let mut scripts = AssetScripts::default();
+        PackageConfig::parse_assets(assets, "example", &mut scripts, &mock_listener).unwrap();
+        assert_eq!(scripts.capabilities, [("/usr/bin/foo".to_owned(), "cap_net_bind_service+ep".to_owned())]);
+        let bad: AssetList = toml::from_str::<CargoDeb>(r#"assets = [{ source = "foo", dest = "usr/bin/foo", mode = "755", capabilities = "cap_net_raw+ep; rm -rf /" }]"#).unwrap().assets.unwrap();
+        assert!(PackageConfig::parse_assets(bad, "example", &mut AssetScripts::default(), &mock_listener).is_err());
+    }
+
+    #[test]
+    fn stat_override_assets() {
+        let assets: AssetList = toml::from_str::<CargoDeb>(r#"assets = [
+            { source = "target/release/foo", dest = "usr/games/", mode = "755", statoverride = { group = "games", mode = "2755" } },
+            { source = "foo.db", dest = "var/lib/foo/foo.db", mode = "640", statoverride = { owner = "foo" } },
+        ]"#).unwrap().assets.unwrap();
+        let mock_listener = crate::listener::MockListener::new();
+        let mut scripts = AssetScripts::default();
+        PackageConfig::parse_assets(assets, "example", &mut scripts, &mock_listener).unwrap();
+        assert_eq!(scripts.stat_overrides, [
+            StatOverride { path: "/usr/games/foo".into(), owner: "root".into(), group: "games".into(), mode: 0o2755 },
+            StatOverride { path: "/var/lib/foo/foo.db".into(), owner: "foo".into(), group: "root".into(), mode: 0o640 },
+        ]);
+        let bad = StatOverrideEntry { owner: None, group: None, mode: Some("u+s".into()) };
+        assert!(asset_stat_override(bad, "foo", "usr/bin/", "755").is_err());
+    }
+
+    #[test]
+    fn ldconfig_libraries() {
+        assert!(is_ldconfig_library(Path::new("usr/lib/libfoo.so.1.2")));
+        assert!(is_ldconfig_library(Path::new("usr/lib/x86_64-linux-gnu/libfoo.so.1")));
+        assert!(!is_ldconfig_library(Path::new("usr/lib/x86_64-linux-gnu/libfoo.so")));
+        assert!(!is_ldconfig_library(Path::new("usr/lib/libfoo.so.conf")));
+        assert!(is_ldconfig_library(Path::new("lib64/libfoo.so.1")));
+        assert!(!is_ldconfig_library(Path::new("usr/lib/foo/libfoo.so")));
+        assert!(!is_ldconfig_library(Path::new("usr/lib/x86_64-linux-gnu/foo.so")));
+        assert!(!is_ldconfig_library(Path::new("usr/lib/libfoo.sorted")));
+    }
+
+    #[test]
+    fn cron_assets() {
+        let cron: CronFiles = toml::from_str(r#"d = "debian/cron.d"
+daily = "debian/daily.sh""#).unwrap();
+        assert_eq!(cron.files().collect::<Vec<_>>(), [("d", "debian/cron.d"), ("daily", "debian/daily.sh")]);
+        assert_eq!(cron_file_name("python3.12-foo+bar"), "python3_12-foo_bar");
+    }
+
+    #[test]
+    fn example_binary_entries() {
+        let deb: CargoDeb = toml::from_str(r#"example-binaries = ["demo", { name = "tool", dest = "/usr/libexec/foo/" }]"#).unwrap();
+        let entries = deb.example_binaries.unwrap();
+        assert_eq!(entries.iter().map(|e| (e.name(), e.dest())).collect::<Vec<_>>(), [("demo", "usr/bin/"), ("tool", "/usr/libexec/foo/")]);
+    }
+
+    #[test]
+    fn maintainer_from_env() {
+        assert_eq!(debian_maintainer(Some("Jane Doe".into()), Some("jane@example.com".into())).as_deref(), Some("Jane Doe <jane@example.com>"));
+        assert_eq!(debian_maintainer(None, Some("Jane Doe <jane@example.com>".into())).as_deref(), Some("Jane Doe <jane@example.com>"));
+        assert_eq!(debian_maintainer(None, Some("jane@example.com".into())), None);
+        assert_eq!(debian_maintainer(Some("Jane Doe".into()), None), None);
+    }
+
+    #[test]
+    fn auto_lists() {
+        assert_eq!(replace_auto("$auto", &[]), None);
+        assert_eq!(replace_auto("$auto, libfoo (= 1.0)", &["openssl (= 3.2.1)".into()]).as_deref(), Some("openssl (= 3.2.1), libfoo (= 1.0)"));
+    }
+
+    #[test]
+    fn section_and_priority() {
+        assert!(check_section("utils").is_ok());
+        assert!(check_section("non-free/net").is_ok());
+        assert!(check_section("universe/rust").is_ok());
+        assert!(check_section("tools").is_err());
+        assert!(check_section("nonfree/net").is_err());
+        assert!(check_priority("optional").is_ok());
+        assert!(check_priority("extra").is_err());
+        assert!(check_priority("high").is_err());
+    }
+
+    #[test]
+    fn provides_versions() {
+        assert!(check_provides("foo-cli (= 2.1), foo-compat, bar (=1:2.0-1)").is_ok());
+        assert!(check_provides("foo-cli (>= 2.1)").is_err());
+        assert!(check_provides("foo-cli | bar").is_err());
+        assert!(check_provides("foo-cli (= x)").is_err());
+    }
+
     #[test]
     fn format_conffiles_empty() {
         let actual = format_conffiles::<String>(&[]);
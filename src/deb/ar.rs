@@ -3,6 +3,7 @@ use crate::CDResult;
 use ar::{Builder, Header};
 use std::fs;
 use std::fs::File;
+use std::io::Read;
 use std::path::PathBuf;
 
 /// The outermost `ar` archive that contains tarballs inside
@@ -27,15 +28,24 @@ impl DebArchive {
     }
 
     pub fn add_control(&mut self, control_tarball: Compressed) -> CDResult<()> {
-        self.add_file(format!("control.tar.{}", control_tarball.extension()), &control_tarball)
+        self.add_compressed(control_tarball.file_name("control.tar"), control_tarball)
     }
 
     pub fn add_data(&mut self, data_tarball: Compressed) -> CDResult<()> {
-        self.add_file(format!("data.tar.{}", data_tarball.extension()), &data_tarball)
+        self.add_compressed(data_tarball.file_name("data.tar"), data_tarball)
+    }
+
+    fn add_compressed(&mut self, dest_path: String, tarball: Compressed) -> CDResult<()> {
+        let len = tarball.len();
+        self.add_file_from_reader(dest_path, len, tarball.into_reader())
     }
 
     fn add_file(&mut self, dest_path: String, data: &[u8]) -> CDResult<()> {
-        let mut header = Header::new(dest_path.into(), data.len() as u64);
+        self.add_file_from_reader(dest_path, data.len() as u64, data)
+    }
+
+    fn add_file_from_reader(&mut self, dest_path: String, len: u64, data: impl Read) -> CDResult<()> {
+        let mut header = Header::new(dest_path.into(), len);
         header.set_mode(0o100644); // dpkg uses 100644
         header.set_mtime(self.mtime_timestamp);
         header.set_uid(0);
@@ -0,0 +1,136 @@
+use crate::config::{Config, PackageConfig};
+use crate::deb::changes::FileSums;
+use crate::error::{CDResult, CargoDebError};
+use crate::util::rfc2822_date;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+use std::{env, fs};
+
+/// Variables that can change the build output, recorded like `dpkg-genbuildinfo` does
+const ENV_VARS: &[&str] = &[
+    "SOURCE_DATE_EPOCH", "RUSTFLAGS", "CARGO_ENCODED_RUSTFLAGS", "RUSTC", "RUSTC_WRAPPER", "RUSTC_BOOTSTRAP",
+    "CARGO_BUILD_TARGET", "CARGO_BUILD_RUSTFLAGS", "CARGO_INCREMENTAL", "CC", "CXX", "CFLAGS", "CXXFLAGS", "CPPFLAGS", "LDFLAGS",
+    "DEB_BUILD_OPTIONS", "LANG", "LC_ALL", "TZ",
+];
+/// Target- and profile-specific settings, e.g. `CARGO_TARGET_<triple>_LINKER` or `CARGO_PROFILE_RELEASE_LTO`
+const ENV_PREFIXES: &[&str] = &["CARGO_TARGET_", "CARGO_PROFILE_"];
+
+/// Build environment of the packages, for tracking where they came from and reproducing them.
+///
+/// See [deb-buildinfo(5)](https://manpages.debian.org/deb-buildinfo.5)
+pub(crate) struct BuildInfo {
+    source: String,
+    version: String,
+    architecture: String,
+    binaries: Vec<String>,
+    build_path: PathBuf,
+    rustc_version: Option<String>,
+    environment: Vec<(String, String)>,
+}
+
+impl BuildInfo {
+    pub(crate) fn new(config: &Config, package_deb: &PackageConfig) -> Self {
+        let mut environment = env::vars()
+            .filter(|(k, _)| k != "CARGO_TARGET_DIR" && (ENV_VARS.contains(&k.as_str()) || ENV_PREFIXES.iter().any(|p| k.starts_with(p))))
+            .collect::<Vec<_>>();
+        environment.sort();
+        Self {
+            source: package_deb.deb_name.clone(),
+            version: package_deb.deb_version.clone(),
+            architecture: package_deb.architecture.clone(),
+            binaries: Vec::new(),
+            build_path: config.package_manifest_dir.clone(),
+            rustc_version: rustc_version(),
+            environment,
+        }
+    }
+
+    pub(crate) fn add_binary(&mut self, package_deb: &PackageConfig) {
+        self.binaries.push(package_deb.deb_name.clone());
+    }
+
+    /// `<name>_<version>_<arch>.buildinfo`, next to the main package
+    pub(crate) fn path(&self, main_deb: &Path) -> PathBuf {
+        main_deb.with_file_name(format!("{}_{}_{}.buildinfo", self.source, self.version.split_once(':').map_or(self.version.as_str(), |(_, v)| v), self.architecture))
+    }
+
+    pub(crate) fn generate(&self, debs: &[PathBuf], build_date: u64) -> CDResult<Vec<u8>> {
+        let files = debs.iter().map(|path| {
+            let sums = FileSums::new(path).map_err(|e| CargoDebError::IoFile("unable to read package for .buildinfo", e, path.clone()))?;
+            let file_name = path.file_name().ok_or("bad path")?.to_string_lossy().into_owned();
+            Ok((file_name, sums))
+        }).collect::<CDResult<Vec<_>>>()?;
+
+        let mut out = Vec::with_capacity(1024);
+        writeln!(&mut out, "Format: 1.0")?;
+        writeln!(&mut out, "Source: {}", self.source)?;
+        writeln!(&mut out, "Binary: {}", self.binaries.join(" "))?;
+        writeln!(&mut out, "Architecture: {}", self.architecture)?;
+        writeln!(&mut out, "Version: {}", self.version)?;
+        writeln!(&mut out, "Checksums-Md5:")?;
+        for (name, sums) in &files {
+            writeln!(&mut out, " {} {} {name}", sums.md5, sums.size)?;
+        }
+        writeln!(&mut out, "Checksums-Sha1:")?;
+        for (name, sums) in &files {
+            writeln!(&mut out, " {} {} {name}", sums.sha1, sums.size)?;
+        }
+        writeln!(&mut out, "Checksums-Sha256:")?;
+        for (name, sums) in &files {
+            writeln!(&mut out, " {} {} {name}", sums.sha256, sums.size)?;
+        }
+        writeln!(&mut out, "Build-Architecture: {}", crate::debian_architecture_from_rust_triple(crate::DEFAULT_TARGET))?;
+        writeln!(&mut out, "Build-Date: {}", rfc2822_date(build_date))?;
+        writeln!(&mut out, "Build-Path: {}", self.build_path.display())?;
+        writeln!(&mut out, "Build-Tool: cargo-deb {}", env!("CARGO_PKG_VERSION"))?;
+        if let Some(rustc_version) = &self.rustc_version {
+            writeln!(&mut out, "Rustc-Version: {rustc_version}")?;
+        }
+        writeln!(&mut out, "Environment:")?;
+        for (key, value) in &self.environment {
+            writeln!(&mut out, " {key}=\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        Ok(out)
+    }
+
+    /// Writes the file next to the main package, and returns its path
+    pub(crate) fn write(&self, debs: &[PathBuf]) -> CDResult<PathBuf> {
+        let path = self.path(&debs[0]);
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        let buildinfo = self.generate(debs, now)?;
+        fs::write(&path, buildinfo).map_err(|e| CargoDebError::IoFile("unable to write .buildinfo file", e, path.clone()))?;
+        Ok(path)
+    }
+}
+
+/// `rustc -V` of the same compiler Cargo uses
+pub(crate) fn rustc_version() -> Option<String> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc).arg("-V").output()
+        .map_err(|e| log::debug!("can't run rustc: {e}")).ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[test]
+fn generates_buildinfo() {
+    let dir = tempfile::tempdir().unwrap();
+    let deb = dir.path().join("example_1.0_amd64.deb");
+    fs::write(&deb, "abc").unwrap();
+    let buildinfo = BuildInfo {
+        source: "example".into(),
+        version: "1.0".into(),
+        architecture: "amd64".into(),
+        binaries: vec!["example".into()],
+        build_path: "/build/example".into(),
+        rustc_version: Some("rustc 1.80.0 (051478957 2024-07-21)".into()),
+        environment: vec![("RUSTFLAGS".into(), "-C \"x\"".into())],
+    };
+    let out = String::from_utf8(buildinfo.generate(std::slice::from_ref(&deb), 0).unwrap()).unwrap();
+    assert!(out.starts_with("Format: 1.0\nSource: example\nBinary: example\nArchitecture: amd64\nVersion: 1.0\nChecksums-Md5:\n 900150983cd24fb0d6963f7d28e17f72 3 example_1.0_amd64.deb\n"), "{out}");
+    assert!(out.contains("\nBuild-Date: Thu, 01 Jan 1970 00:00:00 +0000\nBuild-Path: /build/example\n"), "{out}");
+    assert!(out.contains("\nRustc-Version: rustc 1.80.0 (051478957 2024-07-21)\n"), "{out}");
+    assert!(out.ends_with("\nEnvironment:\n RUSTFLAGS=\"-C \\\"x\\\"\"\n"), "{out}");
+    assert_eq!(buildinfo.path(&deb), dir.path().join("example_1.0_amd64.buildinfo"));
+}
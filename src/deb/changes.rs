@@ -0,0 +1,252 @@
+use crate::changelog::read_debian_changelog;
+use crate::config::{Config, PackageConfig};
+use crate::error::{CDResult, CargoDebError};
+use crate::util::rfc2822_date;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Describes the built packages for repository tools like `dput` and `reprepro`.
+///
+/// See [deb-changes(5)](https://manpages.debian.org/deb-changes.5)
+pub(crate) struct ChangesFile {
+    source: String,
+    version: String,
+    architecture: String,
+    distribution: String,
+    urgency: String,
+    maintainer: String,
+    timestamp: u64,
+    changes: String,
+    binaries: Vec<ChangesBinary>,
+}
+
+struct ChangesBinary {
+    name: String,
+    description: String,
+    section: Option<String>,
+    priority: String,
+    architecture: String,
+}
+
+impl ChangesFile {
+    /// Uses the first entry of the package's changelog, if it has one
+    pub(crate) fn new(config: &Config, package_deb: &PackageConfig) -> CDResult<Self> {
+        let changelog = read_debian_changelog(config, package_deb)?;
+        let entry = changelog.as_ref().and_then(|c| first_changelog_entry(c));
+        let distribution = package_deb.distribution.clone()
+            .or_else(|| entry.as_ref().map(|e| e.distribution.clone()))
+            .unwrap_or_else(|| "unstable".into());
+        let urgency = package_deb.urgency.clone()
+            .or_else(|| entry.as_ref().and_then(|e| e.urgency.clone()))
+            .unwrap_or_else(|| "medium".into());
+        let changes = match entry {
+            Some(entry) => entry.lines.join("\n"),
+            None => format!("{} ({}) {distribution}; urgency={urgency}\n.\n  * Release {}", package_deb.deb_name, package_deb.deb_version, package_deb.deb_version),
+        };
+        Ok(Self {
+            source: package_deb.deb_name.clone(),
+            version: package_deb.deb_version.clone(),
+            architecture: package_deb.architecture.clone(),
+            distribution,
+            urgency,
+            maintainer: package_deb.maintainer.clone(),
+            timestamp: package_deb.default_timestamp,
+            changes,
+            binaries: Vec::new(),
+        })
+    }
+
+    /// In the same order as the `.deb` files given to `write`. Files after the packages, like `.buildinfo`, are listed in the section of the main package.
+    pub(crate) fn add_binary(&mut self, package_deb: &PackageConfig) {
+        self.binaries.push(ChangesBinary {
+            name: package_deb.deb_name.clone(),
+            description: package_deb.description.clone(),
+            section: package_deb.section.clone(),
+            priority: package_deb.priority.clone(),
+            architecture: package_deb.architecture.clone(),
+        });
+    }
+
+    /// `<name>_<version>_<arch>.changes`, next to the main package
+    pub(crate) fn path(&self, main_deb: &Path) -> PathBuf {
+        main_deb.with_file_name(format!("{}_{}_{}.changes", self.source, self.version.split_once(':').map_or(self.version.as_str(), |(_, v)| v), self.architecture))
+    }
+
+    pub(crate) fn generate(&self, paths: &[PathBuf]) -> CDResult<Vec<u8>> {
+        debug_assert!(paths.len() >= self.binaries.len());
+        let files = paths.iter().map(|path| {
+            let sums = FileSums::new(path).map_err(|e| CargoDebError::IoFile("unable to read file for .changes", e, path.clone()))?;
+            let file_name = path.file_name().ok_or("bad path")?.to_string_lossy().into_owned();
+            Ok((file_name, sums))
+        }).collect::<CDResult<Vec<_>>>()?;
+
+        let mut out = Vec::with_capacity(1024);
+        writeln!(&mut out, "Format: 1.8")?;
+        writeln!(&mut out, "Date: {}", rfc2822_date(self.timestamp))?;
+        writeln!(&mut out, "Source: {}", self.source)?;
+        writeln!(&mut out, "Binary: {}", self.binaries.iter().map(|b| b.name.as_str()).collect::<Vec<_>>().join(" "))?;
+        let mut architectures = vec![self.architecture.as_str()];
+        for b in &self.binaries {
+            if !architectures.contains(&b.architecture.as_str()) {
+                architectures.push(&b.architecture);
+            }
+        }
+        writeln!(&mut out, "Architecture: {}", architectures.join(" "))?;
+        writeln!(&mut out, "Version: {}", self.version)?;
+        writeln!(&mut out, "Distribution: {}", self.distribution)?;
+        writeln!(&mut out, "Urgency: {}", self.urgency)?;
+        writeln!(&mut out, "Maintainer: {}", self.maintainer)?;
+        writeln!(&mut out, "Changed-By: {}", self.maintainer)?;
+        writeln!(&mut out, "Description:")?;
+        for b in &self.binaries {
+            writeln!(&mut out, " {} - {}", b.name, b.description)?;
+        }
+        writeln!(&mut out, "Changes:")?;
+        for line in self.changes.lines() {
+            writeln!(&mut out, " {line}")?;
+        }
+        writeln!(&mut out, "Checksums-Sha1:")?;
+        for (name, sums) in &files {
+            writeln!(&mut out, " {} {} {name}", sums.sha1, sums.size)?;
+        }
+        writeln!(&mut out, "Checksums-Sha256:")?;
+        for (name, sums) in &files {
+            writeln!(&mut out, " {} {} {name}", sums.sha256, sums.size)?;
+        }
+        writeln!(&mut out, "Files:")?;
+        for ((name, sums), b) in files.iter().zip(self.binaries.iter().chain(std::iter::repeat(&self.binaries[0]))) {
+            writeln!(&mut out, " {} {} {} {} {name}", sums.md5, sums.size, b.section.as_deref().unwrap_or("-"), b.priority)?;
+        }
+        Ok(out)
+    }
+
+    /// Writes the file next to the main package, and returns its path
+    pub(crate) fn write(&self, paths: &[PathBuf]) -> CDResult<PathBuf> {
+        let path = self.path(&paths[0]);
+        let changes = self.generate(paths)?;
+        fs::write(&path, changes).map_err(|e| CargoDebError::IoFile("unable to write .changes file", e, path.clone()))?;
+        Ok(path)
+    }
+}
+
+/// Hex-encoded checksums that `.changes` files need
+pub(crate) struct FileSums {
+    pub size: u64,
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+impl FileSums {
+    pub(crate) fn new(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let (mut md5, mut sha1, mut sha256) = (Md5::new(), Sha1::new(), Sha256::new());
+        let mut size = 0;
+        let mut buf = vec![0; 1 << 16];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            md5.update(&buf[..n]);
+            sha1.update(&buf[..n]);
+            sha256.update(&buf[..n]);
+            size += n as u64;
+        }
+        Ok(Self {
+            size,
+            md5: to_hex(&md5.finalize()),
+            sha1: to_hex(&sha1.finalize()),
+            sha256: to_hex(&sha256.finalize()),
+        })
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+pub(crate) fn read_changelog(path: &Path) -> CDResult<String> {
+    let read_error = |e| CargoDebError::IoFile("unable to read changelog file", e, path.to_owned());
+    let content = fs::read(path).map_err(read_error)?;
+    let content = if path.extension().is_some_and(|e| e == "gz") {
+        let mut text = Vec::new();
+        flate2::read::GzDecoder::new(&content[..]).read_to_end(&mut text).map_err(read_error)?;
+        text
+    } else {
+        content
+    };
+    Ok(String::from_utf8(content)?)
+}
+
+struct ChangelogEntry {
+    distribution: String,
+    urgency: Option<String>,
+    /// From the header line to the line before the ` -- ` trailer, with blank lines as `.`
+    lines: Vec<String>,
+}
+
+/// Parses the `name (version) distribution; urgency=x` entry at the top of a Debian changelog
+fn first_changelog_entry(changelog: &str) -> Option<ChangelogEntry> {
+    let mut lines = changelog.lines().skip_while(|l| l.trim().is_empty());
+    let header = lines.next()?;
+    let (_, after_version) = header.split_once(") ")?;
+    let (distribution, params) = after_version.split_once(';').unwrap_or((after_version, ""));
+    let urgency = params.split(',')
+        .find_map(|p| p.trim().strip_prefix("urgency="))
+        .map(|u| u.trim().to_owned());
+
+    let mut entry_lines = vec![header.trim_end().to_owned()];
+    entry_lines.extend(lines
+        .take_while(|l| !l.starts_with(" -- "))
+        .map(|l| if l.trim().is_empty() { ".".into() } else { l.trim_end().to_owned() }));
+    while entry_lines.last().is_some_and(|l| l == ".") {
+        entry_lines.pop();
+    }
+    Some(ChangelogEntry {
+        distribution: distribution.trim().to_owned(),
+        urgency,
+        lines: entry_lines,
+    })
+}
+
+#[test]
+fn parses_changelog_entry() {
+    let entry = first_changelog_entry("\nexample (1.2-1) bookworm; urgency=low\n\n  * Fixed things\n\n -- Me <me@example.com>  Mon, 01 Jan 2024 00:00:00 +0000\n\nexample (1.1-1) unstable; urgency=high\n").unwrap();
+    assert_eq!(entry.distribution, "bookworm");
+    assert_eq!(entry.urgency.as_deref(), Some("low"));
+    assert_eq!(entry.lines, ["example (1.2-1) bookworm; urgency=low", ".", "  * Fixed things"]);
+}
+
+#[test]
+fn generates_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let deb = dir.path().join("example_1.0_amd64.deb");
+    fs::write(&deb, "abc").unwrap();
+    let changes = ChangesFile {
+        source: "example".into(),
+        version: "1.0".into(),
+        architecture: "amd64".into(),
+        distribution: "unstable".into(),
+        urgency: "medium".into(),
+        maintainer: "Me <me@example.com>".into(),
+        timestamp: 0,
+        changes: "example (1.0) unstable; urgency=medium\n.\n  * Release 1.0".into(),
+        binaries: vec![ChangesBinary { name: "example".into(), description: "An example".into(), section: None, priority: "optional".into(), architecture: "amd64".into() }],
+    };
+    let out = String::from_utf8(changes.generate(std::slice::from_ref(&deb)).unwrap()).unwrap();
+    assert!(out.starts_with("Format: 1.8\nDate: Thu, 01 Jan 1970 00:00:00 +0000\nSource: example\nBinary: example\n"));
+    assert!(out.contains("\nDescription:\n example - An example\nChanges:\n example (1.0) unstable; urgency=medium\n .\n   * Release 1.0\n"));
+    assert!(out.contains("\nChecksums-Sha1:\n a9993e364706816aba3e25717850c26c9cd0d89d 3 example_1.0_amd64.deb\n"));
+    assert!(out.contains("\nChecksums-Sha256:\n ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad 3 example_1.0_amd64.deb\n"));
+    assert!(out.ends_with("\nFiles:\n 900150983cd24fb0d6963f7d28e17f72 3 - optional example_1.0_amd64.deb\n"));
+    assert_eq!(changes.path(&deb), dir.path().join("example_1.0_amd64.changes"));
+}
@@ -0,0 +1,91 @@
+use crate::error::{CDResult, CargoDebError};
+use sha2::{Digest, Sha256, Sha512};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+/// Hash of the `<deb>.<algorithm>` files written with `--checksums`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    fn hash_file(self, path: &Path) -> io::Result<String> {
+        match self {
+            Self::Sha256 => hash_file::<Sha256>(path),
+            Self::Sha512 => hash_file::<Sha512>(path),
+        }
+    }
+}
+
+impl std::str::FromStr for ChecksumAlgorithm {
+    type Err = CargoDebError;
+
+    fn from_str(s: &str) -> CDResult<Self> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            _ => Err(CargoDebError::Str("supported checksums are sha256 and sha512")),
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Writes `<path>.sha256` or similar in the format of `sha256sum`, so that `sha256sum -c` can check it.
+///
+/// Returns the hex-encoded checksum and the path of the checksum file.
+pub(crate) fn write_checksum_file(path: &Path, algorithm: ChecksumAlgorithm) -> CDResult<(String, PathBuf)> {
+    let checksum = algorithm.hash_file(path).map_err(|e| CargoDebError::IoFile("unable to read package for checksum", e, path.to_owned()))?;
+    let file_name = path.file_name().ok_or("bad path")?.to_string_lossy();
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".");
+    sidecar.push(algorithm.extension());
+    let sidecar = PathBuf::from(sidecar);
+    fs::write(&sidecar, format!("{checksum}  {file_name}\n")).map_err(|e| CargoDebError::IoFile("unable to write checksum file", e, sidecar.clone()))?;
+    Ok((checksum, sidecar))
+}
+
+fn hash_file<D: Digest>(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(super::changes::to_hex(&hasher.finalize()))
+}
+
+#[test]
+fn writes_checksum_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let deb = dir.path().join("example_1.0_amd64.deb");
+    fs::write(&deb, "abc").unwrap();
+    let (checksum, path) = write_checksum_file(&deb, ChecksumAlgorithm::Sha256).unwrap();
+    assert_eq!(checksum, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(path, dir.path().join("example_1.0_amd64.deb.sha256"));
+    assert_eq!(fs::read_to_string(path).unwrap(), format!("{checksum}  example_1.0_amd64.deb\n"));
+    let (checksum, _) = write_checksum_file(&deb, ChecksumAlgorithm::Sha512).unwrap();
+    assert!(checksum.starts_with("ddaf35a193617aba"));
+    assert_eq!("sha512".parse::<ChecksumAlgorithm>().unwrap(), ChecksumAlgorithm::Sha512);
+    assert!("md5".parse::<ChecksumAlgorithm>().is_err());
+}
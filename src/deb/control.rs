@@ -1,13 +1,13 @@
 use crate::config::{Config, PackageConfig};
 use crate::deb::tar::Tarball;
-use crate::dh::{dh_installsystemd, dh_lib};
+use crate::dh::{dh_apparmor, dh_appstream, dh_icons, dh_installalternatives, dh_installdbus, dh_installdeb, dh_installdebconf, dh_installinit, dh_installlogrotate, dh_installpolkit, dh_installsystemd, dh_installsysusers, dh_installtmpfiles, dh_installudev, dh_lib, dh_selinux, dh_setcap, dh_statoverride, dh_ucf};
 use crate::error::{CDResult, CargoDebError};
 use crate::listener::Listener;
 use crate::util::{is_path_file, read_file_to_bytes};
 use dh_lib::ScriptFragments;
-use std::fs;
 use std::io::Write;
 use std::path::Path;
+use std::process::{Command, Output, Stdio};
 
 pub struct ControlArchiveBuilder<'l, W: Write> {
     archive: Tarball<W>,
@@ -29,10 +29,17 @@ impl<'l, W: Write> ControlArchiveBuilder<'l, W> {
         if let Some(files) = package_deb.conf_files() {
             self.add_conf_files(&files)?;
         }
+        dh_installlogrotate::check_configs(&package_deb.assets.resolved, self.listener)?;
+        dh_installudev::check_rules(&package_deb.assets.resolved, self.listener);
+        dh_installdbus::check_files(&package_deb.assets.resolved, self.listener)?;
+        dh_installpolkit::check_files(&package_deb.assets.resolved, self.listener)?;
+        dh_icons::check_desktop_files(&package_deb.assets.resolved, self.listener)?;
+        dh_appstream::check_metainfo(&package_deb.assets.resolved, self.listener)?;
 
         self.generate_scripts(config, package_deb)?;
-        if let Some(rel_path) = &package_deb.triggers_file_rel_path {
-            self.add_triggers_file(config, rel_path)?;
+        if let Some(triggers) = package_deb.archive_triggers(config)? {
+            let source_path = package_deb.triggers_file_rel_path.as_ref().map(|p| config.path_in_package(p));
+            self.add_file_with_log("./triggers".as_ref(), triggers.as_bytes(), 0o644, source_path.as_deref().and_then(Path::to_str))?;
         }
         Ok(())
     }
@@ -45,72 +52,113 @@ impl<'l, W: Write> ControlArchiveBuilder<'l, W> {
     /// postrm and templates) present in the `maintainer_scripts` path to the
     /// archive, if `maintainer_scripts` is configured.
     ///
-    /// Additionally, when `systemd_units` is configured, shell script fragments
+    /// Additionally, generated shell script fragments, such as the ones
     /// "for enabling, disabling, starting, stopping and restarting systemd unit
-    /// files" (quoting man 1 dh_installsystemd) will replace the `#DEBHELPER#`
-    /// token in the provided maintainer scripts.
+    /// files" (quoting man 1 dh_installsystemd) when `systemd_units` is configured,
+    /// will replace the `#DEBHELPER#` token in the provided maintainer scripts.
+    /// Fragments from multiple sources are combined in the order of `dh_lib::add_fragment()`.
     ///
     /// If a shell fragment cannot be inserted because the target script is missing
     /// then the entire script will be generated and appended to the archive.
     ///
     /// # Requirements
     ///
-    /// When `systemd_units` is configured, user supplied `maintainer_scripts` must
+    /// When any fragments are generated, user supplied `maintainer_scripts` must
     /// contain a `#DEBHELPER#` token at the point where shell script fragments
     /// should be inserted.
     fn generate_scripts(&mut self, config: &Config, package_deb: &PackageConfig) -> CDResult<()> {
-        if let Some(ref maintainer_scripts_dir) = package_deb.maintainer_scripts_rel_path {
-            let maintainer_scripts_dir = config.path_in_package(maintainer_scripts_dir);
-            let mut scripts = ScriptFragments::with_capacity(0);
-
-            if let Some(systemd_units_config_vec) = &package_deb.systemd_units {
-                for systemd_units_config in systemd_units_config_vec {
-                    // Select and populate autoscript templates relevant to the unit
-                    // file(s) in this package and the configuration settings chosen.
-                    scripts = dh_installsystemd::generate(
-                        &package_deb.name,
-                        &package_deb.assets.resolved,
-                        &dh_installsystemd::Options::from(systemd_units_config),
-                        self.listener,
-                    )?;
-
-                    // Get Option<&str> from Option<String>
-                    let unit_name = systemd_units_config.unit_name.as_deref();
-
-                    // Replace the #DEBHELPER# token in the users maintainer scripts
-                    // and/or generate maintainer scripts from scratch as needed.
-                    dh_lib::apply(
-                        &maintainer_scripts_dir,
-                        &mut scripts,
-                        &package_deb.name,
-                        unit_name,
-                        self.listener,
-                    )?;
-                }
+        let maintainer_scripts_dir = package_deb.maintainer_scripts_rel_path.as_ref().map(|dir| config.path_in_package(dir));
+        let mut scripts = ScriptFragments::with_capacity(0);
+
+        dh_installsysusers::generate(&mut scripts, &package_deb.name, &package_deb.assets.resolved, self.listener)?;
+
+        if package_deb.init_script.is_some() {
+            dh_installinit::generate(&mut scripts, &package_deb.name, &package_deb.deb_name, self.listener)?;
+        }
+        dh_installudev::generate(&mut scripts, &package_deb.name, &package_deb.assets.resolved, self.listener)?;
+        dh_apparmor::generate(&mut scripts, &package_deb.name, &package_deb.assets.resolved, self.listener)?;
+        dh_selinux::generate(&mut scripts, &package_deb.name, &package_deb.deb_name, &package_deb.assets.resolved, self.listener)?;
+        if let Some(module) = package_deb.dkms_module() {
+            module.generate(&mut scripts, &package_deb.name, self.listener)?;
+        }
+
+        let mut unit_name = None;
+        if let (Some(systemd_units_config_vec), Some(_)) = (&package_deb.systemd_units, &maintainer_scripts_dir) {
+            // install paths of the units that each of the entries has added
+            let entry_units = systemd_units_config_vec.iter()
+                .map(|c| Ok(config.systemd_unit_files(package_deb, c)?.into_values().map(|r| r.path).collect::<Vec<_>>()))
+                .collect::<CDResult<Vec<_>>>()?;
+            for (i, (systemd_units_config, units)) in systemd_units_config_vec.iter().zip(&entry_units).enumerate() {
+                // units added as regular assets use the options of the last entry
+                let is_last = i + 1 == systemd_units_config_vec.len();
+                let is_selected = |path: &Path| units.iter().any(|u| u == path) || (is_last && !entry_units.iter().flatten().any(|u| u == path));
+                // Select and populate autoscript templates relevant to the unit
+                // file(s) in this package and the configuration settings chosen.
+                let systemd_scripts = dh_installsystemd::generate(
+                    &package_deb.name,
+                    &package_deb.assets.resolved,
+                    &is_selected,
+                    &dh_installsystemd::Options::from(systemd_units_config),
+                    self.listener,
+                )?;
+                dh_lib::merge(&mut scripts, systemd_scripts)?;
+                unit_name = systemd_units_config.unit_name.as_deref();
             }
+        } else {
+            // dh_installsystemd creates them when there are systemd-units
+            dh_installtmpfiles::generate(&mut scripts, &package_deb.name, &package_deb.assets.resolved, self.listener)?;
+        }
+        let debconf = package_deb.uses_debconf(config);
+        dh_installdeb::maintscript(&mut scripts, &package_deb.name, &package_deb.maintscript, self.listener)?;
+        dh_ucf::generate(&mut scripts, &package_deb.name, &package_deb.deb_name, &package_deb.ucf_files, debconf, self.listener)?;
+        dh_installalternatives::generate(&mut scripts, &package_deb.name, &package_deb.alternatives, self.listener)?;
+        dh_setcap::generate(&mut scripts, &package_deb.name, &package_deb.capabilities, self.listener)?;
+        dh_statoverride::generate(&mut scripts, &package_deb.name, &package_deb.stat_overrides, self.listener)?;
+
+        // Replace the #DEBHELPER# token in the users maintainer scripts
+        // and/or generate maintainer scripts from scratch as needed.
+        // Without any fragments, the scripts are used as they are.
+        if !scripts.is_empty() {
+            dh_lib::apply(
+                maintainer_scripts_dir.as_deref(),
+                &mut scripts,
+                &package_deb.name,
+                unit_name,
+                self.listener,
+            )?;
+        }
 
-            // Add maintainer scripts to the archive, either those supplied by the
-            // user or if available prefer modified versions generated above.
-            for name in ["config", "preinst", "postinst", "prerm", "postrm", "templates"] {
-                let script_path;
-                let (contents, source_path) = match scripts.remove(name) {
-                    Some(script) => (script, Some("systemd_units")),
-                    None => {
-                        script_path = maintainer_scripts_dir.join(name);
-                        if !is_path_file(&script_path) {
-                            continue;
-                        }
-                        (read_file_to_bytes(&script_path)?, script_path.to_str())
+        // Add maintainer scripts to the archive, either those supplied by the
+        // user or if available prefer modified versions generated above.
+        for name in ["config", "preinst", "postinst", "prerm", "postrm", "templates"] {
+            let script_path;
+            let (mut contents, source_path) = match scripts.remove(name) {
+                Some(script) => (script, Some("generated fragments")),
+                None => {
+                    script_path = maintainer_scripts_dir.as_ref().map(|dir| dir.join(name));
+                    match &script_path {
+                        Some(script_path) if is_path_file(script_path) => (read_file_to_bytes(script_path)?, script_path.to_str()),
+                        _ if debconf && (name == "postinst" || name == "postrm") => (dh_installdebconf::EMPTY_SCRIPT.to_vec(), Some("debconf")),
+                        _ => continue,
                     }
-                };
-
-                // The config, postinst, postrm, preinst, and prerm
-                // control files should use mode 0755; all other control files should use 0644.
-                // See Debian Policy Manual section 10.9
-                // and lintian tag control-file-has-bad-permissions
-                let permissions = if name == "templates" { 0o644 } else { 0o755 };
-                self.add_file_with_log(name.as_ref(), &contents, permissions, source_path)?;
+                }
+            };
+            if name != "templates" {
+                contents = expand_script_variables(&contents, package_deb)?;
+            }
+            if debconf {
+                contents = dh_installdebconf::add_to_script(name, contents);
+            }
+            if name != "templates" {
+                check_script_syntax(name, &contents, self.listener)?;
             }
+
+            // The config, postinst, postrm, preinst, and prerm
+            // control files should use mode 0755; all other control files should use 0644.
+            // See Debian Policy Manual section 10.9
+            // and lintian tag control-file-has-bad-permissions
+            let permissions = if name == "templates" { 0o644 } else { 0o755 };
+            self.add_file_with_log(name.as_ref(), &contents, permissions, source_path)?;
         }
 
         Ok(())
@@ -138,14 +186,280 @@ impl<'l, W: Write> ControlArchiveBuilder<'l, W> {
         self.add_file_with_log("./conffiles".as_ref(), list.as_bytes(), 0o644, None)
     }
 
-    fn add_triggers_file(&mut self, config: &Config, rel_path: &Path) -> CDResult<()> {
-        let path = config.path_in_package(rel_path);
-        let content = match fs::read(&path) {
-            Ok(p) => p,
-            Err(e) => return Err(CargoDebError::IoFile("triggers file", e, path)),
+}
+
+/// Runs `sh -n` (or `bash -n`) on shell scripts, and `shellcheck` if it's installed.
+/// Syntax errors fail the build, and shellcheck's errors are only warnings.
+fn check_script_syntax(name: &str, script: &[u8], listener: &dyn Listener) -> CDResult<()> {
+    let shebang = script.strip_prefix(b"#!").map(|s| String::from_utf8_lossy(s.split(|&c| c == b'\n').next().unwrap_or_default()).into_owned());
+    let mut interpreter = shebang.as_deref().unwrap_or("/bin/sh").split_whitespace();
+    let mut shell = interpreter.next().unwrap_or_default().rsplit('/').next().unwrap_or_default();
+    if shell == "env" {
+        shell = interpreter.next().unwrap_or_default();
+    }
+    let shell = match shell {
+        "sh" | "dash" => "sh",
+        "bash" => "bash",
+        _ => return Ok(()),
+    };
+
+    let Some(output) = run_with_stdin(Command::new(shell).arg("-n"), script) else {
+        // no shell to check with, e.g. on Windows
+        return Ok(());
+    };
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("maintainer script has a syntax error", name.into(), output.stderr));
+    }
+    if let Some(output) = run_with_stdin(Command::new("shellcheck").args(["--severity=error", "--format=gcc", "--shell", shell, "-"]), script) {
+        if !output.status.success() {
+            listener.warning(format!("shellcheck found errors in {name}:\n{}", String::from_utf8_lossy(&output.stdout).trim_end()));
+        }
+    }
+    Ok(())
+}
+
+/// `None` if the command couldn't run
+fn run_with_stdin(cmd: &mut Command, stdin: &[u8]) -> Option<Output> {
+    let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().ok()?;
+    // the command may exit without reading everything
+    let _ = child.stdin.take()?.write_all(stdin);
+    child.wait_with_output().ok()
+}
+
+/// Replaces `{{package}}`, `{{version}}`, `{{arch}}`, and `{{assets:<glob>}}` (absolute paths of the matching assets, separated by spaces).
+///
+/// Other `{{…}}` are left as they are, because scripts may use them for other tools, like `docker ps --format '{{.Names}}'`.
+pub(crate) fn expand_script_variables(script: &[u8], package_deb: &PackageConfig) -> CDResult<Vec<u8>> {
+    let Ok(text) = std::str::from_utf8(script) else { return Ok(script.to_vec()) };
+    if !text.contains("{{") {
+        return Ok(script.to_vec());
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else { break };
+        let variable = &rest[start + 2..start + len];
+        let value = match variable.trim() {
+            "package" => package_deb.deb_name.clone(),
+            "version" => package_deb.deb_version.clone(),
+            "arch" => package_deb.architecture.clone(),
+            v => match v.strip_prefix("assets:") {
+                Some(pattern) => {
+                    let pattern = glob::Pattern::new(pattern.trim().trim_start_matches('/')).map_err(CargoDebError::GlobPatternError)?;
+                    package_deb.assets.resolved.iter()
+                        .filter(|a| pattern.matches_path(&a.c.target_path))
+                        .map(|a| shell_word(format!("/{}", a.c.target_path.display())))
+                        .collect::<Vec<_>>().join(" ")
+                },
+                None => {
+                    out.push_str(&rest[..start + len + 2]);
+                    rest = &rest[start + len + 2..];
+                    continue;
+                },
+            },
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&value);
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out.into_bytes())
+}
+
+/// Quotes the path if it has characters that the shell would split or expand
+fn shell_word(path: String) -> String {
+    if !path.is_empty() && path.bytes().all(|c| c.is_ascii_alphanumeric() || b"/._-+:@%,=".contains(&c)) {
+        return path;
+    }
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+/// Width of the text after the space that starts every line of the `Description` field
+const DESCRIPTION_WIDTH: usize = 79;
+
+/// Lines of an extended description, to write after a space in the `Description` field (Debian Policy 5.6.13).
+///
+/// Paragraphs are rewrapped, and blank lines become `.`. Lines that start with a space are displayed verbatim by apt, so they're kept as they are.
+/// Markdown is converted to plain text: lists and code blocks become verbatim lines, and images, HTML and link URLs are removed.
+pub(crate) fn format_extended_description(text: &str, markdown: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    // paragraph or list item waiting to be wrapped, with the prefixes of its first and other lines
+    let mut pending: Option<(String, String, String)> = None;
+    let mut in_code_block = false;
+    macro_rules! flush {
+        () => {
+            if let Some((first, rest, text)) = pending.take() {
+                push_wrapped(&mut lines, &first, &rest, &text);
+            }
         };
-        self.add_file_with_log("./triggers".as_ref(), &content, 0o644, path.to_str())
     }
+    for line in text.lines() {
+        let line = line.replace('\t', "    ");
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() && !in_code_block {
+            flush!();
+            if lines.last().is_some_and(|l| l != ".") {
+                lines.push(".".into());
+            }
+            continue;
+        }
+        if !markdown {
+            if line.starts_with(' ') {
+                flush!();
+                lines.push(line.into());
+            } else {
+                append_text(&mut pending, trimmed);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush!();
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(if trimmed.is_empty() { ".".into() } else { format!(" {line}") });
+            continue;
+        }
+        // HTML, link reference definitions, and rules or setext heading underlines
+        if trimmed.starts_with('<') || (trimmed.starts_with('[') && trimmed.contains("]: ")) || trimmed.chars().all(|c| matches!(c, '-' | '=' | '*' | '_' | ' ')) {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let text = markdown_inline(trimmed);
+        if text.trim().is_empty() {
+            // only badges
+            continue;
+        }
+        if let Some(heading) = trimmed.strip_prefix('#').map(|h| h.trim_start_matches('#')) {
+            flush!();
+            push_wrapped(&mut lines, "", "", markdown_inline(heading.trim()).trim_end_matches([' ', '#']));
+        } else if let Some(item) = list_item(trimmed) {
+            flush!();
+            let bullet = format!(" {}{} ", " ".repeat(indent), &trimmed[..item]);
+            let rest = " ".repeat(bullet.len());
+            pending = Some((bullet, rest, markdown_inline(&trimmed[item..]).trim().into()));
+        } else if indent >= 4 && pending.is_none() {
+            flush!();
+            lines.push(format!(" {line}"));
+        } else {
+            append_text(&mut pending, text.trim());
+        }
+    }
+    flush!();
+    if lines.last().is_some_and(|l| l == ".") {
+        lines.pop();
+    }
+    lines
+}
+
+fn append_text(pending: &mut Option<(String, String, String)>, text: &str) {
+    let (_, _, pending) = pending.get_or_insert_with(Default::default);
+    if !pending.is_empty() {
+        pending.push(' ');
+    }
+    pending.push_str(text);
+}
+
+/// Length of a markdown list marker like `- ` or `1. `
+fn list_item(line: &str) -> Option<usize> {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return Some(1);
+    }
+    let digits = line.find(|c: char| !c.is_ascii_digit())?;
+    (digits > 0 && line[digits..].starts_with(". ")).then_some(digits + 1)
+}
+
+/// Removes images, link URLs, and emphasis and code markers
+fn markdown_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['[', '`', '*', '_']) {
+        let (before, after) = rest.split_at(pos);
+        let is_image = before.ends_with('!');
+        out.push_str(if is_image { &before[..before.len() - 1] } else { before });
+        if let Some(link) = after.strip_prefix('[') {
+            // [text](url), with nested images in badges like [![alt](img)](url)
+            let mut depth = 1;
+            let end = link.char_indices().find(|&(_, c)| {
+                depth += match c { '[' => 1, ']' => -1, _ => 0 };
+                depth == 0
+            }).map(|(i, _)| i);
+            if let Some(end) = end {
+                if let Some(url_end) = link[end + 1..].strip_prefix('(').and_then(|u| u.find(')')) {
+                    if !is_image {
+                        out.push_str(&markdown_inline(&link[..end]));
+                    }
+                    rest = &link[end + 1 + 1 + url_end + 1..];
+                    continue;
+                }
+            }
+            out.push('[');
+            rest = link;
+        } else if let Some(r) = after.strip_prefix("**").or_else(|| after.strip_prefix("__")).or_else(|| after.strip_prefix('`')) {
+            rest = r;
+        } else {
+            // a single * or _ is more likely to be a literal
+            out.push_str(&after[..1]);
+            rest = &after[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn push_wrapped(lines: &mut Vec<String>, first_prefix: &str, rest_prefix: &str, text: &str) {
+    let mut line = first_prefix.to_owned();
+    let mut has_words = false;
+    for word in text.split_whitespace() {
+        if has_words && line.len() + 1 + word.len() > DESCRIPTION_WIDTH {
+            lines.push(std::mem::replace(&mut line, rest_prefix.to_owned()));
+            has_words = false;
+        }
+        if has_words {
+            line.push(' ');
+        }
+        line.push_str(word);
+        has_words = true;
+    }
+    if has_words {
+        lines.push(line);
+    }
+}
+
+#[test]
+fn extended_description_wrapping() {
+    let text = "A tool that does things.\nIt's the best tool.\n\n\n  verbatim   line\n\nThis paragraph is long enough that it has to be wrapped, because it's longer than the limit.\n";
+    assert_eq!(format_extended_description(text, false), [
+        "A tool that does things. It's the best tool.",
+        ".",
+        "  verbatim   line",
+        ".",
+        "This paragraph is long enough that it has to be wrapped, because it's longer",
+        "than the limit.",
+    ]);
+}
+
+#[test]
+fn extended_description_from_markdown() {
+    let text = "# my-tool\n\n[![Build](https://example.com/badge.svg)](https://example.com/ci)\n\nDoes **useful** things with [files](https://example.com/files) and `paths`.\n\n## Features\n\n- fast\n- works with a very large number of file formats, including all of the common ones and a few rare\n  ones too\n\n```sh\nmy-tool --help\n\nmy-tool file\n```\n<p align=center>hi</p>\n\n[files]: https://example.com\n";
+    assert_eq!(format_extended_description(text, true), [
+        "my-tool",
+        ".",
+        "Does useful things with files and paths.",
+        ".",
+        "Features",
+        ".",
+        " - fast",
+        " - works with a very large number of file formats, including all of the common",
+        "   ones and a few rare ones too",
+        ".",
+        " my-tool --help",
+        ".",
+        " my-tool file",
+    ]);
 }
 
 #[cfg(test)]
@@ -178,6 +492,7 @@ mod tests {
     use crate::listener::MockListener;
     use crate::parse::manifest::SystemdUnitsConfig;
     use crate::util::tests::{add_test_fs_paths, set_test_fs_path_content};
+    use crate::dh::dh_lib::generator_name;
     use crate::CargoLockingFlags;
     use std::io::prelude::Read;
     use std::collections::HashMap;
@@ -225,6 +540,9 @@ mod tests {
             Default::default(),
             None,
             None,
+            None,
+            None,
+            None,
             None, CargoLockingFlags::default(),
             mock_listener,
         )
@@ -327,10 +645,63 @@ mod tests {
             let expected_content = &format!("some contents: {script}");
             let filename = filename_from_path_str(script);
             let actual_content = archived_content.get(&filename).unwrap();
-            assert_eq!(expected_content, actual_content);
+            if filename == "postinst" || filename == "postrm" {
+                // because there's a debconf templates file
+                assert!(actual_content.starts_with(&format!("# Automatically added by {}\n", generator_name())), "{actual_content}");
+                assert!(actual_content.ends_with(expected_content), "{actual_content}");
+            } else {
+                assert_eq!(expected_content, actual_content);
+            }
         }
     }
 
+    #[test]
+    fn expands_script_variables() {
+        let mut listener = MockListener::new();
+        let (_, mut package_deb, _) = prepare(vec![], None, &mut listener);
+        package_deb.deb_version = "1.2.3-1".into();
+        package_deb.architecture = "amd64".into();
+        package_deb.assets.resolved = vec![
+            Asset::new(AssetSource::Data(vec![]), "usr/bin/a".into(), 0o755, IsBuilt::No, false),
+            Asset::new(AssetSource::Data(vec![]), "usr/bin/b".into(), 0o755, IsBuilt::No, false),
+            Asset::new(AssetSource::Data(vec![]), "usr/share/doc/x".into(), 0o644, IsBuilt::No, false),
+            Asset::new(AssetSource::Data(vec![]), "usr/share/my app/it's".into(), 0o644, IsBuilt::No, false),
+        ];
+        let script = expand_script_variables(b"echo {{package}} {{ version }} {{arch}}\nfor b in {{assets:/usr/bin/*}}; do :; done\n", &package_deb).unwrap();
+        assert_eq!(String::from_utf8(script).unwrap(), format!("echo {} 1.2.3-1 amd64\nfor b in /usr/bin/a /usr/bin/b; do :; done\n", package_deb.deb_name));
+        let script = expand_script_variables(b"rm {{assets:/usr/share/my*/*}}\ndocker ps --format '{{.Names}}' {{typo}}", &package_deb).unwrap();
+        assert_eq!(String::from_utf8(script).unwrap(), r"rm '/usr/share/my app/it'\''s'
+docker ps --format '{{.Names}}' {{typo}}");
+        assert_eq!(expand_script_variables(b"${x} {", &package_deb).unwrap(), b"${x} {");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn checks_script_syntax() {
+        let mut listener = MockListener::new();
+        listener.expect_warning().return_const(());
+        assert!(check_script_syntax("postinst", b"#!/bin/sh\nset -e\nif true; then echo ok; fi\n", &listener).is_ok());
+        let err = check_script_syntax("postinst", b"#!/bin/sh\nset -e\n\nif true; then echo ok\n", &listener).unwrap_err();
+        assert!(err.to_string().contains("postinst"), "{err}");
+        assert!(check_script_syntax("config", b"#!/usr/bin/perl\nif (\n", &listener).is_ok());
+    }
+
+    #[test]
+    fn generate_scripts_adds_debconf_scripts() {
+        let mut listener = MockListener::new();
+        let (config, mut package_deb, mut in_ar) = prepare(vec![], None, &mut listener);
+        set_test_fs_path_content("test-resources/testroot/debian/templates", "Template: test/port\nType: string\n".into());
+        set_test_fs_path_content("test-resources/testroot/debian/config", "#!/bin/sh\n".into());
+        package_deb.maintainer_scripts_rel_path.get_or_insert(PathBuf::from("debian"));
+        in_ar.generate_scripts(&config, &package_deb).unwrap();
+
+        let archive_bytes = in_ar.finish().unwrap();
+        let archived_content = extract_contents(&mut tar::Archive::new(&archive_bytes[..]));
+        assert_eq!(archived_content.len(), 4);
+        assert!(archived_content["postinst"].starts_with(&format!("#!/bin/sh\nset -e\n# Automatically added by {}\n. /usr/share/debconf/confmodule\n", generator_name())), "{}", archived_content["postinst"]);
+        assert!(archived_content["postrm"].contains("\tdb_purge\n"));
+    }
+
     #[test]
     fn generate_scripts_augments_maintainer_scripts_for_unit_in_root_package() {
         let maintainer_scripts = vec![
@@ -0,0 +1,457 @@
+use crate::assets::AssetSource;
+use crate::changelog::read_news;
+use crate::config::{is_glob_pattern, Config, PackageConfig};
+use crate::deb::source::{debian_changelog, debian_control};
+use crate::dh::{dh_apparmor, dh_installsystemd, dh_selinux, dh_setcap, dh_statoverride};
+use crate::dh::dh_install::normalized;
+use crate::dh::dh_lib::{pkgfile, ScriptFragments};
+use crate::error::{CDResult, CargoDebError};
+use crate::listener::Listener;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Maintainer scripts and other control files that debhelper reads from `debian/<package>.<name>`
+const CONTROL_FILES: [&str; 6] = ["config", "preinst", "postinst", "prerm", "postrm", "templates"];
+
+/// Writes files to `debian_dir` for building the package with debhelper and dh-cargo instead of cargo-deb.
+///
+/// Existing `control` and `rules` files are an error, and other existing files are kept with a warning. Returns paths of the written files.
+pub(crate) fn write_debian_dir(config: &Config, package_deb: &PackageConfig, debian_dir: &Path, listener: &dyn Listener) -> CDResult<Vec<PathBuf>> {
+    for name in ["control", "rules"] {
+        let path = debian_dir.join(name);
+        if path.exists() {
+            return Err(CargoDebError::IoFile("debian/ directory has already been set up", std::io::ErrorKind::AlreadyExists.into(), path));
+        }
+    }
+    let pkg = &package_deb.deb_name;
+    let install = DebianInstall::new(config, package_deb, debian_dir);
+
+    let mut build_depends = "debhelper-compat (= 13), dh-cargo".to_owned();
+    if dh_apparmor::profiles(&package_deb.assets.resolved).next().is_some() {
+        build_depends.push_str(", dh-apparmor");
+    }
+    if package_deb.dkms.is_some() {
+        build_depends.push_str(", dh-sequence-dkms");
+    }
+    if let Some(extra) = &package_deb.build_depends {
+        build_depends.push_str(", ");
+        build_depends.push_str(extra);
+    }
+    let depends = dh_depends(&package_deb.wildcard_depends);
+    let pre_depends = package_deb.pre_depends.as_deref().map(dh_pre_depends);
+    let multiarch = package_deb.multiarch.map(|m| m.as_str().to_owned());
+    let mut binary_fields = vec![("Depends", depends.as_str())];
+    for (name, enabled) in [("Essential", package_deb.essential), ("Protected", package_deb.protected), ("Important", package_deb.important)] {
+        if enabled {
+            binary_fields.push((name, "yes"));
+        }
+    }
+    for (name, value) in [
+        ("Pre-Depends", &pre_depends),
+        ("Recommends", &package_deb.recommends),
+        ("Suggests", &package_deb.suggests),
+        ("Enhances", &package_deb.enhances),
+        ("Conflicts", &package_deb.conflicts),
+        ("Breaks", &package_deb.breaks),
+        ("Replaces", &package_deb.replaces),
+        ("Provides", &package_deb.provides),
+        ("Built-Using", &package_deb.built_using),
+        ("Static-Built-Using", &package_deb.static_built_using),
+        ("Multi-Arch", &multiarch),
+    ] {
+        if let Some(value) = value {
+            binary_fields.push((name, value));
+        }
+    }
+    binary_fields.extend(package_deb.vendor_fields());
+
+    let mut files = vec![
+        ("changelog".to_owned(), debian_changelog(config, package_deb, listener)?, 0o644),
+        ("control".to_owned(), debian_control(config, package_deb, &build_depends, &binary_fields)?, 0o644),
+        ("copyright".to_owned(), config.generate_copyright_asset(package_deb)?.1, 0o644),
+        ("rules".to_owned(), debian_rules(package_deb, &install).into_bytes(), 0o755),
+        ("source/format".to_owned(), b"3.0 (quilt)\n".to_vec(), 0o644),
+    ];
+    for (extension, lines) in [("install", install.install_lines), ("links", install.link_lines), ("dirs", install.dir_lines)] {
+        if !lines.is_empty() {
+            files.push((format!("{pkg}.{extension}"), lines.into_bytes(), 0o644));
+        }
+    }
+    // dh_installchangelogs installs it as NEWS.Debian
+    if let Some(path) = &package_deb.news {
+        files.push((format!("{pkg}.NEWS"), read_news(&config.path_in_package(path))?.into_bytes(), 0o644));
+    }
+    if let Some(path) = &package_deb.init_script {
+        let init_script = std::fs::read(config.path_in_package(path)).map_err(|e| CargoDebError::IoFile("unable to read init script", e, config.path_in_package(path)))?;
+        files.push((format!("{pkg}.init"), init_script, 0o755));
+    }
+    if let Some(path) = &package_deb.logrotate {
+        let logrotate = std::fs::read(config.path_in_package(path)).map_err(|e| CargoDebError::IoFile("unable to read logrotate config", e, config.path_in_package(path)))?;
+        files.push((format!("{pkg}.logrotate"), logrotate, 0o644));
+    }
+    if let Some(path) = &package_deb.udev_rules {
+        let rules = std::fs::read(config.path_in_package(path)).map_err(|e| CargoDebError::IoFile("unable to read udev rules", e, config.path_in_package(path)))?;
+        files.push((format!("{pkg}.udev"), rules, 0o644));
+    }
+    if let Some(path) = &package_deb.initramfs_hook {
+        let hook = std::fs::read(config.path_in_package(path)).map_err(|e| CargoDebError::IoFile("unable to read initramfs hook", e, config.path_in_package(path)))?;
+        files.push((format!("{pkg}.initramfs-hook"), hook, 0o755));
+    }
+    // dh_dkms installs it as dkms.conf of the sources, which debian/install copies
+    if let Some(module) = package_deb.dkms_module() {
+        let dkms_conf = module.source_dir().join("dkms.conf");
+        if let Some(asset) = package_deb.assets.resolved.iter().find(|a| a.c.target_path == dkms_conf) {
+            files.push((format!("{pkg}.dkms"), asset.source.data()?.into_owned(), 0o644));
+        }
+    }
+    for (suffix, path) in package_deb.cron.files() {
+        let cron_file = std::fs::read(config.path_in_package(path)).map_err(|e| CargoDebError::IoFile("unable to read cron file", e, config.path_in_package(path)))?;
+        files.push((format!("{pkg}.cron.{suffix}"), cron_file, if suffix == "d" { 0o644 } else { 0o755 }));
+    }
+    // dh_installdeb marks files in /etc as conffiles
+    let conffiles: String = package_deb.conf_files.iter()
+        .filter(|f| !f.trim_start_matches('/').starts_with("etc/"))
+        .map(|f| format!("{f}\n")).collect();
+    if !conffiles.is_empty() {
+        files.push((format!("{pkg}.conffiles"), conffiles.into_bytes(), 0o644));
+    }
+    if !package_deb.ucf_files.is_empty() {
+        listener.warning("ucf assets need dh_ucf from the ucf package, so add dh-sequence-ucf to Build-Depends".into());
+        files.push((format!("{pkg}.ucf"), package_deb.ucf_files.iter().map(|(conffile, reference)| format!("{} {}\n", reference.display(), conffile.display())).collect::<String>().into_bytes(), 0o644));
+    }
+    if !package_deb.alternatives.is_empty() {
+        files.push((format!("{pkg}.alternatives"), package_deb.alternatives.iter().map(|a| a.to_debhelper()).collect::<Vec<_>>().join("\n").into_bytes(), 0o644));
+    }
+    if !package_deb.maintscript.is_empty() {
+        files.push((format!("{pkg}.maintscript"), package_deb.maintscript.iter().map(|l| format!("{l}\n")).collect::<String>().into_bytes(), 0o644));
+    }
+    if let Some(triggers) = package_deb.triggers(config)? {
+        files.push((format!("{pkg}.triggers"), triggers.into_bytes(), 0o644));
+    }
+    if let Some(scripts_dir) = &package_deb.maintainer_scripts_rel_path {
+        let scripts_dir = config.path_in_package(scripts_dir);
+        // debhelper already finds them there
+        if scripts_dir.canonicalize().ok() != debian_dir.canonicalize().ok() {
+            for name in CONTROL_FILES {
+                let Some(path) = pkgfile(&scripts_dir, &package_deb.name, &package_deb.name, name, None) else { continue };
+                let script = fs::read(&path).map_err(|e| CargoDebError::IoFile("unable to read maintainer script", e, path.clone()))?;
+                if name != "templates" && !script.windows(10).any(|w| w == b"#DEBHELPER#") {
+                    listener.warning(format!("{} has no #DEBHELPER# token, so debhelper won't add its snippets to it", path.display()));
+                }
+                if name != "templates" && script.windows(2).any(|w| w == b"{{") {
+                    listener.warning(format!("{} may have {{{{variables}}}}, which only cargo-deb expands", path.display()));
+                }
+                files.push((format!("{pkg}.{name}"), script, if name == "templates" { 0o644 } else { 0o755 }));
+            }
+        }
+    }
+
+    // debhelper has no helpers for these
+    let mut fragments = ScriptFragments::new();
+    dh_setcap::generate(&mut fragments, &package_deb.name, &package_deb.capabilities, listener)?;
+    dh_statoverride::generate(&mut fragments, &package_deb.name, &package_deb.stat_overrides, listener)?;
+    dh_selinux::generate(&mut fragments, &package_deb.name, &package_deb.deb_name, &package_deb.assets.resolved, listener)?;
+    add_script_fragments(&mut files, &fragments, package_deb, debian_dir, listener);
+
+    let mut written = Vec::with_capacity(files.len());
+    for (name, data, mode) in files {
+        let path = debian_dir.join(name);
+        if path.exists() {
+            listener.warning(format!("{} already exists, and hasn't been overwritten", path.display()));
+            continue;
+        }
+        write_new_file(&path, &data, mode)?;
+        listener.info(format!("Wrote {}", path.display()));
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Puts the fragments in the maintainer scripts before the `#DEBHELPER#` token, which debhelper replaces with its own.
+/// Scripts are made from scratch if the package has none.
+fn add_script_fragments(files: &mut Vec<(String, Vec<u8>, u32)>, fragments: &ScriptFragments, package_deb: &PackageConfig, debian_dir: &Path, listener: &dyn Listener) {
+    for name in ["preinst", "postinst", "prerm", "postrm"] {
+        let Some(fragment) = fragments.get(&format!("{}.{name}.debhelper", package_deb.name)) else { continue };
+        let file_name = format!("{}.{name}", package_deb.deb_name);
+        if let Some((_, script, _)) = files.iter_mut().find(|(n, ..)| *n == file_name) {
+            *script = insert_fragment(script, fragment);
+        } else if let Some(path) = pkgfile(debian_dir, &package_deb.name, &package_deb.name, name, None) {
+            // the maintainer-scripts dir is debian/, and its scripts aren't overwritten
+            listener.warning(format!("add this to {} before #DEBHELPER#:\n{}", path.display(), String::from_utf8_lossy(fragment)));
+        } else {
+            files.push((file_name, insert_fragment(b"#!/bin/sh\nset -e\n\n#DEBHELPER#\n", fragment), 0o755));
+        }
+    }
+}
+
+/// Before the `#DEBHELPER#` line, or after the `#!` line if the script has no token
+fn insert_fragment(script: &[u8], fragment: &[u8]) -> Vec<u8> {
+    let pos = script.windows(11).position(|w| w == b"#DEBHELPER#")
+        .or_else(|| script.starts_with(b"#!").then(|| script.iter().position(|&c| c == b'\n').map_or(script.len(), |p| p + 1)))
+        .unwrap_or(0);
+    [&script[..pos], fragment, &script[pos..]].concat()
+}
+
+/// The `$auto` dependencies are found by `dh_shlibdeps`
+fn dh_depends(wildcard_depends: &str) -> String {
+    let mut depends = dh_pre_depends(wildcard_depends);
+    if !depends.is_empty() {
+        depends.push_str(", ");
+    }
+    depends.push_str("${misc:Depends}");
+    depends
+}
+
+/// `dh_shlibdeps` puts all library dependencies in `${shlibs:Depends}`
+fn dh_pre_depends(pre_depends: &str) -> String {
+    pre_depends.split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| if d == "$auto" { "${shlibs:Depends}" } else { d })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Assets as `debian/<package>.install` lines, and `install` commands for the files `dh_install` can't handle
+struct DebianInstall {
+    install_lines: String,
+    link_lines: String,
+    dir_lines: String,
+    /// Renamed files, and files built by Cargo
+    install_commands: Vec<String>,
+    uses_rust_type: bool,
+}
+
+impl DebianInstall {
+    fn new(config: &Config, package_deb: &PackageConfig, debian_dir: &Path) -> Self {
+        let build_dir = config.path_in_build("");
+        let mut out = Self { install_lines: String::new(), link_lines: String::new(), dir_lines: String::new(), install_commands: Vec::new(), uses_rust_type: false };
+
+        let resolved = package_deb.assets.resolved.iter().filter_map(|a| match &a.source {
+            AssetSource::Path(path) | AssetSource::Symlink(path) => Some((path.as_path(), &a.c)),
+            AssetSource::SymlinkTo(link_name) => {
+                // dh_link takes the path the link points to, relative to the root
+                let link_target = normalized(&a.c.target_path.parent().unwrap_or(Path::new("")).join(link_name));
+                let _ = writeln!(out.link_lines, "{} {}", Path::new("/").join(link_target).display(), Path::new("/").join(&a.c.target_path).display());
+                None
+            },
+            AssetSource::Directory => {
+                let _ = writeln!(out.dir_lines, "{}", a.c.target_path.display());
+                None
+            },
+            // generated files, like the copyright file, are made by debhelper
+            AssetSource::Data(_) => None,
+        }).collect::<Vec<_>>();
+        // dh_install copies the DKMS sources with their subdirectories
+        let dkms = package_deb.dkms_module().map(|module| (config.path_in_package(&module.source), module.source_dir()));
+        if let Some((source_dir, target_dir)) = &dkms {
+            let source = source_dir.strip_prefix(&config.package_manifest_dir).unwrap_or(source_dir);
+            let _ = writeln!(out.install_lines, "{}/* {}", source.display(), target_dir.display());
+        }
+        let unresolved = package_deb.assets.unresolved.iter()
+            .filter(|a| dkms.as_ref().map_or(true, |(source_dir, _)| !a.source_path.starts_with(source_dir)))
+            .map(|a| (a.source_path.as_path(), &a.c));
+        // dh_installsystemd only finds units named after the package
+        let explicit_unit_files = package_deb.systemd_units.iter().flatten()
+            .flat_map(|u| u.unit_files.iter().flatten())
+            .map(|p| config.path_in_package(p))
+            .collect::<Vec<_>>();
+        for (source_path, c) in resolved.into_iter().chain(unresolved) {
+            let target_path = Path::new("/").join(&c.target_path);
+            // globs are copied into the target directory
+            let is_glob = is_glob_pattern(source_path);
+            let (target_dir, renamed) = if is_glob || c.target_path.to_string_lossy().ends_with('/') {
+                (target_path.as_path(), false)
+            } else {
+                (target_path.parent().unwrap_or(Path::new("/")), source_path.file_name() != target_path.file_name())
+            };
+
+            if let Ok(rel_path) = source_path.strip_prefix(&build_dir) {
+                // dh-cargo installs binaries with cargo install
+                if c.is_built() && !renamed && target_dir == Path::new("/usr/bin") {
+                    continue;
+                }
+                out.uses_rust_type = true;
+                let source = Path::new("target/$(DEB_HOST_RUST_TYPE)/release").join(rel_path);
+                out.push_command(&source, if renamed { &target_path } else { target_dir }, renamed, c.chmod, &package_deb.deb_name);
+                continue;
+            }
+            // dh_installinit, dh_installlogrotate, dh_installudev, dh_installinitramfs, and dh_installcron install them from debian/<package>.init, .logrotate, .udev, .initramfs-hook, and .cron.*
+            if [&package_deb.init_script, &package_deb.logrotate, &package_deb.udev_rules, &package_deb.initramfs_hook].into_iter().flatten().map(String::as_str)
+                .chain(package_deb.cron.files().map(|(_, p)| p))
+                .any(|p| config.path_in_package(p) == source_path) {
+                continue;
+            }
+            // unit files in debian/ are installed by dh_installsystemd
+            if source_path.parent() == Some(debian_dir) && (target_dir.starts_with("/lib/systemd") || target_dir.starts_with("/usr/lib/tmpfiles.d"))
+                && !explicit_unit_files.iter().any(|p| p == source_path) {
+                continue;
+            }
+            let source = source_path.strip_prefix(&config.package_manifest_dir).unwrap_or(source_path);
+            if renamed {
+                out.push_command(source, &target_path, true, c.chmod, &package_deb.deb_name);
+            } else {
+                let _ = writeln!(out.install_lines, "{} {}", source.display(), target_dir.strip_prefix("/").unwrap_or(target_dir).display());
+            }
+        }
+        out
+    }
+
+    /// Installs as `target`, or into the `target` directory
+    fn push_command(&mut self, source: &Path, target: &Path, is_file: bool, chmod: u32, package_name: &str) {
+        let dest = Path::new("debian").join(package_name).join(target.strip_prefix("/").unwrap_or(target));
+        self.install_commands.push(if is_file {
+            format!("install -D -m{chmod:o} '{}' '{}'", source.display(), dest.display())
+        } else {
+            // the source may be a glob, so it's not quoted
+            format!("install -D -m{chmod:o} -t '{}' {}", dest.display(), source.display())
+        });
+    }
+}
+
+fn debian_rules(package_deb: &PackageConfig, install: &DebianInstall) -> String {
+    let mut rules = format!("#!/usr/bin/make -f\n# Generated by cargo-deb {}\n\n", env!("CARGO_PKG_VERSION"));
+    if install.uses_rust_type {
+        rules.push_str("include /usr/share/rustc/architecture.mk\n\n");
+    }
+    rules.push_str("%:\n\tdh $@ --buildsystem cargo\n");
+
+    let systemd_commands = package_deb.systemd_units.iter().flatten().map(|units| {
+        let options = dh_installsystemd::Options::from(units);
+        let mut command = "dh_installsystemd".to_owned();
+        if let Some(name) = &units.unit_name {
+            let _ = write!(command, " --name={name}");
+        }
+        for (flag, set) in [
+            ("--no-enable", options.no_enable),
+            ("--no-start", options.no_start),
+            ("--no-restart-after-upgrade", !options.restart_after_upgrade),
+            ("--no-stop-on-upgrade", options.no_stop_on_upgrade),
+        ] {
+            if set {
+                command.push(' ');
+                command.push_str(flag);
+            }
+        }
+        // the units are installed by debian/install, and listed to be handled with these options
+        for unit_file in units.unit_files.iter().flatten() {
+            if let Some(name) = unit_file.file_name().and_then(|f| f.to_str()).filter(|f| !f.ends_with(".tmpfile")) {
+                command.push(' ');
+                command.push_str(name);
+            }
+        }
+        command
+    }).collect::<Vec<_>>();
+    if systemd_commands.iter().any(|c| c != "dh_installsystemd") {
+        rules.push_str("\noverride_dh_installsystemd:\n");
+        for command in &systemd_commands {
+            let _ = writeln!(rules, "\t{command}");
+        }
+    }
+
+    // the profiles are installed by debian/install, so dh_apparmor only adds its snippets
+    let apparmor_commands = dh_apparmor::profiles(&package_deb.assets.resolved)
+        .filter_map(|p| Some(format!("dh_apparmor --profile-name={} -p{}", p.file_name()?.to_str()?, package_deb.deb_name)));
+    let install_commands = install.install_commands.iter().cloned().chain(apparmor_commands).collect::<Vec<_>>();
+    if !install_commands.is_empty() {
+        rules.push_str("\nexecute_after_dh_install:\n");
+        for command in &install_commands {
+            let _ = writeln!(rules, "\t{command}");
+        }
+    }
+    rules
+}
+
+fn write_new_file(path: &Path, data: &[u8], mode: u32) -> CDResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| CargoDebError::IoFile("unable to create debian/ directory", e, parent.to_owned()))?;
+    }
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(path)
+        .map_err(|e| CargoDebError::IoFile("unable to create file in debian/ directory", e, path.to_owned()))?;
+    file.write_all(data).map_err(|e| CargoDebError::IoFile("unable to write file in debian/ directory", e, path.to_owned()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+#[test]
+fn dh_depends_replaces_auto() {
+    assert_eq!(dh_depends("$auto, foo (>= 1)"), "${shlibs:Depends}, foo (>= 1), ${misc:Depends}");
+    assert_eq!(dh_depends(""), "${misc:Depends}");
+    assert_eq!(dh_pre_depends("dpkg (>= 1.17), $auto"), "dpkg (>= 1.17), ${shlibs:Depends}");
+}
+
+#[test]
+fn inserts_script_fragments() {
+    assert_eq!(insert_fragment(b"#!/bin/sh\nset -e\n#DEBHELPER#\nexit 0\n", b"setcap\n"), b"#!/bin/sh\nset -e\nsetcap\n#DEBHELPER#\nexit 0\n");
+    assert_eq!(insert_fragment(b"#!/bin/sh\necho hi\n", b"setcap\n"), b"#!/bin/sh\nsetcap\necho hi\n");
+    assert_eq!(insert_fragment(b"echo hi\n", b"setcap\n"), b"setcap\necho hi\n");
+}
+
+#[test]
+fn writes_debian_dir() {
+    use crate::assets::{Asset, IsBuilt};
+    use crate::dh::dh_statoverride::StatOverride;
+
+    let mut mock_listener = crate::listener::MockListener::new();
+    mock_listener.expect_info().return_const(());
+    let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, crate::config::DebConfigOverrides::default(), None, None, None, None, None, None, crate::CargoLockingFlags::default(), &mock_listener).unwrap();
+    config.prepare_assets_before_build(&mut package_deb).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let written = write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).unwrap();
+    assert_eq!(written.len(), 5);
+    let control = fs::read_to_string(dir.path().join("control")).unwrap();
+    assert!(control.contains("\nBuild-Depends: debhelper-compat (= 13), dh-cargo\n"), "{control}");
+    assert!(control.contains("\nArchitecture: any\nDepends: ${shlibs:Depends}, ${misc:Depends}\n"), "{control}");
+    // the binary is installed by dh-cargo, and the README is renamed
+    let rules = fs::read_to_string(dir.path().join("rules")).unwrap();
+    assert!(rules.contains("\n%:\n\tdh $@ --buildsystem cargo\n"), "{rules}");
+    assert!(rules.ends_with("\nexecute_after_dh_install:\n\tinstall -D -m644 'README.md' 'debian/cargo-deb/usr/share/doc/cargo-deb/README'\n"), "{rules}");
+    assert!(!dir.path().join("cargo-deb.install").exists());
+
+    assert!(write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).is_err(), "must not overwrite");
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("copyright"), "mine").unwrap();
+    fs::create_dir(dir.path().join("source")).unwrap();
+    fs::write(dir.path().join("source/format"), "3.0 (native)\n").unwrap();
+    mock_listener.expect_warning().times(2).return_const(());
+    let written = write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).unwrap();
+    assert_eq!(written.len(), 3);
+    assert_eq!(fs::read_to_string(dir.path().join("copyright")).unwrap(), "mine");
+    assert!(dir.path().join("rules").exists());
+
+    let dir = tempfile::tempdir().unwrap();
+    package_deb.capabilities = vec![("/usr/bin/cargo-deb".into(), "cap_net_raw+ep".into())];
+    write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).unwrap();
+    let postinst = fs::read_to_string(dir.path().join("cargo-deb.postinst")).unwrap();
+    assert!(postinst.starts_with("#!/bin/sh\nset -e\n\n# Automatically added by"), "{postinst}");
+    assert!(postinst.contains("setcap cap_net_raw+ep \"/usr/bin/cargo-deb\""), "{postinst}");
+    assert!(postinst.ends_with("# End automatically added section\n#DEBHELPER#\n"), "{postinst}");
+
+    let dir = tempfile::tempdir().unwrap();
+    package_deb.capabilities.clear();
+    package_deb.stat_overrides = vec![StatOverride { path: "/usr/bin/cargo-deb".into(), owner: "root".into(), group: "staff".into(), mode: 0o4755 }];
+    write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).unwrap();
+    let postinst = fs::read_to_string(dir.path().join("cargo-deb.postinst")).unwrap();
+    assert!(postinst.contains("dpkg-statoverride --update --add root staff 4755 \"/usr/bin/cargo-deb\""), "{postinst}");
+    let postrm = fs::read_to_string(dir.path().join("cargo-deb.postrm")).unwrap();
+    assert!(postrm.contains("dpkg-statoverride --remove \"/usr/bin/cargo-deb\""), "{postrm}");
+
+    let dir = tempfile::tempdir().unwrap();
+    package_deb.stat_overrides.clear();
+    package_deb.assets.resolved.push(Asset::new(AssetSource::Path("Cargo.toml".into()), "usr/share/selinux/cargo-deb/cargo-deb.pp".into(), 0o644, IsBuilt::No, false));
+    write_debian_dir(&config, &package_deb, dir.path(), &mock_listener).unwrap();
+    let postinst = fs::read_to_string(dir.path().join("cargo-deb.postinst")).unwrap();
+    assert!(postinst.contains("semodule -i \"/usr/share/selinux/cargo-deb/cargo-deb.pp\""), "{postinst}");
+    let prerm = fs::read_to_string(dir.path().join("cargo-deb.prerm")).unwrap();
+    assert!(prerm.contains("semodule -r \"cargo-deb\""), "{prerm}");
+}
@@ -0,0 +1,325 @@
+use crate::error::{CDResult, CargoDebError};
+use ar::{Archive, Builder, Header};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Produces OpenPGP signatures of packages and `.changes` files.
+///
+/// Implement it to sign with a library, such as Sequoia, or a service, when using cargo-deb as a library.
+pub trait SigningBackend: Send + Sync {
+    /// Returns a detached signature of the data
+    fn detach_sign(&self, data: &mut (dyn Read + Send)) -> CDResult<Vec<u8>>;
+
+    /// Returns the text with an inline signature (`-----BEGIN PGP SIGNED MESSAGE-----`), as `.changes` files have
+    fn clearsign(&self, text: &[u8]) -> CDResult<Vec<u8>>;
+}
+
+/// Signs with `gpg`, or another program that accepts the same arguments
+#[derive(Debug, Clone, Default)]
+pub struct GpgSigner {
+    /// `gpg` if `None`
+    pub program: Option<PathBuf>,
+    /// Key ID or fingerprint, passed as `--local-user`. Otherwise gpg's default key is used.
+    pub key_id: Option<String>,
+    /// gpg's `--homedir`
+    pub homedir: Option<PathBuf>,
+}
+
+impl GpgSigner {
+    fn run(&self, mode: &str, data: &mut (dyn Read + Send)) -> CDResult<Vec<u8>> {
+        let program = self.program.as_deref().unwrap_or(Path::new("gpg"));
+        let mut cmd = Command::new(program);
+        cmd.args(["--batch", "--openpgp", mode, "--output", "-"]);
+        if let Some(key_id) = &self.key_id {
+            cmd.arg("--local-user").arg(key_id);
+        }
+        if let Some(homedir) = &self.homedir {
+            cmd.arg("--homedir").arg(homedir);
+        }
+        run_with_stdin(cmd, data, &program.display().to_string())
+    }
+}
+
+impl SigningBackend for GpgSigner {
+    fn detach_sign(&self, data: &mut (dyn Read + Send)) -> CDResult<Vec<u8>> {
+        self.run("--detach-sign", data)
+    }
+
+    fn clearsign(&self, mut text: &[u8]) -> CDResult<Vec<u8>> {
+        self.run("--clearsign", &mut text)
+    }
+}
+
+/// Runs a shell command, e.g. a client of an HSM.
+///
+/// `{in}` in the command is replaced with the path of a file with the data to sign,
+/// and `{out}` with the path where the command must write the signature.
+/// Without them, the data is given on stdin, and the signature is read from stdout.
+#[derive(Debug, Clone)]
+pub struct CommandSigner {
+    /// Makes a detached signature
+    pub detach_sign_command: String,
+    /// Makes an inline signature. Signing of `.changes` files fails without it.
+    pub clearsign_command: Option<String>,
+}
+
+impl CommandSigner {
+    fn run(command: &str, data: &mut (dyn Read + Send)) -> CDResult<Vec<u8>> {
+        let dir = tempfile::tempdir()?;
+        let in_path = dir.path().join("in");
+        let out_path = dir.path().join("out");
+        let uses_in = command.contains("{in}");
+        if uses_in {
+            io::copy(data, &mut File::create(&in_path)?)?;
+        }
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command.replace("{in}", &shell_quote(&in_path)).replace("{out}", &shell_quote(&out_path)));
+        if !command.contains("{out}") {
+            let mut empty = io::empty();
+            return run_with_stdin(cmd, if uses_in { &mut empty } else { data }, command);
+        }
+        let mut child = cmd.stdin(if uses_in { Stdio::null() } else { Stdio::piped() }).spawn()
+            .map_err(|e| CargoDebError::SignerFailed(e, command.into()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            io::copy(data, &mut stdin)?;
+        }
+        if !child.wait()?.success() {
+            return Err(CargoDebError::CommandError("signing failed", command.into(), Vec::new()));
+        }
+        // the path that `{out}` was replaced with
+        fs::read(&out_path).map_err(|e| CargoDebError::IoFile("signing command didn't write the signature", e, out_path))
+    }
+}
+
+impl SigningBackend for CommandSigner {
+    fn detach_sign(&self, data: &mut (dyn Read + Send)) -> CDResult<Vec<u8>> {
+        Self::run(&self.detach_sign_command, data)
+    }
+
+    fn clearsign(&self, mut text: &[u8]) -> CDResult<Vec<u8>> {
+        let command = self.clearsign_command.as_deref().ok_or("inline signatures need a clearsign command (--clearsign-command)")?;
+        Self::run(command, &mut text)
+    }
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// Feeds stdin from another thread, because the program may start writing before it has read everything
+fn run_with_stdin(mut cmd: Command, data: &mut (dyn Read + Send), program: &str) -> CDResult<Vec<u8>> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CargoDebError::SignerFailed(e, program.into()))?;
+
+    let mut stdin = child.stdin.take().ok_or("no stdin")?;
+    let (written, output) = std::thread::scope(|s| {
+        let writer = s.spawn(move || io::copy(data, &mut stdin));
+        let output = child.wait_with_output();
+        (writer.join(), output)
+    });
+    let output = output?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("signing failed", program.into(), output.stderr));
+    }
+    written.map_err(|_| "signing thread panicked")??;
+    if output.stdout.is_empty() {
+        return Err(CargoDebError::CommandError("signer didn't output a signature", program.into(), output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+/// Appends a `_gpgorigin` member to the `.deb`, in the format of `debsigs --sign=origin`.
+///
+/// The signature covers concatenated `debian-binary`, `control.tar.*` and `data.tar.*` members.
+pub fn sign_deb(deb_path: &Path, signer: &dyn SigningBackend) -> CDResult<()> {
+    let read_error = |e| CargoDebError::IoFile("unable to read package for signing", e, deb_path.to_owned());
+
+    // members are read from their offsets, because the ar crate can't give one reader over all of them
+    let mut archive = Archive::new(File::open(deb_path).map_err(read_error)?);
+    let mut signed_data: Box<dyn Read + Send> = Box::new(io::empty());
+    let mut offset = 8; // after "!<arch>\n"
+    let mut mtime = 0;
+    while let Some(entry) = archive.next_entry() {
+        let entry = entry.map_err(read_error)?;
+        let name = entry.header().identifier();
+        if name == b"debian-binary" {
+            mtime = entry.header().mtime();
+        } else if !name.starts_with(b"control.tar") && !name.starts_with(b"data.tar") {
+            return Err(read_error(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected member {}", String::from_utf8_lossy(name)))));
+        }
+        let size = entry.header().size();
+        let mut file = File::open(deb_path).map_err(read_error)?;
+        file.seek(SeekFrom::Start(offset + 60)).map_err(read_error)?;
+        signed_data = Box::new(signed_data.chain(file.take(size)));
+        // members are padded to an even size
+        offset += 60 + size + (size & 1);
+    }
+    let signature = signer.detach_sign(&mut signed_data)?;
+
+    // a new ar archive with one member, without the global header, can be appended to the existing archive
+    let mut header = Header::new(b"_gpgorigin".to_vec(), signature.len() as u64);
+    header.set_mode(0o100644);
+    header.set_mtime(mtime);
+    header.set_uid(0);
+    header.set_gid(0);
+    let mut member = Builder::new(Vec::new());
+    member.append(&header, &signature[..])?;
+    let member = member.into_inner()?;
+    let member = member.strip_prefix(b"!<arch>\n".as_slice()).ok_or("bad ar header")?;
+
+    let write_error = |e| CargoDebError::IoFile("unable to add signature to package", e, deb_path.to_owned());
+    let mut file = OpenOptions::new().append(true).open(deb_path).map_err(write_error)?;
+    file.write_all(member).map_err(write_error)?;
+    Ok(())
+}
+
+/// Replaces the file with its inline-signed version
+pub fn clearsign_file(path: &Path, signer: &dyn SigningBackend) -> CDResult<()> {
+    let text = fs::read(path).map_err(|e| CargoDebError::IoFile("unable to read file for signing", e, path.to_owned()))?;
+    let signed = signer.clearsign(&text)?;
+    fs::write(path, signed).map_err(|e| CargoDebError::IoFile("unable to write signed file", e, path.to_owned()))?;
+    Ok(())
+}
+
+/// Signs an apt repository's `Release` file, and writes `Release.gpg` and `InRelease` next to it
+pub fn sign_release(release_path: &Path, signer: &dyn SigningBackend) -> CDResult<()> {
+    let text = fs::read(release_path).map_err(|e| CargoDebError::IoFile("unable to read Release file", e, release_path.to_owned()))?;
+    let detached = armor_signature(&signer.detach_sign(&mut &text[..])?);
+    let inline = signer.clearsign(&text)?;
+    for (name, data) in [("Release.gpg", detached), ("InRelease", inline)] {
+        let path = release_path.with_file_name(name);
+        fs::write(&path, data).map_err(|e| CargoDebError::IoFile("unable to write signed file", e, path))?;
+    }
+    Ok(())
+}
+
+/// apt expects `Release.gpg` to be ASCII-armored, but signers may output binary signatures
+fn armor_signature(signature: &[u8]) -> Vec<u8> {
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    fn base64(data: &[u8]) -> Vec<u8> {
+        data.chunks(3).flat_map(|c| {
+            let n = (u32::from(c[0]) << 16) | (u32::from(*c.get(1).unwrap_or(&0)) << 8) | u32::from(*c.get(2).unwrap_or(&0));
+            (0..4).map(move |i| if i <= c.len() { BASE64[(n >> (18 - 6 * i) & 63) as usize] } else { b'=' })
+        }).collect()
+    }
+
+    if signature.starts_with(b"-----BEGIN PGP SIGNATURE-----") {
+        return signature.to_vec();
+    }
+    // CRC-24 from RFC 4880 section 6.1
+    let mut crc = 0xB7_04CE_u32;
+    for &b in signature {
+        crc ^= u32::from(b) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= 0x186_4CFB;
+            }
+        }
+    }
+    let mut out = b"-----BEGIN PGP SIGNATURE-----\n\n".to_vec();
+    for line in base64(signature).chunks(64) {
+        out.extend_from_slice(line);
+        out.push(b'\n');
+    }
+    out.push(b'=');
+    out.extend(base64(&crc.to_be_bytes()[1..]));
+    out.extend_from_slice(b"\n-----END PGP SIGNATURE-----\n");
+    out
+}
+
+#[test]
+fn armors_signatures() {
+    assert_eq!(armor_signature(b"hello"), b"-----BEGIN PGP SIGNATURE-----\n\naGVsbG8=\n=R/WK\n-----END PGP SIGNATURE-----\n");
+    let armored = b"-----BEGIN PGP SIGNATURE-----\n\nxx\n-----END PGP SIGNATURE-----\n";
+    assert_eq!(armor_signature(armored), armored);
+}
+
+#[cfg(test)]
+fn ar_members(deb_path: &Path) -> Vec<(String, u64, Vec<u8>)> {
+    let mut archive = Archive::new(File::open(deb_path).unwrap());
+    let mut members = Vec::new();
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry.unwrap();
+        let name = String::from_utf8(entry.header().identifier().to_vec()).unwrap();
+        let mtime = entry.header().mtime();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).unwrap();
+        members.push((name, mtime, data));
+    }
+    members
+}
+
+#[cfg(test)]
+fn test_deb(dir: &Path) -> PathBuf {
+    let deb_path = dir.join("test.deb");
+    let mut ar = Builder::new(File::create(&deb_path).unwrap());
+    for (name, data) in [("debian-binary", &b"2.0\n"[..]), ("control.tar.gz", b"control"), ("data.tar.xz", b"odd")] {
+        let mut header = Header::new(name.as_bytes().to_vec(), data.len() as u64);
+        header.set_mtime(1234);
+        ar.append(&header, data).unwrap();
+    }
+    deb_path
+}
+
+#[test]
+#[cfg(unix)]
+fn appends_gpgorigin() {
+    let dir = tempfile::tempdir().unwrap();
+    let deb_path = test_deb(dir.path());
+
+    // `cat` ignores the arguments, and outputs the signed data as its "signature"
+    let script = dir.path().join("fake-gpg");
+    fs::write(&script, "#!/bin/sh\ncat\n").unwrap();
+    fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let signer = GpgSigner { program: Some(script), key_id: Some("ABCD".into()), homedir: None };
+    sign_deb(&deb_path, &signer).unwrap();
+
+    let members = ar_members(&deb_path);
+    assert_eq!(members.len(), 4);
+    assert_eq!(members[3], ("_gpgorigin".into(), 1234, b"2.0\ncontrolodd".to_vec()));
+}
+
+#[test]
+#[cfg(unix)]
+fn signs_with_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let deb_path = test_deb(dir.path());
+    let signer = CommandSigner {
+        detach_sign_command: "(echo sig; cat {in}) > {out}".into(),
+        clearsign_command: Some("echo signed; cat".into()),
+    };
+    sign_deb(&deb_path, &signer).unwrap();
+    assert_eq!(ar_members(&deb_path)[3].2, b"sig\n2.0\ncontrolodd");
+
+    let changes = dir.path().join("test.changes");
+    fs::write(&changes, "Format: 1.8\n").unwrap();
+    clearsign_file(&changes, &signer).unwrap();
+    assert_eq!(fs::read_to_string(&changes).unwrap(), "signed\nFormat: 1.8\n");
+
+    let release = dir.path().join("Release");
+    fs::write(&release, "Origin: test\n").unwrap();
+    sign_release(&release, &signer).unwrap();
+    assert!(fs::read_to_string(dir.path().join("Release.gpg")).unwrap().starts_with("-----BEGIN PGP SIGNATURE-----\n\nc2lnCk9yaWdpbjogdGVzdAo="));
+    assert_eq!(fs::read_to_string(dir.path().join("InRelease")).unwrap(), "signed\nOrigin: test\n");
+
+    let no_clearsign = CommandSigner { detach_sign_command: "cat".into(), clearsign_command: None };
+    assert!(clearsign_file(&changes, &no_clearsign).is_err());
+
+    let no_output = CommandSigner { detach_sign_command: "true {in} {out}".into(), clearsign_command: None };
+    let err = no_output.detach_sign(&mut &b"data"[..]).unwrap_err().to_string();
+    assert!(err.starts_with("signing command didn't write the signature: /") && err.ends_with("/out"), "{err}");
+}
+
+#[test]
+fn reports_missing_signer() {
+    let signer = GpgSigner { program: Some("/nonexistent/my-signer".into()), key_id: None, homedir: None };
+    let err = signer.detach_sign(&mut &b"data"[..]).unwrap_err();
+    assert_eq!(err.to_string(), "Signing command /nonexistent/my-signer failed to launch");
+}
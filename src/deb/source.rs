@@ -0,0 +1,378 @@
+use crate::changelog::read_debian_changelog;
+use crate::config::{Config, PackageConfig};
+use crate::deb::changes::FileSums;
+use crate::error::{CDResult, CargoDebError};
+use crate::listener::Listener;
+use crate::util::compress::{self, Compressed, Format};
+use crate::util::rfc2822_date;
+use crate::util::wordsplit::WordSplit;
+use crate::CargoLockingFlags;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `Standards-Version` of the generated `debian/control`
+const STANDARDS_VERSION: &str = "4.7.0";
+
+/// Builds a `3.0 (quilt)` source package, and returns paths of the `.dsc`, `.orig.tar.xz`, and `.debian.tar.xz` files.
+///
+/// The `.crate` made by `cargo package` is the upstream tarball, like in Debian's own Rust packages,
+/// and the generated `debian/rules` builds the binary package with cargo-deb.
+///
+/// With `vendor`, dependencies are copied into the upstream tarball with `cargo vendor`, so that the package builds offline.
+pub(crate) fn write_source_package(config: &Config, package_deb: &PackageConfig, variant: Option<&str>, vendor: bool, cargo_locking_flags: CargoLockingFlags, listener: &dyn Listener) -> CDResult<Vec<PathBuf>> {
+    let version = package_deb.version_without_epoch();
+    let (upstream_version, _) = version.rsplit_once('-')
+        .ok_or_else(|| CargoDebError::InvalidVersion("3.0 (quilt) source packages need a Debian revision", package_deb.deb_version.clone()))?;
+    let out_dir = config.deb_output_path(package_deb).parent().ok_or("bad output path")?.to_owned();
+    fs::create_dir_all(&out_dir).map_err(|e| CargoDebError::IoFile("unable to create output directory", e, out_dir.clone()))?;
+
+    let crate_path = cargo_package(config, package_deb, cargo_locking_flags)?;
+    let orig_path = out_dir.join(format!("{}_{upstream_version}.orig.tar.xz", package_deb.deb_name));
+    let locking = if vendor {
+        let orig = vendored_tarball(config, package_deb, &crate_path, cargo_locking_flags, listener)?;
+        write_compressed(orig, &orig_path)?;
+        "--frozen"
+    } else {
+        let has_lockfile = crate_has_lockfile(&crate_path).map_err(|e| CargoDebError::IoFile("unable to read .crate file", e, crate_path.clone()))?;
+        let mut orig = compress::select_compressor(false, Format::Xz, None, None, false, &out_dir)?;
+        let crate_file = File::open(&crate_path).map_err(|e| CargoDebError::IoFile("unable to read .crate file", e, crate_path.clone()))?;
+        io::copy(&mut flate2::read::GzDecoder::new(crate_file), &mut orig)?;
+        write_compressed(orig.finish()?, &orig_path)?;
+        if has_lockfile { "--locked" } else { "" }
+    };
+
+    let rules = debian_rules(package_deb, variant, locking);
+    let files: [(&str, Vec<u8>, u32); 5] = [
+        ("debian/changelog", debian_changelog(config, package_deb, listener)?, 0o644),
+        ("debian/control", debian_control(config, package_deb, build_depends(package_deb), &[])?, 0o644),
+        ("debian/copyright", config.generate_copyright_asset(package_deb)?.1, 0o644),
+        ("debian/rules", rules.into_bytes(), 0o755),
+        ("debian/source/format", b"3.0 (quilt)\n".to_vec(), 0o644),
+    ];
+    let mut tar = tar::Builder::new(compress::select_compressor(false, Format::Xz, None, None, false, &out_dir)?);
+    for dir in ["debian/", "debian/source/"] {
+        let mut header = tar_header(package_deb.default_timestamp, 0o755, 0);
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_cksum();
+        tar.append_data(&mut header, dir, io::empty())?;
+    }
+    for (path, data, mode) in &files {
+        let mut header = tar_header(package_deb.default_timestamp, *mode, data.len() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, path, &data[..])?;
+    }
+    let debian_path = out_dir.join(format!("{}_{version}.debian.tar.xz", package_deb.deb_name));
+    write_compressed(tar.into_inner()?.finish()?, &debian_path)?;
+
+    let dsc_path = out_dir.join(format!("{}_{version}.dsc", package_deb.deb_name));
+    let dsc = generate_dsc(package_deb, &[orig_path.clone(), debian_path.clone()])?;
+    fs::write(&dsc_path, dsc).map_err(|e| CargoDebError::IoFile("unable to write .dsc file", e, dsc_path.clone()))?;
+    Ok(vec![dsc_path, orig_path, debian_path])
+}
+
+/// Runs `cargo package`, and returns the path of the `.crate` file
+pub(crate) fn cargo_package(config: &Config, package_deb: &PackageConfig, cargo_locking_flags: CargoLockingFlags) -> CDResult<PathBuf> {
+    let mut cmd = Command::new("cargo");
+    cmd.current_dir(&config.package_manifest_dir)
+        .args(["package", "--no-verify", "--allow-dirty", "--quiet", "--manifest-path"])
+        .arg(config.package_manifest_dir.join("Cargo.toml"))
+        .args(cargo_locking_flags.flags())
+        .env("CARGO_TARGET_DIR", &config.target_dir);
+    log::debug!("cargo package {:?}", cmd.get_args());
+    let output = cmd.output().map_err(|e| CargoDebError::CommandFailed(e, "cargo"))?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("cargo package failed", package_deb.name.clone(), output.stderr));
+    }
+    Ok(config.target_dir.join("package").join(format!("{}-{}.crate", package_deb.name, package_deb.cargo_version)))
+}
+
+/// Cargo includes the lockfile only for some packages, and `--locked` fails without one
+fn crate_has_lockfile(crate_path: &Path) -> io::Result<bool> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(File::open(crate_path)?));
+    for entry in archive.entries()? {
+        let path = entry?.path()?.into_owned();
+        if path.components().count() == 2 && path.ends_with("Cargo.lock") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Unpacks the `.crate`, runs `cargo vendor` in it, and configures Cargo to use the vendored sources
+fn vendored_tarball(config: &Config, package_deb: &PackageConfig, crate_path: &Path, cargo_locking_flags: CargoLockingFlags, listener: &dyn Listener) -> CDResult<Compressed> {
+    let temp_dir = tempfile::tempdir()?;
+    let crate_file = File::open(crate_path).map_err(|e| CargoDebError::IoFile("unable to read .crate file", e, crate_path.to_owned()))?;
+    tar::Archive::new(flate2::read::GzDecoder::new(crate_file)).unpack(temp_dir.path())
+        .map_err(|e| CargoDebError::IoFile("unable to unpack .crate file", e, crate_path.to_owned()))?;
+    let prefix = format!("{}-{}", package_deb.name, package_deb.cargo_version);
+    let crate_dir = temp_dir.path().join(&prefix);
+
+    listener.info("Vendoring dependencies".into());
+    let mut cmd = Command::new("cargo");
+    cmd.current_dir(&crate_dir)
+        .args(["vendor", "--versioned-dirs", "--manifest-path"])
+        .arg(crate_dir.join("Cargo.toml"))
+        .args(cargo_locking_flags.flags())
+        .arg("vendor");
+    log::debug!("cargo vendor {:?}", cmd.get_args());
+    let output = cmd.output().map_err(|e| CargoDebError::CommandFailed(e, "cargo"))?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("cargo vendor failed", package_deb.name.clone(), output.stderr));
+    }
+    // it's empty when there are no dependencies
+    if !output.stdout.iter().all(u8::is_ascii_whitespace) {
+        let cargo_config_path = crate_dir.join(".cargo/config.toml");
+        let mut cargo_config = fs::read(&cargo_config_path).unwrap_or_default();
+        cargo_config.extend_from_slice(&output.stdout);
+        fs::create_dir_all(crate_dir.join(".cargo"))?;
+        fs::write(&cargo_config_path, cargo_config).map_err(|e| CargoDebError::IoFile("unable to write Cargo config", e, cargo_config_path.clone()))?;
+    }
+
+    let mut tar = tar::Builder::new(compress::select_compressor(false, Format::Xz, None, None, false, &config.target_dir)?);
+    append_dir_sorted(&mut tar, &crate_dir, Path::new(&prefix), package_deb.default_timestamp)?;
+    tar.into_inner()?.finish()
+}
+
+/// Adds the directory in a stable order, with normalized metadata, to keep the tarball reproducible
+fn append_dir_sorted<W: Write>(tar: &mut tar::Builder<W>, dir: &Path, prefix: &Path, time: u64) -> CDResult<()> {
+    let mut header = tar_header(time, 0o755, 0);
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_cksum();
+    tar.append_data(&mut header, prefix, io::empty())?;
+
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let archive_path = prefix.join(entry.file_name());
+        let metadata = fs::metadata(&path).map_err(|e| CargoDebError::IoFile("unable to read vendored file", e, path.clone()))?;
+        if metadata.is_dir() {
+            append_dir_sorted(tar, &path, &archive_path, time)?;
+        } else {
+            let data = fs::read(&path).map_err(|e| CargoDebError::IoFile("unable to read vendored file", e, path.clone()))?;
+            let mode = if is_executable(&metadata) { 0o755 } else { 0o644 };
+            let mut header = tar_header(time, mode, data.len() as u64);
+            header.set_cksum();
+            tar.append_data(&mut header, &archive_path, &data[..])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_: &fs::Metadata) -> bool {
+    false
+}
+
+fn write_compressed(compressed: Compressed, path: &Path) -> CDResult<()> {
+    let mut file = File::create(path).map_err(|e| CargoDebError::IoFile("unable to create source package file", e, path.to_owned()))?;
+    io::copy(&mut compressed.into_reader(), &mut file).map_err(|e| CargoDebError::IoFile("unable to write source package file", e, path.to_owned()))?;
+    Ok(())
+}
+
+fn tar_header(time: u64, mode: u32, size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_mtime(time);
+    header.set_uid(0);
+    header.set_gid(0);
+    let _ = header.set_username("root");
+    let _ = header.set_groupname("root");
+    header.set_mode(mode);
+    header.set_size(size);
+    header
+}
+
+/// The package's changelog, or a single entry for this version
+pub(super) fn debian_changelog(config: &Config, package_deb: &PackageConfig, listener: &dyn Listener) -> CDResult<Vec<u8>> {
+    if let Some(changelog) = read_debian_changelog(config, package_deb)? {
+        // dpkg-buildpackage takes the version from the changelog
+        let changelog_version = changelog.lines().find(|l| !l.trim().is_empty())
+            .and_then(|header| header.split_once('(')?.1.split_once(')'))
+            .map(|(version, _)| version);
+        if changelog_version != Some(package_deb.deb_version.as_str()) {
+            listener.warning(format!("the latest version in the changelog is {}, but the package version is {}", changelog_version.unwrap_or("missing"), package_deb.deb_version));
+        }
+        return Ok(changelog.into_bytes());
+    }
+    Ok(format!("{} ({}) {}; urgency={}\n\n  * Release {}\n\n -- {}  {}\n",
+        package_deb.deb_name, package_deb.deb_version,
+        package_deb.distribution.as_deref().unwrap_or("unstable"), package_deb.urgency.as_deref().unwrap_or("medium"),
+        package_deb.deb_version, package_deb.maintainer, rfc2822_date(package_deb.default_timestamp),
+    ).into_bytes())
+}
+
+/// Source and binary paragraphs, with `binary_fields` added to the binary package's paragraph
+pub(super) fn debian_control(config: &Config, package_deb: &PackageConfig, build_depends: &str, binary_fields: &[(&str, &str)]) -> CDResult<Vec<u8>> {
+    let mut control = Vec::with_capacity(1024);
+    writeln!(&mut control, "Source: {}", package_deb.deb_name)?;
+    writeln!(&mut control, "Section: {}", section(package_deb))?;
+    writeln!(&mut control, "Priority: {}", package_deb.priority)?;
+    writeln!(&mut control, "Maintainer: {}", package_deb.maintainer)?;
+    writeln!(&mut control, "Build-Depends: {build_depends}")?;
+    writeln!(&mut control, "Standards-Version: {STANDARDS_VERSION}")?;
+    if let Some(homepage) = package_deb.homepage_url() {
+        writeln!(&mut control, "Homepage: {homepage}")?;
+    }
+    for (name, value) in package_deb.vcs_fields() {
+        writeln!(&mut control, "{name}: {value}")?;
+    }
+    writeln!(&mut control, "Rules-Requires-Root: no")?;
+    writeln!(&mut control)?;
+    writeln!(&mut control, "Package: {}", package_deb.deb_name)?;
+    writeln!(&mut control, "Architecture: {}", source_architecture(package_deb))?;
+    for (name, value) in binary_fields {
+        writeln!(&mut control, "{name}: {value}")?;
+    }
+    write!(&mut control, "Description:")?;
+    for line in package_deb.description.split_by_chars(79) {
+        writeln!(&mut control, " {line}")?;
+    }
+    for line in package_deb.extended_description_lines(config)? {
+        writeln!(&mut control, " {line}")?;
+    }
+    Ok(control)
+}
+
+/// A makefile that builds the package with cargo-deb, and tells `dpkg-genchanges` about it
+fn debian_rules(package_deb: &PackageConfig, variant: Option<&str>, locking: &str) -> String {
+    let mut cargo_deb_args = format!("--deb-version='{}'", package_deb.deb_version);
+    if !locking.is_empty() {
+        cargo_deb_args.push(' ');
+        cargo_deb_args.push_str(locking);
+    }
+    if let Some(variant) = variant {
+        cargo_deb_args.push_str(&format!(" --variant='{variant}'"));
+    }
+    // it may be from the command line, and the copy of the .deb needs the same file name
+    if let Some(template) = &package_deb.output_template {
+        cargo_deb_args.push_str(&format!(" --output-template='{template}'"));
+    }
+    format!("#!/usr/bin/make -f
+# Generated by cargo-deb {tool_version}
+
+export CARGO_HOME = $(CURDIR)/debian/cargo-home
+export CARGO_TARGET_DIR = $(CURDIR)/target
+DEB_FILE = {deb_file}
+
+build build-arch:
+\tcargo deb {cargo_deb_args}
+
+build-indep:
+
+binary binary-arch: build-arch
+\tcp target/debian/$(DEB_FILE) ../
+\tdpkg-distaddfile $(DEB_FILE) {section} {priority}
+
+binary-indep:
+
+clean:
+\trm -rf target debian/cargo-home debian/files
+
+.PHONY: build build-arch build-indep binary binary-arch binary-indep clean
+",
+        tool_version = env!("CARGO_PKG_VERSION"),
+        // like the file name that cargo-deb makes, which has no epoch
+        deb_file = package_deb.output_filename_for_arch(&package_deb.deb_name, package_deb.version_without_epoch(), "$(shell dpkg-architecture -qDEB_HOST_ARCH)", "deb"),
+        section = section(package_deb),
+        priority = package_deb.priority,
+    )
+}
+
+/// `dpkg-genchanges` needs the same section in `debian/control` and `debian/files`
+fn section(package_deb: &PackageConfig) -> &str {
+    package_deb.section.as_deref().unwrap_or("misc")
+}
+
+fn build_depends(package_deb: &PackageConfig) -> &str {
+    package_deb.build_depends.as_deref().unwrap_or("cargo, rustc")
+}
+
+fn source_architecture(package_deb: &PackageConfig) -> &'static str {
+    if package_deb.architecture == "all" { "all" } else { "any" }
+}
+
+/// See [dsc(5)](https://manpages.debian.org/dsc.5)
+fn generate_dsc(package_deb: &PackageConfig, files: &[PathBuf]) -> CDResult<Vec<u8>> {
+    let files = files.iter().map(|path| {
+        let sums = FileSums::new(path).map_err(|e| CargoDebError::IoFile("unable to read file for .dsc", e, path.clone()))?;
+        let file_name = path.file_name().ok_or("bad path")?.to_string_lossy().into_owned();
+        Ok((file_name, sums))
+    }).collect::<CDResult<Vec<_>>>()?;
+    let architecture = source_architecture(package_deb);
+
+    let mut out = Vec::with_capacity(1024);
+    writeln!(&mut out, "Format: 3.0 (quilt)")?;
+    writeln!(&mut out, "Source: {}", package_deb.deb_name)?;
+    writeln!(&mut out, "Binary: {}", package_deb.deb_name)?;
+    writeln!(&mut out, "Architecture: {architecture}")?;
+    writeln!(&mut out, "Version: {}", package_deb.deb_version)?;
+    writeln!(&mut out, "Maintainer: {}", package_deb.maintainer)?;
+    if let Some(homepage) = package_deb.homepage_url() {
+        writeln!(&mut out, "Homepage: {homepage}")?;
+    }
+    for (name, value) in package_deb.vcs_fields() {
+        writeln!(&mut out, "{name}: {value}")?;
+    }
+    writeln!(&mut out, "Standards-Version: {STANDARDS_VERSION}")?;
+    writeln!(&mut out, "Build-Depends: {}", build_depends(package_deb))?;
+    writeln!(&mut out, "Package-List:")?;
+    writeln!(&mut out, " {} deb {} {} arch={architecture}", package_deb.deb_name, section(package_deb), package_deb.priority)?;
+    writeln!(&mut out, "Checksums-Sha1:")?;
+    for (name, sums) in &files {
+        writeln!(&mut out, " {} {} {name}", sums.sha1, sums.size)?;
+    }
+    writeln!(&mut out, "Checksums-Sha256:")?;
+    for (name, sums) in &files {
+        writeln!(&mut out, " {} {} {name}", sums.sha256, sums.size)?;
+    }
+    writeln!(&mut out, "Files:")?;
+    for (name, sums) in &files {
+        writeln!(&mut out, " {} {} {name}", sums.md5, sums.size)?;
+    }
+    Ok(out)
+}
+
+#[test]
+fn generates_debian_files() {
+    let mut mock_listener = crate::listener::MockListener::new();
+    mock_listener.expect_info().return_const(());
+    let (config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, crate::config::DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+    let rules = debian_rules(&package_deb, Some("static"), "--locked");
+    assert!(rules.contains(&format!("\n\tcargo deb --deb-version='{}' --locked --variant='static'\n", package_deb.deb_version)), "{rules}");
+    assert!(rules.contains(&format!("\nDEB_FILE = cargo-deb_{}_$(shell dpkg-architecture -qDEB_HOST_ARCH).deb\n", package_deb.deb_version)), "{rules}");
+    assert!(debian_rules(&package_deb, None, "--frozen").contains(&format!("\n\tcargo deb --deb-version='{}' --frozen\n", package_deb.deb_version)));
+    assert!(rules.contains("\n\tdpkg-distaddfile $(DEB_FILE) utils optional\n"), "{rules}");
+
+    let version = std::mem::replace(&mut package_deb.deb_version, "2:1.0.0-1".into());
+    let rules = debian_rules(&package_deb, None, "");
+    assert!(rules.contains("\n\tcargo deb --deb-version='2:1.0.0-1'\n"), "{rules}");
+    assert!(rules.contains("\nDEB_FILE = cargo-deb_1.0.0-1_$(shell dpkg-architecture -qDEB_HOST_ARCH).deb\n"), "{rules}");
+    package_deb.output_template = Some("{name}-{version}-{arch}-{distro}.deb".into());
+    let rules = debian_rules(&package_deb, None, "");
+    assert!(rules.contains("\n\tcargo deb --deb-version='2:1.0.0-1' --output-template='{name}-{version}-{arch}-{distro}.deb'\n"), "{rules}");
+    assert!(rules.contains("\nDEB_FILE = cargo-deb-1.0.0-1-$(shell dpkg-architecture -qDEB_HOST_ARCH)-unstable.deb\n"), "{rules}");
+    package_deb.deb_version = version;
+    package_deb.output_template = None;
+
+    let control = String::from_utf8(debian_control(&config, &package_deb, build_depends(&package_deb), &[]).unwrap()).unwrap();
+    assert!(control.starts_with("Source: cargo-deb\nSection: utils\nPriority: optional\n"), "{control}");
+    assert!(control.contains("\nBuild-Depends: cargo, rustc\n"), "{control}");
+    assert!(control.contains("\n\nPackage: cargo-deb\nArchitecture: any\nDescription: Make Debian packages"), "{control}");
+
+    let dir = tempfile::tempdir().unwrap();
+    let orig = dir.path().join("cargo-deb_1.0.orig.tar.xz");
+    fs::write(&orig, "abc").unwrap();
+    let dsc = String::from_utf8(generate_dsc(&package_deb, &[orig]).unwrap()).unwrap();
+    assert!(dsc.starts_with("Format: 3.0 (quilt)\nSource: cargo-deb\nBinary: cargo-deb\nArchitecture: any\n"), "{dsc}");
+    assert!(dsc.contains("\nPackage-List:\n cargo-deb deb utils optional arch=any\n"), "{dsc}");
+    assert!(dsc.ends_with("\nFiles:\n 900150983cd24fb0d6963f7d28e17f72 3 cargo-deb_1.0.orig.tar.xz\n"), "{dsc}");
+}
@@ -4,9 +4,8 @@ use crate::listener::Listener;
 use crate::PackageConfig;
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Component, Path, PathBuf};
-use std::sync::mpsc;
 use std::{fs, io};
 use tar::{EntryType, Header as TarHeader};
 
@@ -27,67 +26,90 @@ impl<W: Write> Tarball<W> {
     }
 
     /// Copies all the files to be packaged into the tar archive.
-    /// Returns MD5 hashes of files copied
+    /// Returns SHA256 hashes of files copied
+    ///
+    /// Files on disk are streamed to the archive, rather than loaded into memory.
     pub fn archive_files(mut self, package_deb: &PackageConfig, rsyncable: bool, listener: &dyn Listener) -> CDResult<(W, HashMap<PathBuf, [u8; 32]>)> {
-        let hashes = std::thread::scope(|s| -> CDResult<_> {
-            let (send, recv) = mpsc::sync_channel(2);
-            let num_items = package_deb.assets.resolved.len();
-            let hash_thread = s.spawn(move || {
-                let mut hashes = HashMap::with_capacity(num_items);
-                hashes.extend(recv.into_iter().map(|(path, data)| {
-                    (path, Sha256::digest(data).into())
-                }));
-                hashes
-            });
-            let mut archive_data_added = 0;
-            let mut prev_is_built = false;
-
-            debug_assert!(package_deb.assets.unresolved.is_empty());
-            for asset in &package_deb.assets.resolved {
-                let mut log_line = format!("{} {}-> {}",
-                    asset.processed_from.as_ref().and_then(|p| p.original_path.as_deref())
-                        .or(asset.source.path())
-                        .unwrap_or_else(|| Path::new("-")).display(),
-                    asset.processed_from.as_ref().map(|p| p.action).unwrap_or_default(),
-                    asset.c.target_path.display()
-                );
-                if let Some(len) = asset.source.file_size() {
-                    let (size, unit) = human_size(len);
-                    use std::fmt::Write;
-                    let _ = write!(&mut log_line, " ({size}{unit})");
-                }
-                listener.info(log_line);
+        let mut hashes = HashMap::with_capacity(package_deb.assets.resolved.len());
+        let mut archive_data_added = 0;
+        let mut prev_is_built = false;
+
+        debug_assert!(package_deb.assets.unresolved.is_empty());
+        for asset in &package_deb.assets.resolved {
+            let mut log_line = format!("{} {}-> {}",
+                asset.processed_from.as_ref().and_then(|p| p.original_path.as_deref())
+                    .or(asset.source.path())
+                    .unwrap_or_else(|| Path::new("-")).display(),
+                asset.processed_from.as_ref().map(|p| p.action).unwrap_or_default(),
+                asset.c.target_path.display()
+            );
+            if let Some(len) = asset.source.file_size() {
+                let (size, unit) = human_size(len);
+                use std::fmt::Write;
+                let _ = write!(&mut log_line, " ({size}{unit})");
+            }
+            listener.info(log_line);
 
-                if let AssetSource::Symlink(source_path) = &asset.source {
+            let (len, reader): (u64, Box<dyn Read>) = match &asset.source {
+                AssetSource::Symlink(source_path) => {
                     let link_name = fs::read_link(source_path)
                         .map_err(|e| CargoDebError::IoFile("symlink asset", e, source_path.clone()))?;
                     self.symlink(&asset.c.target_path, &link_name)?;
-                } else {
-                    let out_data = asset.source.data()?;
-                    if rsyncable {
-                        if archive_data_added > 1_000_000 || prev_is_built != asset.c.is_built() {
-                            self.flush()?;
-                            archive_data_added = 0;
-                        }
-                        // puts synchronization point between non-code and code assets
-                        prev_is_built = asset.c.is_built();
-                        archive_data_added += out_data.len();
-                    }
-                    self.file(&asset.c.target_path, &out_data, asset.c.chmod)?;
-                    send.send((asset.c.target_path.clone(), out_data)).unwrap();
+                    continue;
+                },
+                AssetSource::SymlinkTo(link_name) => {
+                    self.symlink(&asset.c.target_path, link_name)?;
+                    continue;
+                },
+                AssetSource::Directory => {
+                    self.add_directories(&asset.c.target_path)?;
+                    continue;
+                },
+                AssetSource::Path(source_path) => {
+                    let read_error = |e| CargoDebError::IoFile("unable to read asset to add to archive", e, source_path.clone());
+                    let file = fs::File::open(source_path).map_err(read_error)?;
+                    (file.metadata().map_err(read_error)?.len(), Box::new(file))
+                },
+                AssetSource::Data(data) => (data.len() as u64, Box::new(&data[..])),
+            };
+            if rsyncable {
+                if archive_data_added > 1_000_000 || prev_is_built != asset.c.is_built() {
+                    self.flush()?;
+                    archive_data_added = 0;
                 }
+                // puts synchronization point between non-code and code assets
+                prev_is_built = asset.c.is_built();
+                archive_data_added += len;
+            }
+            let mut reader = HashingReader { inner: reader.take(len), hasher: Sha256::new(), len: 0 };
+            self.file_from_reader(&asset.c.target_path, len, &mut reader, asset.c.chmod)
+                .map_err(|e| CargoDebError::IoFile("unable to add asset to archive", e, asset.c.target_path.clone()))?;
+            if reader.len != len {
+                return Err(CargoDebError::IoFile("file changed while it was being archived", io::ErrorKind::UnexpectedEof.into(), asset.c.target_path.clone()));
             }
-            drop(send);
-            Ok(hash_thread.join().unwrap())
-        })?;
+            hashes.insert(asset.c.target_path.clone(), reader.hasher.finalize().into());
+        }
 
         let tar = self.tar.into_inner()?;
         Ok((tar, hashes))
     }
 
-    fn directory(&mut self, path: &Path) -> io::Result<()> {
+    /// All entries are owned by root, and have the same timestamp, so that the archive doesn't depend on who built it, and when.
+    ///
+    /// GNU headers are used, because `dpkg` supports them, and they allow paths longer than 100 bytes (via `././@LongLink` entries)
+    /// and files larger than 8GB (via base-256 sizes), which ustar can't represent.
+    fn header(&self) -> TarHeader {
         let mut header = TarHeader::new_gnu();
         header.set_mtime(self.time);
+        header.set_uid(0);
+        header.set_gid(0);
+        let _ = header.set_username("root");
+        let _ = header.set_groupname("root");
+        header
+    }
+
+    fn directory(&mut self, path: &Path) -> io::Result<()> {
+        let mut header = self.header();
         header.set_size(0);
         header.set_mode(0o755);
         // Lintian insists on dir paths ending with /, which Rust doesn't
@@ -100,10 +122,15 @@ impl<W: Write> Tarball<W> {
         self.tar.append_data(&mut header, path_str, &mut io::empty())
     }
 
-    fn add_parent_directories(&mut self, path: &Path) -> CDResult<()> {
+    fn add_parent_directories(&mut self, path: &Path) -> io::Result<()> {
+        self.add_directories(path.parent().ok_or(io::ErrorKind::InvalidInput)?)
+    }
+
+    /// Adds the directory and its parents, unless they've been added already
+    fn add_directories(&mut self, dir: &Path) -> io::Result<()> {
         // Append each of the directories found in the file's pathname to the archive before adding the file
         // For each directory pathname found, attempt to add it to the list of directories
-        let asset_relative_dir = Path::new(".").join(path.parent().ok_or("invalid asset")?);
+        let asset_relative_dir = Path::new(".").join(dir);
         let mut directory = PathBuf::new();
         for comp in asset_relative_dir.components() {
             match comp {
@@ -120,26 +147,24 @@ impl<W: Write> Tarball<W> {
     }
 
     pub(crate) fn file<P: AsRef<Path>>(&mut self, path: P, out_data: &[u8], chmod: u32) -> CDResult<()> {
-        self.file_(path.as_ref(), out_data, chmod)
+        self.file_from_reader(path.as_ref(), out_data.len() as u64, out_data, chmod)?;
+        Ok(())
     }
 
-    fn file_(&mut self, path: &Path, out_data: &[u8], chmod: u32) -> CDResult<()> {
+    fn file_from_reader(&mut self, path: &Path, len: u64, data: impl Read, chmod: u32) -> io::Result<()> {
         self.add_parent_directories(path)?;
 
-        let mut header = TarHeader::new_gnu();
-        header.set_mtime(self.time);
+        let mut header = self.header();
         header.set_mode(chmod);
-        header.set_size(out_data.len() as u64);
+        header.set_size(len);
         header.set_cksum();
-        self.tar.append_data(&mut header, path, out_data)?;
-        Ok(())
+        self.tar.append_data(&mut header, path, data)
     }
 
     pub(crate) fn symlink(&mut self, path: &Path, link_name: &Path) -> CDResult<()> {
         self.add_parent_directories(path.as_ref())?;
 
-        let mut header = TarHeader::new_gnu();
-        header.set_mtime(self.time);
+        let mut header = self.header();
         header.set_entry_type(EntryType::Symlink);
         header.set_size(0);
         header.set_mode(0o777);
@@ -157,6 +182,22 @@ impl<W: Write> Tarball<W> {
     }
 }
 
+/// Hashes the data as it's being archived
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.len += n as u64;
+        Ok(n)
+    }
+}
+
 fn human_size(len: u64) -> (u64, &'static str) {
     if len < 1000 {
         return (len, "B");
@@ -166,3 +207,66 @@ fn human_size(len: u64) -> (u64, &'static str) {
     }
     ((len + 999_999) / 1_000_000, "MB")
 }
+
+#[test]
+fn reproducible_headers() {
+    let mut tarball = Tarball::new(Vec::new(), 1_234_567);
+    tarball.file("usr/bin/foo", b"hello", 0o755).unwrap();
+    tarball.symlink(Path::new("usr/bin/bar"), Path::new("foo")).unwrap();
+    let data = tarball.into_inner().unwrap();
+
+    let mut archive = tar::Archive::new(&data[..]);
+    let entries = archive.entries().unwrap().map(|e| {
+        let e = e.unwrap();
+        let h = e.header();
+        assert_eq!(h.mtime().unwrap(), 1_234_567);
+        assert_eq!(h.uid().unwrap(), 0);
+        assert_eq!(h.gid().unwrap(), 0);
+        assert_eq!(h.username().unwrap(), Some("root"));
+        assert_eq!(h.groupname().unwrap(), Some("root"));
+        e.path().unwrap().into_owned()
+    }).collect::<Vec<_>>();
+    assert_eq!(entries, ["usr/", "usr/bin/", "usr/bin/foo", "usr/bin/bar"].map(PathBuf::from));
+}
+
+#[test]
+fn hashes_streamed_data() {
+    let data = vec![7u8; 100_000];
+    let mut reader = HashingReader { inner: &data[..], hasher: Sha256::new(), len: 0 };
+    let mut tarball = Tarball::new(Vec::new(), 0);
+    tarball.file_from_reader(Path::new("usr/share/foo"), data.len() as u64, &mut reader, 0o644).unwrap();
+    assert_eq!(reader.len, data.len() as u64);
+    assert_eq!(<[u8; 32]>::from(reader.hasher.finalize()), <[u8; 32]>::from(Sha256::digest(&data)));
+
+    let tar = tarball.into_inner().unwrap();
+    let mut archive = tar::Archive::new(&tar[..]);
+    let mut entry = archive.entries().unwrap().map(|e| e.unwrap()).find(|e| e.header().entry_type().is_file()).unwrap();
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content).unwrap();
+    assert_eq!(content, data);
+}
+
+#[test]
+fn long_paths_and_sizes() {
+    let long_dir = "usr/share/".to_string() + &"d".repeat(120);
+    let long_file = format!("{long_dir}/{}", "f".repeat(120));
+    let long_link = format!("{long_dir}/{}", "l".repeat(120));
+    let mut tarball = Tarball::new(Vec::new(), 0);
+    tarball.file(&long_file, b"long", 0o644).unwrap();
+    tarball.symlink(Path::new("usr/bin/link"), Path::new(&long_link)).unwrap();
+    let data = tarball.into_inner().unwrap();
+
+    let mut archive = tar::Archive::new(&data[..]);
+    let entries = archive.entries().unwrap().map(|e| {
+        let e = e.unwrap();
+        (e.path().unwrap().into_owned(), e.link_name().unwrap().map(|l| l.into_owned()))
+    }).collect::<Vec<_>>();
+    assert!(entries.contains(&(PathBuf::from(format!("{long_dir}/")), None)));
+    assert!(entries.contains(&(PathBuf::from(&long_file), None)));
+    assert!(entries.contains(&(PathBuf::from("usr/bin/link"), Some(PathBuf::from(long_link)))));
+
+    // sizes that don't fit in octal use the GNU base-256 encoding
+    let mut header = Tarball::new(Vec::new(), 0).header();
+    header.set_size(10 << 30);
+    assert_eq!(header.size().unwrap(), 10 << 30);
+}
@@ -0,0 +1,151 @@
+use crate::error::CDResult;
+use crate::util::compress::decompress;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+struct ArMember {
+    name: String,
+    mtime: u64,
+    data: Vec<u8>,
+}
+
+/// Compares two `.deb` files, and describes the first difference found, in the order of the `ar` members and tar entries.
+///
+/// Returns `None` if the files are identical.
+pub(crate) fn first_difference(a: &Path, b: &Path) -> CDResult<Option<String>> {
+    let (a, b) = (fs::read(a)?, fs::read(b)?);
+    if a == b {
+        return Ok(None);
+    }
+    let (a, b) = (ar_members(&a)?, ar_members(&b)?);
+    for i in 0..a.len().max(b.len()) {
+        let (a, b) = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(m), None) | (None, Some(m)) => return Ok(Some(format!("only one of the packages has {}", m.name))),
+            (None, None) => unreachable!(),
+        };
+        if a.name != b.name {
+            return Ok(Some(format!("ar member {i} is {} vs {}", a.name, b.name)));
+        }
+        if a.mtime != b.mtime {
+            return Ok(Some(format!("{} has mtime {} vs {}", a.name, a.mtime, b.mtime)));
+        }
+        if a.data == b.data {
+            continue;
+        }
+        if let Some(ext) = a.name.strip_prefix("control.tar").or_else(|| a.name.strip_prefix("data.tar")) {
+            let ext = ext.trim_start_matches('.');
+            let (a_tar, b_tar) = (decompress(ext, &a.data)?, decompress(ext, &b.data)?);
+            if let Some(difference) = first_tar_difference(&a_tar, &b_tar)? {
+                return Ok(Some(format!("{}: {difference}", a.name)));
+            }
+            return Ok(Some(format!("{} has the same files, but is compressed differently", a.name)));
+        }
+        return Ok(Some(format!("{} differs", a.name)));
+    }
+    Ok(Some("the ar archive headers differ".into()))
+}
+
+fn ar_members(data: &[u8]) -> CDResult<Vec<ArMember>> {
+    let mut archive = ar::Archive::new(data);
+    let mut members = Vec::new();
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(entry.header().identifier()).into_owned();
+        let mtime = entry.header().mtime();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        members.push(ArMember { name, mtime, data });
+    }
+    Ok(members)
+}
+
+fn first_tar_difference(a: &[u8], b: &[u8]) -> CDResult<Option<String>> {
+    let mut a = tar::Archive::new(a);
+    let mut b = tar::Archive::new(b);
+    let mut a_entries = a.entries()?;
+    let mut b_entries = b.entries()?;
+    loop {
+        let (mut a, mut b) = match (a_entries.next().transpose()?, b_entries.next().transpose()?) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(e), None) | (None, Some(e)) => return Ok(Some(format!("only one of the packages has {}", e.path()?.display()))),
+            (None, None) => return Ok(None),
+        };
+        let path = a.path()?.into_owned();
+        if path != b.path()? {
+            return Ok(Some(format!("{} vs {}", path.display(), b.path()?.display())));
+        }
+        let (ah, bh) = (a.header().clone(), b.header().clone());
+        let field = if ah.mtime()? != bh.mtime()? {
+            "mtime"
+        } else if ah.mode()? != bh.mode()? {
+            "mode"
+        } else if ah.uid()? != bh.uid()? || ah.gid()? != bh.gid()? || ah.username_bytes() != bh.username_bytes() || ah.groupname_bytes() != bh.groupname_bytes() {
+            "owner"
+        } else if ah.link_name_bytes() != bh.link_name_bytes() {
+            "symlink target"
+        } else if ah.size()? != bh.size()? {
+            "size"
+        } else {
+            let (mut a_data, mut b_data) = (Vec::new(), Vec::new());
+            a.read_to_end(&mut a_data)?;
+            b.read_to_end(&mut b_data)?;
+            if a_data != b_data {
+                "content"
+            } else if ah.as_bytes()[..] != bh.as_bytes()[..] {
+                "tar header"
+            } else {
+                continue;
+            }
+        };
+        return Ok(Some(format!("{} has different {field}", path.display())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deb::ar::DebArchive;
+    use crate::deb::tar::Tarball;
+    use crate::util::compress::{select_compressor, Format};
+    use std::io::Write;
+
+    fn make_deb(path: &Path, content: &[u8], time: u64) {
+        let mut deb = DebArchive::new(path.to_owned(), 0).unwrap();
+        let mut control = select_compressor(true, Format::Gzip, None, None, false, path.parent().unwrap()).unwrap();
+        control.write_all(b"not really a tarball").unwrap();
+        deb.add_control(control.finish().unwrap()).unwrap();
+        let mut tarball = Tarball::new(select_compressor(true, Format::Gzip, None, None, false, path.parent().unwrap()).unwrap(), time);
+        tarball.file("usr/share/a", b"same", 0o644).unwrap();
+        tarball.file("usr/share/b", content, 0o644).unwrap();
+        deb.add_data(tarball.into_inner().unwrap().finish().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn same() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.deb"), dir.path().join("b.deb"));
+        make_deb(&a, b"hello", 1);
+        make_deb(&b, b"hello", 1);
+        assert!(first_difference(&a, &b).unwrap().is_none());
+    }
+
+    #[test]
+    fn different_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.deb"), dir.path().join("b.deb"));
+        make_deb(&a, b"hello", 1);
+        make_deb(&b, b"world", 1);
+        assert_eq!(first_difference(&a, &b).unwrap().unwrap(), "data.tar.gz: usr/share/b has different content");
+    }
+
+    #[test]
+    fn different_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.deb"), dir.path().join("b.deb"));
+        make_deb(&a, b"hello", 1);
+        make_deb(&b, b"hello", 2);
+        assert_eq!(first_difference(&a, &b).unwrap().unwrap(), "data.tar.gz: usr/ has different mtime");
+    }
+}
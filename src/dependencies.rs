@@ -1,10 +1,35 @@
 use crate::debian_triple_from_rust_triple;
 use crate::error::{CDResult, CargoDebError};
+use crate::librust::debian_crate_name;
+use crate::symbols::{compare_versions, split_version, InstalledLibraries};
+use serde::Deserialize;
+use std::ffi::OsString;
 use std::path::Path;
 use std::process::Command;
 
+/// Libraries in the package being built, which aren't in any installed package
+#[derive(Debug, Default)]
+pub(crate) struct OwnLibraries<'a> {
+    /// Name of the package, which the binaries don't need to depend on
+    pub package: &'a str,
+    /// File names of the libraries
+    pub names: Vec<&'a str>,
+    /// Where the libraries are before they're packaged
+    pub dirs: Vec<&'a Path>,
+}
+
+/// Dependencies from the `shlibs:*` variables of dpkg-shlibdeps
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ShlibsDependencies {
+    pub pre_depends: Vec<String>,
+    pub depends: Vec<String>,
+    pub recommends: Vec<String>,
+}
+
 /// Resolves the dependencies based on the output of dpkg-shlibdeps on the binary.
-pub(crate) fn resolve(path: &Path, target: Option<&str>) -> CDResult<Vec<String>> {
+///
+/// The dependencies are for the `field`, like `Depends` or `Pre-Depends`, and all the fields that dpkg-shlibdeps sets are returned.
+pub(crate) fn resolve(path: &Path, target: Option<&str>, field: &str, own_libraries: &OwnLibraries<'_>) -> CDResult<ShlibsDependencies> {
     let temp_folder = tempfile::tempdir()?;
     let debian_folder = temp_folder.path().join("debian");
     let control_file_path = debian_folder.join("control");
@@ -12,15 +37,31 @@ pub(crate) fn resolve(path: &Path, target: Option<&str>) -> CDResult<Vec<String>
     // dpkg-shlibdeps requires a (possibly empty) debian/control file to exist in its working
     // directory. The executable location doesn't matter.
     let _ = std::fs::File::create(control_file_path);
+    // dpkg-shlibdeps needs an shlibs entry for versioned libraries. They're for the package itself, which is excluded with -x.
+    let shlibs_local = own_libraries.names.iter()
+        .filter_map(|name| shlibs_name_version(name))
+        .map(|(name, version)| format!("{name} {version} {}\n", own_libraries.package))
+        .collect::<String>();
+    if !shlibs_local.is_empty() {
+        std::fs::write(debian_folder.join("shlibs.local"), shlibs_local)?;
+    }
 
     // Print result to stdout instead of a file.
-    let mut args = vec!["-O"];
-    let libpath_arg;
+    let mut args = vec![OsString::from("-O")];
+    if !own_libraries.package.is_empty() {
+        args.push(format!("-x{}", own_libraries.package).into());
+    }
     // determine library search path from target
     if let Some(target) = target {
-        libpath_arg = format!("-l/usr/{}/lib", debian_triple_from_rust_triple(target));
-        args.push(&libpath_arg);
+        args.push(format!("-l/usr/{}/lib", debian_triple_from_rust_triple(target)).into());
+    }
+    for dir in &own_libraries.dirs {
+        let mut arg = OsString::from("-l");
+        arg.push(dir);
+        args.push(arg);
     }
+    // applies to the binaries after it
+    args.push(format!("-d{field}").into());
     const DPKG_SHLIBDEPS_COMMAND: &str = "dpkg-shlibdeps";
     let output = Command::new(DPKG_SHLIBDEPS_COMMAND)
         .args(args)
@@ -37,26 +78,401 @@ pub(crate) fn resolve(path: &Path, target: Option<&str>) -> CDResult<Vec<String>
     }
 
     log::debug!("dpkg-shlibdeps for {}: {}", path.display(), String::from_utf8_lossy(&output.stdout));
+    parse_substvars(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `shlibs:Depends=…` lines, and the other fields
+fn parse_substvars(output: &str) -> CDResult<ShlibsDependencies> {
+    let mut deps = ShlibsDependencies::default();
+    let mut found = false;
+    for (name, value) in output.lines().filter_map(|line| line.strip_prefix("shlibs:")?.split_once('=')) {
+        found = true;
+        let field = match name {
+            "Pre-Depends" => &mut deps.pre_depends,
+            "Depends" => &mut deps.depends,
+            "Recommends" => &mut deps.recommends,
+            _ => continue,
+        };
+        field.extend(value.split(',')
+            .map(str::trim)
+            // libgcc guaranteed by LSB to always be present
+            .filter(|dep| !dep.is_empty() && !dep.starts_with("libgcc-") && !dep.starts_with("libgcc1"))
+            .map(String::from));
+    }
+    if !found {
+        return Err(CargoDebError::Str("Failed to find dependency specification."));
+    }
+    Ok(deps)
+}
+
+/// Library name and version in shlibs files, like `libfoo 1` for `libfoo.so.1`, or `libfoo 1.2` for `libfoo-1.2.so`
+fn shlibs_name_version(soname: &str) -> Option<(&str, &str)> {
+    if let Some((name, version)) = soname.split_once(".so.") {
+        return Some((name, version));
+    }
+    soname.strip_suffix(".so")?.rsplit_once('-').filter(|(_, version)| version.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Libraries whose packages aren't named after their sonames
+const SONAME_PACKAGES: [(&str, &str); 13] = [
+    ("libc.so.6", "libc6"),
+    ("libm.so.6", "libc6"),
+    ("libdl.so.2", "libc6"),
+    ("libpthread.so.0", "libc6"),
+    ("librt.so.1", "libc6"),
+    ("libutil.so.1", "libc6"),
+    ("libresolv.so.2", "libc6"),
+    ("libz.so.1", "zlib1g"),
+    ("libstdc++.so.6", "libstdc++6"),
+    ("libcrypto.so.3", "libssl3"),
+    ("libcrypto.so.1.1", "libssl1.1"),
+    ("libselinux.so.1", "libselinux1"),
+    ("libgcc_s.so.1", "libgcc-s1"),
+];
 
-    let deps = output.stdout.as_slice().split(|&c| c == b'\n')
-        .find_map(|line| line.strip_prefix(b"shlibs:Depends="))
-        .ok_or(CargoDebError::Str("Failed to find dependency specification."))?
-        .split(|&c| c == b',')
-        .filter_map(|dep| std::str::from_utf8(dep).ok())
-        .map(|dep| dep.trim_matches(|c: char| c.is_ascii_whitespace()))
+/// Whether `dpkg-shlibdeps` can be used, which isn't the case outside of Debian and its derivatives
+pub(crate) fn has_dpkg_shlibdeps() -> bool {
+    Command::new("dpkg-shlibdeps").arg("--version").output().is_ok_and(|out| out.status.success())
+}
+
+/// Finds the dependencies from the libraries the binary links to, for when `dpkg-shlibdeps` isn't installed.
+///
+/// The `symbols` files of the `installed` libraries give the package names and the minimum versions for the symbols the binary uses.
+/// Packages of libraries that aren't installed are guessed from their sonames (Debian Policy 8.1), like `libssl3` for `libssl.so.3`, without versions.
+/// `own_libraries` are file names of libraries in the package.
+pub(crate) fn resolve_from_sonames(path: &Path, own_libraries: &[&str], installed: &InstalledLibraries) -> CDResult<Vec<String>> {
+    let data = std::fs::read(path).map_err(|e| CargoDebError::IoFile("unable to read binary", e, path.into()))?;
+    let needed = needed_libraries(&data)
+        .map_err(|e| CargoDebError::IoFile("unable to read libraries of binary", std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()), path.into()))?;
+    log::debug!("libraries of {}: {needed:?}", path.display());
+    let used = undefined_symbols(&data).unwrap_or_else(|e| {
+        log::debug!("can't read symbols of {}: {e}", path.display());
+        Vec::new()
+    });
+    let mut deps = Vec::<(String, Option<String>)>::new();
+    for soname in needed.iter().filter(|soname| !own_libraries.contains(&soname.as_str())) {
+        // unversioned symbols can be from any of the libraries
+        let symbols = used.iter().filter(|(file, _)| file.as_ref().map_or(true, |f| f == soname)).map(|(_, s)| s.as_str());
+        let (package, min_version) = match installed.dependency(soname, symbols) {
+            Some(dependency) => dependency,
+            None => match soname_package(soname) {
+                Some(package) => (package, None),
+                None => continue,
+            },
+        };
         // libgcc guaranteed by LSB to always be present
-        .filter(|dep| !dep.starts_with("libgcc-") && !dep.starts_with("libgcc1"))
-        .map(|dep| dep.to_string())
-        .collect();
+        if package.starts_with("libgcc-") || package.starts_with("libgcc1") {
+            continue;
+        }
+        match deps.iter_mut().find(|(p, _)| *p == package) {
+            Some((_, version)) => {
+                if min_version.as_deref().is_some_and(|v| version.as_deref().map_or(true, |old| compare_versions(v, old).is_gt())) {
+                    *version = min_version;
+                }
+            },
+            None => deps.push((package, min_version)),
+        }
+    }
+    Ok(deps.into_iter().map(|(package, version)| match version {
+        Some(version) => format!("{package} (>= {version})"),
+        None => package,
+    }).collect())
+}
 
-    Ok(deps)
+/// Name of the Debian package with the library, like `libfoo2-1` for `libfoo2.so.1`
+fn soname_package(soname: &str) -> Option<String> {
+    if let Some(&(_, package)) = SONAME_PACKAGES.iter().find(|&&(s, _)| s == soname) {
+        return Some(package.into());
+    }
+    // the dynamic loader is in libc6
+    if soname.starts_with("ld-linux") {
+        return Some("libc6".into());
+    }
+    let package = match soname.split_once(".so.") {
+        Some((name, version)) if name.ends_with(|c: char| c.is_ascii_digit()) => format!("{name}-{version}"),
+        Some((name, version)) => format!("{name}{version}"),
+        None => soname.strip_suffix(".so")?.to_owned(),
+    };
+    Some(package.to_ascii_lowercase().replace('_', "-"))
+}
+
+#[cfg(not(feature = "debug-id"))]
+fn needed_libraries(_: &[u8]) -> std::io::Result<Vec<String>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "cargo-deb was built without ELF support (the debug-id feature)"))
+}
+
+/// Sonames in `DT_NEEDED` entries
+#[cfg(feature = "debug-id")]
+fn needed_libraries(data: &[u8]) -> Result<Vec<String>, elf::ParseError> {
+    use elf::abi::{DT_NEEDED, SHT_DYNAMIC};
+    use elf::endian::AnyEndian;
+    use elf::ElfBytes;
+
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let Some(shdrs) = elf.section_headers() else { return Ok(Vec::new()) };
+    let Some(dynamic_shdr) = shdrs.iter().find(|s| s.sh_type == SHT_DYNAMIC) else { return Ok(Vec::new()) };
+    let strtab = elf.section_data_as_strtab(&shdrs.get(dynamic_shdr.sh_link as usize)?)?;
+    let Some(dynamic) = elf.dynamic()? else { return Ok(Vec::new()) };
+    dynamic.iter()
+        .filter(|d| d.d_tag == DT_NEEDED)
+        .map(|d| Ok(strtab.get(d.d_val() as usize)?.to_owned()))
+        .collect()
+}
+
+#[cfg(not(feature = "debug-id"))]
+fn undefined_symbols(_: &[u8]) -> std::io::Result<Vec<(Option<String>, String)>> {
+    Ok(Vec::new())
+}
+
+/// Symbols the binary needs from libraries, as `name@version`, and the sonames of the libraries with the version nodes.
+/// Unversioned symbols are `name@Base`, like in `symbols` files.
+#[cfg(feature = "debug-id")]
+fn undefined_symbols(data: &[u8]) -> Result<Vec<(Option<String>, String)>, elf::ParseError> {
+    use elf::endian::AnyEndian;
+    use elf::ElfBytes;
+
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let Some((symtab, strtab)) = elf.dynamic_symbol_table()? else { return Ok(Vec::new()) };
+    let versions = elf.symbol_version_table()?;
+    let mut symbols = Vec::new();
+    for (i, symbol) in symtab.iter().enumerate().filter(|(_, s)| s.is_undefined() && s.st_name != 0) {
+        let name = strtab.get(symbol.st_name as usize)?;
+        let requirement = versions.as_ref().map(|v| v.get_requirement(i)).transpose()?.flatten();
+        symbols.push(match requirement {
+            Some(req) => (Some(req.file.to_owned()), format!("{name}@{}", req.name)),
+            None => (None, format!("{name}@Base")),
+        });
+    }
+    Ok(symbols)
+}
+
+#[test]
+fn soname_packages() {
+    assert_eq!(soname_package("libssl.so.3").as_deref(), Some("libssl3"));
+    assert_eq!(soname_package("libcrypto.so.3").as_deref(), Some("libssl3"));
+    assert_eq!(soname_package("libgtk-4.so.1").as_deref(), Some("libgtk-4-1"));
+    assert_eq!(soname_package("libdbus-1.so.3").as_deref(), Some("libdbus-1-3"));
+    assert_eq!(soname_package("libFLAC.so.12").as_deref(), Some("libflac12"));
+    assert_eq!(soname_package("ld-linux-x86-64.so.2").as_deref(), Some("libc6"));
+    assert_eq!(soname_package("libfoo-1.2.so").as_deref(), Some("libfoo-1.2"));
+    assert_eq!(soname_package("weird"), None);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "debug-id"))]
+fn resolve_from_sonames_test() {
+    let exe = std::env::current_exe().unwrap();
+    let deps = resolve_from_sonames(&exe, &[], &InstalledLibraries::default()).unwrap();
+    assert!(deps.contains(&"libc6".to_owned()), "{deps:?}");
+    assert!(!deps.iter().any(|d| d.starts_with("libgcc")), "{deps:?}");
+
+    let installed = InstalledLibraries::read(Path::new("/"), crate::debian_architecture_from_rust_triple(crate::DEFAULT_TARGET));
+    if installed.dependency("libc.so.6", ["__libc_start_main@GLIBC_2.34"]).is_some_and(|(_, version)| version.is_some()) {
+        let deps = resolve_from_sonames(&exe, &[], &installed).unwrap();
+        assert!(deps.iter().any(|d| d.starts_with("libc6 (>= 2.")), "{deps:?}");
+    }
 }
 
 #[test]
 #[cfg(target_os = "linux")]
 fn resolve_test() {
     let exe = std::env::current_exe().unwrap();
-    let deps = resolve(&exe, None).unwrap();
-    assert!(deps.iter().any(|d| d.starts_with("libc")));
-    assert!(!deps.iter().any(|d| d.starts_with("libgcc")), "{deps:?}");
+    let deps = resolve(&exe, None, "Depends", &OwnLibraries::default()).unwrap();
+    assert!(deps.depends.iter().any(|d| d.starts_with("libc")));
+    assert!(!deps.depends.iter().any(|d| d.starts_with("libgcc")), "{deps:?}");
+    let deps = resolve(&exe, None, "Pre-Depends", &OwnLibraries::default()).unwrap();
+    assert!(deps.depends.is_empty() && deps.pre_depends.iter().any(|d| d.starts_with("libc")), "{deps:?}");
+}
+
+#[test]
+fn shlibs_substvars() {
+    let deps = parse_substvars("shlibs:Pre-Depends=libc6 (>= 2.34)\nshlibs:Depends=libssl3 (>= 3.0.0), libgcc-s1 (>= 4.2)\nshlibs:Recommends=libfoo1\nshlibs:Suggests=libbar1\n").unwrap();
+    assert_eq!(deps, ShlibsDependencies {
+        pre_depends: vec!["libc6 (>= 2.34)".into()],
+        depends: vec!["libssl3 (>= 3.0.0)".into()],
+        recommends: vec!["libfoo1".into()],
+    });
+    assert_eq!(parse_substvars("shlibs:Depends=\n").unwrap(), ShlibsDependencies::default());
+    assert!(parse_substvars("dpkg-shlibdeps: warning: …").is_err());
+
+    assert_eq!(shlibs_name_version("libfoo.so.1"), Some(("libfoo", "1")));
+    assert_eq!(shlibs_name_version("libfoo.so.1.2.3"), Some(("libfoo", "1.2.3")));
+    assert_eq!(shlibs_name_version("libfoo-1.2.so"), Some(("libfoo", "1.2")));
+    assert_eq!(shlibs_name_version("libfoo.so"), None);
+    assert_eq!(shlibs_name_version("libfoo-sys.so"), None);
+}
+
+/// Crates that vendor C libraries, and the Debian source packages of the libraries.
+///
+/// The version of the library is taken from the build metadata of the crate's version, like `300.2.3+3.2.1`.
+const VENDORED_C_LIBRARIES: [(&str, &str); 7] = [
+    ("openssl-src", "openssl"),
+    ("zstd-sys", "libzstd"),
+    ("bzip2-sys", "bzip2"),
+    ("curl-sys", "curl"),
+    ("libgit2-sys", "libgit2"),
+    ("libnghttp2-sys", "nghttp2"),
+    ("tikv-jemalloc-sys", "jemalloc"),
+];
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `None` for path dependencies
+    pub source: Option<String>,
+    /// SHA-256 of the `.crate` file, for crates from registries
+    pub checksum: Option<String>,
+    /// `name`, or `name version (source)` when several versions are locked
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// Packages in `Cargo.lock` that the `root` package needs, transitively, sorted by name.
+///
+/// Cargo.lock doesn't tell which dependencies are dev-dependencies, so the root's are given in `dev_dependencies`.
+pub(crate) fn locked_dependencies(lockfile_path: &Path, root: &str, root_version: &str, dev_dependencies: &[&str]) -> CDResult<Vec<LockedPackage>> {
+    let lockfile = match std::fs::read_to_string(lockfile_path) {
+        Ok(lockfile) => lockfile,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CargoDebError::IoFile("unable to read Cargo.lock", e, lockfile_path.into())),
+    };
+    let mut packages = toml::from_str::<Lockfile>(&lockfile)
+        .map_err(|e| CargoDebError::IoFile("unable to parse Cargo.lock", std::io::Error::new(std::io::ErrorKind::InvalidData, e), lockfile_path.into()))?
+        .package;
+
+    let Some(root_pos) = packages.iter().position(|p| p.name == root && p.version == root_version) else { return Ok(Vec::new()) };
+    let mut needed = vec![false; packages.len()];
+    let mut stack = packages[root_pos].dependencies.iter()
+        .filter(|dep| !dev_dependencies.contains(&dep.split(' ').next().unwrap_or_default()))
+        .cloned().collect::<Vec<_>>();
+    while let Some(dep) = stack.pop() {
+        // "name", or "name version (source)" when several versions are locked
+        let mut parts = dep.split(' ');
+        let (name, version) = (parts.next().unwrap_or_default(), parts.next());
+        let found = packages.iter().position(|p| p.name == name && version.map_or(true, |v| p.version == v));
+        if let Some(pos) = found.filter(|&pos| pos != root_pos && !needed[pos]) {
+            needed[pos] = true;
+            stack.extend(packages[pos].dependencies.iter().cloned());
+        }
+    }
+    let mut needed = needed.into_iter();
+    packages.retain(|_| needed.next().unwrap_or(false));
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+/// `Built-Using` entries for C libraries that crates have copies of, with versions of the Debian source packages.
+///
+/// `Built-Using` needs Debian versions, which the vendored copies don't have, so a library is only listed
+/// when a source package (from `source_versions`) of the same upstream version is installed.
+/// Other libraries are `Err`, like `Err("openssl 3.2.1")`.
+pub(crate) fn vendored_c_libraries(packages: &[LockedPackage], source_versions: &[(String, String)]) -> Vec<Result<String, String>> {
+    packages.iter().filter_map(|p| {
+        let &(_, source_package) = VENDORED_C_LIBRARIES.iter().find(|&&(name, _)| name == p.name)?;
+        // like "zstd.1.5.6" or "curl-8.6.0"
+        let (_, build_metadata) = p.version.split_once('+')?;
+        let version = build_metadata.trim_start_matches(|c: char| !c.is_ascii_digit());
+        if version.is_empty() {
+            return None;
+        }
+        // repacked sources are like "1.5.6+dfsg-1"
+        let debian_version = source_versions.iter()
+            .find(|(name, debian_version)| name == source_package && split_version(debian_version).1.split(['+', '~']).next() == Some(version));
+        Some(match debian_version {
+            Some((_, debian_version)) => Ok(format!("{source_package} (= {debian_version})")),
+            None => Err(format!("{source_package} {version}")),
+        })
+    }).collect()
+}
+
+/// Source packages and their versions of the installed packages, or none if dpkg isn't installed
+pub(crate) fn installed_source_versions() -> Vec<(String, String)> {
+    let output = match Command::new("dpkg-query").args(["-W", "-f", "${source:Package} ${source:Version}\n"]).output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(name, version)| (name.to_owned(), version.to_owned()))
+        .collect()
+}
+
+/// `Static-Built-Using` entries for crates from registries, named like Debian's `rust-<crate>` source packages
+pub(crate) fn static_built_using(packages: &[LockedPackage]) -> Vec<String> {
+    packages.iter()
+        .filter(|p| p.source.as_deref().is_some_and(|s| s.starts_with("registry+") || s.starts_with("sparse+")))
+        .map(|p| format!("rust-{} (= {})", debian_crate_name(&p.name), p.version.replacen('-', "~", 1)))
+        .collect()
+}
+
+#[test]
+fn lockfile_c_libraries() {
+    let dir = tempfile::tempdir().unwrap();
+    let lockfile_path = dir.path().join("Cargo.lock");
+    std::fs::write(&lockfile_path, r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "1.0.0"
+dependencies = ["openssl", "zstd", "test-helper"]
+
+[[package]]
+name = "openssl"
+version = "0.10.64"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["openssl-sys"]
+
+[[package]]
+name = "openssl-sys"
+version = "0.9.102"
+dependencies = ["cc", "openssl-src"]
+
+[[package]]
+name = "openssl-src"
+version = "300.2.3+3.2.1"
+dependencies = ["cc"]
+
+[[package]]
+name = "cc"
+version = "1.0.90"
+
+[[package]]
+name = "zstd"
+version = "0.13.0-rc.1"
+source = "sparse+https://index.crates.io/"
+dependencies = ["zstd-sys 2.0.10+zstd.1.5.6"]
+
+[[package]]
+name = "zstd-sys"
+version = "2.0.10+zstd.1.5.6"
+
+[[package]]
+name = "zstd-sys"
+version = "1.4.0+zstd.1.4.0"
+
+[[package]]
+name = "test-helper"
+version = "0.1.0"
+dependencies = ["bzip2-sys"]
+
+[[package]]
+name = "bzip2-sys"
+version = "0.1.11+1.0.8"
+"#).unwrap();
+    let packages = locked_dependencies(&lockfile_path, "app", "1.0.0", &["test-helper"]).unwrap();
+    let names = packages.iter().map(|p| format!("{} {}", p.name, p.version)).collect::<Vec<_>>();
+    assert_eq!(names, ["cc 1.0.90", "openssl 0.10.64", "openssl-src 300.2.3+3.2.1", "openssl-sys 0.9.102", "zstd 0.13.0-rc.1", "zstd-sys 2.0.10+zstd.1.5.6"]);
+    let source_versions = [("openssl".into(), "3.2.1-3".into()), ("libzstd".into(), "1.5.5+dfsg2-2".into()), ("libzstd".into(), "1.5.6+dfsg-1".into())];
+    assert_eq!(vendored_c_libraries(&packages, &source_versions), [Ok("openssl (= 3.2.1-3)".into()), Ok("libzstd (= 1.5.6+dfsg-1)".into())]);
+    assert_eq!(vendored_c_libraries(&packages, &source_versions[1..2]), [Err("openssl 3.2.1".into()), Err("libzstd 1.5.6".into())]);
+    assert_eq!(static_built_using(&packages), ["rust-openssl (= 0.10.64)", "rust-zstd (= 0.13.0~rc.1)"]);
+    assert!(locked_dependencies(&dir.path().join("missing.lock"), "app", "1.0.0", &[]).unwrap().is_empty());
 }
@@ -0,0 +1,47 @@
+//! Loads the AppArmor profiles of the package, like `dh_apparmor` from the apparmor package does.
+//!
+//! The `postinst` creates the `local/` file that profiles include for the admin's changes, and reloads the profile,
+//! the `prerm` unloads it, and purging removes the admin's files of the profile.
+//!
+//! See [dh_apparmor(1)](https://manpages.debian.org/dh_apparmor.1) and [apparmor_parser(8)](https://manpages.debian.org/apparmor_parser.8).
+use crate::assets::Asset;
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::util::fname_from_path;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub(crate) const APPARMOR_DIR: &str = "etc/apparmor.d";
+
+/// Profiles are files directly in `/etc/apparmor.d`. Its subdirectories have abstractions and tunables for the profiles to include.
+pub(crate) fn profiles(assets: &[Asset]) -> impl Iterator<Item = &Path> {
+    assets.iter().map(|a| a.c.target_path.as_path()).filter(|p| p.parent() == Some(Path::new(APPARMOR_DIR)))
+}
+
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    for path in profiles(assets) {
+        let replacements = HashMap::from([("PROFILE", fname_from_path(path))]);
+        autoscript(scripts, package, "postinst", "postinst-apparmor", &replacements, false, listener)?;
+        autoscript(scripts, package, "prerm", "prerm-apparmor", &replacements, false, listener)?;
+        autoscript(scripts, package, "postrm", "postrm-apparmor", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn apparmor_fragments() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let assets = ["etc/apparmor.d/usr.bin.mypkg", "etc/apparmor.d/abstractions/mypkg", "etc/mypkg.conf"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &assets, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tAPP_PROFILE=\"/etc/apparmor.d/usr.bin.mypkg\"\n"), "{postinst}");
+    assert!(!postinst.contains("abstractions"));
+    let prerm = std::str::from_utf8(&scripts["mypkg.prerm.debhelper"]).unwrap();
+    assert!(prerm.contains("apparmor_parser -R \"/etc/apparmor.d/usr.bin.mypkg\""), "{prerm}");
+    assert!(scripts.contains_key("mypkg.postrm.debhelper"));
+}
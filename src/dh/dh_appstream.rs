@@ -0,0 +1,143 @@
+//! AppStream metadata of the package, which software centers like GNOME Software and KDE Discover show.
+//! Metainfo files are generated from the package's metadata, or installed as they are, and checked with `appstreamcli validate`.
+//!
+//! See [appstreamcli(1)](https://manpages.debian.org/appstreamcli.1) and [the AppStream specification](https://www.freedesktop.org/software/appstream/docs/).
+use crate::assets::Asset;
+use crate::listener::Listener;
+use crate::CDResult;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+pub(crate) const METAINFO_DIR: &str = "usr/share/metainfo";
+
+/// The directory that older AppStream versions used
+const APPDATA_DIR: &str = "usr/share/appdata";
+
+/// Fields of a generated metainfo file
+pub(crate) struct Component<'a> {
+    pub id: &'a str,
+    pub metadata_license: &'a str,
+    pub project_license: Option<&'a str>,
+    pub name: &'a str,
+    pub summary: &'a str,
+    /// Paragraphs
+    pub description: Vec<String>,
+    pub homepage: Option<&'a str>,
+    /// Makes it a desktop application, instead of a console application
+    pub desktop_id: Option<&'a str>,
+    /// Names of executables in `/usr/bin`
+    pub binaries: Vec<&'a str>,
+    pub version: &'a str,
+}
+
+impl Component<'_> {
+    pub(crate) fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let component_type = if self.desktop_id.is_some() { "desktop-application" } else { "console-application" };
+        let _ = writeln!(xml, "<component type=\"{component_type}\">");
+        let _ = writeln!(xml, "  <id>{}</id>", escape(self.id));
+        let _ = writeln!(xml, "  <metadata_license>{}</metadata_license>", escape(self.metadata_license));
+        if let Some(license) = self.project_license {
+            let _ = writeln!(xml, "  <project_license>{}</project_license>", escape(license));
+        }
+        let _ = writeln!(xml, "  <name>{}</name>", escape(self.name));
+        let _ = writeln!(xml, "  <summary>{}</summary>", escape(self.summary.trim_end_matches('.')));
+        let description = if self.description.is_empty() { vec![self.summary.to_owned()] } else { self.description.clone() };
+        xml.push_str("  <description>\n");
+        for paragraph in &description {
+            let _ = writeln!(xml, "    <p>{}</p>", escape(paragraph));
+        }
+        xml.push_str("  </description>\n");
+        if let Some(url) = self.homepage {
+            let _ = writeln!(xml, "  <url type=\"homepage\">{}</url>", escape(url));
+        }
+        if let Some(desktop_id) = self.desktop_id {
+            let _ = writeln!(xml, "  <launchable type=\"desktop-id\">{}</launchable>", escape(desktop_id));
+        }
+        if !self.binaries.is_empty() {
+            xml.push_str("  <provides>\n");
+            for binary in &self.binaries {
+                let _ = writeln!(xml, "    <binary>{}</binary>", escape(binary));
+            }
+            xml.push_str("  </provides>\n");
+        }
+        let _ = writeln!(xml, "  <releases>\n    <release version=\"{}\"/>\n  </releases>", escape(self.version));
+        xml.push_str("</component>\n");
+        xml
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Paragraphs of the extended description, from the lines of the `Description` field
+pub(crate) fn description_paragraphs(lines: &[String]) -> Vec<String> {
+    let mut paragraphs = vec![String::new()];
+    for line in lines.iter().map(|l| l.trim()) {
+        if line == "." {
+            paragraphs.push(String::new());
+        } else if let Some(paragraph) = paragraphs.last_mut() {
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+        }
+    }
+    paragraphs.retain(|p| !p.is_empty());
+    paragraphs
+}
+
+/// Runs `appstreamcli validate` on the metainfo files, if it's installed, and warns about the problems it finds
+pub(crate) fn check_metainfo(assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    let mut temp_dir = None;
+    for asset in assets {
+        let Some(dir) = asset.c.target_path.parent() else { continue };
+        if dir == Path::new(APPDATA_DIR) {
+            listener.warning(format!("/{} is in the legacy directory; metainfo files should be in /{METAINFO_DIR}", asset.c.target_path.display()));
+        } else if dir != Path::new(METAINFO_DIR) {
+            continue;
+        }
+        let Some(file_name) = asset.c.target_path.file_name() else { continue };
+        let temp_dir = match &temp_dir {
+            Some(dir) => dir,
+            None => temp_dir.insert(tempfile::tempdir()?),
+        };
+        // it checks that the file name matches the id
+        let path = temp_dir.path().join(file_name);
+        std::fs::write(&path, asset.source.data()?)?;
+        let Ok(output) = Command::new("appstreamcli").args(["validate", "--no-net", "--no-color"]).arg(&path).output() else {
+            // appstream isn't installed
+            return Ok(());
+        };
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            listener.warning(format!("appstreamcli found problems in /{}:\n{}", asset.c.target_path.display(), stdout.trim_end()));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn generates_metainfo() {
+    let lines = [" First line".to_owned(), " continued.".into(), " .".into(), " Second & last.".into()];
+    let component = Component {
+        id: "org.example.MyApp",
+        metadata_license: "CC0-1.0",
+        project_license: Some("MIT"),
+        name: "myapp",
+        summary: "Does <things>.",
+        description: description_paragraphs(&lines),
+        homepage: None,
+        desktop_id: Some("org.example.MyApp.desktop"),
+        binaries: vec!["myapp"],
+        version: "1.2.3",
+    };
+    let xml = component.to_xml();
+    assert!(xml.contains("<component type=\"desktop-application\">\n  <id>org.example.MyApp</id>\n"), "{xml}");
+    assert!(xml.contains("  <summary>Does &lt;things&gt;</summary>\n"), "{xml}");
+    assert!(xml.contains("    <p>First line continued.</p>\n    <p>Second &amp; last.</p>\n"), "{xml}");
+    assert!(xml.contains("<launchable type=\"desktop-id\">org.example.MyApp.desktop</launchable>"), "{xml}");
+    assert!(xml.contains("<release version=\"1.2.3\"/>"), "{xml}");
+}
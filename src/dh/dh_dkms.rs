@@ -0,0 +1,93 @@
+//! Packages sources of out-of-tree kernel modules for DKMS, which builds them for each installed kernel, like `dh_dkms` does.
+//!
+//! The sources are installed in `/usr/src/<name>-<version>/` with a `dkms.conf`.
+//! The `postinst` adds them to DKMS and builds them for the running kernel, and the `prerm` removes them from all kernels.
+//!
+//! See [dh_dkms(1)](https://manpages.debian.org/dh_dkms.1) and [dkms(8)](https://manpages.debian.org/dkms.8).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::parse::manifest::DkmsConfig;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// What `${misc:Depends}` gets from `dh_dkms`
+pub(crate) const DKMS_DEPENDS: &str = "dkms";
+
+/// DKMS's default location of modules, which takes precedence over the kernel's own modules
+const DEFAULT_DEST_MODULE_LOCATION: &str = "/updates/dkms";
+
+/// The module sources and the name and version that DKMS knows them by
+#[derive(Debug, Clone)]
+pub(crate) struct DkmsModule {
+    pub source: String,
+    pub name: String,
+    pub version: String,
+    config: DkmsConfig,
+}
+
+impl DkmsModule {
+    /// The name defaults to the package name without `-dkms`, and the version to the upstream version
+    pub(crate) fn new(config: &DkmsConfig, deb_name: &str, upstream_version: &str) -> Self {
+        Self {
+            source: config.source.clone(),
+            name: config.name.clone().unwrap_or_else(|| deb_name.strip_suffix("-dkms").unwrap_or(deb_name).to_owned()),
+            version: config.version.clone().unwrap_or_else(|| upstream_version.to_owned()),
+            config: config.clone(),
+        }
+    }
+
+    pub(crate) fn source_dir(&self) -> PathBuf {
+        Path::new("usr/src").join(format!("{}-{}", self.name, self.version))
+    }
+
+    /// Each of the built modules is named after the package, unless there's a list of them
+    pub(crate) fn dkms_conf(&self) -> String {
+        let mut conf = format!("PACKAGE_NAME=\"{}\"\nPACKAGE_VERSION=\"{}\"\n", self.name, self.version);
+        let default_modules = [self.name.clone()];
+        let modules = self.config.modules.as_deref().unwrap_or(&default_modules);
+        let location = self.config.dest_module_location.as_deref().unwrap_or(DEFAULT_DEST_MODULE_LOCATION);
+        for (i, module) in modules.iter().enumerate() {
+            let _ = write!(conf, "BUILT_MODULE_NAME[{i}]=\"{module}\"\nDEST_MODULE_LOCATION[{i}]=\"{location}\"\n");
+        }
+        if let Some(make) = &self.config.make {
+            let _ = writeln!(conf, "MAKE[0]=\"{make}\"");
+        }
+        if let Some(clean) = &self.config.clean {
+            let _ = writeln!(conf, "CLEAN=\"{clean}\"");
+        }
+        let _ = writeln!(conf, "AUTOINSTALL=\"{}\"", if self.config.autoinstall.unwrap_or(true) { "yes" } else { "no" });
+        conf
+    }
+
+    pub(crate) fn generate(&self, scripts: &mut ScriptFragments, package: &str, listener: &dyn Listener) -> CDResult<()> {
+        let replacements = HashMap::from([
+            ("MODULE_NAME", self.name.clone()),
+            ("MODULE_VERSION", self.version.clone()),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-dkms", &replacements, false, listener)?;
+        autoscript(scripts, package, "prerm", "prerm-dkms", &replacements, false, listener)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn dkms_conf_and_fragments() {
+    let config: DkmsConfig = toml::from_str("source = \"kmod\"\nmodules = [\"mymod\", \"mymod_extra\"]\nmake = \"make KVER=$kernelver\"").unwrap();
+    let module = DkmsModule::new(&config, "mymod-dkms", "1.2.3");
+    assert_eq!(module.source_dir(), Path::new("usr/src/mymod-1.2.3"));
+    assert_eq!(module.dkms_conf(), "PACKAGE_NAME=\"mymod\"\nPACKAGE_VERSION=\"1.2.3\"\n\
+        BUILT_MODULE_NAME[0]=\"mymod\"\nDEST_MODULE_LOCATION[0]=\"/updates/dkms\"\n\
+        BUILT_MODULE_NAME[1]=\"mymod_extra\"\nDEST_MODULE_LOCATION[1]=\"/updates/dkms\"\n\
+        MAKE[0]=\"make KVER=$kernelver\"\nAUTOINSTALL=\"yes\"\n");
+
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    module.generate(&mut scripts, "mymod-dkms", &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mymod-dkms.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\t\tdkms add -m \"mymod\" -v \"1.2.3\"\n"), "{postinst}");
+    let prerm = std::str::from_utf8(&scripts["mymod-dkms.prerm.debhelper"]).unwrap();
+    assert!(prerm.contains("dkms remove -m \"mymod\" -v \"1.2.3\" --all"), "{prerm}");
+}
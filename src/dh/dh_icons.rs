@@ -0,0 +1,89 @@
+//! Installs icons of desktop entries in the hicolor theme, and checks desktop entries with `desktop-file-validate`.
+//! Like with `dh_icons`, dpkg triggers update the icon caches.
+//!
+//! See [dh_icons(1)](https://manpages.debian.org/dh_icons.1), [desktop-file-validate(1)](https://manpages.debian.org/desktop-file-validate.1),
+//! and [the Icon Theme Specification](https://specifications.freedesktop.org/icon-theme-spec/latest/).
+use crate::assets::Asset;
+use crate::listener::Listener;
+use crate::CDResult;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) const APPLICATIONS_DIR: &str = "usr/share/applications";
+pub(crate) const ICONS_DIR: &str = "usr/share/icons";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// `Icon=` of the `[Desktop Entry]` group, if it's a name that the icon theme would look up
+pub(crate) fn desktop_icon_name(text: &str) -> Option<&str> {
+    let mut in_group = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+        } else if let Some(value) = line.strip_prefix("Icon").map(str::trim_start).and_then(|l| l.strip_prefix('=')).filter(|_| in_group) {
+            let value = value.trim();
+            return Some(value).filter(|v| !v.is_empty() && !v.contains('/'));
+        }
+    }
+    None
+}
+
+/// SVG icons are scalable, and PNG icons go in the directory of their size
+pub(crate) fn icon_target_path(icon_name: &str, source_path: &Path, data: &[u8]) -> Result<PathBuf, &'static str> {
+    let hicolor = Path::new(ICONS_DIR).join("hicolor");
+    match source_path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => Ok(hicolor.join("scalable/apps").join(format!("{icon_name}.svg"))),
+        Some("png") => {
+            // the IHDR chunk with the width and height comes first
+            if data.len() < 24 || !data.starts_with(PNG_SIGNATURE) || &data[12..16] != b"IHDR" {
+                return Err("it's not a PNG file");
+            }
+            let width = u32::from_be_bytes(data[16..20].try_into().unwrap());
+            let height = u32::from_be_bytes(data[20..24].try_into().unwrap());
+            Ok(hicolor.join(format!("{width}x{height}/apps")).join(format!("{icon_name}.png")))
+        },
+        _ => Err("icons must be .png or .svg files"),
+    }
+}
+
+/// Runs `desktop-file-validate` on desktop entries, if it's installed, and warns about the errors it finds
+pub(crate) fn check_desktop_files(assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    let mut temp_dir = None;
+    for asset in assets.iter().filter(|a| a.c.target_path.parent() == Some(Path::new(APPLICATIONS_DIR))) {
+        let Some(file_name) = asset.c.target_path.file_name().filter(|f| f.to_string_lossy().ends_with(".desktop")) else { continue };
+        let temp_dir = match &temp_dir {
+            Some(dir) => dir,
+            None => temp_dir.insert(tempfile::tempdir()?),
+        };
+        // it checks the file name too
+        let path = temp_dir.path().join(file_name);
+        std::fs::write(&path, asset.source.data()?)?;
+        let Ok(output) = Command::new("desktop-file-validate").arg(&path).output() else {
+            // desktop-file-utils isn't installed
+            return Ok(());
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let errors = stdout.lines().filter(|l| l.contains("error:")).map(|l| l.replace(&*path.to_string_lossy(), "")).collect::<Vec<_>>();
+        if !output.status.success() || !errors.is_empty() {
+            listener.warning(format!("desktop-file-validate found errors in /{}:\n{}", asset.c.target_path.display(), errors.join("\n")));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn icon_paths() {
+    let mut png = PNG_SIGNATURE.to_vec();
+    png.extend([0, 0, 0, 13]);
+    png.extend(b"IHDR");
+    png.extend(48u32.to_be_bytes());
+    png.extend(48u32.to_be_bytes());
+    assert_eq!(icon_target_path("myapp", Path::new("icons/48.png"), &png).unwrap(), Path::new("usr/share/icons/hicolor/48x48/apps/myapp.png"));
+    assert_eq!(icon_target_path("myapp", Path::new("icons/logo.svg"), b"<svg/>").unwrap(), Path::new("usr/share/icons/hicolor/scalable/apps/myapp.svg"));
+    assert!(icon_target_path("myapp", Path::new("icons/48.png"), b"GIF89a").is_err());
+    assert!(icon_target_path("myapp", Path::new("icons/logo.ico"), b"").is_err());
+
+    assert_eq!(desktop_icon_name("[Desktop Entry]\nName=My App\nIcon = myapp\n"), Some("myapp"));
+    assert_eq!(desktop_icon_name("[Desktop Entry]\nIcon=/opt/myapp.png\n"), None);
+    assert_eq!(desktop_icon_name("[Desktop Action new]\nIcon=other\n"), None);
+}
@@ -0,0 +1,164 @@
+//! Reads the lists of files that `dh_install`, `dh_installdocs`, `dh_installdirs` and `dh_link`
+//! take from `debian/<package>.install`, `.docs`, `.dirs` and `.links` files.
+//!
+//! Only the basic syntax is supported: whitespace-separated paths and `#` comments.
+//! Executable files for `dh-exec` and variable substitutions aren't.
+//!
+//! See [dh_install(1)](https://manpages.debian.org/dh_install.1) and [dh_link(1)](https://manpages.debian.org/dh_link.1).
+use crate::assets::{Asset, AssetSource, IsBuilt, RawAsset};
+use crate::config::is_glob_pattern;
+use crate::dh::dh_lib::pkgfile;
+use crate::error::{CDResult, CargoDebError};
+use crate::util::read_file_to_string;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Directories where `dh_fixperms` makes files executable
+const BIN_DIRS: [&str; 6] = ["bin", "sbin", "usr/bin", "usr/sbin", "usr/games", "usr/libexec"];
+
+/// Returns assets to resolve like the ones in `Cargo.toml`, which can be relative to `package_dir`, and the links and directories.
+pub(crate) fn read_debhelper_files(dir: &Path, package_dir: &Path, deb_name: &str) -> CDResult<(Vec<RawAsset>, Vec<Asset>)> {
+    let mut raw_assets = Vec::new();
+    let mut assets = Vec::new();
+
+    for line in read_lines(dir, deb_name, "install")? {
+        let (target_dir, sources) = match line.as_slice() {
+            // installed to the same path
+            [source] => (Path::new(source).parent().unwrap_or(Path::new("")).to_owned(), &line[..]),
+            [sources @ .., target_dir] => (PathBuf::from(target_dir), sources),
+            [] => continue,
+        };
+        for source in sources {
+            raw_assets.push(raw_asset(package_dir, source, &target_dir));
+        }
+    }
+    let doc_dir = Path::new("usr/share/doc").join(deb_name);
+    for line in read_lines(dir, deb_name, "docs")? {
+        raw_assets.extend(line.iter().map(|source| raw_asset(package_dir, source, &doc_dir)));
+    }
+    for line in read_lines(dir, deb_name, "dirs")? {
+        assets.extend(line.iter().map(|path| {
+            Asset::new(AssetSource::Directory, path.trim_end_matches('/').into(), 0o755, IsBuilt::No, false)
+        }));
+    }
+    for line in read_lines(dir, deb_name, "links")? {
+        if line.len() % 2 != 0 {
+            return Err(CargoDebError::Str("debian/*.links lines must have pairs of a link's target and its path"));
+        }
+        for pair in line.chunks(2) {
+            let (target, link) = (normalized(Path::new(&pair[0])), normalized(Path::new(&pair[1])));
+            assets.push(Asset::new(AssetSource::SymlinkTo(link_name(&target, &link)), link, 0o777, IsBuilt::No, false));
+        }
+    }
+    Ok((raw_assets, assets))
+}
+
+/// Lines split into paths, without comments
+fn read_lines(dir: &Path, deb_name: &str, filename: &str) -> CDResult<Vec<Vec<String>>> {
+    let Some(path) = pkgfile(dir, deb_name, deb_name, filename, None) else { return Ok(Vec::new()) };
+    let content = read_file_to_string(&path).map_err(|e| CargoDebError::IoFile("unable to read debhelper file", e, path.clone()))?;
+    if content.starts_with("#!") {
+        return Err(CargoDebError::IoFile("executable debhelper files aren't supported", std::io::ErrorKind::Unsupported.into(), path));
+    }
+    Ok(content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.split_whitespace().map(String::from).collect())
+        .collect())
+}
+
+fn raw_asset(package_dir: &Path, source: &str, target_dir: &Path) -> RawAsset {
+    let source = source.trim_end_matches('/');
+    let source_on_disk = package_dir.join(source);
+    let target_dir = target_dir.strip_prefix("/").unwrap_or(target_dir);
+    let executable = BIN_DIRS.iter().any(|d| target_dir == Path::new(d)) || is_executable(&source_on_disk);
+    // directories are copied with their contents
+    let (source_path, target_path) = if !is_glob_pattern(Path::new(source)) && source_on_disk.is_dir() {
+        let name = Path::new(source).file_name().unwrap_or_default();
+        (Path::new(source).join("**/*"), target_dir.join(name))
+    } else {
+        (PathBuf::from(source), target_dir.join(""))
+    };
+    RawAsset {
+        source_path,
+        target_path,
+        chmod: if executable { 0o755 } else { 0o644 },
+        separate_debug_symbols: None,
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_: &Path) -> bool {
+    false
+}
+
+/// Relative to the root, without `.` and `..`
+pub(crate) fn normalized(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::Normal(c) => out.push(c),
+            Component::ParentDir => { out.pop(); },
+            _ => {},
+        }
+    }
+    out
+}
+
+/// Like `dh_link`, links within a top-level directory are relative, and others are absolute (Debian Policy 10.5)
+fn link_name(target: &Path, link: &Path) -> PathBuf {
+    if target.components().next() != link.components().next() {
+        return Path::new("/").join(target);
+    }
+    let link_dir = link.parent().unwrap_or(Path::new(""));
+    let common = link_dir.components().zip(target.components()).take_while(|(a, b)| a == b).count();
+    let mut name = PathBuf::new();
+    for _ in common..link_dir.components().count() {
+        name.push("..");
+    }
+    name.extend(target.components().skip(common));
+    name
+}
+
+#[test]
+fn link_names() {
+    assert_eq!(link_name(Path::new("usr/share/foo/bar"), Path::new("usr/bin/bar")), Path::new("../share/foo/bar"));
+    assert_eq!(link_name(Path::new("usr/lib/libx.so.1"), Path::new("usr/lib/libx.so")), Path::new("libx.so.1"));
+    assert_eq!(link_name(Path::new("etc/foo.conf"), Path::new("usr/share/foo/foo.conf")), Path::new("/etc/foo.conf"));
+    assert_eq!(normalized(Path::new("/usr/bin/../share/./x")), Path::new("usr/share/x"));
+}
+
+#[test]
+fn reads_debhelper_files() {
+    use crate::util::tests::{add_test_fs_paths, set_test_fs_path_content};
+
+    let package_dir = tempfile::tempdir().unwrap();
+    fs::create_dir(package_dir.path().join("data")).unwrap();
+    let _g = add_test_fs_paths(&[]);
+    set_test_fs_path_content("debian/example.install", "# comment\ntarget/release/tool usr/lib/example\nREADME.md NEWS usr/share/example/\ndata usr/share/example\nusr/share/applications/*.desktop\n".into());
+    set_test_fs_path_content("debian/docs", "CONTRIBUTING.md\n".into());
+    set_test_fs_path_content("debian/example.dirs", "/var/lib/example/\n".into());
+    set_test_fs_path_content("debian/example.links", "/usr/lib/example/tool /usr/bin/tool /etc/example.conf usr/share/example/example.conf\n".into());
+
+    let (raw, assets) = read_debhelper_files(Path::new("debian"), package_dir.path(), "example").unwrap();
+    let raw = raw.iter().map(|a| (a.source_path.to_str().unwrap(), a.target_path.to_str().unwrap(), a.chmod)).collect::<Vec<_>>();
+    assert_eq!(raw, [
+        ("target/release/tool", "usr/lib/example/", 0o644),
+        ("README.md", "usr/share/example/", 0o644),
+        ("NEWS", "usr/share/example/", 0o644),
+        ("data/**/*", "usr/share/example/data", 0o644),
+        ("usr/share/applications/*.desktop", "usr/share/applications/", 0o644),
+        ("CONTRIBUTING.md", "usr/share/doc/example/", 0o644),
+    ]);
+    assert!(matches!(assets[0].source, AssetSource::Directory));
+    assert_eq!(assets[0].c.target_path, Path::new("var/lib/example"));
+    assert!(matches!(&assets[1].source, AssetSource::SymlinkTo(p) if p == Path::new("../lib/example/tool")));
+    assert_eq!(assets[1].c.target_path, Path::new("usr/bin/tool"));
+    assert!(matches!(&assets[2].source, AssetSource::SymlinkTo(p) if p == Path::new("/etc/example.conf")));
+}
@@ -0,0 +1,70 @@
+//! Registers alternatives with `update-alternatives`, like `dh_installalternatives` does for `debian/<package>.alternatives`.
+//!
+//! See [dh_installalternatives(1)](https://manpages.debian.org/dh_installalternatives.1) and [update-alternatives(1)](https://manpages.debian.org/update-alternatives.1).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+
+/// A file of the package that provides the generic `link` of the `name` group
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Alternative {
+    pub name: String,
+    pub link: String,
+    /// Absolute path of the asset
+    pub path: String,
+    pub priority: i32,
+    /// `(link, name, path)` of links that follow the master link, like man pages
+    pub slaves: Vec<(String, String, String)>,
+}
+
+impl Alternative {
+    /// The format of `debian/<package>.alternatives`
+    pub(crate) fn to_debhelper(&self) -> String {
+        let mut out = format!("Name: {}\nLink: {}\nAlternative: {}\n", self.name, self.link, self.path);
+        if !self.slaves.is_empty() {
+            out.push_str("Dependents:\n");
+            for (link, name, path) in &self.slaves {
+                out.push_str(&format!("  {link} {name} {path}\n"));
+            }
+        }
+        out.push_str(&format!("Priority: {}\n", self.priority));
+        out
+    }
+}
+
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, alternatives: &[Alternative], listener: &dyn Listener) -> CDResult<()> {
+    for alt in alternatives {
+        let slaves: String = alt.slaves.iter().map(|(link, name, path)| format!(" \\\n\t\t--slave {link} {name} {path}")).collect();
+        let replacements = HashMap::from([
+            ("NAME", alt.name.clone()),
+            ("LINK", alt.link.clone()),
+            ("PATH", alt.path.clone()),
+            ("PRIORITY", alt.priority.to_string()),
+            ("SLAVES", slaves),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-alternatives", &replacements, false, listener)?;
+        autoscript(scripts, package, "prerm", "prerm-alternatives", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn alternatives_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    let alt = Alternative {
+        name: "editor".into(),
+        link: "/usr/bin/editor".into(),
+        path: "/usr/bin/foo".into(),
+        priority: 50,
+        slaves: vec![("/usr/share/man/man1/editor.1.gz".into(), "editor.1.gz".into(), "/usr/share/man/man1/foo.1.gz".into())],
+    };
+    generate(&mut scripts, "mypkg", std::slice::from_ref(&alt), &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tupdate-alternatives --install /usr/bin/editor editor /usr/bin/foo 50 \\\n\t\t--slave /usr/share/man/man1/editor.1.gz editor.1.gz /usr/share/man/man1/foo.1.gz\n"), "{postinst}");
+    let prerm = std::str::from_utf8(&scripts["mypkg.prerm.debhelper"]).unwrap();
+    assert!(prerm.contains("\tupdate-alternatives --remove editor /usr/bin/foo\n"), "{prerm}");
+    assert_eq!(alt.to_debhelper(), "Name: editor\nLink: /usr/bin/editor\nAlternative: /usr/bin/foo\nDependents:\n  /usr/share/man/man1/editor.1.gz editor.1.gz /usr/share/man/man1/foo.1.gz\nPriority: 50\n");
+}
@@ -0,0 +1,131 @@
+//! Checks D-Bus service activation files and bus policies of the package.
+//! debhelper has no helper for them anymore, because `dbus-daemon` notices new files by itself.
+//!
+//! See [dbus-daemon(1)](https://manpages.debian.org/dbus-daemon.1) and [the D-Bus specification](https://dbus.freedesktop.org/doc/dbus-specification.html#message-bus-starting-services).
+use crate::assets::Asset;
+use crate::listener::Listener;
+use crate::CDResult;
+use std::path::Path;
+
+pub(crate) const SYSTEM_SERVICES_DIR: &str = "usr/share/dbus-1/system-services";
+pub(crate) const SESSION_SERVICES_DIR: &str = "usr/share/dbus-1/services";
+pub(crate) const SYSTEM_POLICY_DIR: &str = "usr/share/dbus-1/system.d";
+
+const SYSTEMD_SYSTEM_DIRS: [&str; 2] = ["lib/systemd/system", "usr/lib/systemd/system"];
+
+/// Keys of the `[D-BUS Service]` section of an activation file
+#[derive(Debug, Default, PartialEq)]
+struct ActivationFile {
+    name: Option<String>,
+    exec: Option<String>,
+    user: Option<String>,
+    systemd_service: Option<String>,
+}
+
+impl ActivationFile {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut file = Self::default();
+        let mut in_section = false;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            if line.starts_with('[') {
+                in_section = line == "[D-BUS Service]";
+                continue;
+            }
+            if !in_section {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("'{line}' isn't a key=value line"))?;
+            let value = Some(value.trim().to_owned());
+            match key.trim() {
+                "Name" => file.name = value,
+                "Exec" => file.exec = value,
+                "User" => file.user = value,
+                "SystemdService" => file.systemd_service = value,
+                _ => {},
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// Problems with an activation file. System services are checked against the systemd units in `assets`.
+fn activation_file_problems(path: &Path, text: &str, system: bool, assets: &[Asset]) -> CDResult<Vec<String>> {
+    let file = match ActivationFile::parse(text) {
+        Ok(file) => file,
+        Err(e) => return Ok(vec![e]),
+    };
+    let mut problems = Vec::new();
+    let Some(name) = &file.name else {
+        return Ok(vec!["it has no Name= in a [D-BUS Service] section".into()]);
+    };
+    if path.file_name().is_some_and(|f| *f != *format!("{name}.service")) {
+        problems.push(format!("it should be named {name}.service, because dbus-daemon looks for the file of the bus name"));
+    }
+    if file.exec.is_none() && file.systemd_service.is_none() {
+        problems.push("it has neither Exec= nor SystemdService=".into());
+    }
+    if !system {
+        return Ok(problems);
+    }
+    if file.user.is_none() {
+        problems.push("system services need User=".into());
+    }
+    if let Some(unit) = &file.systemd_service {
+        let unit_asset = assets.iter().find(|a| {
+            SYSTEMD_SYSTEM_DIRS.iter().any(|dir| a.c.target_path.parent() == Some(Path::new(dir))) && a.c.target_path.file_name().is_some_and(|f| *f == **unit)
+        });
+        match unit_asset {
+            None => problems.push(format!("the package has no {unit} unit for its SystemdService=")),
+            Some(asset) => {
+                let unit_text = asset.source.data()?;
+                let bus_name = String::from_utf8_lossy(&unit_text).lines()
+                    .find_map(|l| Some(l.trim().strip_prefix("BusName=")?.trim().to_owned()));
+                if bus_name.as_deref() != Some(name.as_str()) {
+                    problems.push(format!("{unit} should have BusName={name}, for systemd to know when the service is ready"));
+                }
+            },
+        }
+    }
+    Ok(problems)
+}
+
+/// Warns about activation files that dbus-daemon would ignore or fail to start, and policies that aren't `<busconfig>` files
+pub(crate) fn check_files(assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    for asset in assets {
+        let path = &asset.c.target_path;
+        let Some(dir) = path.parent() else { continue };
+        if dir == Path::new("etc/dbus-1/system.d") {
+            listener.warning(format!("/{} is for the local admin's policies; packages should install them in /{SYSTEM_POLICY_DIR}", path.display()));
+        } else if dir == Path::new(SYSTEM_POLICY_DIR) {
+            if !String::from_utf8_lossy(&asset.source.data()?).contains("<busconfig") {
+                listener.warning(format!("/{} isn't a D-Bus <busconfig> policy", path.display()));
+            }
+        } else if dir == Path::new(SYSTEM_SERVICES_DIR) || dir == Path::new(SESSION_SERVICES_DIR) {
+            if path.extension().map_or(true, |ext| ext != "service") {
+                listener.warning(format!("dbus-daemon ignores /{}, because the file name doesn't end with .service", path.display()));
+                continue;
+            }
+            let text = String::from_utf8_lossy(&asset.source.data()?).into_owned();
+            for problem in activation_file_problems(path, &text, dir == Path::new(SYSTEM_SERVICES_DIR), assets)? {
+                listener.warning(format!("D-Bus service /{}: {problem}", path.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn checks_activation_files() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let unit = |bus_name: &str| Asset::new(AssetSource::Data(format!("[Service]\nType=dbus\nBusName={bus_name}\n").into_bytes()), "lib/systemd/system/mypkg.service".into(), 0o644, IsBuilt::No, false);
+    let service = "[D-BUS Service]\nName=org.example.MyPkg\nExec=/usr/bin/mypkg\nUser=root\nSystemdService=mypkg.service\n";
+    let path = Path::new("usr/share/dbus-1/system-services/org.example.MyPkg.service");
+    assert_eq!(activation_file_problems(path, service, true, &[unit("org.example.MyPkg")]).unwrap(), Vec::<String>::new());
+    assert_eq!(activation_file_problems(path, service, true, &[unit("org.example.Other")]).unwrap().len(), 1);
+    assert_eq!(activation_file_problems(path, service, true, &[]).unwrap(), ["the package has no mypkg.service unit for its SystemdService="]);
+
+    let session = "[D-BUS Service]\nName=org.example.Other\n";
+    let problems = activation_file_problems(Path::new("usr/share/dbus-1/services/org.example.MyPkg.service"), session, false, &[]).unwrap();
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert_eq!(activation_file_problems(path, "[D-BUS Service]\nExec=/x\n", true, &[]).unwrap().len(), 1);
+}
@@ -0,0 +1,35 @@
+//! Calls `dpkg-maintscript-helper` like `dh_installdeb` does for the lines of `debian/<package>.maintscript`,
+//! for removing and moving conffiles, and replacing directories with symlinks or the other way around.
+//!
+//! See [dh_installdeb(1)](https://manpages.debian.org/dh_installdeb.1) and [dpkg-maintscript-helper(1)](https://manpages.debian.org/dpkg-maintscript-helper.1).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+
+/// `dpkg-maintscript-helper` needs to run in all scripts, because it handles each step of the upgrade in a different one
+const SCRIPTS: [&str; 4] = ["preinst", "postinst", "prerm", "postrm"];
+
+/// Each of the `lines` are `dpkg-maintscript-helper` arguments, like `rm_conffile /etc/foo.conf 1.2-1~`
+pub(crate) fn maintscript(scripts: &mut ScriptFragments, package: &str, lines: &[String], listener: &dyn Listener) -> CDResult<()> {
+    for line in lines {
+        let replacements = HashMap::from([("PARAMS", line.clone())]);
+        for script in SCRIPTS {
+            autoscript(scripts, package, script, "maintscript-helper", &replacements, false, listener)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn maintscript_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    maintscript(&mut scripts, "mypkg", &["rm_conffile /etc/a.conf 1.2-1~".into(), "mv_conffile /etc/b /etc/c".into()], &listener).unwrap();
+    assert_eq!(scripts.len(), 4);
+    let prerm = std::str::from_utf8(&scripts["mypkg.prerm.debhelper"]).unwrap();
+    assert!(prerm.find("mv_conffile").unwrap() < prerm.find("rm_conffile").unwrap(), "{prerm}");
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\ndpkg-maintscript-helper rm_conffile /etc/a.conf 1.2-1~ -- \"$@\"\n"), "{postinst}");
+}
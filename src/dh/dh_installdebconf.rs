@@ -0,0 +1,59 @@
+//! Makes maintainer scripts work with debconf, like `dh_installdebconf` does, when the package has a `templates` file.
+//!
+//! The `postinst` gets the confmodule sourced at the top, which runs the `config` script again if needed,
+//! and the `postrm` purges the package's answers from the debconf database.
+//!
+//! See [dh_installdebconf(1)](https://manpages.debian.org/dh_installdebconf.1) and [debconf-devel(7)](https://manpages.debian.org/debconf-devel.7).
+use crate::dh::dh_lib::generator_name;
+
+/// What `${misc:Depends}` gets from `dh_installdebconf`
+pub(crate) const DEBCONF_DEPENDS: &str = "debconf (>= 0.5) | debconf-2.0";
+
+const CONFMODULE: &str = ". /usr/share/debconf/confmodule\n";
+
+/// The `postrm-debconf` autoscript of debhelper
+const POSTRM_PURGE: &str = "if [ \"$1\" = purge ] && [ -e /usr/share/debconf/confmodule ]; then\n\t. /usr/share/debconf/confmodule\n\tdb_purge\n\tdb_stop\nfi\n";
+
+/// An empty script to add the debconf code to
+pub(crate) const EMPTY_SCRIPT: &[u8] = b"#!/bin/sh\nset -e\n";
+
+/// Returns the script with the debconf code inserted, unless the script already has it
+pub(crate) fn add_to_script(script_name: &str, contents: Vec<u8>) -> Vec<u8> {
+    let (code, marker) = match script_name {
+        "postinst" => (CONFMODULE, "/usr/share/debconf/confmodule"),
+        "postrm" => (POSTRM_PURGE, "db_purge"),
+        _ => return contents,
+    };
+    let text = String::from_utf8_lossy(&contents);
+    if text.contains(marker) {
+        return contents;
+    }
+    let pos = header_len(&text);
+    let bin_name = generator_name();
+    format!("{}# Automatically added by {bin_name}\n{code}# End automatically added section\n{}", &text[..pos], &text[pos..]).into_bytes()
+}
+
+/// Length of the shebang, comments and `set` commands at the start of the script
+fn header_len(text: &str) -> usize {
+    let mut len = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !(trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("set ")) || !line.ends_with('\n') {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
+#[test]
+fn adds_debconf_code() {
+    let postinst = add_to_script("postinst", b"#!/bin/sh\n# comment\nset -e\n\ndb_get example/port\n".to_vec());
+    let bin_name = generator_name();
+    assert_eq!(String::from_utf8(postinst).unwrap(), format!("#!/bin/sh\n# comment\nset -e\n\n# Automatically added by {bin_name}\n. /usr/share/debconf/confmodule\n# End automatically added section\ndb_get example/port\n"));
+    let sourced = b"#!/bin/sh\n. /usr/share/debconf/confmodule\n".to_vec();
+    assert_eq!(add_to_script("postinst", sourced.clone()), sourced);
+    let postrm = String::from_utf8(add_to_script("postrm", EMPTY_SCRIPT.to_vec())).unwrap();
+    assert!(postrm.starts_with(&format!("#!/bin/sh\nset -e\n# Automatically added by {bin_name}\nif [ \"$1\" = purge ]")), "{postrm}");
+    assert_eq!(add_to_script("prerm", b"x".to_vec()), b"x");
+}
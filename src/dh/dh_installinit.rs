@@ -0,0 +1,37 @@
+//! Installs a SysV init script and registers it with `update-rc.d`, like `dh_installinit` does for `debian/<package>.init`.
+//!
+//! On systems running systemd, `invoke-rc.d --skip-systemd-native` leaves the service to its systemd unit, if there's one.
+//!
+//! See [dh_installinit(1)](https://manpages.debian.org/dh_installinit.1) and [update-rc.d(8)](https://manpages.debian.org/update-rc.d.8).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+
+/// The script is installed as `/etc/init.d/<script_name>`
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, script_name: &str, listener: &dyn Listener) -> CDResult<()> {
+    let replacements = HashMap::from([
+        ("SCRIPT", script_name.to_owned()),
+        ("INITPARMS", "defaults".to_owned()),
+        ("ERROR_HANDLER", "exit 1".to_owned()),
+    ]);
+    autoscript(scripts, package, "postinst", "postinst-init", &replacements, true, listener)?;
+    autoscript(scripts, package, "prerm", "prerm-init", &replacements, true, listener)?;
+    autoscript(scripts, package, "postrm", "postrm-init", &replacements, false, listener)?;
+    Ok(())
+}
+
+#[test]
+fn init_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", "mypkg", &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.service"]).unwrap();
+    assert!(postinst.contains("\t\tupdate-rc.d mypkg defaults >/dev/null\n"), "{postinst}");
+    assert!(postinst.contains("invoke-rc.d --skip-systemd-native mypkg $_dh_action || exit 1\n"), "{postinst}");
+    let prerm = std::str::from_utf8(&scripts["mypkg.prerm.service"]).unwrap();
+    assert!(prerm.contains("invoke-rc.d --skip-systemd-native mypkg stop || exit 1\n"), "{prerm}");
+    let postrm = std::str::from_utf8(&scripts["mypkg.postrm.debhelper"]).unwrap();
+    assert!(postrm.contains("update-rc.d mypkg remove >/dev/null"), "{postrm}");
+}
@@ -0,0 +1,77 @@
+//! Checks logrotate configs of the package, which `dh_installlogrotate` would install from `debian/<package>.logrotate`.
+//!
+//! See [dh_installlogrotate(1)](https://manpages.debian.org/dh_installlogrotate.1) and [logrotate(8)](https://manpages.debian.org/logrotate.8).
+use crate::assets::Asset;
+use crate::listener::Listener;
+use crate::CDResult;
+use std::path::Path;
+use std::process::Command;
+
+pub(crate) const LOGROTATE_DIR: &str = "etc/logrotate.d";
+
+/// Assets in `/etc/logrotate.d`
+fn logrotate_configs(assets: &[Asset]) -> impl Iterator<Item = &Asset> {
+    assets.iter().filter(|a| a.c.target_path.parent() == Some(Path::new(LOGROTATE_DIR)))
+}
+
+/// Runs `logrotate -d` on the configs, if it's installed, and warns about the errors it finds
+pub(crate) fn check_configs(assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    check_configs_with("logrotate", assets, listener)
+}
+
+fn check_configs_with(logrotate: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    let mut configs = logrotate_configs(assets).peekable();
+    if configs.peek().is_none() {
+        return Ok(());
+    }
+    let temp_dir = tempfile::tempdir()?;
+    let state_path = temp_dir.path().join("state");
+    for asset in configs {
+        let config_path = temp_dir.path().join("config");
+        std::fs::write(&config_path, asset.source.data()?)?;
+        // -d doesn't rotate anything, and the state file keeps it away from the system's state
+        let Ok(output) = Command::new(logrotate).arg("-d").arg("-s").arg(&state_path).arg(&config_path).output() else {
+            // logrotate isn't installed
+            return Ok(());
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let errors = stderr.lines().filter(|l| l.starts_with("error:")).collect::<Vec<_>>().join("\n");
+            listener.warning(format!("logrotate found errors in /{}:\n{}", asset.c.target_path.display(), if errors.is_empty() { stderr.trim_end() } else { &errors }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn finds_logrotate_configs() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let assets = ["etc/logrotate.d/mypkg", "etc/mypkg.conf", "etc/logrotate.d/sub/x"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    let found = logrotate_configs(&assets).map(|a| a.c.target_path.to_str().unwrap()).collect::<Vec<_>>();
+    assert_eq!(found, ["etc/logrotate.d/mypkg"]);
+}
+
+#[test]
+fn checks_logrotate_configs() {
+    use crate::assets::{AssetSource, IsBuilt};
+    use std::os::unix::fs::PermissionsExt;
+
+    // a stand-in for logrotate, which rejects configs with `bad` in them
+    let dir = tempfile::tempdir().unwrap();
+    let logrotate = dir.path().join("logrotate");
+    std::fs::write(&logrotate, "#!/bin/sh\nif grep -q bad \"$4\"; then echo 'reading config file'; echo 'error: bad line' >&2; exit 1; fi\n").unwrap();
+    std::fs::set_permissions(&logrotate, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let logrotate = logrotate.to_str().unwrap();
+    let config = |name: &str, data: &str| Asset::new(AssetSource::Data(data.into()), Path::new(LOGROTATE_DIR).join(name), 0o644, IsBuilt::No, false);
+
+    let mut mock_listener = crate::listener::MockListener::new();
+    mock_listener.expect_warning().times(0);
+    check_configs_with(logrotate, &[config("good", "/var/log/good.log {}\n")], &mock_listener).unwrap();
+    // not installed
+    check_configs_with("/nonexistent/logrotate", &[config("bad", "bad")], &mock_listener).unwrap();
+
+    let mut mock_listener = crate::listener::MockListener::new();
+    mock_listener.expect_warning().withf(|w| w == "logrotate found errors in /etc/logrotate.d/bad:\nerror: bad line").times(1).return_const(());
+    check_configs_with(logrotate, &[config("good", "/var/log/good.log {}\n"), config("bad", "bad")], &mock_listener).unwrap();
+}
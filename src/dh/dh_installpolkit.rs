@@ -0,0 +1,111 @@
+//! Checks polkit actions and rules of the package. debhelper has no helper for them, and polkit notices new files by itself.
+//!
+//! See [polkit(8)](https://manpages.debian.org/polkit.8).
+use crate::assets::Asset;
+use crate::listener::Listener;
+use crate::CDResult;
+use std::path::Path;
+use std::process::Command;
+
+pub(crate) const ACTIONS_DIR: &str = "usr/share/polkit-1/actions";
+pub(crate) const RULES_DIR: &str = "usr/share/polkit-1/rules.d";
+
+/// Rules directory for the local admin
+const ETC_RULES_DIR: &str = "etc/polkit-1/rules.d";
+
+/// Directory of `.policy` or `.rules` files
+pub(crate) fn target_dir(path: &str) -> Option<&'static str> {
+    match Path::new(path).extension()?.to_str()? {
+        "policy" => Some(ACTIONS_DIR),
+        "rules" => Some(RULES_DIR),
+        _ => None,
+    }
+}
+
+/// Problems with the contents of an actions file that polkit would reject or ignore
+fn policy_problems(path: &Path, text: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !text.contains("<policyconfig>") {
+        problems.push("it has no <policyconfig> element".to_owned());
+        return problems;
+    }
+    let ids = regex::Regex::new(r#"<action[ \t\r\n]+id[ \t\r\n]*=[ \t\r\n]*"([^"]*)""#).unwrap();
+    let ids = ids.captures_iter(text).map(|c| c[1].to_owned()).collect::<Vec<_>>();
+    if ids.is_empty() {
+        problems.push("it has no <action id=\"…\"> elements".to_owned());
+    }
+    for id in &ids {
+        if !id.contains('.') || !id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || ".-".contains(c)) {
+            problems.push(format!("the action id '{id}' should be lowercase and in the reverse-DNS form, like org.example.action"));
+        }
+    }
+    if text.matches("<defaults>").count() < ids.len() {
+        problems.push("each action needs <defaults>".to_owned());
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    if !ids.is_empty() && !ids.iter().all(|id| id.starts_with(&*stem)) {
+        problems.push(format!("it should be named after the prefix of its action ids, instead of {stem}.policy"));
+    }
+    problems
+}
+
+/// Runs `xmllint` on the file, if it's installed
+fn xmllint_error(text: &str) -> CDResult<Option<String>> {
+    use std::io::Write;
+    let mut file = tempfile::NamedTempFile::new()?;
+    file.write_all(text.as_bytes())?;
+    let Ok(output) = Command::new("xmllint").arg("--noout").arg("--nonet").arg(file.path()).output() else {
+        return Ok(None);
+    };
+    Ok((!output.status.success()).then(|| String::from_utf8_lossy(&output.stderr).trim_end().replace(&*file.path().to_string_lossy(), "line")))
+}
+
+/// Warns about actions that polkit would reject, and about rules that could be changed by anyone but root.
+/// Rules are JavaScript that grants privileges, so they have to be owned and only writable by root.
+pub(crate) fn check_files(assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    for asset in assets {
+        let path = &asset.c.target_path;
+        let Some(dir) = path.parent() else { continue };
+        if dir == Path::new(ETC_RULES_DIR) {
+            listener.warning(format!("/{} is for the local admin's rules; packages should install them in /{RULES_DIR}", path.display()));
+        } else if dir == Path::new(RULES_DIR) {
+            if path.extension().map_or(true, |ext| ext != "rules") {
+                listener.warning(format!("polkit ignores /{}, because the file name doesn't end with .rules", path.display()));
+            }
+            if asset.c.chmod & 0o022 != 0 {
+                listener.warning(format!("/{} can be changed by other users than root (mode {:o})", path.display(), asset.c.chmod));
+            }
+        } else if dir == Path::new(ACTIONS_DIR) {
+            if path.extension().map_or(true, |ext| ext != "policy") {
+                listener.warning(format!("polkit ignores /{}, because the file name doesn't end with .policy", path.display()));
+                continue;
+            }
+            let text = String::from_utf8_lossy(&asset.source.data()?).into_owned();
+            if let Some(error) = xmllint_error(&text)? {
+                listener.warning(format!("polkit actions /{} aren't valid XML:\n{error}", path.display()));
+            }
+            for problem in policy_problems(path, &text) {
+                listener.warning(format!("polkit actions /{}: {problem}", path.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn checks_policies() {
+    let path = Path::new("usr/share/polkit-1/actions/org.example.mypkg.policy");
+    let policy = r#"<?xml version="1.0" encoding="UTF-8"?>
+<policyconfig>
+  <action id="org.example.mypkg.run">
+    <description>Run</description>
+    <defaults><allow_active>auth_admin</allow_active></defaults>
+  </action>
+</policyconfig>"#;
+    assert_eq!(policy_problems(path, policy), Vec::<String>::new());
+    let problems = policy_problems(Path::new("usr/share/polkit-1/actions/mypkg.policy"), &policy.replace("org.example.mypkg.run", "Run").replace("<defaults>", ""));
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert_eq!(policy_problems(path, "<busconfig/>").len(), 1);
+    assert_eq!(target_dir("x/50-mypkg.rules"), Some(RULES_DIR));
+    assert_eq!(target_dir("x/mypkg.pkla"), None);
+}
@@ -22,7 +22,7 @@ use crate::assets::Asset;
 use crate::dh::dh_lib::{autoscript, pkgfile, ScriptFragments};
 use crate::listener::Listener;
 use crate::util::{fname_from_path, MyJoin};
-use crate::CDResult;
+use crate::{CDResult, CargoDebError};
 
 /// From man 1 dh_installsystemd on Ubuntu 20.04 LTS. See:
 ///   <http://manpages.ubuntu.com/manpages/focal/en/man1/dh_installsystemd.1.html>
@@ -41,6 +41,8 @@ use crate::CDResult;
 ///            currently only used by systemd.
 const LIB_SYSTEMD_SYSTEM_DIR: &str = "lib/systemd/system/";
 const USR_LIB_TMPFILES_D_DIR: &str = "usr/lib/tmpfiles.d/";
+/// Units that start a service when they're triggered
+const TRIGGER_UNIT_SUFFIXES: [&str; 3] = [".timer", ".socket", ".path"];
 const SYSTEMD_UNIT_FILE_INSTALL_MAPPINGS: [(&str, &str, &str); 12] = [
     ("",  "mount",   LIB_SYSTEMD_SYSTEM_DIR),
     ("",  "path",    LIB_SYSTEMD_SYSTEM_DIR),
@@ -175,6 +177,28 @@ pub fn find_units(dir: &Path, main_package: &str, unit_name: Option<&str>) -> Pa
     installables
 }
 
+/// Install recipes for unit files that are installed with their own file names,
+/// like `debian/foo-worker.service`, regardless of the package's name.
+pub fn explicit_units(paths: &[PathBuf]) -> CDResult<PackageUnitFiles> {
+    let mut installables = HashMap::new();
+    for src_path in paths {
+        let file_name = src_path.file_name().and_then(|f| f.to_str()).unwrap_or_default();
+        let (stem, unit_type) = file_name.rsplit_once('.').unwrap_or_default();
+        let (_, _, install_dir) = SYSTEMD_UNIT_FILE_INSTALL_MAPPINGS.iter()
+            .find(|(_, t, _)| *t == unit_type)
+            .ok_or_else(|| CargoDebError::InvalidField("systemd unit-files", src_path.display().to_string(), "the file name must end with the unit type, like .service or .timer"))?;
+        let install_filename = match unit_type {
+            "tmpfile" => format!("{stem}.conf"),
+            _ => file_name.to_owned(),
+        };
+        installables.insert(src_path.clone(), InstallRecipe {
+            path: Path::new(install_dir).join(install_filename),
+            mode: 0o644,
+        });
+    }
+    Ok(installables)
+}
+
 /// Determine if the given string is a systemd unit file comment line.
 ///
 /// See:
@@ -217,16 +241,20 @@ fn unquote(s: &str) -> &str {
 ///
 /// Pass the `ScriptFragments` result to `apply()`.
 ///
+/// Only the unit (and tmpfiles) assets with the install paths that `is_selected` are
+/// analysed, so that each set of units can have its own options. Units they refer to
+/// via `Also=` are included.
+///
 /// See:
 ///   <https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installsystemd?h=applied/12.10ubuntu1#n288>
-pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &dyn Listener) -> CDResult<ScriptFragments> {
+pub fn generate(package: &str, assets: &[Asset], is_selected: &dyn Fn(&Path) -> bool, options: &Options, listener: &dyn Listener) -> CDResult<ScriptFragments> {
     let mut scripts = ScriptFragments::new();
 
     // add postinst code blocks to handle tmpfiles
     // see: https://salsa.debian.org/debian/debhelper/-/blob/master/dh_installsystemd#L305
     let tmp_file_names = assets
         .iter()
-        .filter(|a| a.c.target_path.starts_with(USR_LIB_TMPFILES_D_DIR))
+        .filter(|a| a.c.target_path.starts_with(USR_LIB_TMPFILES_D_DIR) && is_selected(&a.c.target_path))
         .map(|v| fname_from_path(v.source.path().unwrap()))
         .collect::<Vec<String>>()
         .join(" ");
@@ -247,7 +275,7 @@ pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &d
     installed_non_template_units.extend(
         assets
             .iter()
-            .filter(|a| a.c.target_path.parent() == Some(LIB_SYSTEMD_SYSTEM_DIR.as_ref()))
+            .filter(|a| a.c.target_path.parent() == Some(LIB_SYSTEMD_SYSTEM_DIR.as_ref()) && is_selected(&a.c.target_path))
             .map(|a| fname_from_path(a.c.target_path.as_path()))
             .filter(|fname| !fname.contains('@')),
     );
@@ -259,6 +287,8 @@ pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &d
     let mut enable_units = BTreeSet::new();
     let mut start_units = BTreeSet::new();
     let mut seen = BTreeSet::new();
+    // services started by timer, socket, and path units
+    let mut triggered_units = BTreeSet::new();
 
     // note: we do not support handling of services with a sysv-equivalent
     // see: https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installsystemd?h=applied/12.10ubuntu1#n373
@@ -283,6 +313,10 @@ pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &d
             let data = assets.iter().find(move |&item| item.c.target_path == needle).unwrap().source.data()?;
             let reader = data.into_owned();
 
+            let is_trigger = TRIGGER_UNIT_SUFFIXES.iter().any(|suffix| unit.ends_with(suffix));
+            let mut triggered_unit = None;
+            let mut accepts_connections = false;
+
             // for every line in the file look for specific keys that we are
             // interested in:
             // From: https://www.freedesktop.org/software/systemd/man/systemd.syntax.html
@@ -305,28 +339,39 @@ pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &d
                 if let Some((key, value)) = possible_kv_pair {
                     let other_unit = unquote(value).to_string();
                     match key {
-                        "Also" => {
-                            // The seen lookup prevents us from looping forever over
-                            // unit files that refer to each other. An actual
-                            // real-world example of such a loop is systemd's
-                            // systemd-readahead-drop.service, which contains
-                            // Also=systemd-readahead-collect.service, and that file
-                            // in turn contains Also=systemd-readahead-drop.service,
-                            // thus forming an endless loop.
-                            // see: https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installsystemd?h=applied/12.10ubuntu1#n340
-                            if seen.insert(other_unit.clone()) {
-                                also_units.insert(other_unit);
-                            }
+                        // The seen lookup prevents us from looping forever over
+                        // unit files that refer to each other. An actual
+                        // real-world example of such a loop is systemd's
+                        // systemd-readahead-drop.service, which contains
+                        // Also=systemd-readahead-collect.service, and that file
+                        // in turn contains Also=systemd-readahead-drop.service,
+                        // thus forming an endless loop.
+                        // see: https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installsystemd?h=applied/12.10ubuntu1#n340
+                        "Also" if seen.insert(other_unit.clone()) => {
+                            also_units.insert(other_unit);
                         },
                         "Alias" => {
                             aliases.insert(other_unit);
                         },
+                        // Unit= of timers and paths, and Service= of sockets
+                        "Unit" | "Service" if is_trigger => {
+                            triggered_unit = Some(other_unit);
+                        },
+                        // each connection starts an instance of a template service
+                        "Accept" if is_trigger => {
+                            accepts_connections = matches!(unquote(value), "yes" | "true" | "on" | "1");
+                        },
                         _ => (),
                     };
                 } else if line.starts_with("[Install]") {
                     enable_units.insert(unit.clone());
                 }
             }
+            // by default the unit starts the service with the same name
+            if is_trigger && !accepts_connections {
+                let stem = unit.rsplit_once('.').map_or(unit.as_str(), |(stem, _)| stem);
+                triggered_units.insert(triggered_unit.unwrap_or_else(|| format!("{stem}.service")));
+            }
         }
         units = also_units;
     }
@@ -344,6 +389,22 @@ pub fn generate(package: &str, assets: &[Asset], options: &Options, listener: &d
             &map!{ "UNITFILES" => enable_units.join(" ") }, false, listener)?;
     }
 
+    // services started by other units of the package are only restarted if they're running,
+    // and are stopped after the units that start them, so that they're not started again.
+    // Fragments of prerm run in the reverse order, so these go first.
+    let (triggered_units, start_units): (BTreeSet<_>, BTreeSet<_>) = start_units.into_iter().partition(|u| triggered_units.contains(u));
+    if !triggered_units.is_empty() {
+        let replace = map! { "UNITFILES" => triggered_units.join(" "), "RESTART_ACTION" => "try-restart".into() };
+        if options.restart_after_upgrade {
+            autoscript(&mut scripts, package, "postinst", "postinst-systemd-restartnostart", &replace, true, listener)?;
+        }
+        let snippet = if options.no_stop_on_upgrade || options.restart_after_upgrade { "prerm-systemd-restart" } else { "prerm-systemd" };
+        autoscript(&mut scripts, package, "prerm", snippet, &replace, true, listener)?;
+        if start_units.is_empty() {
+            autoscript(&mut scripts, package, "postrm", "postrm-systemd-reload-only", &replace, false, listener)?;
+        }
+    }
+
     // update the maintainer scripts to start units, where the exact action to
     // be taken is influenced by the options passed to us.
     // see: https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installsystemd?h=applied/12.10ubuntu1#n398
@@ -537,12 +598,64 @@ mod tests {
         assert_eq!(7, pkg_unit_files.len());
     }
 
+    #[test]
+    fn explicit_units_keep_their_names() {
+        let units = explicit_units(&["debian/foo-worker.service".into(), "debian/foo.tmpfile".into()]).unwrap();
+        assert_eq!(units[Path::new("debian/foo-worker.service")].path, Path::new("lib/systemd/system/foo-worker.service"));
+        assert_eq!(units[Path::new("debian/foo.tmpfile")].path, Path::new("usr/lib/tmpfiles.d/foo.conf"));
+        assert!(explicit_units(&["debian/foo.conf".into()]).is_err());
+    }
+
+    #[test]
+    fn generate_uses_only_selected_units() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        set_test_fs_path_content("debian/a.service", "[Service]\nExecStart=/usr/bin/a\n".into());
+        set_test_fs_path_content("debian/b.service", "[Service]\nExecStart=/usr/bin/b\n".into());
+        let assets = ["a", "b"].map(|name| Asset::new(
+            AssetSource::Path(format!("debian/{name}.service").into()),
+            format!("lib/systemd/system/{name}.service").into(),
+            0o644,
+            crate::assets::IsBuilt::No,
+            false,
+        ));
+        let fragments = generate("mypkg", &assets, &|p| p.ends_with("b.service"), &Options::default(), &mock_listener).unwrap();
+        let prerm = String::from_utf8(fragments["mypkg.prerm.service"].clone()).unwrap();
+        assert!(prerm.contains("b.service") && !prerm.contains("a.service"), "{prerm}");
+    }
+
+    #[test]
+    fn generate_starts_timers_instead_of_their_services() {
+        let mut mock_listener = crate::listener::MockListener::new();
+        mock_listener.expect_info().return_const(());
+        set_test_fs_path_content("debian/backup.service", "[Service]\nType=oneshot\nExecStart=/usr/bin/backup\n".into());
+        set_test_fs_path_content("debian/backup.timer", "[Timer]\nOnCalendar=daily\n[Install]\nWantedBy=timers.target\n".into());
+        set_test_fs_path_content("debian/web.socket", "[Socket]\nListenStream=80\nService=httpd.service\n".into());
+        set_test_fs_path_content("debian/httpd.service", "[Service]\nExecStart=/usr/bin/httpd\n".into());
+        let assets = ["backup.service", "backup.timer", "web.socket", "httpd.service"].map(|name| Asset::new(
+            AssetSource::Path(format!("debian/{name}").into()),
+            format!("lib/systemd/system/{name}").into(),
+            0o644,
+            crate::assets::IsBuilt::No,
+            false,
+        ));
+        let options = Options { restart_after_upgrade: true, ..Options::default() };
+        let fragments = generate("mypkg", &assets, &|_| true, &options, &mock_listener).unwrap();
+        let postinst = String::from_utf8(fragments["mypkg.postinst.service"].clone()).unwrap();
+        assert!(postinst.contains("deb-systemd-invoke try-restart backup.service httpd.service"), "{postinst}");
+        assert!(postinst.contains("deb-systemd-invoke $_dh_action backup.timer web.socket"), "{postinst}");
+        let prerm = String::from_utf8(fragments["mypkg.prerm.service"].clone()).unwrap();
+        let stop_triggers = prerm.find("stop backup.timer web.socket").unwrap();
+        let stop_services = prerm.find("stop backup.service httpd.service").unwrap();
+        assert!(stop_triggers < stop_services, "{prerm}");
+    }
+
     #[test]
     fn generate_with_empty_inputs_does_nothing() {
         let mut mock_listener = crate::listener::MockListener::new();
         mock_listener.expect_info().times(0).return_const(());
 
-        let fragments = generate("", &[], &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("", &[], &|_| true, &Options::default(), &mock_listener).unwrap();
 
         assert!(fragments.is_empty());
     }
@@ -560,7 +673,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert!(fragments.is_empty());
     }
 
@@ -578,7 +691,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert!(fragments.is_empty());
     }
 
@@ -596,7 +709,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert!(fragments.is_empty());
     }
 
@@ -618,7 +731,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert_eq!(1, fragments.len());
 
         let (fragment_name, fragment_bytes) = fragments.into_iter().next().unwrap();
@@ -669,7 +782,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert_eq!(0, fragments.len());
     }
 
@@ -686,7 +799,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert_eq!(0, fragments.len());
     }
 
@@ -704,7 +817,7 @@ mod tests {
             false,
         )];
 
-        let fragments = generate("mypkg", &assets, &Options::default(), &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &Options::default(), &mock_listener).unwrap();
         assert_eq!(0, fragments.len());
     }
 
@@ -814,7 +927,7 @@ WantedBy=multi-user.target");
         ]);
 
         // generate!
-        let fragments = generate("mypkg", &assets, &options, &mock_listener).unwrap();
+        let fragments = generate("mypkg", &assets, &|_| true, &options, &mock_listener).unwrap();
 
         // verify, though don't verify creation of autoscript fragments as that
         // is verified in tests of the lower level functionality, instead verify
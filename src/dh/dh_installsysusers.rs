@@ -0,0 +1,44 @@
+//! Creates system users and groups in the `postinst` from the package's `sysusers.d` files, like `dh_installsysusers` does.
+//!
+//! See [dh_installsysusers(1)](https://manpages.debian.org/dh_installsysusers.1) and [sysusers.d(5)](https://manpages.debian.org/sysusers.d.5).
+use crate::assets::Asset;
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::util::fname_from_path;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+const SYSUSERS_DIR: &str = "usr/lib/sysusers.d";
+
+/// What `${misc:Depends}` gets from `dh_installsysusers`
+pub(crate) const SYSUSERS_DEPENDS: &str = "systemd | systemd-standalone-sysusers | systemd-sysusers";
+
+/// Files of the assets in `/usr/lib/sysusers.d`
+pub(crate) fn sysusers_files(assets: &[Asset]) -> impl Iterator<Item = &Path> {
+    assets.iter().map(|a| a.c.target_path.as_path())
+        .filter(|p| p.parent() == Some(Path::new(SYSUSERS_DIR)) && p.extension().is_some_and(|ext| ext == "conf"))
+}
+
+/// Users have to be created before anything else that may need them, so this should be the first `postinst` fragment
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    for path in sysusers_files(assets) {
+        let replacements = HashMap::from([("CONFILE_BASE", fname_from_path(path))]);
+        autoscript(scripts, package, "postinst", "postinst-sysusers", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn sysusers_fragments() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let assets = ["usr/lib/sysusers.d/mypkg.conf", "usr/lib/sysusers.d/README", "etc/mypkg.conf"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &assets, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tsystemd-sysusers ${DPKG_ROOT:+--root=\"$DPKG_ROOT\"} mypkg.conf\n"), "{postinst}");
+    assert_eq!(1, postinst.matches("systemd-sysusers").count());
+}
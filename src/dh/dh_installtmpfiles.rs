@@ -0,0 +1,45 @@
+//! Creates the package's `tmpfiles.d` files and directories in the `postinst`, like `dh_installtmpfiles` does.
+//!
+//! When `systemd-units` are configured, `dh_installsystemd` does the same, like it did in older versions of debhelper.
+//!
+//! See [dh_installtmpfiles(1)](https://manpages.debian.org/dh_installtmpfiles.1) and [tmpfiles.d(5)](https://manpages.debian.org/tmpfiles.d.5).
+use crate::assets::Asset;
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::util::fname_from_path;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+const TMPFILES_DIRS: [&str; 2] = ["usr/lib/tmpfiles.d", "etc/tmpfiles.d"];
+
+/// What `${misc:Depends}` gets from `dh_installtmpfiles`
+pub(crate) const TMPFILES_DEPENDS: &str = "systemd | systemd-standalone-tmpfiles | systemd-tmpfiles";
+
+/// Files of the assets in the `tmpfiles.d` directories
+pub(crate) fn tmpfiles_files(assets: &[Asset]) -> impl Iterator<Item = &Path> {
+    assets.iter().map(|a| a.c.target_path.as_path())
+        .filter(|p| p.parent().is_some_and(|dir| TMPFILES_DIRS.iter().any(|d| dir == Path::new(d))) && p.extension().is_some_and(|ext| ext == "conf"))
+}
+
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    let names = tmpfiles_files(assets).map(fname_from_path).collect::<Vec<_>>();
+    if !names.is_empty() {
+        let replacements = HashMap::from([("TMPFILES", names.join(" "))]);
+        autoscript(scripts, package, "postinst", "postinst-init-tmpfiles", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn tmpfiles_fragments() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let assets = ["usr/lib/tmpfiles.d/mypkg.conf", "etc/tmpfiles.d/mypkg-cache.conf", "usr/lib/mypkg/x.conf"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &assets, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("systemd-tmpfiles --create mypkg.conf mypkg-cache.conf >/dev/null"), "{postinst}");
+}
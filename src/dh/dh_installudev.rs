@@ -0,0 +1,72 @@
+//! Reloads udev rules of the package after installing and removing it, and checks that udev won't ignore the rules files.
+//! `dh_installudev` would install the rules from `debian/<package>.udev`.
+//!
+//! See [dh_installudev(1)](https://manpages.debian.org/dh_installudev.1) and [udev(7)](https://manpages.debian.org/udev.7).
+use crate::assets::Asset;
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub(crate) const UDEV_RULES_DIR: &str = "usr/lib/udev/rules.d";
+
+/// The rules directory before the `/usr` merge, which udev still reads
+const OLD_UDEV_RULES_DIR: &str = "lib/udev/rules.d";
+
+/// Rules directory for the local admin
+const ETC_UDEV_RULES_DIR: &str = "etc/udev/rules.d";
+
+/// The priority of the rules installed by `dh_installudev`
+pub(crate) const DEFAULT_PRIORITY: &str = "60";
+
+/// Assets in any of the udev rules directories
+fn rules_files(assets: &[Asset]) -> impl Iterator<Item = &Path> {
+    assets.iter().map(|a| a.c.target_path.as_path())
+        .filter(|p| p.parent().is_some_and(|dir| [UDEV_RULES_DIR, OLD_UDEV_RULES_DIR, ETC_UDEV_RULES_DIR].iter().any(|d| dir == Path::new(d))))
+}
+
+/// Like lintian, warns about rules that udev ignores or that belong to the local admin
+pub(crate) fn check_rules(assets: &[Asset], listener: &dyn Listener) {
+    for path in rules_files(assets) {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.starts_with(ETC_UDEV_RULES_DIR) {
+            listener.warning(format!("/{} is for the local admin's rules; packages should install rules in /{UDEV_RULES_DIR}", path.display()));
+        } else if !file_name.ends_with(".rules") {
+            listener.warning(format!("udev ignores /{}, because the file name doesn't end with .rules", path.display()));
+        } else if !file_name.starts_with(|c: char| c.is_ascii_digit()) {
+            listener.warning(format!("/{} should start with a number, like {DEFAULT_PRIORITY}-{file_name}, because udev reads the rules in the order of their names", path.display()));
+        }
+    }
+}
+
+/// Makes udev use the new rules, and stop using the removed ones
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    if rules_files(assets).any(|p| !p.starts_with(ETC_UDEV_RULES_DIR) && p.extension().is_some_and(|ext| ext == "rules")) {
+        // the snippets have nothing to replace
+        for (script, snippet_filename) in [("postinst", "postinst-udev"), ("postrm", "postrm-udev")] {
+            autoscript(scripts, package, script, snippet_filename, &HashMap::new(), false, listener)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn udev_fragments_and_checks() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    listener.expect_warning().times(3).return_const(());
+    let assets = ["usr/lib/udev/rules.d/60-mypkg.rules", "lib/udev/rules.d/mypkg.rules", "usr/lib/udev/rules.d/70-mypkg", "etc/udev/rules.d/60-mypkg.rules", "etc/mypkg.conf"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    check_rules(&assets, &listener);
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &assets, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tudevadm control --reload-rules || true\n"), "{postinst}");
+    assert!(scripts.contains_key("mypkg.postrm.debhelper"));
+
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &assets[4..], &listener).unwrap();
+    assert!(scripts.is_empty());
+}
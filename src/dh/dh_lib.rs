@@ -27,16 +27,36 @@ use crate::CDResult;
 ///   <https://git.launchpad.net/ubuntu/+source/debhelper/tree/autoscripts?h=applied/12.10ubuntu1>
 /// To understand which scripts are invoked when, consult:
 ///   <https://www.debian.org/doc/debian-policy/ap-flowcharts.htm>
-
-static AUTOSCRIPTS: [(&str, &[u8]); 10] = [
+static AUTOSCRIPTS: [(&str, &[u8]); 31] = [
+    ("maintscript-helper", include_bytes!("../../autoscripts/maintscript-helper")),
+    ("postinst-alternatives", include_bytes!("../../autoscripts/postinst-alternatives")),
+    ("postinst-apparmor", include_bytes!("../../autoscripts/postinst-apparmor")),
+    ("postinst-dkms", include_bytes!("../../autoscripts/postinst-dkms")),
+    ("postinst-init", include_bytes!("../../autoscripts/postinst-init")),
     ("postinst-init-tmpfiles", include_bytes!("../../autoscripts/postinst-init-tmpfiles")),
+    ("postinst-selinux", include_bytes!("../../autoscripts/postinst-selinux")),
+    ("postinst-setcap", include_bytes!("../../autoscripts/postinst-setcap")),
+    ("postinst-statoverride", include_bytes!("../../autoscripts/postinst-statoverride")),
     ("postinst-systemd-dont-enable", include_bytes!("../../autoscripts/postinst-systemd-dont-enable")),
     ("postinst-systemd-enable", include_bytes!("../../autoscripts/postinst-systemd-enable")),
     ("postinst-systemd-restart", include_bytes!("../../autoscripts/postinst-systemd-restart")),
     ("postinst-systemd-restartnostart", include_bytes!("../../autoscripts/postinst-systemd-restartnostart")),
     ("postinst-systemd-start", include_bytes!("../../autoscripts/postinst-systemd-start")),
+    ("postinst-sysusers", include_bytes!("../../autoscripts/postinst-sysusers")),
+    ("postinst-ucf", include_bytes!("../../autoscripts/postinst-ucf")),
+    ("postinst-udev", include_bytes!("../../autoscripts/postinst-udev")),
+    ("postrm-apparmor", include_bytes!("../../autoscripts/postrm-apparmor")),
+    ("postrm-init", include_bytes!("../../autoscripts/postrm-init")),
+    ("postrm-statoverride", include_bytes!("../../autoscripts/postrm-statoverride")),
     ("postrm-systemd", include_bytes!("../../autoscripts/postrm-systemd")),
     ("postrm-systemd-reload-only", include_bytes!("../../autoscripts/postrm-systemd-reload-only")),
+    ("postrm-ucf", include_bytes!("../../autoscripts/postrm-ucf")),
+    ("postrm-udev", include_bytes!("../../autoscripts/postrm-udev")),
+    ("prerm-alternatives", include_bytes!("../../autoscripts/prerm-alternatives")),
+    ("prerm-apparmor", include_bytes!("../../autoscripts/prerm-apparmor")),
+    ("prerm-dkms", include_bytes!("../../autoscripts/prerm-dkms")),
+    ("prerm-init", include_bytes!("../../autoscripts/prerm-init")),
+    ("prerm-selinux", include_bytes!("../../autoscripts/prerm-selinux")),
     ("prerm-systemd", include_bytes!("../../autoscripts/prerm-systemd")),
     ("prerm-systemd-restart", include_bytes!("../../autoscripts/prerm-systemd-restart")),
 ];
@@ -142,6 +162,7 @@ pub(crate) fn get_embedded_autoscript(snippet_filename: &str) -> String {
 ///
 /// Takes a map of values to search and replace in the selected "autoscript"
 /// fragment such as a systemd unit name placeholder and value.
+/// With no replacements, the fragment is used as it is.
 ///
 /// # Cargo Deb specific behaviour
 ///
@@ -166,44 +187,53 @@ pub(crate) fn autoscript(
     service_order: bool,
     listener: &dyn Listener,
 ) -> CDResult<()> {
-    let bin_name = std::env::current_exe().unwrap();
-    let bin_name = bin_name.file_name().unwrap();
-    let bin_name = bin_name.to_str().unwrap();
+    listener.info(format!("Maintainer script {script} will be augmented with autoscript {snippet_filename}"));
+    add_fragment(scripts, package, script, &autoscript_sed(snippet_filename, replacements), service_order)
+}
+
+/// Adds generated shell code to the fragments of a maintainer script, to be inserted by `apply()`.
+///
+/// Like in debhelper, fragments of `preinst` and `postinst` run in the order they were added,
+/// and fragments of `prerm` and `postrm` in the reverse order, so that removal undoes things in the opposite order.
+/// Fragments added with `service_order` go after (or in removal scripts, before) the others.
+pub(crate) fn add_fragment(scripts: &mut ScriptFragments, package: &str, script: &str, snippet: &str, service_order: bool) -> CDResult<()> {
+    let bin_name = generator_name();
     let outfile_ext = if service_order { "service" } else { "debhelper" };
     let outfile = format!("{package}.{script}.{outfile_ext}");
 
-    listener.info(format!("Maintainer script {script} will be augmented with autoscript {snippet_filename}"));
+    let mut fragment = format!("# Automatically added by {bin_name}\n");
+    fragment.push_str(snippet);
+    if !snippet.ends_with('\n') {
+        fragment.push('\n');
+    }
+    fragment.push_str("# End automatically added section\n");
+    merge_fragment(scripts, outfile, fragment.into_bytes())
+}
 
-    if scripts.contains_key(&outfile) && (script == "postrm" || script == "prerm") {
-        if !replacements.is_empty() {
-            let existing_text = std::str::from_utf8(scripts.get(&outfile).unwrap())?;
-
-            // prepend new text to existing script fragment
-            let new_text = [
-                &format!("# Automatically added by {bin_name}\n"),
-                &autoscript_sed(snippet_filename, replacements),
-                "# End automatically added section\n",
-                existing_text,
-            ].concat();
-            scripts.insert(outfile, new_text.into());
-        } else {
-            // We don't support sed commands yet.
-            unimplemented!();
-        }
-    } else if !replacements.is_empty() {
-        // append to existing script fragment (if any)
-        let new_text = [
-            std::str::from_utf8(scripts.get(&outfile).unwrap_or(&Vec::new()))?,
-            &format!("# Automatically added by {bin_name}\n"),
-            &autoscript_sed(snippet_filename, replacements),
-            "# End automatically added section\n",
-        ].concat();
-        scripts.insert(outfile, new_text.into());
-    } else {
-        // We don't support sed commands yet.
-        unimplemented!();
+/// Name of the running executable, for the `# Automatically added by` comments in maintainer scripts
+pub(crate) fn generator_name() -> String {
+    let bin_name = std::env::current_exe().unwrap();
+    let bin_name = bin_name.file_name().unwrap();
+    bin_name.to_str().unwrap().to_owned()
+}
+
+/// Combines fragments of separately generated `ScriptFragments`, in the same order as `add_fragment()`
+pub(crate) fn merge(scripts: &mut ScriptFragments, fragments: ScriptFragments) -> CDResult<()> {
+    for (outfile, fragment) in fragments {
+        merge_fragment(scripts, outfile, fragment)?;
     }
+    Ok(())
+}
 
+fn merge_fragment(scripts: &mut ScriptFragments, outfile: String, fragment: Vec<u8>) -> CDResult<()> {
+    std::str::from_utf8(&fragment)?;
+    let is_removal = outfile.split('.').rev().nth(1).is_some_and(|script| script == "prerm" || script == "postrm");
+    let text = scripts.entry(outfile).or_default();
+    if is_removal {
+        text.splice(0..0, fragment);
+    } else {
+        text.extend_from_slice(&fragment);
+    }
     Ok(())
 }
 
@@ -246,10 +276,10 @@ fn autoscript_sed(snippet_filename: &str, replacements: &HashMap<&str, String>)
 /// # References
 ///
 /// <https://git.launchpad.net/ubuntu/+source/debhelper/tree/lib/Debian/Debhelper/Dh_Lib.pm?h=applied/12.10ubuntu1#n2161>
-fn debhelper_script_subst(user_scripts_dir: &Path, scripts: &mut ScriptFragments, package: &str, script: &str, unit_name: Option<&str>,
+fn debhelper_script_subst(user_scripts_dir: Option<&Path>, scripts: &mut ScriptFragments, package: &str, script: &str, unit_name: Option<&str>,
     listener: &dyn Listener) -> CDResult<()>
 {
-    let user_file = pkgfile(user_scripts_dir, package, package, script, unit_name);
+    let user_file = user_scripts_dir.and_then(|dir| pkgfile(dir, package, package, script, unit_name));
     let mut generated_scripts: Vec<String> = vec![
         format!("{package}.{script}.debhelper"),
         format!("{package}.{script}.service"),
@@ -299,7 +329,7 @@ fn debhelper_script_subst(user_scripts_dir: &Path, scripts: &mut ScriptFragments
 /// on disk supplied by the user.
 ///
 /// See: <https://git.launchpad.net/ubuntu/+source/debhelper/tree/dh_installdeb?h=applied/12.10ubuntu1#n300>
-pub(crate) fn apply(user_scripts_dir: &Path, scripts: &mut ScriptFragments, package: &str, unit_name: Option<&str>, listener: &dyn Listener) -> CDResult<()> {
+pub(crate) fn apply(user_scripts_dir: Option<&Path>, scripts: &mut ScriptFragments, package: &str, unit_name: Option<&str>, listener: &dyn Listener) -> CDResult<()> {
     for script in &["postinst", "preinst", "prerm", "postrm"] {
         // note: we don't support custom defines thus we don't have the final
         // 'package_subst' argument to debhelper_script_subst().
@@ -425,16 +455,15 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "not implemented")]
-    fn autoscript_panics_in_sed_mode() {
+    fn autoscript_without_replacements() {
         let mut mock_listener = crate::listener::MockListener::new();
         mock_listener.expect_info().times(1).return_const(());
         let mut scripts = ScriptFragments::new();
 
-        // sed mode is when no search -> replacement pairs are defined
-        let sed_mode = &HashMap::new();
+        autoscript(&mut scripts, "mypkg", "postinst", "postinst-udev", &HashMap::new(), false, &mock_listener).unwrap();
 
-        autoscript(&mut scripts, "mypkg", "somescript", "idontexist", sed_mode, false, &mock_listener).unwrap();
+        let fragment = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+        assert!(fragment.contains(get_embedded_autoscript("postinst-udev").as_str()), "{fragment}");
     }
 
     #[test]
@@ -443,14 +472,35 @@ mod tests {
         actual_scripts.sort_unstable();
 
         let expected_scripts = vec![
+            "maintscript-helper",
+            "postinst-alternatives",
+            "postinst-apparmor",
+            "postinst-dkms",
+            "postinst-init",
             "postinst-init-tmpfiles",
+            "postinst-selinux",
+            "postinst-setcap",
+            "postinst-statoverride",
             "postinst-systemd-dont-enable",
             "postinst-systemd-enable",
             "postinst-systemd-restart",
             "postinst-systemd-restartnostart",
             "postinst-systemd-start",
+            "postinst-sysusers",
+            "postinst-ucf",
+            "postinst-udev",
+            "postrm-apparmor",
+            "postrm-init",
+            "postrm-statoverride",
             "postrm-systemd",
             "postrm-systemd-reload-only",
+            "postrm-ucf",
+            "postrm-udev",
+            "prerm-alternatives",
+            "prerm-apparmor",
+            "prerm-dkms",
+            "prerm-init",
+            "prerm-selinux",
             "prerm-systemd",
             "prerm-systemd-restart",
         ];
@@ -594,7 +644,7 @@ mod tests {
         let mut scripts = ScriptFragments::new();
 
         assert_eq!(0, scripts.len());
-        debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
+        debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
         assert_eq!(0, scripts.len());
     }
 
@@ -609,7 +659,7 @@ mod tests {
 
         let mut scripts = ScriptFragments::new();
 
-        match debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener) {
+        match debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener) {
             Ok(_) => (),
             Err(CargoDebError::DebHelperReplaceFailed(_)) => panic!("Test failed as expected"),
             Err(err) => panic!("Unexpected error {err:?}"),
@@ -628,7 +678,7 @@ mod tests {
         let mut scripts = ScriptFragments::new();
 
         assert_eq!(0, scripts.len());
-        debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
+        debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
         assert_eq!(1, scripts.len());
         assert!(scripts.contains_key("myscript"));
     }
@@ -647,7 +697,7 @@ mod tests {
         scripts.insert("mypkg.myscript.debhelper".to_owned(), "injected".as_bytes().to_vec());
 
         assert_eq!(1, scripts.len());
-        debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
+        debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
         assert_eq!(2, scripts.len());
         assert!(scripts.contains_key("mypkg.myscript.debhelper"));
         assert!(scripts.contains_key("myscript"));
@@ -669,7 +719,7 @@ mod tests {
         scripts.insert("mypkg.myscript.debhelper".to_owned(), "injected".as_bytes().to_vec());
 
         assert_eq!(1, scripts.len());
-        debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
+        debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener).unwrap();
         assert_eq!(2, scripts.len());
         assert!(scripts.contains_key("mypkg.myscript.debhelper"));
         assert!(scripts.contains_key("myscript"));
@@ -701,7 +751,7 @@ mod tests {
         scripts.insert(format!("mypkg.{maintainer_script}.service"), "second".as_bytes().to_vec());
 
         assert_eq!(2, scripts.len());
-        debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", maintainer_script, None, &mock_listener).unwrap();
+        debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", maintainer_script, None, &mock_listener).unwrap();
         assert_eq!(3, scripts.len());
         assert!(scripts.contains_key(&format!("mypkg.{maintainer_script}.debhelper")));
         assert!(scripts.contains_key(&format!("mypkg.{maintainer_script}.service")));
@@ -735,7 +785,7 @@ mod tests {
         let mut scripts = ScriptFragments::new();
 
         assert_eq!(0, scripts.len());
-        let result = debhelper_script_subst(Path::new(""), &mut scripts, "mypkg", "myscript", None, &mock_listener);
+        let result = debhelper_script_subst(Some(Path::new("")), &mut scripts, "mypkg", "myscript", None, &mock_listener);
 
         assert!(matches!(result, Err(CargoDebError::Io(_))));
         if let CargoDebError::Io(err) = result.unwrap_err() {
@@ -745,11 +795,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fragments_of_removal_scripts_are_in_reverse_order() {
+        let mut scripts = ScriptFragments::new();
+        add_fragment(&mut scripts, "mypkg", "postinst", "first", false).unwrap();
+        add_fragment(&mut scripts, "mypkg", "prerm", "first", false).unwrap();
+        let mut other = ScriptFragments::new();
+        add_fragment(&mut other, "mypkg", "postinst", "second", false).unwrap();
+        add_fragment(&mut other, "mypkg", "prerm", "second", false).unwrap();
+        merge(&mut scripts, other).unwrap();
+
+        let postinst = script_to_string(&scripts, "mypkg.postinst.debhelper");
+        assert!(postinst.find("first").unwrap() < postinst.find("second").unwrap(), "{postinst}");
+        let prerm = script_to_string(&scripts, "mypkg.prerm.debhelper");
+        assert!(prerm.find("second").unwrap() < prerm.find("first").unwrap(), "{prerm}");
+        assert!(prerm.ends_with("first\n# End automatically added section\n"));
+    }
+
     #[test]
     fn apply_with_no_matching_files() {
         let mut mock_listener = crate::listener::MockListener::new();
         mock_listener.expect_info().times(0).return_const(());
-        apply(Path::new(""), &mut ScriptFragments::new(), "mypkg", None, &mock_listener).unwrap();
+        apply(Some(Path::new("")), &mut ScriptFragments::new(), "mypkg", None, &mock_listener).unwrap();
     }
 
     #[rstest]
@@ -765,6 +832,6 @@ mod tests {
         let mut mock_listener = crate::listener::MockListener::new();
         mock_listener.expect_info().times(scripts.len()).return_const(());
 
-        apply(Path::new(""), &mut ScriptFragments::new(), "mypkg", None, &mock_listener).unwrap();
+        apply(Some(Path::new("")), &mut ScriptFragments::new(), "mypkg", None, &mock_listener).unwrap();
     }
 }
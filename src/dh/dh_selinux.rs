@@ -0,0 +1,52 @@
+//! Installs SELinux policy modules of the package with `semodule`, when SELinux is enabled.
+//! debhelper has no helper for them, so `--init-debian-dir` puts this code in the `postinst` and `prerm` it exports.
+//!
+//! See [semodule(8)](https://manpages.debian.org/semodule.8).
+use crate::assets::Asset;
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Modules are installed in `usr/share/selinux/<package>/`
+pub(crate) fn modules_dir(deb_name: &str) -> PathBuf {
+    Path::new("usr/share/selinux").join(deb_name)
+}
+
+/// `.pp` files in the package's modules directory
+fn modules<'a>(assets: &'a [Asset], deb_name: &str) -> impl Iterator<Item = &'a Path> {
+    let dir = modules_dir(deb_name);
+    assets.iter().map(|a| a.c.target_path.as_path())
+        .filter(move |p| p.parent() == Some(&dir) && p.extension().is_some_and(|ext| ext == "pp"))
+}
+
+/// Modules are named after their files, and removed in the `prerm`, before the files are gone
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, deb_name: &str, assets: &[Asset], listener: &dyn Listener) -> CDResult<()> {
+    for path in modules(assets, deb_name) {
+        let module = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let replacements = HashMap::from([
+            ("MODULE", module),
+            ("MODULE_PATH", path.to_string_lossy().into_owned()),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-selinux", &replacements, false, listener)?;
+        autoscript(scripts, package, "prerm", "prerm-selinux", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn selinux_fragments() {
+    use crate::assets::{AssetSource, IsBuilt};
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let assets = ["usr/share/selinux/mypkg/mypkg.pp", "usr/share/selinux/mypkg/mypkg.te", "usr/share/selinux/other/other.pp"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", "mypkg", &assets, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tsemodule -i \"/usr/share/selinux/mypkg/mypkg.pp\" ||"), "{postinst}");
+    assert_eq!(1, postinst.matches("semodule").count());
+    let prerm = std::str::from_utf8(&scripts["mypkg.prerm.debhelper"]).unwrap();
+    assert!(prerm.contains("\tsemodule -r \"mypkg\" || true\n"), "{prerm}");
+}
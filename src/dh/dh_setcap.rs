@@ -0,0 +1,33 @@
+//! Sets file capabilities in the `postinst`, because dpkg doesn't keep extended attributes of files in the archive.
+//!
+//! Packages built with debhelper call `setcap` in their own `postinst`, which is where `--init-debian-dir` puts this code too.
+//! The script only warns if `setcap` is missing or fails, for example on filesystems without extended attributes.
+//!
+//! See [setcap(8)](https://manpages.debian.org/setcap.8) and [capabilities(7)](https://manpages.debian.org/capabilities.7).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+
+/// `files` are pairs of the absolute path of an asset and its capabilities, like `cap_net_bind_service+ep`
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, files: &[(String, String)], listener: &dyn Listener) -> CDResult<()> {
+    for (path, capabilities) in files {
+        let replacements = HashMap::from([
+            ("PATH", path.clone()),
+            ("CAPABILITIES", capabilities.clone()),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-setcap", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn setcap_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", &[("/usr/bin/ping".into(), "cap_net_raw+ep".into())], &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\t\tif ! setcap cap_net_raw+ep \"/usr/bin/ping\"; then\n"), "{postinst}");
+    assert!(postinst.contains("command -v setcap"), "{postinst}");
+}
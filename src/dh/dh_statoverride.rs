@@ -0,0 +1,47 @@
+//! Sets the owner and mode of files with `dpkg-statoverride` in the `postinst`, and removes the overrides on purge.
+//!
+//! This keeps setuid bits and ownership by system users out of the archive,
+//! and lets the admin change them in a way that upgrades keep.
+//! There's no `dh_` command for it, so `--init-debian-dir` copies this code into the exported `postinst` and `postrm`.
+//!
+//! See [dpkg-statoverride(1)](https://manpages.debian.org/dpkg-statoverride.1).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StatOverride {
+    /// Absolute path of the asset
+    pub path: String,
+    pub owner: String,
+    pub group: String,
+    pub mode: u32,
+}
+
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, overrides: &[StatOverride], listener: &dyn Listener) -> CDResult<()> {
+    for o in overrides {
+        let replacements = HashMap::from([
+            ("PATH", o.path.clone()),
+            ("OWNER", o.owner.clone()),
+            ("GROUP", o.group.clone()),
+            ("MODE", format!("{:04o}", o.mode)),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-statoverride", &replacements, false, listener)?;
+        autoscript(scripts, package, "postrm", "postrm-statoverride", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn statoverride_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    let o = StatOverride { path: "/usr/games/foo".into(), owner: "root".into(), group: "games".into(), mode: 0o2755 };
+    generate(&mut scripts, "mypkg", &[o], &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\t\tdpkg-statoverride --update --add root games 2755 \"/usr/games/foo\"\n"), "{postinst}");
+    let postrm = std::str::from_utf8(&scripts["mypkg.postrm.debhelper"]).unwrap();
+    assert!(postrm.contains("\t\tdpkg-statoverride --remove \"/usr/games/foo\"\n"), "{postrm}");
+}
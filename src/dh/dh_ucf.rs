@@ -0,0 +1,39 @@
+//! Registers configuration files with `ucf`, like `dh_ucf` does for the lines of `debian/<package>.ucf`.
+//!
+//! Unlike conffiles, `ucf` can manage configuration files that are generated or changed by maintainer scripts,
+//! and asks about changes to them when the package is upgraded.
+//!
+//! See [dh_ucf(1)](https://manpages.debian.org/dh_ucf.1) and [ucf(1)](https://manpages.debian.org/ucf.1).
+use crate::dh::dh_lib::{autoscript, ScriptFragments};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// `files` are pairs of the configuration file's path and its reference copy in the package, which `ucfr` registers as `deb_name`'s.
+/// With `debconf`, `ucf` is told that the `postinst` uses debconf too.
+pub(crate) fn generate(scripts: &mut ScriptFragments, package: &str, deb_name: &str, files: &[(PathBuf, PathBuf)], debconf: bool, listener: &dyn Listener) -> CDResult<()> {
+    for (conffile, reference) in files {
+        let replacements = HashMap::from([
+            ("PACKAGE", deb_name.to_owned()),
+            ("CONFFILE", conffile.display().to_string()),
+            ("REFERENCE", reference.display().to_string()),
+            ("UCF_FLAGS", if debconf { "--debconf-ok ".into() } else { String::new() }),
+        ]);
+        autoscript(scripts, package, "postinst", "postinst-ucf", &replacements, false, listener)?;
+        autoscript(scripts, package, "postrm", "postrm-ucf", &replacements, false, listener)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn ucf_fragments() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_info().return_const(());
+    let mut scripts = ScriptFragments::new();
+    generate(&mut scripts, "mypkg", "mypkg", &[("/etc/mypkg.conf".into(), "/usr/share/mypkg/mypkg.conf".into())], false, &listener).unwrap();
+    let postinst = std::str::from_utf8(&scripts["mypkg.postinst.debhelper"]).unwrap();
+    assert!(postinst.contains("\tucf \"/usr/share/mypkg/mypkg.conf\" \"/etc/mypkg.conf\"\n\tucfr mypkg \"/etc/mypkg.conf\"\n"), "{postinst}");
+    let postrm = std::str::from_utf8(&scripts["mypkg.postrm.debhelper"]).unwrap();
+    assert!(postrm.contains("ucfr --purge mypkg \"/etc/mypkg.conf\""), "{postrm}");
+}
@@ -23,6 +23,10 @@ quick_error! {
             display("Command {} failed to launch", cmd)
             source(err)
         }
+        SignerFailed(err: io::Error, cmd: String) {
+            display("Signing command {} failed to launch", cmd)
+            source(err)
+        }
         CommandError(msg: &'static str, arg: String, reason: Vec<u8>) {
             display("{} ({}): {}", msg, arg, String::from_utf8_lossy(reason))
         }
@@ -49,6 +53,12 @@ quick_error! {
         StripFailed(name: PathBuf, reason: String) {
             display("unable to strip binary '{}': {}", name.display(), reason)
         }
+        RpathRemovalFailed(name: PathBuf, reason: String) {
+            display("unable to remove RPATH from '{}': {}", name.display(), reason)
+        }
+        AuditDataMissing(name: PathBuf) {
+            display("'{}' has no dependency list of cargo-auditable (the .dep-v0 section)", name.display())
+        }
         SystemTime(err: time::SystemTimeError) {
             from()
             display("unable to get system time")
@@ -85,6 +95,18 @@ quick_error! {
             display("unable to parse glob pattern")
             source(err)
         }
+        InvalidRelationship(msg: &'static str, value: String) {
+            display("{}: {}", msg, value)
+        }
+        InvalidField(field: &'static str, value: String, msg: &'static str) {
+            display("{} '{}' is invalid: {}", field, value, msg)
+        }
+        InvalidChangelog(msg: String, path: PathBuf) {
+            display("{}: {}", path.display(), msg)
+        }
+        LicensePolicy(crates: String) {
+            display("license-policy doesn't allow the licenses of the dependencies: {}", crates)
+        }
         AssetFileNotFound(path: PathBuf) {
             display("Asset file path does not match any files: {}", path.display())
         }
@@ -93,6 +115,22 @@ quick_error! {
             display("unable to iterate asset glob result")
             source(err)
         }
+        NotReproducible(difference: String, first: PathBuf) {
+            display("the package is not reproducible, {}. The first build has been kept in {}", difference, first.display())
+        }
+        UploadTargetNotFound(name: String, available: String) {
+            display("upload target '{}' is not in [package.metadata.deb.upload] or --upload-config. Available targets are: {}", name, available)
+        }
+        UploadConfigParsing(err: Box<toml::de::Error>, path: PathBuf) {
+            display("unable to parse upload config {}", path.display())
+            source(err)
+        }
+        UploadFailed(target: String, reason: String) {
+            display("upload to {} failed: {}", target, reason)
+        }
+        InvalidCompressionLevel(level: String, format: &'static str) {
+            display("compression level {} is not supported by {}", level, format)
+        }
         #[cfg(feature = "lzma")]
         LzmaCompressionError(err: xz2::stream::Error) {
             display("lzma compression error: {:?}", err)
@@ -1,4 +1,5 @@
-#![recursion_limit = "128"]
+// for `quick_error!`, which expands recursively once per variant of `CargoDebError`
+#![recursion_limit = "256"]
 #![allow(clippy::case_sensitive_file_extension_comparisons)]
 #![allow(clippy::if_not_else)]
 #![allow(clippy::missing_errors_doc)]
@@ -28,35 +29,75 @@ The library interface is experimental. See `main.rs` for usage.
 
 pub mod deb {
     pub mod ar;
+    pub(crate) mod buildinfo;
+    pub(crate) mod changes;
+    pub mod checksums;
     pub mod control;
+    pub(crate) mod debian_dir;
+    pub mod sign;
+    pub(crate) mod source;
     pub mod tar;
+    pub(crate) mod verify;
 }
 #[macro_use]
 mod util;
 mod dh {
+    pub(crate) mod dh_apparmor;
+    pub(crate) mod dh_appstream;
+    pub(crate) mod dh_dkms;
+    pub(crate) mod dh_icons;
+    pub(crate) mod dh_install;
+    pub(crate) mod dh_installalternatives;
+    pub(crate) mod dh_installdbus;
+    pub(crate) mod dh_installdeb;
+    pub(crate) mod dh_installdebconf;
+    pub(crate) mod dh_installinit;
+    pub(crate) mod dh_installlogrotate;
+    pub(crate) mod dh_installpolkit;
     pub(crate) mod dh_installsystemd;
+    pub(crate) mod dh_installsysusers;
+    pub(crate) mod dh_installtmpfiles;
+    pub(crate) mod dh_installudev;
     pub(crate) mod dh_lib;
+    pub(crate) mod dh_selinux;
+    pub(crate) mod dh_setcap;
+    pub(crate) mod dh_statoverride;
+    pub(crate) mod dh_ucf;
 }
 pub mod listener;
 pub(crate) mod parse {
     pub(crate) mod cargo;
     pub(crate) mod manifest;
 }
-pub use crate::config::{Config, DebugSymbols, PackageConfig};
+pub use crate::config::{Config, DbgsymFormat, DebugSymbols, PackageConfig};
 pub use crate::deb::ar::DebArchive;
 pub use crate::error::*;
 pub use crate::util::compress;
-use crate::util::compress::{CompressConfig, Format};
+use crate::util::compress::{CompressConfig, Format, Level};
 
 pub mod assets;
+mod auditable;
+mod changelog;
+mod completions;
 pub mod config;
 mod dependencies;
 mod error;
+mod librust;
+mod licenses;
+mod manpages;
+mod sbom;
+mod symbols;
+mod translations;
+mod upload;
 
 use crate::assets::{Asset, AssetSource, IsBuilt, ProcessedFrom, compress_assets};
+use crate::deb::buildinfo::BuildInfo;
+use crate::deb::changes::ChangesFile;
 use crate::deb::control::ControlArchiveBuilder;
 use crate::deb::tar::Tarball;
+use crate::librust::CrateSources;
 use crate::listener::Listener;
+use crate::upload::Uploader;
 use config::DebConfigOverrides;
 use rayon::prelude::*;
 use std::env;
@@ -64,6 +105,7 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
+use std::sync::OnceLock;
 
 const TAR_REJECTS_CUR_DIR: bool = true;
 
@@ -87,7 +129,7 @@ impl CargoDeb {
         if self.options.system_xz {
             listener.warning("--system-xz is deprecated, use --compress-system instead.".into());
 
-            self.options.compress_type = Format::Xz;
+            self.options.compress_type = Some(Format::Xz);
             self.options.compress_system = true;
         }
 
@@ -95,74 +137,322 @@ impl CargoDeb {
         // cargo build accordingly. you could argue that the other way around is
         // more desirable. However for now we want all commands coming in via the
         // same `interface`
-        let selected_profile = self.options.profile;
-        if selected_profile.as_deref() == Some("dev") {
+        if self.options.profile.as_deref() == Some("dev") {
             listener.warning("dev profile is not supported and will be a hard error in the future. \
                 cargo-deb is for making releases, and it doesn't make sense to use it with dev profiles.".into());
             listener.warning("To enable debug symbols set `[profile.release] debug = true` instead.".into());
         }
 
+        if self.options.source {
+            return self.make_source(listener);
+        }
+        if self.options.init_debian_dir {
+            let (config, mut package_deb) = self.load_config(listener)?;
+            config.prepare_assets_before_build(&mut package_deb)?;
+            deb::debian_dir::write_debian_dir(&config, &package_deb, &config.path_in_package("debian"), listener)?;
+            return Ok(());
+        }
+
+        let BuiltPackages { debs: generated, changes, buildinfo, uploader } = self.make_deb(!self.options.no_build, true, listener)?;
+
+        if self.options.verify_reproducible {
+            self.verify_reproducible(&generated, listener)?;
+        }
+
+        // signatures have timestamps, so they're added after the check for reproducibility
+        if let Some(signer) = self.options.sign.as_deref() {
+            for deb in &generated {
+                deb::sign::sign_deb(deb, signer)?;
+                listener.info(format!("Signed {}", deb.display()));
+            }
+        }
+
+        // checksums in the .buildinfo and .changes files must be of the final, signed packages
+        let mut uploaded_files = generated.clone();
+        if let Some(buildinfo) = buildinfo {
+            let path = buildinfo.write(&generated)?;
+            listener.info(format!("Wrote {}", path.display()));
+            uploaded_files.push(path);
+        }
+        if let Some(changes) = changes {
+            let path = changes.write(&uploaded_files)?;
+            if let Some(signer) = self.options.sign.as_deref() {
+                deb::sign::clearsign_file(&path, signer)?;
+            }
+            listener.info(format!("Wrote {}", path.display()));
+            uploaded_files.push(path);
+        }
+
+        let checksums = generated.iter().map(|deb| {
+            self.options.checksums.iter().map(|&algorithm| {
+                let (checksum, path) = deb::checksums::write_checksum_file(deb, algorithm)?;
+                listener.info(format!("Wrote {}", path.display()));
+                uploaded_files.push(path);
+                Ok((algorithm, checksum))
+            }).collect::<CDResult<Vec<_>>>()
+        }).collect::<CDResult<Vec<_>>>()?;
+
+        if let Some(uploader) = uploader {
+            uploader.upload(&generated, &uploaded_files, self.options.sign.is_some(), listener)?;
+        }
+
+        // the main package is last, because scripts may read the last line of the output
+        for (deb, deb_checksums) in generated.iter().zip(&checksums).rev() {
+            for (algorithm, checksum) in deb_checksums {
+                listener.generated_checksum(deb, *algorithm, checksum);
+            }
+            listener.generated_archive(deb);
+        }
+
+        if self.options.install {
+            install_deb(&generated[0])?;
+        }
+        Ok(())
+    }
+
+    /// Packages the same build again, and checks that it's identical
+    fn verify_reproducible(&mut self, generated: &[PathBuf], listener: &dyn Listener) -> CDResult<()> {
+        let first = generated.iter().map(|generated| {
+            let mut first = generated.as_os_str().to_owned();
+            first.push(".first");
+            let first = PathBuf::from(first);
+            // building again deletes other versions of the same package
+            fs::rename(generated, &first)?;
+            Ok(first)
+        }).collect::<CDResult<Vec<_>>>()?;
+        // stripped binaries from the cache would be the same files as in the first packages
+        let again = self.make_deb(false, false, listener)?.debs;
+        if again.len() != first.len() {
+            return Err(CargoDebError::NotReproducible("the number of packages changed".into(), first[0].clone()));
+        }
+        for (first, again) in first.into_iter().zip(again) {
+            match deb::verify::first_difference(&first, &again)? {
+                None => {
+                    listener.info(format!("{} is reproducible", again.display()));
+                    fs::remove_file(&first)?;
+                },
+                Some(difference) => return Err(CargoDebError::NotReproducible(difference, first)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a source package instead of building the binary package
+    fn make_source(&self, listener: &dyn Listener) -> CDResult<()> {
+        let (config, mut package_deb) = self.load_config(listener)?;
+        let uploader = (!self.options.upload.is_empty())
+            .then(|| Uploader::new(&self.options.upload, self.options.upload_config.as_deref().map(Path::new), &package_deb))
+            .transpose()?;
+        let mut files = deb::source::write_source_package(&config, &package_deb, self.options.variant.as_deref(), self.options.vendor, self.options.cargo_locking_flags, listener)?;
+        let signer = self.options.sign.as_deref();
+        if let Some(signer) = signer {
+            deb::sign::clearsign_file(&files[0], signer)?;
+        }
+        let source_files = files.clone();
+        if self.options.changes || uploader.as_ref().is_some_and(Uploader::needs_changes) {
+            // the package isn't built yet, so its files are the source files
+            package_deb.architecture = "source".into();
+            let mut changes = ChangesFile::new(&config, &package_deb)?;
+            changes.add_binary(&package_deb);
+            let path = changes.write(&files)?;
+            if let Some(signer) = signer {
+                deb::sign::clearsign_file(&path, signer)?;
+            }
+            listener.info(format!("Wrote {}", path.display()));
+            files.push(path);
+        }
+        if let Some(uploader) = uploader {
+            uploader.upload(&source_files, &files, signer.is_some(), listener)?;
+        }
+        // the .dsc is the last line
+        for file in source_files.iter().rev() {
+            listener.generated_archive(file);
+        }
+        Ok(())
+    }
+
+    fn load_config(&self, listener: &dyn Listener) -> CDResult<(Config, PackageConfig)> {
         let root_manifest_path = self.options.manifest_path.as_deref().map(Path::new);
-        let (mut config, mut package_deb) = Config::from_manifest(
+        Config::from_manifest(
             root_manifest_path,
             self.options.selected_package_name.as_deref(),
-            self.options.output_path,
+            self.options.output_path.clone(),
             self.options.target.as_deref(),
             self.options.variant.as_deref(),
-            self.options.overrides,
-            selected_profile,
+            self.options.overrides.clone(),
+            self.options.profile.clone(),
             self.options.separate_debug_symbols,
             self.options.compress_debug_symbols,
+            self.options.generate_dbgsym_package,
+            self.options.ddeb,
+            self.options.dwz,
             self.options.cargo_locking_flags,
             listener,
-        )?;
+        )
+    }
+
+    fn make_deb(&mut self, build: bool, use_strip_cache: bool, listener: &dyn Listener) -> CDResult<BuiltPackages> {
+        let (mut config, mut package_deb) = self.load_config(listener)?;
+        if self.options.keyring && package_deb.keyring.is_none() {
+            return Err("--keyring needs a [package.metadata.deb.keyring] section".into());
+        }
+        // checked before the build, so that a typo doesn't waste it
+        let uploader = (!self.options.upload.is_empty())
+            .then(|| Uploader::new(&self.options.upload, self.options.upload_config.as_deref().map(Path::new), &package_deb))
+            .transpose()?;
+        if self.options.librust_dev {
+            let crate_path = deb::source::cargo_package(&config, &package_deb, self.options.cargo_locking_flags)?;
+            let librust_package = package_deb.librust_package(&config, &CrateSources::from_crate_file(&crate_path)?)?;
+            config.reset_deb_temp_directory(&librust_package)?;
+            return self.write_packages(&config, &[&librust_package], uploader, listener);
+        }
         config.prepare_assets_before_build(&mut package_deb)?;
 
-        if !self.options.no_build {
+        if build {
             config.set_cargo_build_flags_for_package(&package_deb, &mut self.options.cargo_build_flags);
+            if package_deb.auditable {
+                auditable::check_installed()?;
+                self.options.cargo_build_cmd = auditable::build_command(&self.options.cargo_build_cmd);
+            }
             cargo_build(&config, self.options.target.as_deref(), &self.options.cargo_build_cmd, &self.options.cargo_build_flags, self.options.verbose)?;
         }
 
         package_deb.resolve_assets()?;
+        if package_deb.generate_man_pages {
+            manpages::add_generated_man_pages(&config, &mut package_deb, listener)?;
+        }
+        if package_deb.completions.generate {
+            completions::add_generated_completions(&config, &mut package_deb, listener)?;
+        }
         package_deb.resolve_binary_dependencies(config.target.as_deref(), listener)?;
 
         compress_assets(&mut package_deb, listener)?;
 
-        if self.options.strip_override.unwrap_or(config.debug_symbols != DebugSymbols::Keep) {
-            strip_binaries(&mut config, &mut package_deb, self.options.target.as_deref(), listener)?;
+        if self.options.strip_override == Some(true) && config.debug_symbols == DebugSymbols::Keep {
+            config.debug_symbols = DebugSymbols::Strip;
+        }
+        if self.options.strip_override.unwrap_or(config.debug_symbols != DebugSymbols::Keep || package_deb.has_separate_debug_symbols_override()) {
+            strip_binaries_with_cache(&mut config, &mut package_deb, self.options.target.as_deref(), use_strip_cache, listener)?;
         } else {
             log::debug!("not stripping debug={:?} strip-flag={:?}", config.debug_symbols, self.options.strip_override);
         }
 
+        check_rpaths(&config, &mut package_deb, listener)?;
+        if package_deb.auditable {
+            auditable::check_binaries(&mut package_deb, listener)?;
+        }
+
+        if let Some(dir) = &self.options.debuginfod_out {
+            write_debuginfod_dir(&package_deb, Path::new(dir), listener)?;
+        }
+
         package_deb.sort_assets_by_type();
 
-        let generated = write_deb(&config, &package_deb, &CompressConfig {
+        let dbgsym_package = if let DebugSymbols::Separate { dbgsym: Some(format), .. } = config.debug_symbols {
+            let dbgsym = package_deb.split_dbgsym(format);
+            if dbgsym.is_none() {
+                listener.warning("no debug symbols were found, so the dbgsym package won't be created".into());
+            }
+            dbgsym
+        } else {
+            None
+        };
+
+        let keyring_package = if self.options.keyring { package_deb.keyring_package(&config)? } else { None };
+
+        let packages = std::iter::once(&package_deb).chain(&dbgsym_package).chain(&keyring_package).collect::<Vec<_>>();
+        self.write_packages(&config, &packages, uploader, listener)
+    }
+
+    /// Writes the `.deb` files, the main package first
+    fn write_packages(&self, config: &Config, packages: &[&PackageConfig], uploader: Option<Uploader>, listener: &dyn Listener) -> CDResult<BuiltPackages> {
+        let compress_type = self.options.compress_type.or(config.compress_type).unwrap_or(Format::Xz);
+        let control_compress_type = self.options.control_compress_type.or(config.control_compress_type).unwrap_or(compress_type);
+        let compress_level = self.options.compress_level.or(config.compress_level);
+        // levels of different formats aren't interchangeable
+        let control_compress_level = self.options.control_compress_level.or(config.control_compress_level)
+            .or(if control_compress_type == compress_type { compress_level } else { None });
+        let compress_config = CompressConfig {
             fast: self.options.fast,
-            compress_type: self.options.compress_type,
+            compress_type,
+            control_compress_type,
+            compress_level,
+            control_compress_level,
+            compress_threads: self.options.compress_threads,
             compress_system: self.options.compress_system,
             rsyncable: self.options.rsyncable,
-        }, listener)?;
-
-        listener.generated_archive(&generated);
-
-        if self.options.install {
-            install_deb(&generated)?;
+        };
+        let mut changes = (self.options.changes || uploader.as_ref().is_some_and(Uploader::needs_changes)).then(|| ChangesFile::new(config, packages[0])).transpose()?;
+        let mut buildinfo = self.options.buildinfo.then(|| BuildInfo::new(config, packages[0]));
+        let mut debs = Vec::new();
+        for &package in packages {
+            debs.push(write_deb(config, package, &compress_config, listener)?);
+            changes.iter_mut().for_each(|c| c.add_binary(package));
+            buildinfo.iter_mut().for_each(|b| b.add_binary(package));
         }
-        Ok(())
+        if let Some(sbom) = packages[0].sbom.as_ref().filter(|s| s.output) {
+            let path = sbom.path_next_to(&debs[0]);
+            fs::write(&path, sbom.generate(packages[0])).map_err(|e| CargoDebError::IoFile("unable to write SBOM", e, path.clone()))?;
+            listener.info(format!("Wrote {}", path.display()));
+        }
+        Ok(BuiltPackages { debs, changes, buildinfo, uploader })
     }
 }
 
+/// Packages made by one run of `cargo deb`
+struct BuiltPackages {
+    /// The main package, and the dbgsym and keyring packages if any
+    debs: Vec<PathBuf>,
+    /// Description of the packages for the `.changes` file, if requested
+    changes: Option<ChangesFile>,
+    buildinfo: Option<BuildInfo>,
+    /// Repositories selected with `--upload`
+    uploader: Option<Uploader>,
+}
+
 pub struct CargoDebOptions {
     pub no_build: bool,
     pub strip_override: Option<bool>,
     pub separate_debug_symbols: Option<bool>,
     pub compress_debug_symbols: Option<bool>,
+    /// Move separated debug symbols to a `-dbgsym` package
+    pub generate_dbgsym_package: Option<bool>,
+    /// Name the dbgsym package `.ddeb`, implies `generate_dbgsym_package`
+    pub ddeb: Option<bool>,
+    /// Run `dwz` on separated debug symbols
+    pub dwz: Option<bool>,
+    /// Copy separated debug symbols to this directory, in the layout of a debuginfod server
+    pub debuginfod_out: Option<String>,
+    /// Embed a signature in the built packages, like `debsigs`, and sign the `.changes` file
+    pub sign: Option<Box<dyn deb::sign::SigningBackend>>,
+    /// Write a `.changes` file describing the packages
+    pub changes: bool,
+    /// Write a source package instead of building the binary package
+    pub source: bool,
+    /// Include dependencies in the source package, for builds without network access
+    pub vendor: bool,
+    /// Write a `debian/` directory for building with debhelper instead
+    pub init_debian_dir: bool,
+    /// Package the crate's sources as `librust-<name>-dev` instead
+    pub librust_dev: bool,
+    /// Write a `.buildinfo` file with the build environment
+    pub buildinfo: bool,
+    /// Write `<deb>.<algorithm>` files with checksums of the packages
+    pub checksums: Vec<deb::checksums::ChecksumAlgorithm>,
+    /// Also build a package with the repository key from `[package.metadata.deb.keyring]`
+    pub keyring: bool,
+    /// Names of targets in `[package.metadata.deb.upload]` or `upload_config` to publish the packages to
+    pub upload: Vec<String>,
+    /// TOML file with more upload targets, which can keep them out of `Cargo.toml`
+    pub upload_config: Option<String>,
     /// Don't compress heavily
     pub fast: bool,
     /// Build with --verbose
     pub verbose: bool,
     /// Run dpkg -i
     pub install: bool,
+    /// Package twice and compare
+    pub verify_reproducible: bool,
     pub selected_package_name: Option<String>,
     pub output_path: Option<String>,
     pub variant: Option<String>,
@@ -171,7 +461,15 @@ pub struct CargoDebOptions {
     pub cargo_build_cmd: String,
     pub cargo_build_flags: Vec<String>,
     pub overrides: DebConfigOverrides,
-    pub compress_type: Format,
+    /// `None` uses the format from `Cargo.toml`, defaulting to xz
+    pub compress_type: Option<Format>,
+    /// `None` uses the format from `Cargo.toml`, defaulting to `compress_type`
+    pub control_compress_type: Option<Format>,
+    /// `None` uses the level from `Cargo.toml`, or a default for the format
+    pub compress_level: Option<Level>,
+    pub control_compress_level: Option<Level>,
+    /// `None` uses all cores
+    pub compress_threads: Option<u32>,
     pub compress_system: bool,
     pub system_xz: bool,
     pub rsyncable: bool,
@@ -207,9 +505,25 @@ impl Default for CargoDebOptions {
             strip_override: None,
             separate_debug_symbols: None,
             compress_debug_symbols: None,
+            generate_dbgsym_package: None,
+            ddeb: None,
+            dwz: None,
+            debuginfod_out: None,
+            sign: None,
+            changes: false,
+            source: false,
+            vendor: false,
+            init_debian_dir: false,
+            librust_dev: false,
+            buildinfo: false,
+            checksums: Vec::new(),
+            keyring: false,
+            upload: Vec::new(),
+            upload_config: None,
             fast: false,
             verbose: false,
             install: false,
+            verify_reproducible: false,
             selected_package_name: None,
             output_path: None,
             variant: None,
@@ -218,7 +532,11 @@ impl Default for CargoDebOptions {
             cargo_build_cmd: "build".into(),
             cargo_build_flags: Vec::new(),
             overrides: DebConfigOverrides::default(),
-            compress_type: Format::Xz,
+            compress_type: None,
+            control_compress_type: None,
+            compress_level: None,
+            control_compress_level: None,
+            compress_threads: None,
             compress_system: false,
             system_xz: false,
             rsyncable: false,
@@ -238,17 +556,21 @@ pub fn install_deb(path: &Path) -> CDResult<()> {
     Ok(())
 }
 
-pub fn write_deb(config: &Config, package_deb: &PackageConfig, &compress::CompressConfig { fast, compress_type, compress_system, rsyncable }: &compress::CompressConfig, listener: &dyn Listener) -> Result<PathBuf, CargoDebError> {
+pub fn write_deb(config: &Config, package_deb: &PackageConfig, &compress::CompressConfig { fast, compress_type, control_compress_type, compress_level, control_compress_level, compress_threads, compress_system, rsyncable }: &compress::CompressConfig, listener: &dyn Listener) -> Result<PathBuf, CargoDebError> {
+    let deb_temp_dir = config.deb_temp_dir(package_deb);
+    fs::create_dir_all(&deb_temp_dir)?;
+    // The control and data archives are independent, except the sha256sums that need hashes of the data,
+    // so they're compressed in parallel, and only the small sums file is appended to the control archive at the end.
     let (control_builder, data_result) = rayon::join(
-        move || {
+        || {
             // The control archive is the metadata for the package manager
-            let mut control_builder = ControlArchiveBuilder::new(util::compress::select_compressor(fast, compress_type, compress_system)?, package_deb.default_timestamp, listener);
+            let mut control_builder = ControlArchiveBuilder::new(util::compress::select_compressor(fast, control_compress_type, control_compress_level, compress_threads, compress_system, &deb_temp_dir)?, package_deb.default_timestamp, listener);
             control_builder.generate_archive(config, package_deb)?;
             Ok::<_, CargoDebError>(control_builder)
         },
-        move || {
+        || {
             // Initialize the contents of the data archive (files that go into the filesystem).
-            let dest = util::compress::select_compressor(fast, compress_type, compress_system)?;
+            let dest = util::compress::select_compressor(fast, compress_type, compress_level, compress_threads, compress_system, &deb_temp_dir)?;
             let archive = Tarball::new(dest, package_deb.default_timestamp);
             let (compressed, asset_hashes) = archive.archive_files(package_deb, rsyncable, listener)?;
             let sums = package_deb.generate_sha256sums(&asset_hashes)?;
@@ -268,12 +590,11 @@ pub fn write_deb(config: &Config, package_deb: &PackageConfig, &compress::Compre
     let compressed_data_size = data_compressed.len();
     listener.info(format!(
         "compressed/original ratio {compressed_data_size}/{original_data_size} ({}%)",
-        compressed_data_size * 100 / original_data_size
+        compressed_data_size * 100 / (original_data_size as u64).max(1)
     ));
     deb_contents.add_data(data_compressed)?;
     let generated = deb_contents.finish()?;
 
-    let deb_temp_dir = config.deb_temp_dir(package_deb);
     let _ = fs::remove_dir(deb_temp_dir);
 
     Ok(generated)
@@ -327,7 +648,7 @@ pub fn cargo_build(config: &Config, target: Option<&str>, build_command: &str, b
 fn debian_triple_from_rust_triple(rust_target_triple: &str) -> String {
     let mut p = rust_target_triple.split('-');
     let arch = p.next().unwrap();
-    let abi = p.last().unwrap_or("gnu");
+    let abi = p.next_back().unwrap_or("gnu");
 
     let (darch, dabi) = match (arch, abi) {
         ("i586" | "i686", _) => ("i386", "gnu"),
@@ -348,7 +669,7 @@ fn debian_triple_from_rust_triple(rust_target_triple: &str) -> String {
 pub(crate) fn debian_architecture_from_rust_triple(target: &str) -> &str {
     let mut parts = target.split('-');
     let arch = parts.next().unwrap();
-    let abi = parts.last().unwrap_or("");
+    let abi = parts.next_back().unwrap_or("");
     match (arch, abi) {
         // https://wiki.debian.org/Multiarch/Tuples
         // rustc --print target-list
@@ -386,6 +707,11 @@ fn ensure_success(status: ExitStatus) -> io::Result<()> {
 
 /// Strips the binary that was created with cargo
 pub fn strip_binaries(config: &mut Config, package_deb: &mut PackageConfig, target: Option<&str>, listener: &dyn Listener) -> CDResult<()> {
+    strip_binaries_with_cache(config, package_deb, target, true, listener)
+}
+
+/// `strip_binaries`, which can reuse the stripped files from previous runs if `use_cache`
+fn strip_binaries_with_cache(config: &mut Config, package_deb: &mut PackageConfig, target: Option<&str>, use_cache: bool, listener: &dyn Listener) -> CDResult<()> {
     let mut cargo_config = None;
     let objcopy_tmp;
     let strip_tmp;
@@ -408,101 +734,117 @@ pub fn strip_binaries(config: &mut Config, package_deb: &mut PackageConfig, targ
             }
         }
     }
+    // if the tools have been configured, it's better to report their errors
+    let objcopy = BinTool::new(objcopy_cmd, objcopy_cmd == Path::new("objcopy"), &["llvm-objcopy", "rust-objcopy"], listener);
+    let strip = BinTool::new(strip_cmd, strip_cmd == Path::new("strip"), &["llvm-strip", "rust-strip"], listener);
 
     let stripped_binaries_output_dir = config.default_deb_output_dir();
-    let (separate_debug_symbols, compress_debug_symbols) = match config.debug_symbols {
-        DebugSymbols::Keep | DebugSymbols::Strip => (false, false),
-        DebugSymbols::Separate { compress } => (true, compress),
+    let (separate_debug_symbols, compress_debug_symbols, dwz) = match config.debug_symbols {
+        DebugSymbols::Keep | DebugSymbols::Strip => (false, false, false),
+        DebugSymbols::Separate { compress, dwz, .. } => (true, compress, dwz),
     };
+    // assets can opt in to separate debug symbols, while the others keep theirs
+    let keep_debug_symbols = config.debug_symbols == DebugSymbols::Keep;
+    let conf_path = cargo_config.as_ref().map(|c| c.path())
+        .unwrap_or_else(|| Path::new(".cargo/config"));
+    let strip_cache_root = stripped_binaries_output_dir.join("strip-cache").join(&package_deb.name);
 
-    let added_debug_assets = package_deb.built_binaries_mut().into_par_iter().enumerate()
+    let debug_files = package_deb.built_binaries_mut().into_par_iter().enumerate()
         .filter(|(_, asset)| !asset.source.archive_as_symlink_only()) // data won't be included, so nothing to strip
+        .filter(|(_, asset)| !keep_debug_symbols || asset.c.separate_debug_symbols == Some(true))
         .map(|(i, asset)| {
-        let (new_source, new_debug_asset) = if let Some(path) = asset.source.path() {
+        let (new_source, debug_file) = if let Some(path) = asset.source.path() {
             if !path.exists() {
                 return Err(CargoDebError::StripFailed(path.to_owned(), "The file doesn't exist".into()));
             }
 
-            let conf_path = cargo_config.as_ref().map(|c| c.path())
-                .unwrap_or_else(|| Path::new(".cargo/config"));
             let file_name = path.file_stem().ok_or(CargoDebError::Str("bad path"))?.to_string_lossy();
             let stripped_temp_path = stripped_binaries_output_dir.join(format!("{file_name}.tmp{i}-stripped"));
             let _ = fs::remove_file(&stripped_temp_path);
 
-            log::debug!("stripping with {} from {} into {}", strip_cmd.display(), path.display(), stripped_temp_path.display());
-            Command::new(strip_cmd)
-               // same as dh_strip
-               .args(["--strip-unneeded", "--remove-section=.comment", "--remove-section=.note"])
-               .arg("-o").arg(&stripped_temp_path)
-               .arg(path)
-               .status()
-               .and_then(ensure_success)
-               .map_err(|err| {
-                    if let Some(target) = target {
-                        CargoDebError::StripFailed(path.to_owned(), format!("{}: {}.\nhint: Target-specific strip commands are configured in [target.{}] strip = {{ path = \"{}\" }} in {}", strip_cmd.display(), err, target, strip_cmd.display(), conf_path.display()))
-                    } else {
-                        CargoDebError::CommandFailed(err, "strip")
-                    }
-                })?;
-
-            if !stripped_temp_path.exists() {
-                return Err(CargoDebError::StripFailed(path.to_owned(), format!("{} command failed to create output '{}'", strip_cmd.display(), stripped_temp_path.display())));
-            }
-
-            let new_debug_asset = if separate_debug_symbols && asset.c.is_built() {
-                log::debug!("extracting debug info with {} from {}", objcopy_cmd.display(), path.display());
-
-                // parse the ELF and use debug-id-based path if available
-                let debug_target_path = get_target_debug_path(asset, path)?;
-
-                // --add-gnu-debuglink reads the file path given, so it can't get to-be-installed target path
-                // and the recommended fallback solution is to give it relative path in the same dir
-                let debug_temp_path = stripped_temp_path.with_file_name(debug_target_path.file_name().ok_or(CargoDebError::Str("bad path"))?);
+            let separate = asset.c.separate_debug_symbols.unwrap_or(separate_debug_symbols) && asset.c.is_built();
+            // parse the ELF and use debug-id-based path if available
+            let debug_target_path = separate.then(|| get_target_debug_path(asset, path)).transpose()?;
+            // --add-gnu-debuglink reads the file path given, so it can't get to-be-installed target path
+            // and the recommended fallback solution is to give it relative path in the same dir
+            let debug_temp_path = debug_target_path.as_ref()
+                .map(|p| Ok::<_, CargoDebError>(stripped_temp_path.with_file_name(p.file_name().ok_or(CargoDebError::Str("bad path"))?)))
+                .transpose()?;
+
+            // dwz changes the debug files depending on all of them together, so they can't be cached individually
+            let cache = if dwz || !use_cache { None } else {
+                let settings = format!("{strip_cmd:?} {objcopy_cmd:?} {:?} {:?} {separate} {compress_debug_symbols}", config.strip_flags, config.strip_keep_sections);
+                StripCache::new(&strip_cache_root, path, &settings)
+                    .map_err(|e| log::debug!("can't cache {}: {e}", path.display())).ok()
+            };
+            let cached = cache.as_ref().is_some_and(|cache| cache.restore(&stripped_temp_path, debug_temp_path.as_deref()));
 
-                let _ = fs::remove_file(&debug_temp_path);
-                let mut args: &[_] = &["--only-keep-debug", "--compress-debug-sections=zstd"];
-                if !compress_debug_symbols {
-                    args = &args[..1];
-                }
-                Command::new(objcopy_cmd)
-                    .args(args)
-                    .arg(path)
-                    .arg(&debug_temp_path)
-                    .status()
-                    .and_then(ensure_success)
-                    .map_err(|err| {
+            if cached {
+                log::debug!("reusing stripped {} from {}", path.display(), strip_cache_root.display());
+            } else {
+                log::debug!("stripping with {} from {} into {}", strip_cmd.display(), path.display(), stripped_temp_path.display());
+                strip.run(|cmd| {
+                    cmd.args(&config.strip_flags)
+                       .args(config.strip_keep_sections.iter().map(|s| format!("--keep-section={s}")))
+                       .arg("-o").arg(&stripped_temp_path)
+                       .arg(path);
+                   })
+                   .map_err(|err| {
                         if let Some(target) = target {
-                            CargoDebError::StripFailed(path.to_owned(), format!("{}: {}.\nhint: Target-specific strip commands are configured in [target.{}] objcopy = {{ path =\"{}\" }} in {}", objcopy_cmd.display(), err, target, objcopy_cmd.display(), conf_path.display()))
+                            CargoDebError::StripFailed(path.to_owned(), format!("{}: {}.\nhint: Target-specific strip commands are configured in [target.{}] strip = {{ path = \"{}\" }} in {}", strip_cmd.display(), err, target, strip_cmd.display(), conf_path.display()))
                         } else {
-                            CargoDebError::CommandFailed(err, "objcopy")
+                            CargoDebError::CommandFailed(err, "strip")
                         }
                     })?;
 
-                let relative_debug_temp_path = debug_temp_path.file_name().ok_or(CargoDebError::Str("bad path"))?;
-                log::debug!("linking debug info with {} from {} into {:?}", objcopy_cmd.display(), stripped_temp_path.display(), relative_debug_temp_path);
-                Command::new(objcopy_cmd)
-                    .current_dir(debug_temp_path.parent().ok_or(CargoDebError::Str("bad path"))?)
-                    .arg("--add-gnu-debuglink")
-                    // intentionally relative - the file name must match debug_target_path
-                    .arg(relative_debug_temp_path)
-                    .arg(&stripped_temp_path)
-                    .status()
-                    .and_then(ensure_success)
-                    .map_err(|err| CargoDebError::CommandFailed(err, "objcopy"))?;
-
-                Some(Asset::new(
-                    AssetSource::Path(debug_temp_path),
-                    debug_target_path,
-                    0o644,
-                    IsBuilt::No,
-                    false,
-                ).processed(if compress_debug_symbols { "compress"} else {"separate"}, path.to_path_buf()))
+                if !stripped_temp_path.exists() {
+                    return Err(CargoDebError::StripFailed(path.to_owned(), format!("{} command failed to create output '{}'", strip_cmd.display(), stripped_temp_path.display())));
+                }
+            }
+
+            let debug_file = if let (Some(debug_target_path), Some(debug_temp_path)) = (debug_target_path, debug_temp_path) {
+                if !cached {
+                    log::debug!("extracting debug info with {} from {}", objcopy_cmd.display(), path.display());
+
+                    let _ = fs::remove_file(&debug_temp_path);
+                    let mut args: &[_] = &["--only-keep-debug", "--compress-debug-sections=zstd"];
+                    // dwz can't read compressed sections, so they're compressed after it runs
+                    if !compress_debug_symbols || dwz {
+                        args = &args[..1];
+                    }
+                    objcopy.run(|cmd| {
+                            cmd.args(args)
+                                .arg(path)
+                                .arg(&debug_temp_path);
+                        })
+                        .map_err(|err| {
+                            if let Some(target) = target {
+                                CargoDebError::StripFailed(path.to_owned(), format!("{}: {}.\nhint: Target-specific strip commands are configured in [target.{}] objcopy = {{ path =\"{}\" }} in {}", objcopy_cmd.display(), err, target, objcopy_cmd.display(), conf_path.display()))
+                            } else {
+                                CargoDebError::CommandFailed(err, "objcopy")
+                            }
+                        })?;
+                }
+
+                Some(DebugFile {
+                    temp_path: debug_temp_path,
+                    target_path: debug_target_path,
+                    stripped_temp_path: stripped_temp_path.clone(),
+                    original_path: path.to_path_buf(),
+                    cache: if cached { None } else { cache },
+                    cached,
+                })
             } else {
+                if !cached {
+                    if let Some(cache) = cache {
+                        cache.store(&stripped_temp_path, None);
+                    }
+                }
                 None // no new asset
             };
             listener.info(format!("Stripped '{}'", path.display()));
 
-            (AssetSource::Path(stripped_temp_path), new_debug_asset)
+            (AssetSource::Path(stripped_temp_path), debug_file)
         } else {
             // This is unexpected - emit a warning if we come across it
             listener.warning(format!("Found built asset with non-path source '{asset:?}'"));
@@ -514,15 +856,359 @@ pub fn strip_binaries(config: &mut Config, package_deb: &mut PackageConfig, targ
             original_path: old_source.into_path(),
             action: "strip",
         });
-        Ok::<_, CargoDebError>(new_debug_asset)
+        Ok::<_, CargoDebError>(debug_file)
+    }).collect::<Result<Vec<_>, _>>()?;
+    let debug_files = debug_files.into_iter().flatten().collect::<Vec<_>>();
+
+    if dwz && !debug_files.is_empty() {
+        let common_debug_asset = run_dwz(&debug_files, &stripped_binaries_output_dir, package_deb, target)?;
+        package_deb.assets.resolved.extend(common_debug_asset);
+    }
+
+    let added_debug_assets = debug_files.into_par_iter().map(|debug_file| {
+        let DebugFile { temp_path, target_path, stripped_temp_path, original_path, cache, cached } = debug_file;
+        let asset = Asset::new(
+            AssetSource::Path(temp_path),
+            target_path,
+            0o644,
+            IsBuilt::No,
+            false,
+        ).processed(if compress_debug_symbols { "compress"} else {"separate"}, original_path);
+        // cached files have been linked already
+        if cached {
+            return Ok(asset);
+        }
+        let temp_path = asset.source.path().ok_or("bad path")?;
+
+        if compress_debug_symbols && dwz {
+            objcopy.run(|cmd| {
+                cmd.arg("--compress-debug-sections=zstd").arg(temp_path);
+            })
+            .map_err(|err| CargoDebError::CommandFailed(err, "objcopy"))?;
+        }
+
+        let relative_debug_temp_path = temp_path.file_name().ok_or(CargoDebError::Str("bad path"))?;
+        log::debug!("linking debug info with {} from {} into {:?}", objcopy_cmd.display(), stripped_temp_path.display(), relative_debug_temp_path);
+        let debug_temp_dir = temp_path.parent().ok_or(CargoDebError::Str("bad path"))?;
+        objcopy.run(|cmd| {
+            cmd.current_dir(debug_temp_dir)
+                .arg("--add-gnu-debuglink")
+                // intentionally relative - the file name must match debug_target_path
+                .arg(relative_debug_temp_path)
+                .arg(&stripped_temp_path);
+        })
+        .map_err(|err| CargoDebError::CommandFailed(err, "objcopy"))?;
+
+        if let Some(cache) = cache {
+            cache.store(&stripped_temp_path, Some(temp_path));
+        }
+        Ok::<_, CargoDebError>(asset)
     }).collect::<Result<Vec<_>, _>>()?;
 
     package_deb.assets.resolved
-        .extend(added_debug_assets.into_iter().flatten());
+        .extend(added_debug_assets);
+
+    // without the cache, nothing would be marked as used
+    if use_cache {
+        StripCache::remove_unused(&strip_cache_root);
+    }
+
+    Ok(())
+}
 
+/// Copies debug files and their stripped executables to `<dir>/<build-id>/debuginfo` and `<dir>/<build-id>/executable`,
+/// which is the layout `debuginfod` uses to serve them.
+fn write_debuginfod_dir(package_deb: &PackageConfig, dir: &Path, listener: &dyn Listener) -> CDResult<()> {
+    let mut copied = 0;
+    for debug_asset in &package_deb.assets.resolved {
+        let Some(build_id) = config::build_id_from_debug_path(&debug_asset.c.target_path) else { continue };
+        let Some(debug_path) = debug_asset.source.path() else { continue };
+        let id_dir = dir.join(&build_id);
+        fs::create_dir_all(&id_dir).map_err(|e| CargoDebError::IoFile("unable to create debuginfod dir", e, id_dir.clone()))?;
+        fs::copy(debug_path, id_dir.join("debuginfo")).map_err(|e| CargoDebError::IoFile("unable to copy debug info", e, debug_path.to_owned()))?;
+
+        // the stripped executable was processed from the same file as its debug info
+        let original_path = debug_asset.processed_from.as_ref().and_then(|p| p.original_path.as_deref());
+        let executable = package_deb.assets.resolved.iter()
+            .filter(|a| a.processed_from.as_ref().is_some_and(|p| p.action == "strip" && p.original_path.as_deref() == original_path))
+            .find_map(|a| a.source.path());
+        if let Some(executable) = executable {
+            fs::copy(executable, id_dir.join("executable")).map_err(|e| CargoDebError::IoFile("unable to copy executable", e, executable.to_owned()))?;
+        }
+        copied += 1;
+    }
+    if copied == 0 {
+        listener.warning("no debug symbols with a build-id were found for --debuginfod-out (use --separate-debug-symbols)".into());
+    } else {
+        listener.info(format!("copied {copied} debug files to {}", dir.display()));
+    }
     Ok(())
 }
 
+/// `strip` or `objcopy` from binutils, with a fallback to LLVM's tools
+struct BinTool<'a> {
+    cmd: &'a Path,
+    /// LLVM's tools are used only when binutils' default tool fails
+    can_fall_back: bool,
+    llvm_names: &'static [&'static str],
+    llvm_cmd: OnceLock<Option<PathBuf>>,
+    listener: &'a dyn Listener,
+}
+
+impl<'a> BinTool<'a> {
+    fn new(cmd: &'a Path, can_fall_back: bool, llvm_names: &'static [&'static str], listener: &'a dyn Listener) -> Self {
+        Self { cmd, can_fall_back, llvm_names, llvm_cmd: OnceLock::new(), listener }
+    }
+
+    /// Binutils are usually built only for the host architecture,
+    /// but the LLVM tools (e.g. from rustup's `llvm-tools` component) support all targets.
+    fn run(&self, args: impl Fn(&mut Command)) -> io::Result<()> {
+        let mut cmd = Command::new(self.cmd);
+        args(&mut cmd);
+        let err = match cmd.status().and_then(ensure_success) {
+            Ok(()) => return Ok(()),
+            Err(err) if self.can_fall_back => err,
+            Err(err) => return Err(err),
+        };
+        let llvm_cmd = self.llvm_cmd.get_or_init(|| {
+            let found = find_llvm_tool(self.llvm_names);
+            if let Some(found) = &found {
+                self.listener.warning(format!("{} failed ({err}), trying {} instead", self.cmd.display(), found.display()));
+            }
+            found
+        });
+        let Some(llvm_cmd) = llvm_cmd else { return Err(err) };
+        let mut cmd = Command::new(llvm_cmd);
+        args(&mut cmd);
+        cmd.status().and_then(ensure_success)
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn bin_tool_fallback() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let llvm_strip = dir.path().join("llvm-strip");
+    fs::write(&llvm_strip, "#!/bin/sh\n[ \"$1\" = --version ] && exit 0\necho \"$@\" > \"$(dirname \"$0\")/ran\"\n").unwrap();
+    fs::set_permissions(&llvm_strip, fs::Permissions::from_mode(0o755)).unwrap();
+    // `llvm_names` are static in the real code
+    let llvm_name: &'static str = Box::leak(llvm_strip.display().to_string().into_boxed_str());
+    let llvm_names: &'static [&'static str] = Box::leak(Box::new([llvm_name]));
+    let missing = dir.path().join("strip");
+    let args = |cmd: &mut Command| { cmd.arg("--strip-debug").arg("bin"); };
+
+    let mut mock_listener = listener::MockListener::new();
+    mock_listener.expect_warning().withf(|w| w.contains("strip failed") && w.ends_with("llvm-strip instead")).times(1).return_const(());
+    let strip = BinTool::new(&missing, true, llvm_names, &mock_listener);
+    strip.run(args).unwrap();
+    assert_eq!(fs::read_to_string(dir.path().join("ran")).unwrap(), "--strip-debug bin\n");
+    // the LLVM tool is found once
+    strip.run(args).unwrap();
+
+    // configured tools don't fall back
+    let mock_listener = listener::MockListener::new();
+    assert!(BinTool::new(&missing, false, llvm_names, &mock_listener).run(args).is_err());
+    assert!(BinTool::new(&missing, true, &["/nonexistent/llvm-strip"], &mock_listener).run(args).is_err());
+}
+
+/// Looks in `rustc`'s sysroot (where `rustup component add llvm-tools` installs them), and then in `PATH`
+fn find_llvm_tool(names: &[&str]) -> Option<PathBuf> {
+    let sysroot = Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+        .args(["--print", "sysroot"])
+        .output().ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok());
+    let mut candidates = Vec::new();
+    if let Some(pattern) = sysroot.map(|s| Path::new(s.trim()).join("lib/rustlib/*/bin")) {
+        for bin_dir in glob::glob(pattern.to_str()?).ok()?.flatten() {
+            candidates.extend(names.iter().map(|name| bin_dir.join(format!("{name}{}", env::consts::EXE_SUFFIX))).filter(|p| p.exists()));
+        }
+    }
+    candidates.extend(names.iter().map(PathBuf::from));
+    // the tools may be incomplete or missing their libLLVM
+    candidates.into_iter().find(|cmd| {
+        Command::new(cmd).arg("--version").output().is_ok_and(|out| out.status.success())
+    })
+}
+
+/// Debug info extracted from a binary, not linked to the binary yet
+struct DebugFile {
+    temp_path: PathBuf,
+    target_path: PathBuf,
+    stripped_temp_path: PathBuf,
+    original_path: PathBuf,
+    /// Where to save the files once they're done
+    cache: Option<StripCache>,
+    /// Restored from the cache, so it's done already
+    cached: bool,
+}
+
+/// Stripped binaries and their debug info from previous runs, in `target/debian/strip-cache/<package>/<hash of input and settings>/`
+struct StripCache {
+    dir: PathBuf,
+}
+
+impl StripCache {
+    fn new(root: &Path, binary_path: &Path, settings: &str) -> io::Result<Self> {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(settings.as_bytes());
+        hasher.update([0]);
+        io::copy(&mut fs::File::open(binary_path)?, &mut hasher)?;
+        let hash = hasher.finalize().iter().take(16).map(|b| format!("{b:02x}")).collect::<String>();
+        Ok(Self { dir: root.join(hash) })
+    }
+
+    /// Returns `true` if the files have been copied from the cache
+    fn restore(&self, stripped_path: &Path, debug_path: Option<&Path>) -> bool {
+        let copy = |name: &str, path: &Path| fs::copy(self.dir.join(name), path);
+        let restored = copy("stripped", stripped_path).is_ok() && debug_path.map_or(true, |p| copy("debug", p).is_ok());
+        if restored {
+            // marks the entry as used
+            let _ = fs::write(self.dir.join(".used"), "");
+        }
+        restored
+    }
+
+    /// The cache is only an optimization, so errors are ignored
+    fn store(&self, stripped_path: &Path, debug_path: Option<&Path>) {
+        let _ = fs::remove_dir_all(&self.dir);
+        let stored = fs::create_dir_all(&self.dir).is_ok() && [("stripped", Some(stripped_path)), ("debug", debug_path)].into_iter().all(|(name, path)| {
+            path.map_or(true, |path| fs::copy(path, self.dir.join(name)).is_ok())
+        });
+        if stored {
+            let _ = fs::write(self.dir.join(".used"), "");
+        } else {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Deletes entries that haven't been used in this run, so that the cache doesn't grow forever
+    fn remove_unused(root: &Path) {
+        let Ok(entries) = fs::read_dir(root) else { return };
+        for entry in entries.flatten() {
+            let used = entry.path().join(".used");
+            if fs::remove_file(&used).is_err() {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+}
+
+#[test]
+fn strip_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("strip-cache");
+    let (binary, stripped, debug) = (dir.path().join("bin"), dir.path().join("bin-stripped"), dir.path().join("bin.debug"));
+    fs::write(&binary, "unstripped").unwrap();
+
+    let cache = StripCache::new(&root, &binary, "strip -g").unwrap();
+    assert!(!cache.restore(&stripped, Some(&debug)), "miss");
+    fs::write(&stripped, "stripped").unwrap();
+    fs::write(&debug, "debug").unwrap();
+    cache.store(&stripped, Some(&debug));
+    fs::remove_file(&stripped).unwrap();
+    fs::remove_file(&debug).unwrap();
+
+    let cache = StripCache::new(&root, &binary, "strip -g").unwrap();
+    assert!(cache.restore(&stripped, Some(&debug)), "hit");
+    assert_eq!(fs::read_to_string(&stripped).unwrap(), "stripped");
+    assert_eq!(fs::read_to_string(&debug).unwrap(), "debug");
+
+    assert!(!StripCache::new(&root, &binary, "strip").unwrap().restore(&stripped, None), "other settings");
+    fs::write(&binary, "rebuilt").unwrap();
+    let changed = StripCache::new(&root, &binary, "strip -g").unwrap();
+    assert!(!changed.restore(&stripped, Some(&debug)), "changed input");
+
+    // the old entry has been used in this run, and is kept once
+    StripCache::remove_unused(&root);
+    assert!(cache.dir.exists());
+    StripCache::remove_unused(&root);
+    assert!(!cache.dir.exists());
+}
+
+/// Deduplicates DWARF in the debug files with `dwz`, like `dh_dwz` does.
+///
+/// When there are multiple files, debug info common to all of them is moved to a file in `/usr/lib/debug/.dwz/`,
+/// which is returned as a new asset.
+fn run_dwz(debug_files: &[DebugFile], temp_dir: &Path, package_deb: &PackageConfig, target: Option<&str>) -> CDResult<Option<Asset>> {
+    let common_file = (debug_files.len() > 1).then(|| {
+        let common_temp_path = temp_dir.join(format!("{}.dwz.debug", package_deb.deb_name));
+        let _ = fs::remove_file(&common_temp_path);
+        let common_target_path = Path::new("usr/lib/debug/.dwz")
+            .join(debian_triple_from_rust_triple(target.unwrap_or(DEFAULT_TARGET)))
+            .join(format!("{}.debug", package_deb.deb_name));
+        (common_temp_path, common_target_path)
+    });
+    log::debug!("running dwz on {} files", debug_files.len());
+    let output = dwz_command(debug_files, common_file.as_ref())
+        .output()
+        .map_err(|err| CargoDebError::CommandFailed(err, "dwz"))?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("dwz failed", debug_files[0].original_path.display().to_string(), output.stderr));
+    }
+    Ok(dwz_common_asset(common_file, debug_files))
+}
+
+/// `common_file` is where `dwz` writes the common debug info, and where it will be installed
+fn dwz_command(debug_files: &[DebugFile], common_file: Option<&(PathBuf, PathBuf)>) -> Command {
+    let mut cmd = Command::new("dwz");
+    if let Some((common_temp_path, common_target_path)) = common_file {
+        cmd.arg("-m").arg(common_temp_path);
+        // the path that the debuggers will look for
+        cmd.arg("-M").arg(Path::new("/").join(common_target_path));
+    }
+    cmd.args(debug_files.iter().map(|f| &f.temp_path));
+    cmd
+}
+
+/// dwz doesn't create the common file if there was nothing in common
+fn dwz_common_asset(common_file: Option<(PathBuf, PathBuf)>, debug_files: &[DebugFile]) -> Option<Asset> {
+    common_file.filter(|(temp_path, _)| temp_path.exists()).map(|(temp_path, target_path)| {
+        Asset::new(
+            AssetSource::Path(temp_path),
+            target_path,
+            0o644,
+            IsBuilt::No,
+            false,
+        ).processed("dwz", debug_files[0].original_path.clone())
+    })
+}
+
+#[test]
+fn dwz_common_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let debug_file = |name: &str| DebugFile {
+        temp_path: dir.path().join(format!("{name}.debug")),
+        target_path: Path::new("usr/lib/debug").join(name),
+        stripped_temp_path: dir.path().join(name),
+        original_path: Path::new("target/release").join(name),
+        cache: None,
+        cached: false,
+    };
+    let debug_files = [debug_file("a"), debug_file("b")];
+    let common_file = (dir.path().join("pkg.dwz.debug"), PathBuf::from("usr/lib/debug/.dwz/x86_64-linux-gnu/pkg.debug"));
+
+    let cmd = dwz_command(&debug_files[..1], None);
+    assert_eq!(cmd.get_args().collect::<Vec<_>>(), [debug_files[0].temp_path.as_os_str()]);
+    let cmd = dwz_command(&debug_files, Some(&common_file));
+    assert_eq!(cmd.get_args().collect::<Vec<_>>(), [
+        "-m".as_ref(), common_file.0.as_os_str(),
+        "-M".as_ref(), "/usr/lib/debug/.dwz/x86_64-linux-gnu/pkg.debug".as_ref(),
+        debug_files[0].temp_path.as_os_str(), debug_files[1].temp_path.as_os_str(),
+    ]);
+
+    assert!(dwz_common_asset(None, &debug_files).is_none());
+    assert!(dwz_common_asset(Some(common_file.clone()), &debug_files).is_none(), "nothing in common");
+    fs::write(&common_file.0, "common").unwrap();
+    let asset = dwz_common_asset(Some(common_file.clone()), &debug_files).unwrap();
+    assert_eq!(asset.c.target_path, common_file.1);
+    assert_eq!(asset.source.path(), Some(common_file.0.as_path()));
+}
+
 fn get_target_debug_path(asset: &Asset, asset_path: &Path) -> Result<PathBuf, CargoDebError> {
     let target_debug_path = match elf_gnu_debug_id(asset_path) {
         Ok(Some(path)) => {
@@ -573,6 +1259,108 @@ fn elf_gnu_debug_id(elf_file_path: &Path) -> Result<Option<PathBuf>, elf::ParseE
     Ok(None)
 }
 
+/// Lintian reports RPATH and RUNPATH of binaries as `custom-library-search-path`, because packages should use system library paths.
+///
+/// Warns about them, or with `remove-rpath` writes a copy of the binary without them.
+fn check_rpaths(config: &Config, package_deb: &mut PackageConfig, listener: &dyn Listener) -> CDResult<()> {
+    let output_dir = config.default_deb_output_dir();
+    for (i, asset) in package_deb.built_binaries_mut().into_iter().enumerate() {
+        let Some(path) = asset.source.path().filter(|_| !asset.source.archive_as_symlink_only()) else { continue };
+        let mut data = fs::read(path).map_err(|e| CargoDebError::IoFile("unable to read binary", e, path.to_owned()))?;
+        let rpaths = match elf_rpaths(&data) {
+            Ok(rpaths) if !rpaths.is_empty() => rpaths,
+            Ok(_) => continue,
+            Err(e) => {
+                log::debug!("elf: {e} in {}", path.display());
+                continue;
+            },
+        };
+        if !config.remove_rpath {
+            listener.warning(format!("'{}' has RPATH/RUNPATH '{}', which Debian packages shouldn't use (custom-library-search-path)\nnote: set remove-rpath = true in [package.metadata.deb] to remove it", path.display(), rpaths.join(":")));
+            continue;
+        }
+        remove_elf_rpath(&mut data).map_err(|e| CargoDebError::RpathRemovalFailed(path.to_owned(), e.to_string()))?;
+        let file_name = path.file_name().ok_or(CargoDebError::Str("bad path"))?.to_string_lossy();
+        let new_path = output_dir.join(format!("{file_name}.tmp{i}-no-rpath"));
+        fs::write(&new_path, &data).map_err(|e| CargoDebError::IoFile("unable to write binary", e, new_path.clone()))?;
+        listener.info(format!("Removed RPATH/RUNPATH '{}' from '{}'", rpaths.join(":"), path.display()));
+
+        let old_source = std::mem::replace(&mut asset.source, AssetSource::Path(new_path));
+        // stripped binaries are already temporary copies, and keep their original path
+        if asset.processed_from.is_none() {
+            asset.processed_from = Some(ProcessedFrom {
+                original_path: old_source.into_path(),
+                action: "remove-rpath",
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "debug-id"))]
+fn elf_rpaths(_: &[u8]) -> io::Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(feature = "debug-id"))]
+fn remove_elf_rpath(_: &mut [u8]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "cargo-deb was built without ELF support (the debug-id feature)"))
+}
+
+/// Values of `DT_RPATH` and `DT_RUNPATH` entries
+#[cfg(feature = "debug-id")]
+fn elf_rpaths(data: &[u8]) -> Result<Vec<String>, elf::ParseError> {
+    use elf::abi::{DT_RPATH, DT_RUNPATH, SHT_DYNAMIC};
+    use elf::endian::AnyEndian;
+    use elf::ElfBytes;
+
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let Some(shdrs) = elf.section_headers() else { return Ok(Vec::new()) };
+    let Some(dynamic_shdr) = shdrs.iter().find(|s| s.sh_type == SHT_DYNAMIC) else { return Ok(Vec::new()) };
+    let strtab = elf.section_data_as_strtab(&shdrs.get(dynamic_shdr.sh_link as usize)?)?;
+    let Some(dynamic) = elf.dynamic()? else { return Ok(Vec::new()) };
+    dynamic.iter()
+        .filter(|d| d.d_tag == DT_RPATH || d.d_tag == DT_RUNPATH)
+        .map(|d| Ok(strtab.get(d.d_val() as usize)?.to_owned()))
+        .collect()
+}
+
+/// Removes `DT_RPATH` and `DT_RUNPATH` entries from the dynamic section, like `chrpath -d`,
+/// by moving the following entries up, and padding the end with `DT_NULL`.
+#[cfg(feature = "debug-id")]
+fn remove_elf_rpath(data: &mut [u8]) -> Result<(), elf::ParseError> {
+    use elf::abi::{DT_RPATH, DT_RUNPATH, SHT_DYNAMIC};
+    use elf::endian::{AnyEndian, EndianParse};
+    use elf::file::Class;
+    use elf::ElfBytes;
+
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    let Some(dynamic_shdr) = elf.section_headers().and_then(|shdrs| shdrs.iter().find(|s| s.sh_type == SHT_DYNAMIC)) else { return Ok(()) };
+    let is_64 = elf.ehdr.class == Class::ELF64;
+    let is_little = elf.ehdr.endianness.is_little();
+    let Some(dynamic) = elf.dynamic()? else { return Ok(()) };
+    let entries = dynamic.iter()
+        .filter(|d| d.d_tag != DT_RPATH && d.d_tag != DT_RUNPATH)
+        .map(|d| (d.d_tag, d.d_val()))
+        .collect::<Vec<_>>();
+
+    let start = dynamic_shdr.sh_offset as usize;
+    let section = data.get_mut(start..start + dynamic_shdr.sh_size as usize)
+        .ok_or(elf::ParseError::SliceReadError((start, start + dynamic_shdr.sh_size as usize)))?;
+    section.fill(0);
+    let entry_size = if is_64 { 16 } else { 8 };
+    for ((tag, val), out) in entries.into_iter().zip(section.chunks_exact_mut(entry_size)) {
+        let (tag_out, val_out) = out.split_at_mut(entry_size / 2);
+        match (is_64, is_little) {
+            (true, true) => { tag_out.copy_from_slice(&tag.to_le_bytes()); val_out.copy_from_slice(&val.to_le_bytes()); },
+            (true, false) => { tag_out.copy_from_slice(&tag.to_be_bytes()); val_out.copy_from_slice(&val.to_be_bytes()); },
+            (false, true) => { tag_out.copy_from_slice(&(tag as i32).to_le_bytes()); val_out.copy_from_slice(&(val as u32).to_le_bytes()); },
+            (false, false) => { tag_out.copy_from_slice(&(tag as i32).to_be_bytes()); val_out.copy_from_slice(&(val as u32).to_be_bytes()); },
+        }
+    }
+    Ok(())
+}
+
 #[cfg(target_os = "linux")]
 fn warn_if_not_linux() {
 }
@@ -0,0 +1,257 @@
+//! Packages of crate sources in the layout of Debian's Rust team, for building with dh-cargo.
+//!
+//! See [the Debian Rust packaging policy](https://wiki.debian.org/Teams/RustPackaging/Policy).
+use crate::deb::changes::to_hex;
+use crate::error::{CDResult, CargoDebError};
+use cargo_toml::{DepsSet, Manifest};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Sources of a crate made by `cargo package`
+pub(crate) struct CrateSources {
+    pub name: String,
+    pub version: String,
+    /// Paths are relative to the crate's root, with the executable bit
+    pub files: Vec<(PathBuf, Vec<u8>, bool)>,
+    /// Of the `.crate` file, for `.cargo-checksum.json`
+    pub checksum: String,
+    manifest: Manifest,
+}
+
+impl CrateSources {
+    pub(crate) fn from_crate_file(crate_path: &Path) -> CDResult<Self> {
+        let read_error = |e| CargoDebError::IoFile("unable to read .crate file", e, crate_path.to_owned());
+        let mut crate_data = Vec::new();
+        File::open(crate_path).and_then(|mut f| f.read_to_end(&mut crate_data)).map_err(read_error)?;
+        let checksum = to_hex(&Sha256::digest(&crate_data));
+
+        let mut files = Vec::new();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&crate_data[..]));
+        for entry in archive.entries().map_err(read_error)? {
+            let mut entry = entry.map_err(read_error)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            // the first component is name-version
+            let path = entry.path().map_err(read_error)?.components().skip(1).collect::<PathBuf>();
+            let executable = entry.header().mode().map_err(read_error)? & 0o111 != 0;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).map_err(read_error)?;
+            files.push((path, data, executable));
+        }
+        let manifest_data = files.iter().find(|(path, ..)| path == Path::new("Cargo.toml"))
+            .ok_or_else(|| CargoDebError::IoFile("the .crate file has no Cargo.toml", std::io::ErrorKind::NotFound.into(), crate_path.to_owned()))?;
+        let manifest = Manifest::from_slice(&manifest_data.1).map_err(|e| CargoDebError::TomlParsing(e, crate_path.to_owned()))?;
+        let package = manifest.package.as_ref().ok_or("bad package")?;
+        Ok(Self {
+            name: package.name.clone(),
+            version: package.version().to_owned(),
+            files,
+            checksum,
+            manifest,
+        })
+    }
+
+    /// `librust-<name>-dev`
+    pub(crate) fn deb_name(&self) -> String {
+        format!("librust-{}-dev", debian_crate_name(&self.name))
+    }
+
+    /// Where dh-cargo looks for crates
+    pub(crate) fn registry_dir(&self) -> PathBuf {
+        Path::new("usr/share/cargo/registry").join(format!("{}-{}", self.name, self.version))
+    }
+
+    /// Makes Cargo accept the directory as a vendored crate
+    pub(crate) fn cargo_checksum_json(&self) -> String {
+        format!("{{\"package\":\"{}\",\"files\":{{}}}}", self.checksum)
+    }
+
+    /// Returns `(Depends, Suggests, Provides)`.
+    ///
+    /// Features that need only non-optional dependencies are provided as `librust-<name>+<feature>-dev`.
+    /// Optional dependencies are suggested instead, because Debian's tools would make a separate package for each such feature.
+    pub(crate) fn relationships(&self, deb_version: &str) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let mut deps = DepsSet::new();
+        let m = &self.manifest;
+        for set in [&m.dependencies, &m.build_dependencies].into_iter().chain(m.target.values().flat_map(|t| [&t.dependencies, &t.build_dependencies])) {
+            deps.extend(set.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        let mut depends = BTreeSet::new();
+        let mut suggests = BTreeSet::new();
+        for (name, dep) in &deps {
+            let package_name = dep.package().unwrap_or(name);
+            let packages = dependency_packages(package_name, dep.req(), dep.detail().map_or(true, |d| d.default_features), dep.req_features());
+            if dep.optional() {
+                suggests.extend(packages);
+            } else {
+                depends.extend(packages);
+            }
+        }
+
+        let mut features = BTreeMap::new();
+        for name in m.features.keys() {
+            let mut visited = BTreeSet::new();
+            if let Some(dep_features) = self.feature_requirements(name, &deps, &mut visited) {
+                features.insert(name.as_str(), dep_features);
+            }
+        }
+        if !m.features.contains_key("default") {
+            features.insert("default", Vec::new());
+        }
+
+        let debian_name = debian_crate_name(&self.name);
+        let mut keys = vec![String::new()];
+        keys.extend(version_keys(&self.version).into_iter().map(|k| format!("-{k}")));
+        let mut provides = Vec::new();
+        for key in &keys {
+            if !key.is_empty() {
+                provides.push(format!("librust-{debian_name}{key}-dev (= {deb_version})"));
+            }
+            for feature in features.keys() {
+                provides.push(format!("librust-{debian_name}{key}+{}-dev (= {deb_version})", debian_crate_name(feature)));
+            }
+        }
+        for dep_features in features.into_values() {
+            depends.extend(dep_features);
+        }
+        (depends.into_iter().collect(), suggests.into_iter().collect(), provides)
+    }
+
+    /// Packages for features of dependencies that the feature enables, or `None` if it enables an optional dependency
+    fn feature_requirements<'a>(&'a self, feature: &'a str, deps: &DepsSet, visited: &mut BTreeSet<&'a str>) -> Option<Vec<String>> {
+        if !visited.insert(feature) {
+            return Some(Vec::new());
+        }
+        let Some(requirements) = self.manifest.features.get(feature) else {
+            // an implicit feature of an optional dependency
+            return if deps.get(feature).is_some_and(|d| d.optional()) { None } else { Some(Vec::new()) };
+        };
+        let mut packages = Vec::new();
+        for req in requirements {
+            if let Some(dep_name) = req.strip_prefix("dep:") {
+                if deps.get(dep_name).is_some_and(|d| d.optional()) {
+                    return None;
+                }
+            } else if let Some((dep_name, dep_feature)) = req.split_once('/') {
+                let weak = dep_name.ends_with('?');
+                let dep_name = dep_name.trim_end_matches('?');
+                let dep = deps.get(dep_name)?;
+                if dep.optional() {
+                    if weak {
+                        continue;
+                    }
+                    return None;
+                }
+                packages.extend(dependency_packages(dep.package().unwrap_or(dep_name), dep.req(), false, &[dep_feature.to_owned()]));
+            } else {
+                packages.extend(self.feature_requirements(req, deps, visited)?);
+            }
+        }
+        Some(packages)
+    }
+}
+
+/// Debian package names can't have underscores or uppercase letters
+pub(crate) fn debian_crate_name(name: &str) -> String {
+    name.replace('_', "-").to_lowercase()
+}
+
+/// `1.2.3` is provided as `1`, `1.2`, and `1.2.3`
+fn version_keys(version: &str) -> Vec<String> {
+    let base = version.split(['-', '+']).next().unwrap_or(version);
+    let parts = base.split('.').collect::<Vec<_>>();
+    (1..=parts.len().min(3)).map(|n| parts[..n].join(".")).collect()
+}
+
+/// Dependencies on the librust package of a crate, for the version requirement and features
+fn dependency_packages(crate_name: &str, req: &str, default_features: bool, features: &[String]) -> Vec<String> {
+    let mut key = None;
+    let mut constraints = Vec::new();
+    for comparator in req.split(',').map(str::trim).filter(|c| !c.is_empty() && *c != "*") {
+        let (op, version) = match comparator.find(|c: char| c.is_ascii_digit()) {
+            Some(pos) => (comparator[..pos].trim(), comparator[pos..].trim().trim_end_matches(".*")),
+            None => continue,
+        };
+        // like semver, prerelease versions sort before releases
+        let deb_version = version.replacen('-', "~", 1);
+        match op {
+            "" | "^" | "~" | "=" => {
+                let parts = version.split(['-', '+']).next().unwrap_or(version).split('.').collect::<Vec<_>>();
+                // the parts that must match for the requirement to be compatible
+                let key_len = match (op, parts.as_slice()) {
+                    ("=", _) => parts.len(),
+                    ("~", _) => parts.len().min(2),
+                    (_, ["0", "0", ..]) => parts.len(),
+                    (_, ["0", ..]) => parts.len().min(2),
+                    _ => 1,
+                };
+                key.get_or_insert_with(|| parts[..key_len].join("."));
+                constraints.push(format!("(>= {deb_version}-~~)"));
+            },
+            ">=" => constraints.push(format!("(>= {deb_version}-~~)")),
+            "<" => constraints.push(format!("(<< {deb_version}-~~)")),
+            ">" => constraints.push(format!("(>> {deb_version})")),
+            "<=" => constraints.push(format!("(<= {deb_version})")),
+            _ => {},
+        }
+    }
+
+    let base = match &key {
+        Some(key) => format!("librust-{}-{key}", debian_crate_name(crate_name)),
+        None => format!("librust-{}", debian_crate_name(crate_name)),
+    };
+    let mut names = vec![if default_features { format!("{base}+default-dev") } else { format!("{base}-dev") }];
+    names.extend(features.iter().map(|f| format!("{base}+{}-dev", debian_crate_name(f))));
+
+    let mut packages = Vec::new();
+    for name in names {
+        if constraints.is_empty() {
+            packages.push(name);
+        } else {
+            packages.extend(constraints.iter().map(|c| format!("{name} {c}")));
+        }
+    }
+    packages
+}
+
+#[test]
+fn dependency_package_names() {
+    assert_eq!(dependency_packages("serde_json", "1.0.100", true, &[]), ["librust-serde-json-1+default-dev (>= 1.0.100-~~)"]);
+    assert_eq!(dependency_packages("rand", "^0.8", false, &["small_rng".into()]), ["librust-rand-0.8-dev (>= 0.8-~~)", "librust-rand-0.8+small-rng-dev (>= 0.8-~~)"]);
+    assert_eq!(dependency_packages("x", "0.0.3", true, &[]), ["librust-x-0.0.3+default-dev (>= 0.0.3-~~)"]);
+    assert_eq!(dependency_packages("x", ">= 1.2, < 1.5", false, &[]), ["librust-x-dev (>= 1.2-~~)", "librust-x-dev (<< 1.5-~~)"]);
+    assert_eq!(dependency_packages("x", "~1.2.3", false, &[]), ["librust-x-1.2-dev (>= 1.2.3-~~)"]);
+    assert_eq!(dependency_packages("x", "1.0.0-beta.2", false, &[]), ["librust-x-1-dev (>= 1.0.0~beta.2-~~)"]);
+    assert_eq!(dependency_packages("x", "*", true, &[]), ["librust-x+default-dev"]);
+    assert_eq!(version_keys("0.3.1-rc.1"), ["0", "0.3", "0.3.1"]);
+}
+
+#[test]
+fn feature_provides() {
+    let manifest = Manifest::from_str(r#"
+        [package]
+        name = "my_crate"
+        version = "0.3.1"
+        [dependencies]
+        serde = { version = "1.0", default-features = false }
+        log = { version = "0.4", optional = true }
+        [features]
+        default = ["std"]
+        std = ["serde/std"]
+        logging = ["dep:log"]
+    "#).unwrap();
+    let sources = CrateSources { name: "my_crate".into(), version: "0.3.1".into(), files: Vec::new(), checksum: String::new(), manifest };
+    assert_eq!(sources.deb_name(), "librust-my-crate-dev");
+    let (depends, suggests, provides) = sources.relationships("0.3.1-1");
+    assert_eq!(depends, ["librust-serde-1+std-dev (>= 1.0-~~)", "librust-serde-1-dev (>= 1.0-~~)"]);
+    assert_eq!(suggests, ["librust-log-0.4+default-dev (>= 0.4-~~)"]);
+    assert!(provides.contains(&"librust-my-crate-0.3-dev (= 0.3.1-1)".to_owned()));
+    assert!(provides.contains(&"librust-my-crate+default-dev (= 0.3.1-1)".to_owned()));
+    assert!(provides.contains(&"librust-my-crate-0.3.1+std-dev (= 0.3.1-1)".to_owned()));
+    assert!(!provides.iter().any(|p| p.contains("+logging")), "{provides:?}");
+}
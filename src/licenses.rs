@@ -0,0 +1,406 @@
+//! Licenses of the crates that are statically linked into the package's binaries, for a
+//! [machine-readable copyright file](https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/).
+//!
+//! Their `license` expressions are from `cargo metadata`, and texts from the `LICENSE*` or `COPYING*` files of the crates' sources.
+//! Packages without a `license-file` get the texts of common permissive licenses from `license-texts/`.
+use crate::assets::{Asset, AssetSource, IsBuilt};
+use crate::dependencies::LockedPackage;
+use crate::parse::manifest::{CargoMetadataPackage, LicensePolicy};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Licenses that Debian has in `/usr/share/common-licenses/`, by their DEP-5 names
+const COMMON_LICENSES: [&str; 14] = ["Apache-2.0", "Artistic", "BSD", "CC0-1.0", "GFDL-1.2", "GFDL-1.3", "GPL-1", "GPL-2", "GPL-3", "LGPL-2", "LGPL-2.1", "LGPL-3", "MPL-1.1", "MPL-2.0"];
+
+/// Texts of licenses that aren't in `/usr/share/common-licenses/`, by their SPDX identifiers
+const EMBEDDED_TEXTS: [(&str, &str); 9] = [
+    ("0BSD", include_str!("../license-texts/0BSD")),
+    ("BSD-2-Clause", include_str!("../license-texts/BSD-2-Clause")),
+    ("BSD-3-Clause", include_str!("../license-texts/BSD-3-Clause")),
+    ("BSL-1.0", include_str!("../license-texts/BSL-1.0")),
+    ("ISC", include_str!("../license-texts/ISC")),
+    ("MIT", include_str!("../license-texts/MIT")),
+    ("MIT-0", include_str!("../license-texts/MIT-0")),
+    ("Unlicense", include_str!("../license-texts/Unlicense")),
+    ("Zlib", include_str!("../license-texts/Zlib")),
+];
+
+/// A crate in the package's dependencies
+#[derive(Debug, Clone)]
+pub(crate) struct CrateLicense {
+    pub name: String,
+    pub version: String,
+    pub authors: Vec<String>,
+    /// SPDX expression, like `MIT OR Apache-2.0`
+    pub license: Option<String>,
+    /// Texts of the licenses in the expression, by their SPDX identifiers
+    pub texts: BTreeMap<String, PathBuf>,
+    /// All `LICENSE*` and `COPYING*` files of the crate
+    pub files: Vec<PathBuf>,
+}
+
+/// Crates from `cargo metadata` that are `linked` dependencies
+pub(crate) fn dependency_licenses(packages: &[CargoMetadataPackage], linked: &[LockedPackage]) -> Vec<CrateLicense> {
+    let mut crates = packages.iter()
+        .filter(|p| linked.iter().any(|l| l.name == p.name && l.version == p.version))
+        .map(|p| {
+            let dir = p.manifest_path.parent().unwrap_or(Path::new(""));
+            let license = p.license.clone();
+            let files = license_files(dir, p.license_file.as_deref().map(|f| dir.join(f)));
+            let texts = license_texts(license.as_deref(), &files);
+            CrateLicense { name: p.name.clone(), version: p.version.clone(), authors: p.authors.clone(), license, texts, files }
+        })
+        .collect::<Vec<_>>();
+    crates.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    crates
+}
+
+/// SPDX identifiers of the licenses in the expression, without the exceptions
+pub(crate) fn license_ids(expression: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut after_with = false;
+    for token in expression.split(|c: char| c.is_ascii_whitespace() || matches!(c, '(' | ')' | '/')).filter(|t| !t.is_empty()) {
+        match token {
+            "OR" | "AND" | "or" | "and" => {},
+            "WITH" | "with" => after_with = true,
+            _ if after_with => after_with = false,
+            id => if !ids.contains(&id) { ids.push(id) },
+        }
+    }
+    ids
+}
+
+/// Debian's names for licenses, like `GPL-2+` for `GPL-2.0-or-later`
+pub(crate) fn dep5_license_name(id: &str) -> String {
+    let (id, or_later) = match id.strip_suffix("-or-later").or_else(|| id.strip_suffix('+')) {
+        Some(id) => (id, "+"),
+        None => (id.strip_suffix("-only").unwrap_or(id), ""),
+    };
+    let id = if id.starts_with("GPL-") || id.starts_with("LGPL-") || id.starts_with("AGPL-") || id.starts_with("GFDL-") {
+        id.strip_suffix(".0").unwrap_or(id)
+    } else {
+        id
+    };
+    format!("{id}{or_later}")
+}
+
+/// The SPDX expression in the syntax of the `License` field, like `MIT or Apache-2.0`
+pub(crate) fn dep5_license_expression(expression: &str) -> String {
+    // the old syntax of Cargo was `MIT/Apache-2.0`
+    let expression = expression.replace('/', " OR ");
+    let mut out = Vec::new();
+    let mut tokens = expression.split_ascii_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "OR" | "or" => out.push("or".to_owned()),
+            "AND" | "and" => out.push("and".to_owned()),
+            "WITH" | "with" => {
+                let exception = tokens.next().unwrap_or_default();
+                out.push(format!("with {exception} exception"));
+            },
+            id => {
+                let (open, rest) = id.split_at(id.len() - id.trim_start_matches('(').len());
+                let close_len = rest.len() - rest.trim_end_matches(')').len();
+                let (name, close) = rest.split_at(rest.len() - close_len);
+                out.push(format!("{open}{}{close}", dep5_license_name(name)));
+            },
+        }
+    }
+    out.join(" ")
+}
+
+/// `LICENSE-MIT`, `LICENSE-APACHE`, or `COPYING` files in the crate's dir, and its `license-file`
+fn license_files(dir: &Path, license_file: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(dir).into_iter().flatten().flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.file_name().and_then(|f| f.to_str()).is_some_and(|f| {
+            let f = f.to_ascii_uppercase();
+            f.starts_with("LICENSE") || f.starts_with("LICENCE") || f.starts_with("COPYING") || f.starts_with("UNLICENSE")
+        }))
+        .collect::<Vec<_>>();
+    files.sort();
+    if let Some(license_file) = license_file.filter(|f| !files.contains(f)) {
+        files.push(license_file);
+    }
+    files
+}
+
+/// The license files matched to the identifiers in the expression
+fn license_texts(expression: Option<&str>, files: &[PathBuf]) -> BTreeMap<String, PathBuf> {
+    let ids = expression.map(license_ids).unwrap_or_default();
+    let mut texts = BTreeMap::new();
+    if let ([id], [file]) = (ids.as_slice(), files) {
+        texts.insert((*id).to_owned(), file.clone());
+        return texts;
+    }
+    for id in ids {
+        // like "apache" for Apache-2.0, and "bsd" for BSD-3-Clause
+        let family = id.split(['-', '.']).next().unwrap_or(id).to_ascii_lowercase();
+        // BSL-1.0 is the Boost license
+        let family = if family == "bsl" { "boost".into() } else { family };
+        let found = files.iter().find(|f| {
+            f.file_name().and_then(|f| f.to_str()).is_some_and(|f| f.to_ascii_lowercase().contains(&family))
+        });
+        if let Some(file) = found {
+            texts.insert(id.to_owned(), file.clone());
+        }
+    }
+    texts
+}
+
+/// Whether the license's text doesn't need to be in the copyright file
+pub(crate) fn is_common_license(dep5_name: &str) -> bool {
+    COMMON_LICENSES.contains(&dep5_name.trim_end_matches('+'))
+}
+
+/// The SPDX template of the license, without the copyright line
+pub(crate) fn embedded_text(id: &str) -> Option<&'static str> {
+    EMBEDDED_TEXTS.iter().find(|(i, _)| *i == id).map(|(_, text)| *text)
+}
+
+fn common_license_reference(dep5_name: &str) -> String {
+    let file = dep5_name.trim_end_matches('+');
+    format!("On Debian systems, the complete text of the {file} license can be found in `/usr/share/common-licenses/{file}'.\n")
+}
+
+/// Identifiers of licenses that have neither a text nor a copy in `/usr/share/common-licenses/`, with the crates that use them
+pub(crate) fn missing_texts(crates: &[CrateLicense]) -> BTreeMap<&str, Vec<&str>> {
+    let mut missing = BTreeMap::<&str, Vec<&str>>::new();
+    for krate in crates {
+        for id in krate.license.as_deref().map(license_ids).unwrap_or_default() {
+            if !is_common_license(&dep5_license_name(id)) && !crates.iter().any(|c| c.texts.contains_key(id)) {
+                missing.entry(id).or_default().push(&krate.name);
+            }
+        }
+    }
+    missing
+}
+
+/// Appends `Files` paragraphs of the crates, and stand-alone `License` paragraphs with the texts of their licenses.
+/// `own_license` is the expression of the package that has no license file, which gets the embedded texts.
+pub(crate) fn append_dep5_paragraphs(copyright: &mut Vec<u8>, own_license: Option<&str>, crates: &[CrateLicense]) -> io::Result<()> {
+    if !copyright.is_empty() && !copyright.ends_with(b"\n") {
+        copyright.push(b'\n');
+    }
+    for krate in crates {
+        writeln!(copyright, "\nFiles: vendor/{}-{}/*", krate.name, krate.version)?;
+        if krate.authors.is_empty() {
+            writeln!(copyright, "Copyright: The {} developers", krate.name)?;
+        } else {
+            writeln!(copyright, "Copyright: {}", krate.authors.join("\n           "))?;
+        }
+        writeln!(copyright, "License: {}", krate.license.as_deref().map_or_else(|| "UNKNOWN".into(), dep5_license_expression))?;
+        writeln!(copyright, "Comment: The {} {} crate, statically linked", krate.name, krate.version)?;
+    }
+
+    let mut paragraphs = BTreeMap::new();
+    for id in own_license.map(license_ids).unwrap_or_default() {
+        let name = dep5_license_name(id);
+        let text = if is_common_license(&name) { Some(common_license_reference(&name)) } else { embedded_text(id).map(String::from) };
+        if let Some(text) = text {
+            paragraphs.insert(name, text);
+        }
+    }
+    for krate in crates {
+        for id in krate.license.as_deref().map(license_ids).unwrap_or_default() {
+            let name = dep5_license_name(id);
+            if paragraphs.contains_key(&name) {
+                continue;
+            }
+            let text = if is_common_license(&name) {
+                Some(common_license_reference(&name))
+            } else {
+                crates.iter().find_map(|c| c.texts.get(id)).and_then(|path| fs::read_to_string(path).ok())
+            };
+            if let Some(text) = text {
+                paragraphs.insert(name, text);
+            }
+        }
+    }
+    for (name, text) in paragraphs {
+        writeln!(copyright, "\nLicense: {name}")?;
+        for line in text.trim_end().lines() {
+            if line.trim().is_empty() {
+                copyright.write_all(b" .\n")?;
+            } else {
+                writeln!(copyright, " {}", line.trim_end())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the expression has licenses that the policy allows, like `MIT` of `MIT OR GPL-3.0-only` if GPL is denied.
+/// `AND` needs all of its licenses to be allowed, and malformed expressions are never allowed.
+pub(crate) fn is_allowed(expression: &str, policy: &LicensePolicy) -> bool {
+    let expression = expression.replace('/', " OR ").replace('(', " ( ").replace(')', " ) ");
+    let mut tokens = expression.split_ascii_whitespace().peekable();
+    let allowed = allowed_or(&mut tokens, policy);
+    allowed.is_some_and(|allowed| allowed && tokens.next().is_none())
+}
+
+type Tokens<'a> = std::iter::Peekable<std::str::SplitAsciiWhitespace<'a>>;
+
+fn allowed_or(tokens: &mut Tokens<'_>, policy: &LicensePolicy) -> Option<bool> {
+    let mut allowed = allowed_and(tokens, policy)?;
+    while tokens.next_if(|&t| t == "OR" || t == "or").is_some() {
+        allowed |= allowed_and(tokens, policy)?;
+    }
+    Some(allowed)
+}
+
+fn allowed_and(tokens: &mut Tokens<'_>, policy: &LicensePolicy) -> Option<bool> {
+    let mut allowed = allowed_license(tokens, policy)?;
+    while tokens.next_if(|&t| t == "AND" || t == "and").is_some() {
+        allowed &= allowed_license(tokens, policy)?;
+    }
+    Some(allowed)
+}
+
+fn allowed_license(tokens: &mut Tokens<'_>, policy: &LicensePolicy) -> Option<bool> {
+    let token = tokens.next()?;
+    if token == "(" {
+        let allowed = allowed_or(tokens, policy)?;
+        return (tokens.next()? == ")").then_some(allowed);
+    }
+    if matches!(token, ")" | "OR" | "or" | "AND" | "and" | "WITH" | "with") {
+        return None;
+    }
+    if tokens.next_if(|&t| t == "WITH" || t == "with").is_some() {
+        tokens.next()?;
+    }
+    let matches = |pattern: &String| match pattern.strip_suffix('*') {
+        Some(prefix) => token.starts_with(prefix),
+        None => same_license(pattern, token),
+    };
+    Some((policy.allow.is_empty() || policy.allow.iter().any(matches)) && !policy.deny.iter().any(matches))
+}
+
+/// Deprecated identifiers are the same as the new ones, like `GPL-2.0` and `GPL-2.0-only`
+fn same_license(a: &str, b: &str) -> bool {
+    let normalized = |id: &str| id.strip_suffix("-only").map(str::to_owned)
+        .or_else(|| id.strip_suffix('+').map(|id| format!("{id}-or-later")))
+        .unwrap_or_else(|| id.to_owned());
+    normalized(a).eq_ignore_ascii_case(&normalized(b))
+}
+
+/// Crates with licenses that the policy doesn't allow, with their versions and licenses
+pub(crate) fn policy_violations<'a>(crates: &'a [CrateLicense], policy: &LicensePolicy) -> Vec<(&'a str, &'a str, &'a str)> {
+    crates.iter()
+        .map(|c| (c.name.as_str(), c.version.as_str(), c.license.as_deref().unwrap_or("")))
+        .filter(|&(.., license)| if license.is_empty() { !policy.allow.is_empty() } else { !is_allowed(license, policy) })
+        .collect()
+}
+
+/// Assets of the license files of the crates, in a dir for each crate, with links to the first copy of identical files
+pub(crate) fn license_file_assets(crates: &[CrateLicense], licenses_dir: &Path) -> io::Result<Vec<Asset>> {
+    let mut assets = Vec::new();
+    let mut installed = BTreeMap::<Vec<u8>, PathBuf>::new();
+    for krate in crates {
+        let crate_dir = format!("{}-{}", krate.name, krate.version);
+        for path in &krate.files {
+            let Some(file_name) = path.file_name() else { continue };
+            let target_path = licenses_dir.join(&crate_dir).join(file_name);
+            let content = fs::read(path)?;
+            if let Some(first) = installed.get(&content) {
+                let link_to = Path::new("..").join(first.strip_prefix(licenses_dir).unwrap_or(first));
+                assets.push(Asset::new(AssetSource::SymlinkTo(link_to), target_path, 0o777, IsBuilt::No, false));
+            } else {
+                installed.insert(content, target_path.clone());
+                assets.push(Asset::new(AssetSource::Path(path.clone()), target_path, 0o644, IsBuilt::No, false));
+            }
+        }
+    }
+    Ok(assets)
+}
+
+#[test]
+fn spdx_to_dep5() {
+    assert_eq!(license_ids("(MIT OR Apache-2.0) AND Unicode-3.0"), ["MIT", "Apache-2.0", "Unicode-3.0"]);
+    assert_eq!(license_ids("Apache-2.0 WITH LLVM-exception OR MIT/Apache-2.0"), ["Apache-2.0", "MIT"]);
+    assert_eq!(dep5_license_expression("MIT OR Apache-2.0"), "MIT or Apache-2.0");
+    assert_eq!(dep5_license_expression("MIT/Apache-2.0"), "MIT or Apache-2.0");
+    assert_eq!(dep5_license_expression("(GPL-2.0-or-later AND BSD-3-Clause)"), "(GPL-2+ and BSD-3-Clause)");
+    assert_eq!(dep5_license_expression("Apache-2.0 WITH LLVM-exception"), "Apache-2.0 with LLVM-exception exception");
+    assert_eq!(dep5_license_name("LGPL-2.1-only"), "LGPL-2.1");
+    assert_eq!(dep5_license_name("GPL-3.0"), "GPL-3");
+    assert!(is_common_license("GPL-2+"));
+    assert!(!is_common_license("MIT"));
+}
+
+#[test]
+fn dep5_paragraphs() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("LICENSE-MIT"), "Permission is hereby granted\n\nTHE SOFTWARE IS PROVIDED \"AS IS\"\n").unwrap();
+    fs::write(dir.path().join("LICENSE-APACHE"), "Apache License").unwrap();
+    fs::write(dir.path().join("README.md"), "").unwrap();
+    let files = license_files(dir.path(), Some(dir.path().join("LICENSE-MIT")));
+    assert_eq!(files, [dir.path().join("LICENSE-APACHE"), dir.path().join("LICENSE-MIT")]);
+    let texts = license_texts(Some("MIT OR Apache-2.0"), &files);
+    assert_eq!(texts.keys().collect::<Vec<_>>(), ["Apache-2.0", "MIT"]);
+    let crates = [
+        CrateLicense { name: "serde".into(), version: "1.0.0".into(), authors: vec!["A <a@example.com>".into(), "B".into()], license: Some("MIT OR Apache-2.0".into()), texts, files },
+        CrateLicense { name: "odd".into(), version: "0.1.0".into(), authors: vec![], license: Some("Zlib".into()), texts: BTreeMap::new(), files: Vec::new() },
+    ];
+    assert_eq!(missing_texts(&crates).into_iter().collect::<Vec<_>>(), [("Zlib", vec!["odd"])]);
+    let mut copyright = b"Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/".to_vec();
+    append_dep5_paragraphs(&mut copyright, None, &crates).unwrap();
+    let copyright = String::from_utf8(copyright).unwrap();
+    assert!(copyright.contains("/1.0/\n\nFiles: vendor/serde-1.0.0/*\nCopyright: A <a@example.com>\n           B\nLicense: MIT or Apache-2.0\n"), "{copyright}");
+    assert!(copyright.contains("\nFiles: vendor/odd-0.1.0/*\nCopyright: The odd developers\nLicense: Zlib\n"), "{copyright}");
+    assert!(copyright.contains("\nLicense: Apache-2.0\n On Debian systems"), "{copyright}");
+    assert!(copyright.ends_with("\n\nLicense: MIT\n Permission is hereby granted\n .\n THE SOFTWARE IS PROVIDED \"AS IS\"\n"), "{copyright}");
+}
+
+#[test]
+fn own_license_paragraphs() {
+    let mut copyright = b"License: MIT or Apache-2.0 or LicenseRef-Proprietary\n".to_vec();
+    append_dep5_paragraphs(&mut copyright, Some("MIT OR Apache-2.0 OR LicenseRef-Proprietary"), &[]).unwrap();
+    let copyright = String::from_utf8(copyright).unwrap();
+    assert!(copyright.contains("\n\nLicense: Apache-2.0\n On Debian systems, the complete text of the Apache-2.0 license can be found in `/usr/share/common-licenses/Apache-2.0'.\n"), "{copyright}");
+    assert!(copyright.contains("\n\nLicense: MIT\n Permission is hereby granted, free of charge,"), "{copyright}");
+    assert!(copyright.contains("\n .\n THE SOFTWARE IS PROVIDED"), "{copyright}");
+    assert!(!copyright.contains("License: LicenseRef"), "{copyright}");
+    assert!(EMBEDDED_TEXTS.iter().all(|(id, text)| !is_common_license(id) && !text.contains("Copyright (")));
+}
+
+#[test]
+fn bundles_unique_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("LICENSE-MIT"), dir.path().join("COPYING"));
+    fs::write(&a, "MIT").unwrap();
+    fs::write(&b, "MIT").unwrap();
+    let crate_license = |name: &str, files: Vec<PathBuf>| CrateLicense { name: name.into(), version: "1.0.0".into(), authors: vec![], license: None, texts: BTreeMap::new(), files };
+    let crates = [crate_license("a", vec![a.clone()]), crate_license("b", vec![b])];
+    let assets = license_file_assets(&crates, Path::new("usr/share/doc/pkg/licenses")).unwrap();
+    assert_eq!(assets.len(), 2);
+    assert!(matches!(&assets[0].source, AssetSource::Path(p) if *p == a));
+    assert_eq!(assets[0].c.target_path, Path::new("usr/share/doc/pkg/licenses/a-1.0.0/LICENSE-MIT"));
+    assert!(matches!(&assets[1].source, AssetSource::SymlinkTo(p) if p == Path::new("../a-1.0.0/LICENSE-MIT")));
+    assert_eq!(assets[1].c.target_path, Path::new("usr/share/doc/pkg/licenses/b-1.0.0/COPYING"));
+}
+
+#[test]
+fn license_policy() {
+    let policy = LicensePolicy { allow: vec![], deny: vec!["GPL-3.0-only".into(), "AGPL-*".into()] };
+    assert!(is_allowed("MIT OR Apache-2.0", &policy));
+    assert!(is_allowed("MIT OR GPL-3.0", &policy));
+    assert!(!is_allowed("GPL-3.0", &policy));
+    assert!(!is_allowed("MIT AND GPL-3.0-only", &policy));
+    assert!(is_allowed("(MIT AND GPL-3.0-only) OR BSD-3-Clause", &policy));
+    assert!(!is_allowed("AGPL-3.0-or-later", &policy));
+    assert!(!is_allowed("MIT OR", &policy));
+    assert!(!is_allowed("(MIT", &policy));
+    let policy = LicensePolicy { allow: vec!["MIT".into(), "Apache-2.0".into()], deny: vec![] };
+    assert!(is_allowed("MIT/Apache-2.0", &policy));
+    assert!(is_allowed("Apache-2.0 WITH LLVM-exception", &policy));
+    assert!(!is_allowed("Unicode-3.0 AND (MIT OR Apache-2.0)", &policy));
+    let crates = [
+        CrateLicense { name: "a".into(), version: "1.0.0".into(), authors: vec![], license: Some("MIT".into()), texts: BTreeMap::new(), files: vec![] },
+        CrateLicense { name: "b".into(), version: "2.0.0".into(), authors: vec![], license: Some("MPL-2.0".into()), texts: BTreeMap::new(), files: vec![] },
+        CrateLicense { name: "c".into(), version: "3.0.0".into(), authors: vec![], license: None, texts: BTreeMap::new(), files: vec![] },
+    ];
+    assert_eq!(policy_violations(&crates, &policy), [("b", "2.0.0", "MPL-2.0"), ("c", "3.0.0", "")]);
+}
@@ -1,3 +1,4 @@
+use crate::deb::checksums::ChecksumAlgorithm;
 use std::io::Write;
 use std::path::Path;
 
@@ -10,6 +11,9 @@ pub trait Listener: Send + Sync {
     fn generated_archive(&self, path: &Path) {
         println!("{}", path.display());
     }
+
+    /// Checksum of the .deb file requested with `--checksums`, notified before `generated_archive`
+    fn generated_checksum(&self, _path: &Path, _algorithm: ChecksumAlgorithm, _checksum: &str) {}
 }
 
 pub struct NoOpListener;
@@ -19,6 +23,36 @@ impl Listener for NoOpListener {
     fn generated_archive(&self, _: &Path) {}
 }
 
+/// Prints generated files as JSON lines on stdout, for `--message-format=json`.
+///
+/// Messages go to stderr, so that stdout has only JSON.
+pub struct JsonListener {
+    pub verbose: bool,
+    /// Don't print warnings
+    pub quiet: bool,
+}
+impl Listener for JsonListener {
+    fn warning(&self, s: String) {
+        if !self.quiet {
+            eprintln!("warning: {s}");
+        }
+    }
+
+    fn info(&self, s: String) {
+        if self.verbose {
+            eprintln!("info: {s}");
+        }
+    }
+
+    fn generated_archive(&self, path: &Path) {
+        println!("{}", serde_json::json!({ "reason": "generated-archive", "path": path }));
+    }
+
+    fn generated_checksum(&self, path: &Path, algorithm: ChecksumAlgorithm, checksum: &str) {
+        println!("{}", serde_json::json!({ "reason": "checksum", "path": path, "algorithm": algorithm.extension(), "checksum": checksum }));
+    }
+}
+
 pub struct StdErrListener {
     pub verbose: bool,
 }
@@ -1,6 +1,8 @@
-use cargo_deb::compress::Format;
+use cargo_deb::compress::{Format, Level};
+use cargo_deb::deb::sign::{CommandSigner, GpgSigner, SigningBackend};
 use cargo_deb::{listener, CargoDeb, CargoDebError, CargoDebOptions, CargoLockingFlags};
 use std::env;
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
@@ -14,14 +16,41 @@ fn main() -> ExitCode {
     cli_opts.optflag("", "no-separate-debug-symbols", "Do not strip debug symbols into a separate .debug file");
     cli_opts.optflag("", "separate-debug-symbols", "Strip debug symbols into a separate .debug file");
     cli_opts.optflag("", "compress-debug-symbols", "Apply objcopy --compress-debug-sections");
+    cli_opts.optflag("", "dbgsym", "Move separated debug symbols to a -dbgsym package");
+    cli_opts.optflag("", "ddeb", "Make the -dbgsym package a .ddeb, as Ubuntu expects");
+    cli_opts.optflag("", "dwz", "Deduplicate separated debug symbols with dwz");
+    cli_opts.optopt("", "debuginfod-out", "Also copy separated debug symbols to a directory laid out by build-id for debuginfod", "DIR");
+    cli_opts.optflag("", "sign", "Sign the package with gpg, like debsigs");
+    cli_opts.optopt("", "sign-key", "Key ID to sign with, instead of gpg's default key. Implies --sign", "id");
+    cli_opts.optopt("", "gpg-homedir", "gpg home directory with the signing keyring", "dir");
+    cli_opts.optopt("", "signer", "Program to run instead of gpg, with the same arguments. Implies --sign", "path");
+    cli_opts.optopt("", "sign-command", "Shell command that makes a detached signature, instead of gpg. {in} and {out} are paths of the data and the signature, or stdin and stdout. Implies --sign", "cmd");
+    cli_opts.optopt("", "clearsign-command", "Shell command that makes an inline signature of the .changes file, like --sign-command", "cmd");
+    cli_opts.optflag("", "source", "Make a 3.0 (quilt) source package (.dsc) instead of the .deb");
+    cli_opts.optflag("", "init-debian-dir", "Write a debian/ directory for building the package with debhelper and dh-cargo");
+    cli_opts.optflag("", "librust-dev", "Package the crate's sources as librust-<name>-dev, like Debian's Rust team");
+    cli_opts.optflag("", "vendor", "With --source, include vendored dependencies in the source package");
+    cli_opts.optflag("", "changes", "Also write a .changes file for upload tools like dput");
+    cli_opts.optflag("", "buildinfo", "Also write a .buildinfo file with the build environment");
+    cli_opts.optflag("", "sbom", "Also write an SBOM of the statically linked crates next to the .deb");
+    cli_opts.optopt("", "sysroot", "Root of the target system with its dpkg database, for $auto dependencies when cross-compiling", "path");
+    cli_opts.optflagopt("", "checksums", "Also write <deb>.sha256 files, or the given checksums", "sha256,sha512");
+    cli_opts.optopt("", "message-format", "Print the generated files as plain paths, or JSON lines with their checksums", "human|json");
+    cli_opts.optflag("", "keyring", "Also build a package with the repository key and apt source from [package.metadata.deb.keyring]");
+    cli_opts.optmulti("", "upload", "Publish the packages to this target from [package.metadata.deb.upload] or --upload-config. Can be repeated", "name");
+    cli_opts.optopt("", "upload-config", "TOML file with more upload targets, instead of Cargo.toml", "file");
     cli_opts.optopt("o", "output", "Write .deb to this file or directory", "path");
+    cli_opts.optopt("", "output-template", "File name of the .deb, with {name}, {version}, {arch} and {distro} placeholders", "template");
     cli_opts.optopt("p", "package", "Select which Cargo workspace package to use", "name");
     cli_opts.optflag("", "install", "Immediately install the created deb package");
+    cli_opts.optflag("", "verify-reproducible", "Package twice, and check that both packages are identical");
     cli_opts.optflag("q", "quiet", "Don't print warnings");
     cli_opts.optflag("v", "verbose", "Print progress");
     cli_opts.optflag("", "version", "Show version of the cargo-deb tool");
     cli_opts.optopt("", "deb-version", "Override version string for the package", "version");
-    cli_opts.optopt("", "deb-revision", "Override revision suffix string for the package", "num");
+    cli_opts.optopt("", "deb-revision", "Override revision suffix string for the package, or `auto` to increment it", "num|auto");
+    cli_opts.optflag("", "strict", "Fail on invalid section and priority values instead of warning");
+    cli_opts.optflag("", "version-from-changelog", "Take the version from debian/changelog, and check that it matches Cargo.toml");
     cli_opts.optopt("", "maintainer", "Override Maintainer field", "name");
     cli_opts.optopt("", "manifest-path", "Cargo project file location", "./Cargo.toml");
     cli_opts.optflag("", "offline", "Passed to Cargo");
@@ -32,8 +61,12 @@ fn main() -> ExitCode {
     cli_opts.optopt("", "profile", "Select which Cargo build profile to use", "release|<custom>");
     cli_opts.optflag("", "no-build", "Assume the project is already built");
     cli_opts.optopt("", "cargo-build", "Override cargo build subcommand", "subcommand");
-    cli_opts.optflag("", "fast", "Use faster compression, which makes a larger deb file");
-    cli_opts.optopt("Z", "compress-type", "Compress with the given compression format", "gz|xz");
+    cli_opts.optflag("", "fast", "Use faster compression, which makes a larger deb file. Skips stripping unless --strip is set");
+    cli_opts.optopt("Z", "compress-type", "Compress with the given compression format", "gz|xz|zstd|none");
+    cli_opts.optopt("", "control-compress-type", "Compress control.tar with a different format than data.tar", "gz|xz|zstd");
+    cli_opts.optopt("", "compress-level", "Compression level, e.g. 0-9 for xz, or 9e for xz --extreme", "num");
+    cli_opts.optopt("", "control-compress-level", "Compression level for control.tar", "num");
+    cli_opts.optopt("", "compress-threads", "Number of threads for xz and zstd compression (default: all cores)", "num");
     cli_opts.optflag("", "compress-system", "Use the corresponding command-line tool for compression");
     cli_opts.optflag("", "system-xz", "Compress using command-line xz command instead of built-in. Deprecated, use --compress-system instead");
     cli_opts.optflag("", "rsyncable", "Use worse compression, but reduce differences between versions of packages");
@@ -85,11 +118,43 @@ fn main() -> ExitCode {
 
     let install = matches.opt_present("install");
 
-    let compress_type = match matches.opt_str("compress-type").as_deref() {
-        Some("gz" | "gzip") => Format::Gzip,
-        Some("xz") | None => Format::Xz,
-        _ => {
-            print_error(&CargoDebError::Str("unrecognized compression format. Supported: gzip, xz"));
+    let (compress_type, control_compress_type) = match ["compress-type", "control-compress-type"].map(|opt| matches.opt_str(opt).as_deref().map(str::parse::<Format>).transpose()) {
+        [Ok(data), Ok(control)] => (data, control),
+        [Err(err), _] | [_, Err(err)] => {
+            print_error(&err);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let (compress_level, control_compress_level) = match ["compress-level", "control-compress-level"].map(|opt| matches.opt_str(opt).as_deref().map(str::parse::<Level>).transpose()) {
+        [Ok(data), Ok(control)] => (data, control),
+        [Err(err), _] | [_, Err(err)] => {
+            print_error(&err);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let checksums = match matches.opt_default("checksums", "sha256").map(|list| list.split(',').map(str::parse).collect::<Result<Vec<_>, _>>()).transpose() {
+        Ok(checksums) => checksums.unwrap_or_default(),
+        Err(err) => {
+            print_error(&err);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let json = match matches.opt_str("message-format").as_deref() {
+        None | Some("human") => false,
+        Some("json") => true,
+        Some(_) => {
+            print_error(&CargoDebError::Str("--message-format must be human or json"));
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let compress_threads = match matches.opt_str("compress-threads").map(|t| t.parse::<u32>()).transpose() {
+        Ok(t) => t,
+        Err(err) => {
+            print_error(&CargoDebError::NumParse("--compress-threads must be a number", err));
             return ExitCode::FAILURE;
         },
     };
@@ -103,8 +168,11 @@ fn main() -> ExitCode {
     let verbose = matches.opt_present("verbose") || env::var_os("RUST_LOG").is_some_and(|v| v == "debug");
 
     // Listener conditionally prints warnings
-    let (listener_tmp1, listener_tmp2);
-    let listener: &dyn listener::Listener = if quiet {
+    let (listener_tmp1, listener_tmp2, listener_tmp3);
+    let listener: &dyn listener::Listener = if json {
+        listener_tmp3 = listener::JsonListener { verbose, quiet };
+        &listener_tmp3
+    } else if quiet {
         listener_tmp1 = listener::NoOpListener;
         &listener_tmp1
     } else {
@@ -119,13 +187,49 @@ fn main() -> ExitCode {
         listener.warning(format!("--deb-version takes precedence over --deb-revision. Revision '{}' will be ignored", deb_revision.as_deref().unwrap_or_default()));
     }
 
+    let version_from_changelog = matches.opt_present("version-from-changelog");
+    if version_from_changelog && (deb_version.is_some() || deb_revision.is_some()) {
+        listener.warning("--version-from-changelog takes precedence over --deb-version and --deb-revision".into());
+    }
+
+    if matches.opt_present("vendor") && !matches.opt_present("source") {
+        listener.warning("--vendor applies only to source packages made with --source".into());
+    }
+
     match CargoDeb::new(CargoDebOptions {
         no_build: matches.opt_present("no-build"),
-        strip_override: if matches.opt_present("strip") { Some(true) } else if matches.opt_present("no-strip") { Some(false) } else { None },
+        // --fast is for quick iteration, where stripping is a waste of time
+        strip_override: if matches.opt_present("strip") { Some(true) } else if matches.opt_present("no-strip") || matches.opt_present("fast") { Some(false) } else { None },
         separate_debug_symbols: if matches.opt_present("separate-debug-symbols") { Some(true) } else if matches.opt_present("no-separate-debug-symbols") { Some(false) } else { None },
         compress_debug_symbols: if matches.opt_present("compress-debug-symbols") { Some(true) } else { None },
+        generate_dbgsym_package: if matches.opt_present("dbgsym") { Some(true) } else { None },
+        ddeb: if matches.opt_present("ddeb") { Some(true) } else { None },
+        dwz: if matches.opt_present("dwz") { Some(true) } else { None },
+        debuginfod_out: matches.opt_str("debuginfod-out"),
+        changes: matches.opt_present("changes"),
+        source: matches.opt_present("source"),
+        vendor: matches.opt_present("vendor"),
+        init_debian_dir: matches.opt_present("init-debian-dir"),
+        librust_dev: matches.opt_present("librust-dev"),
+        buildinfo: matches.opt_present("buildinfo"),
+        checksums,
+        keyring: matches.opt_present("keyring"),
+        upload: matches.opt_strs("upload"),
+        upload_config: matches.opt_str("upload-config"),
+        sign: if let Some(detach_sign_command) = matches.opt_str("sign-command") {
+            Some(Box::new(CommandSigner { detach_sign_command, clearsign_command: matches.opt_str("clearsign-command") }) as Box<dyn SigningBackend>)
+        } else if matches.opt_present("sign") || matches.opt_present("sign-key") || matches.opt_present("signer") {
+            Some(Box::new(GpgSigner {
+                program: matches.opt_str("signer").map(PathBuf::from),
+                key_id: matches.opt_str("sign-key"),
+                homedir: matches.opt_str("gpg-homedir").map(PathBuf::from),
+            }))
+        } else {
+            None
+        },
         verbose,
         install,
+        verify_reproducible: matches.opt_present("verify-reproducible"),
         // when installing locally it won't be transferred anywhere, so allow faster compression
         fast: install || matches.opt_present("fast"),
         variant: matches.opt_str("variant"),
@@ -136,9 +240,18 @@ fn main() -> ExitCode {
         overrides: cargo_deb::config::DebConfigOverrides {
             deb_version,
             deb_revision,
+            version_from_changelog,
             maintainer: matches.opt_str("maintainer"),
+            strict: matches.opt_present("strict"),
+            output_template: matches.opt_str("output-template"),
+            sbom: matches.opt_present("sbom"),
+            sysroot: matches.opt_str("sysroot").map(PathBuf::from),
         },
         compress_type,
+        control_compress_type,
+        compress_level,
+        control_compress_level,
+        compress_threads,
         compress_system: matches.opt_present("compress-system"),
         system_xz: matches.opt_present("system-xz"),
         rsyncable: matches.opt_present("rsyncable"),
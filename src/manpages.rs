@@ -0,0 +1,162 @@
+//! Man pages for binaries of the package that don't have one, found in the output of build scripts, or made with `help2man`.
+//!
+//! `clap_mangen` in `build.rs` conventionally writes `<binary>.1` to `OUT_DIR`,
+//! which is in `target/<profile>/build/<crate>-<hash>/out/`.
+//!
+//! See [clap_mangen](https://docs.rs/clap_mangen) and [help2man(1)](https://manpages.debian.org/help2man.1).
+use crate::assets::{Asset, AssetSource, IsBuilt};
+use crate::config::{Config, PackageConfig};
+use crate::listener::Listener;
+use crate::CDResult;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+/// Sections of man pages for binaries in these directories
+const BINARY_DIRS: [(&str, &str); 2] = [("usr/bin", "1"), ("usr/sbin", "8")];
+
+/// How deep to look for man pages in `OUT_DIR`
+const MAX_DEPTH: usize = 3;
+
+/// Seconds for `help2man` to run the binary, which may wait for input instead of printing its help
+const HELP2MAN_TIMEOUT: &str = "10";
+
+/// Names of the man pages in the section, without the extension, like `foo` for `usr/share/man/man1/foo.1.gz`
+fn existing_man_pages<'a>(assets: &'a [Asset], section: &str) -> HashSet<&'a str> {
+    let dir = Path::new("usr/share/man").join(format!("man{section}"));
+    let extension = format!(".{section}");
+    assets.iter().map(|a| a.c.target_path.as_path())
+        .filter(|p| p.parent() == Some(&dir))
+        .filter_map(|p| p.file_name()?.to_str()?.trim_end_matches(".gz").strip_suffix(&*extension))
+        .collect()
+}
+
+/// The most recently built `<file_name>` in `OUT_DIR`s of the crate's build scripts
+fn find_in_out_dirs(build_dir: &Path, crate_name: &str, file_name: &str) -> Option<PathBuf> {
+    let prefix = format!("{crate_name}-");
+    let mut found = Vec::new();
+    for entry in fs::read_dir(build_dir).ok()?.flatten() {
+        if entry.file_name().to_str().is_some_and(|n| n.starts_with(&prefix) && !n[prefix.len()..].contains('-')) {
+            find_file(&entry.path().join("out"), file_name, MAX_DEPTH, &mut found);
+        }
+    }
+    found.into_iter().max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+fn find_file(dir: &Path, file_name: &str, depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                find_file(&path, file_name, depth - 1, found);
+            }
+        } else if entry.file_name() == file_name {
+            found.push(path);
+        }
+    }
+}
+
+/// Runs `--help` and `--version` of the binary, if `help2man` is installed, and the binary runs on this machine.
+///
+/// `timeout` stops the binary too, since it signals the whole process group.
+fn help2man(binary: &Path, section: &str, description: &str) -> Option<Vec<u8>> {
+    let output = Command::new("timeout")
+        .args([HELP2MAN_TIMEOUT, "help2man", "--no-info", "--no-discard-stderr", "--section", section, "--name", description])
+        .arg(binary)
+        .output().ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+/// The most recently built `<file_name>` in `OUT_DIR`s of the package's build scripts.
+/// Build directories are named after the package, but may have underscores.
+pub(crate) fn find_build_script_output(config: &Config, package_deb: &PackageConfig, file_name: &str) -> Option<PathBuf> {
+    let build_dir = config.path_in_build("build");
+    [package_deb.name.clone(), package_deb.name.replace('-', "_")].iter()
+        .find_map(|crate_name| find_in_out_dirs(&build_dir, crate_name, file_name))
+}
+
+/// Built binaries in the directory, with their names
+pub(crate) fn built_binaries<'a>(package_deb: &'a PackageConfig, dir: &'a str) -> impl Iterator<Item = (&'a str, &'a Path)> {
+    package_deb.assets.resolved.iter()
+        .filter(move |a| a.c.is_built() && a.c.target_path.parent() == Some(Path::new(dir)))
+        .filter_map(|a| Some((a.c.target_path.file_name()?.to_str()?, a.source.path()?)))
+}
+
+/// Adds man pages for built binaries that have none, and warns about the binaries that still have none, like lintian would.
+///
+/// Cross-compiled binaries can't run for `help2man`, so they need a man page from `build.rs`.
+pub(crate) fn add_generated_man_pages(config: &Config, package_deb: &mut PackageConfig, listener: &dyn Listener) -> CDResult<()> {
+    let mut man_pages = Vec::new();
+    for (dir, section) in BINARY_DIRS {
+        let existing = existing_man_pages(&package_deb.assets.resolved, section);
+        for (name, source_path) in built_binaries(package_deb, dir) {
+            if existing.contains(name) {
+                continue;
+            }
+            let file_name = format!("{name}.{section}");
+            let source = find_build_script_output(config, package_deb, &file_name)
+                .map(AssetSource::Path)
+                .or_else(|| config.target.is_none().then(|| help2man(source_path, section, &package_deb.description)).flatten().map(AssetSource::Data));
+            let Some(source) = source else {
+                let alternative = if config.target.is_none() { ", or install help2man" } else { "" };
+                listener.warning(format!("/{dir}/{name} has no man page. Generate {file_name} with clap_mangen in OUT_DIR of build.rs{alternative}"));
+                continue;
+            };
+            let target_path = Path::new("usr/share/man").join(format!("man{section}")).join(&file_name);
+            listener.info(format!("Adding man page /{}", target_path.display()));
+            man_pages.push(Asset::new(source, target_path, 0o644, IsBuilt::No, false));
+        }
+    }
+    package_deb.assets.resolved.extend(man_pages);
+    Ok(())
+}
+
+#[test]
+fn finds_man_pages() {
+    let assets = ["usr/share/man/man1/foo.1.gz", "usr/share/man/man1/bar.1", "usr/share/man/man8/baz.8", "usr/share/man/man1/sub/x.1"]
+        .map(|p| Asset::new(AssetSource::Data(vec![]), p.into(), 0o644, IsBuilt::No, false));
+    assert_eq!(existing_man_pages(&assets, "1"), HashSet::from(["foo", "bar"]));
+
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("mycrate-0123abcd/out/man");
+    fs::create_dir_all(&out).unwrap();
+    fs::create_dir_all(dir.path().join("mycrate-sys-0123abcd/out")).unwrap();
+    fs::write(out.join("mycrate.1"), ".TH MYCRATE 1").unwrap();
+    fs::write(dir.path().join("mycrate-sys-0123abcd/out/other.1"), "").unwrap();
+    assert_eq!(find_in_out_dirs(dir.path(), "mycrate", "mycrate.1"), Some(out.join("mycrate.1")));
+    assert_eq!(find_in_out_dirs(dir.path(), "mycrate", "other.1"), None);
+}
+
+#[test]
+fn adds_generated_man_pages() {
+    use crate::config::DebConfigOverrides;
+    use crate::CargoLockingFlags;
+
+    let mut mock_listener = crate::listener::MockListener::new();
+    mock_listener.expect_info().return_const(());
+    mock_listener.expect_warning().withf(|w| w == "/usr/bin/nopage has no man page. Generate nopage.1 with clap_mangen in OUT_DIR of build.rs").times(1).return_const(());
+    let (mut config, mut package_deb) = Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, DebConfigOverrides::default(), None, None, None, None, None, None, CargoLockingFlags::default(), &mock_listener).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    config.target_dir = dir.path().to_owned();
+    // help2man would run the binaries, but they're for another machine
+    config.target = Some("aarch64-unknown-linux-gnu".into());
+    let out = config.path_in_build("build").join(format!("{}-0123abcd/out", package_deb.name));
+    fs::create_dir_all(&out).unwrap();
+    fs::write(out.join("fromout.1"), ".TH FROMOUT 1").unwrap();
+
+    let binary = |name: &str| Asset::new(AssetSource::Path(dir.path().join(name)), Path::new("usr/bin").join(name), 0o755, IsBuilt::SamePackage, false);
+    package_deb.assets.resolved = vec![
+        binary("haspage"), binary("fromout"), binary("nopage"),
+        Asset::new(AssetSource::Data(vec![]), "usr/share/man/man1/haspage.1.gz".into(), 0o644, IsBuilt::No, false),
+    ];
+    add_generated_man_pages(&config, &mut package_deb, &mock_listener).unwrap();
+
+    let added = &package_deb.assets.resolved[4..];
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].c.target_path, Path::new("usr/share/man/man1/fromout.1"));
+    assert_eq!(added[0].source.path(), Some(out.join("fromout.1").as_path()));
+}
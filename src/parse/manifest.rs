@@ -1,10 +1,11 @@
 use crate::error::{CDResult, CargoDebError};
+use crate::util::compress::Level;
 use crate::CargoLockingFlags;
 use cargo_toml::DebugSetting;
 use log::{debug, warn};
 use serde::Deserialize;
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -19,12 +20,16 @@ use std::process::Command;
 /// `unit_name`: (optjonal) in cases where the `unit_scripts` directory contains
 /// multiple units, only process those matching this unit name.
 ///
+/// `unit_files`: (optional) paths of unit files to install with their own file
+/// names, instead of searching `unit_scripts` for files named after the package.
+///
 /// For details on the other options please see `dh_installsystemd::Options`.
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct SystemdUnitsConfig {
     pub unit_scripts: Option<PathBuf>,
     pub unit_name: Option<String>,
+    pub unit_files: Option<Vec<PathBuf>>,
     pub enable: Option<bool>,
     pub start: Option<bool>,
     pub restart_after_upgrade: Option<bool>,
@@ -41,21 +46,32 @@ pub(crate) fn manifest_debug_flag(manifest: &cargo_toml::Manifest<CargoPackageMe
 }
 
 /// Debian-compatible version of the semver version
-pub(crate) fn manifest_version_string<'a>(package: &'a cargo_toml::Package<CargoPackageMetadata>, revision: Option<&str>) -> Cow<'a, str> {
+///
+/// `template` is the `version` from the metadata, with the git placeholders already replaced by `expand_git_placeholders`.
+pub(crate) fn manifest_version_string<'a>(package: &'a cargo_toml::Package<CargoPackageMetadata>, revision: Option<&str>, epoch: Option<u32>, prerelease_tilde: bool, template: Option<&str>) -> Cow<'a, str> {
     let mut version = Cow::Borrowed(package.version());
 
     // Make debian's version ordering (newer versions) more compatible with semver's.
     // Keep "semver-1" and "semver-xxx" as-is (assuming these are irrelevant, or debian revision already),
-    // but change "semver-beta.1" to "semver~beta.1"
+    // but change "semver-beta.1" and "semver-rc" to "semver~beta.1" and "semver~rc"
     let mut parts = version.splitn(2, '-');
     let semver_main = parts.next().unwrap();
-    if let Some(semver_pre) = parts.next() {
+    if let Some(semver_pre) = parts.next().filter(|_| prerelease_tilde) {
         let pre_ascii = semver_pre.as_bytes();
-        if pre_ascii.iter().any(|c| !c.is_ascii_digit()) && pre_ascii.iter().any(u8::is_ascii_digit) {
+        let first_identifier = semver_pre.split(['.', '+']).next().unwrap_or_default().to_ascii_lowercase();
+        if (pre_ascii.iter().any(|c| !c.is_ascii_digit()) && pre_ascii.iter().any(u8::is_ascii_digit)) || PRERELEASE_IDENTIFIERS.contains(&first_identifier.as_str()) {
             version = Cow::Owned(format!("{semver_main}~{semver_pre}"));
         }
     }
 
+    if let Some(template) = template {
+        version = Cow::Owned(template.replace("{crate_version}", &version));
+    }
+
+    if let Some(epoch) = epoch {
+        version = Cow::Owned(format!("{epoch}:{version}"));
+    }
+
     let revision = revision.unwrap_or("1");
     if !revision.is_empty() {
         let v = version.to_mut();
@@ -65,6 +81,44 @@ pub(crate) fn manifest_version_string<'a>(package: &'a cargo_toml::Package<Cargo
     version
 }
 
+/// Replaces `{commit_count}`, `{commits_since_tag}`, `{short_sha}` and `{commit_date}` in the `version` template with data from git
+pub(crate) fn expand_git_placeholders(template: &str, dir: &Path) -> CDResult<String> {
+    let git = |args: &[&str]| -> CDResult<String> {
+        let output = Command::new("git").current_dir(dir).env("TZ", "UTC").args(args).output()
+            .map_err(|e| CargoDebError::CommandFailed(e, "git"))?;
+        if !output.status.success() {
+            return Err(CargoDebError::CommandError("git for the version template failed", args.join(" "), output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+
+    let mut version = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once('{') {
+        version.push_str(before);
+        let (name, after) = after.split_once('}').ok_or(CargoDebError::InvalidVersion("unclosed { in the version template", template.into()))?;
+        let value = match name {
+            "crate_version" => "{crate_version}".into(),
+            "commit_count" => git(&["rev-list", "--count", "HEAD"])?,
+            "short_sha" => git(&["rev-parse", "--short", "HEAD"])?,
+            "commit_date" => git(&["log", "-1", "--format=%cd", "--date=format-local:%Y%m%d%H%M%S"])?,
+            // like v1.2.0-5-gabc1234, or the commit count if there are no tags
+            "commits_since_tag" => match git(&["describe", "--tags", "--long"]) {
+                Ok(describe) => describe.rsplit('-').nth(1).unwrap_or_default().to_owned(),
+                Err(_) => git(&["rev-list", "--count", "HEAD"])?,
+            },
+            _ => return Err(CargoDebError::InvalidVersion("the version template can use {crate_version}, {commit_count}, {commits_since_tag}, {short_sha} and {commit_date}", template.into())),
+        };
+        version.push_str(&value);
+        rest = after;
+    }
+    version.push_str(rest);
+    Ok(version)
+}
+
+/// Pre-release versions that don't have a number, like `1.0.0-rc`
+const PRERELEASE_IDENTIFIERS: [&str; 8] = ["alpha", "beta", "rc", "pre", "preview", "dev", "snapshot", "nightly"];
+
 #[derive(Clone, Debug, Deserialize, Default)]
 pub(crate) struct CargoPackageMetadata {
     pub deb: Option<CargoDeb>,
@@ -100,13 +154,138 @@ impl DependencyList {
     }
 }
 
+/// Either `9` or `"9e"`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum CompressionLevel {
+    Int(u32),
+    Str(String),
+}
+
+impl CompressionLevel {
+    pub fn parse(&self) -> CDResult<Level> {
+        match self {
+            Self::Int(level) => Ok(Level { level: *level, extreme: false }),
+            Self::Str(s) => s.parse(),
+        }
+    }
+}
+
+/// `Multi-Arch` control field, see [the multiarch spec](https://wiki.ubuntu.com/MultiarchSpec)
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Multiarch {
+    /// Can be installed for several architectures at the same time, like libraries
+    Same,
+    /// Satisfies dependencies of packages of any architecture, like tools
+    Foreign,
+    /// Dependencies can choose with `:any`
+    Allowed,
+    No,
+}
+
+impl Multiarch {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Same => "same",
+            Self::Foreign => "foreign",
+            Self::Allowed => "allowed",
+            Self::No => "no",
+        }
+    }
+}
+
+/// How `git-changelog` lists commits
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GitChangelog {
+    /// Every commit's subject
+    Commits,
+    /// Without `chore:`, `ci:`, etc. commits
+    ConventionalCommits,
+}
+
 /// Type-alias for list of assets
 ///
-pub(crate) type AssetList = Vec<Vec<String>>;
+pub(crate) type AssetList = Vec<AssetEntry>;
 
 /// Type-alias for a merge map,
 ///
-pub(crate) type MergeMap<'a> = BTreeMap<&'a str, [&'a str; 2]>;
+pub(crate) type MergeMap<'a> = BTreeMap<&'a str, &'a AssetEntry>;
+
+/// `["source", "dest", "mode"]`, or a table with the same fields and per-asset settings
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AssetEntry {
+    Array(Vec<String>),
+    Table(Box<AssetTable>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct AssetTable {
+    pub source: String,
+    pub dest: String,
+    pub mode: String,
+    /// Overrides the package's `separate-debug-symbols` for this binary
+    pub separate_debug_symbols: Option<bool>,
+    /// The `dest` is a configuration file managed by `ucf`, and the asset is installed as its reference copy in `/usr/share/<package>`
+    pub ucf: Option<bool>,
+    /// The asset provides an alternative for `update-alternatives`
+    pub alternative: Option<AlternativeEntry>,
+    /// File capabilities to set in the `postinst`, like `cap_net_bind_service+ep`
+    pub capabilities: Option<String>,
+    /// Owner, group, and mode that the `postinst` sets with `dpkg-statoverride`
+    pub statoverride: Option<StatOverrideEntry>,
+}
+
+/// Ownership and permissions of an installed asset, instead of the ones in the archive
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct StatOverrideEntry {
+    /// `root` by default
+    pub owner: Option<String>,
+    /// `root` by default
+    pub group: Option<String>,
+    /// Octal, like `"4755"`. The asset's `mode` by default
+    pub mode: Option<String>,
+}
+
+/// An alternatives group that an asset is a part of
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct AlternativeEntry {
+    /// Name of the group, like `editor`
+    pub name: String,
+    /// The generic path, like `/usr/bin/editor`
+    pub link: String,
+    /// The alternative with the highest priority is used in automatic mode
+    pub priority: i32,
+    /// Links that are switched together with the main one, like man pages
+    pub slaves: Option<Vec<AlternativeSlave>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct AlternativeSlave {
+    pub name: String,
+    pub link: String,
+    /// Absolute path of the file in the package
+    pub path: String,
+}
+
+impl AssetEntry {
+    /// Source, destination, and mode
+    pub(crate) fn parts(&self) -> Option<[&str; 3]> {
+        match self {
+            Self::Array(a) => match &a[..] {
+                [src, dest, perm, ..] => Some([src, dest, perm]),
+                _ => None,
+            },
+            Self::Table(t) => Some([&t.source, &t.dest, &t.mode]),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -116,34 +295,479 @@ pub(crate) struct CargoDeb {
     pub copyright: Option<String>,
     pub license_file: Option<LicenseFile>,
     pub changelog: Option<String>,
+    pub git_changelog: Option<GitChangelog>,
+    pub news: Option<String>,
+    pub upstream_changelog: Option<String>,
+    pub distribution: Option<String>,
+    pub urgency: Option<String>,
     pub depends: Option<DependencyList>,
     pub pre_depends: Option<DependencyList>,
+    /// `Build-Depends` of the source package
+    pub build_depends: Option<DependencyList>,
     pub recommends: Option<DependencyList>,
     pub suggests: Option<DependencyList>,
-    pub enhances: Option<String>,
-    pub conflicts: Option<String>,
-    pub breaks: Option<String>,
-    pub replaces: Option<String>,
-    pub provides: Option<String>,
+    pub enhances: Option<DependencyList>,
+    pub conflicts: Option<DependencyList>,
+    pub breaks: Option<DependencyList>,
+    pub replaces: Option<DependencyList>,
+    pub provides: Option<DependencyList>,
+    /// `$auto` lists C libraries vendored by crates in `Cargo.lock`
+    pub built_using: Option<DependencyList>,
+    /// Lists crates from `Cargo.lock` in `Static-Built-Using`
+    pub static_built_using: Option<bool>,
+    pub multiarch: Option<Multiarch>,
+    pub vcs_git: Option<String>,
+    pub vcs_browser: Option<String>,
+    pub origin: Option<String>,
+    /// URL of the bug tracker, like `debbugs://bugs.example.com`
+    pub bugs: Option<String>,
+    /// Debtags
+    pub tag: Option<DependencyList>,
+    pub essential: Option<bool>,
+    pub protected: Option<bool>,
+    pub important: Option<bool>,
+    /// Adds `X-Cargo-*` fields with the toolchain, profile and features
+    pub provenance_fields: Option<bool>,
     pub extended_description: Option<String>,
     pub extended_description_file: Option<String>,
     pub section: Option<String>,
     pub priority: Option<String>,
     pub revision: Option<String>,
+    /// Template of the upstream part of the version, like `{crate_version}+git{commit_count}`
+    pub version: Option<String>,
+    pub epoch: Option<u32>,
+    /// Use `~` for semver pre-releases, so that they sort before the release
+    pub prerelease_tilde: Option<bool>,
+    /// File name of the `.deb`, like `{name}_{version}_{arch}.deb`
+    pub output_template: Option<String>,
     pub conf_files: Option<Vec<String>>,
     pub assets: Option<AssetList>,
     pub merge_assets: Option<MergeAssets>,
     pub triggers_file: Option<String>,
+    /// Lines of the triggers file, like `activate-noawait update-icon-caches`
+    pub triggers: Option<Vec<String>>,
+    /// Conffiles and directories to change with `dpkg-maintscript-helper`
+    pub maintscript: Option<Vec<MaintscriptEntry>>,
+    /// SysV init script, installed as `/etc/init.d/<package>`
+    pub init_script: Option<String>,
+    /// Config for logrotate, installed as `/etc/logrotate.d/<package>`
+    pub logrotate: Option<String>,
+    /// Files for `/etc/cron.d` and `/etc/cron.<period>`
+    pub cron: Option<CronFiles>,
+    /// udev rules, installed as `/usr/lib/udev/rules.d/60-<package>.rules`
+    pub udev_rules: Option<String>,
+    /// D-Bus service activation files and the bus policy
+    pub dbus: Option<DbusFiles>,
+    /// polkit `.policy` and `.rules` files
+    pub polkit: Option<Vec<String>>,
+    /// AppArmor profiles, installed in `/etc/apparmor.d`
+    pub apparmor_profiles: Option<Vec<String>>,
+    /// SELinux policy modules, installed in `/usr/share/selinux/<package>`
+    pub selinux_modules: Option<Vec<String>>,
+    /// initramfs-tools hook, installed as `/usr/share/initramfs-tools/hooks/<package>`
+    pub initramfs_hook: Option<String>,
+    /// Sources of a kernel module to build with DKMS
+    pub dkms: Option<DkmsConfig>,
+    /// `.desktop` files and their icons
+    pub desktop_entries: Option<Vec<DesktopEntry>>,
+    /// AppStream metainfo file, or the id of the generated one
+    pub metainfo: Option<MetainfoEntry>,
+    /// Add man pages generated by build scripts or `help2man` for binaries that have none
+    pub generate_man_pages: Option<bool>,
+    /// Shell completions files, or `true` for the ones generated by build scripts
+    pub completions: Option<CompletionsEntry>,
+    /// `true` for the readme and other upstream docs, or a list of files to install in `/usr/share/doc/<package>/`
+    pub docs: Option<DocsEntry>,
+    /// Install sources of examples in `/usr/share/doc/<package>/examples/`
+    pub examples: Option<bool>,
+    /// Examples to build, and install like binaries
+    pub example_binaries: Option<Vec<ExampleBinaryEntry>>,
+    /// Gettext catalogs to install in `/usr/share/locale/`
+    pub translations: Option<TranslationsConfig>,
+    /// Add copyright and licenses of the dependencies to the copyright file
+    pub dependency_copyright: Option<bool>,
+    /// Install the license files of the dependencies in `/usr/share/doc/<package>/licenses/`
+    pub license_texts: Option<bool>,
+    /// SPDX identifiers of the licenses the dependencies may or must not have
+    pub license_policy: Option<LicensePolicy>,
+    /// Software bill of materials of the statically linked crates
+    pub sbom: Option<SbomConfig>,
+    /// Build with `cargo auditable`, and check that the binaries have its dependency list
+    pub auditable: Option<bool>,
+    /// Root of the target system for `$auto` dependencies of cross-compiled binaries
+    pub sysroot: Option<String>,
     pub maintainer_scripts: Option<String>,
+    pub debhelper_files: Option<String>,
     pub features: Option<Vec<String>>,
     pub default_features: Option<bool>,
     pub separate_debug_symbols: Option<bool>,
     pub compress_debug_symbols: Option<bool>,
+    pub dbgsym: Option<bool>,
+    pub ddeb: Option<bool>,
+    pub dwz: Option<bool>,
+    /// Replaces the default flags of `strip`
+    pub strip_flags: Option<Vec<String>>,
+    /// Appended to the flags of `strip`
+    pub extra_strip_flags: Option<Vec<String>>,
+    /// Sections passed to `strip --keep-section`
+    pub strip_keep_sections: Option<Vec<String>>,
+    pub remove_rpath: Option<bool>,
+    /// Settings that apply only when building for the given Rust target triple
+    pub targets: Option<HashMap<String, CargoDebTarget>>,
     pub preserve_symlinks: Option<bool>,
+    pub compression: Option<String>,
+    pub control_compression: Option<String>,
+    pub compression_level: Option<CompressionLevel>,
+    pub control_compression_level: Option<CompressionLevel>,
     pub systemd_units: Option<SystemUnitsSingleOrMultiple>,
+    /// Named repositories for `--upload`
+    pub upload: Option<HashMap<String, UploadTarget>>,
+    pub keyring: Option<KeyringConfig>,
     pub variants: Option<HashMap<String, CargoDeb>>,
 }
 
+/// `[package.metadata.deb.targets.<triple>]`
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct CargoDebTarget {
+    pub strip_flags: Option<Vec<String>>,
+    pub extra_strip_flags: Option<Vec<String>>,
+}
+
+/// `[package.metadata.deb.keyring]`, the package built with `--keyring`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct KeyringConfig {
+    /// `<name>-archive-keyring` by default
+    pub name: Option<String>,
+    /// Public key of the repository, armored or binary
+    pub key_file: String,
+    /// URL of the apt repository
+    pub uri: String,
+    /// `stable` by default
+    pub suites: Option<Vec<String>>,
+    /// `main` by default
+    pub components: Option<Vec<String>>,
+    pub architectures: Option<Vec<String>>,
+}
+
+/// An entry of `[[package.metadata.deb.desktop-entries]]`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct DesktopEntry {
+    /// Path to the `.desktop` file
+    pub file: String,
+    /// PNG and SVG files of the icon in different sizes
+    pub icons: Option<Vec<String>>,
+    /// Name of the icon for the theme, by default the `Icon=` of the desktop file
+    pub icon_name: Option<String>,
+}
+
+/// Identifiers can end with `*`, like `GPL-*`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct LicensePolicy {
+    /// If set, the dependencies can only have these licenses
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Format of the SBOM file
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct SbomConfig {
+    /// `cyclonedx` by default
+    pub format: Option<SbomFormat>,
+    /// Install it in `/usr/share/doc/<package>/`. `true` by default.
+    pub install: Option<bool>,
+    /// Also write it next to the `.deb` file
+    pub output: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct TranslationsConfig {
+    /// Directory with `<language>.po` or `.mo` files. `po` by default.
+    pub dir: Option<String>,
+    /// Name of the `.mo` files. The crate name by default.
+    pub domain: Option<String>,
+    /// Install only these languages
+    pub languages: Option<Vec<String>>,
+}
+
+/// Name of an example, installed in `/usr/bin/`, or a table with its `dest`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ExampleBinaryEntry {
+    Name(String),
+    #[serde(rename_all = "kebab-case")]
+    Detailed { name: String, dest: String },
+}
+
+impl ExampleBinaryEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Detailed { name, .. } => name,
+        }
+    }
+
+    pub fn dest(&self) -> &str {
+        match self {
+            Self::Name(_) => "usr/bin/",
+            Self::Detailed { dest, .. } => dest,
+        }
+    }
+}
+
+/// Either `true` for the docs found in the package, or paths of docs files
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum DocsEntry {
+    Auto(bool),
+    Files(Vec<String>),
+}
+
+/// Either `true` for completions generated by build scripts, or paths of completions files
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum CompletionsEntry {
+    Generate(bool),
+    Files(CompletionFiles),
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct CompletionFiles {
+    pub bash: Option<String>,
+    pub zsh: Option<String>,
+    pub fish: Option<String>,
+    /// The command that the files are for, instead of guessing it from their names
+    pub command: Option<String>,
+    /// Also add the completions generated by build scripts for other binaries
+    pub generate: Option<bool>,
+}
+
+/// Either a path to a metainfo file, or `{ id = "org.example.App" }` to generate one
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum MetainfoEntry {
+    Path(String),
+    Generate(GeneratedMetainfo),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct GeneratedMetainfo {
+    /// Reverse-DNS id of the component
+    pub id: String,
+    /// License of the metainfo file, `CC0-1.0` by default
+    pub metadata_license: Option<String>,
+}
+
+/// `[package.metadata.deb.dkms]`, for the `dkms.conf` of kernel module sources
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct DkmsConfig {
+    /// Directory with the sources and the `Makefile` of the kernel module
+    pub source: String,
+    /// `PACKAGE_NAME`, by default the package name without `-dkms`
+    pub name: Option<String>,
+    /// `PACKAGE_VERSION`, by default the upstream version of the package
+    pub version: Option<String>,
+    /// `BUILT_MODULE_NAME`s, by default the name
+    pub modules: Option<Vec<String>>,
+    pub dest_module_location: Option<String>,
+    /// `MAKE[0]`, if the default `make` command of DKMS doesn't build the modules
+    pub make: Option<String>,
+    pub clean: Option<String>,
+    /// Build the modules for new kernels, true by default
+    pub autoinstall: Option<bool>,
+}
+
+/// Paths of cron files, installed as `/etc/cron.d/<package>` or `/etc/cron.<period>/<package>`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct CronFiles {
+    /// A crontab, with the user in each line
+    pub d: Option<String>,
+    /// Scripts run by `run-parts`
+    pub hourly: Option<String>,
+    pub daily: Option<String>,
+    pub weekly: Option<String>,
+    pub monthly: Option<String>,
+    pub yearly: Option<String>,
+}
+
+impl CronFiles {
+    /// Suffix of the `/etc/cron.*` directory, and the source path
+    pub(crate) fn files(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [("d", &self.d), ("hourly", &self.hourly), ("daily", &self.daily), ("weekly", &self.weekly), ("monthly", &self.monthly), ("yearly", &self.yearly)]
+            .into_iter().filter_map(|(suffix, path)| Some((suffix, path.as_deref()?)))
+    }
+}
+
+/// Paths of D-Bus files
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct DbusFiles {
+    /// Activation files of the system bus, named after their bus names
+    pub system_services: Option<Vec<String>>,
+    /// Activation files of the session bus
+    pub session_services: Option<Vec<String>>,
+    /// `<busconfig>` policy of the system bus, installed as `<package>.conf`
+    pub policy: Option<String>,
+}
+
+/// An entry of `maintscript`, with one of the `dpkg-maintscript-helper` commands
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct MaintscriptEntry {
+    /// Path of a conffile that the new version doesn't have
+    pub rm_conffile: Option<String>,
+    /// Old path of a conffile that's been moved `to` a new path
+    pub mv_conffile: Option<String>,
+    pub to: Option<String>,
+    /// Path of a symlink, to the old `target`, that has been replaced with a directory
+    pub symlink_to_dir: Option<String>,
+    /// Path of a directory that has been replaced with a symlink to the new `target`
+    pub dir_to_symlink: Option<String>,
+    pub target: Option<String>,
+    /// The last version that had the old file, usually with `~` at the end, like `1.2-1~`
+    pub prior_version: Option<String>,
+}
+
+impl MaintscriptEntry {
+    /// Arguments of `dpkg-maintscript-helper`, like `rm_conffile /etc/foo.conf 1.2-1~`
+    pub(crate) fn helper_args(&self) -> CDResult<String> {
+        let invalid = |path: &str, msg| CargoDebError::InvalidField("maintscript", path.into(), msg);
+        let (command, path, other_path) = match (&self.rm_conffile, &self.mv_conffile, &self.symlink_to_dir, &self.dir_to_symlink) {
+            (Some(path), None, None, None) => ("rm_conffile", path, None),
+            (None, Some(path), None, None) => ("mv_conffile", path, Some(self.to.as_ref().ok_or_else(|| invalid(path, "mv-conffile needs the new path in `to`"))?)),
+            (None, None, Some(path), None) => ("symlink_to_dir", path, Some(self.target.as_ref().ok_or_else(|| invalid(path, "symlink-to-dir needs the symlink's old `target`"))?)),
+            (None, None, None, Some(path)) => ("dir_to_symlink", path, Some(self.target.as_ref().ok_or_else(|| invalid(path, "dir-to-symlink needs the symlink's new `target`"))?)),
+            _ => return Err(CargoDebError::Str("each maintscript entry needs one of rm-conffile, mv-conffile, symlink-to-dir, or dir-to-symlink")),
+        };
+        if !path.starts_with('/') {
+            return Err(invalid(path, "the path must be absolute"));
+        }
+        let mut args = vec![command, path.as_str()];
+        args.extend(other_path.map(String::as_str));
+        args.extend(self.prior_version.as_deref());
+        if args.iter().any(|a| a.is_empty() || a.contains(char::is_whitespace)) {
+            return Err(invalid(path, "the paths and version can't have spaces"));
+        }
+        Ok(args.join(" "))
+    }
+}
+
+/// `[package.metadata.deb.upload.<name>]`, or `[<name>]` in a file given to `--upload-config`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub(crate) enum UploadTarget {
+    Aptly(AptlyUpload),
+    Artifactory(ArtifactoryUpload),
+    Packagecloud(PackagecloudUpload),
+    Sftp(SftpUpload),
+    S3(S3Upload),
+    Dput(DputUpload),
+}
+
+/// Adds packages to a local repo through the aptly REST API
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct AptlyUpload {
+    /// Base URL of the API server, without `/api`
+    pub url: String,
+    /// Name of the local repo
+    pub repo: String,
+    /// If set, updates the published repository of this distribution
+    pub publish_distribution: Option<String>,
+    /// Prefix of the published repository, `.` by default
+    pub publish_prefix: Option<String>,
+    pub user: Option<String>,
+    /// Name of the environment variable with the password
+    pub password_env: Option<String>,
+}
+
+/// Deploys packages to a Debian repository in Artifactory
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct ArtifactoryUpload {
+    /// URL of the repository, e.g. `https://example.jfrog.io/artifactory/debian-local`
+    pub url: String,
+    pub distribution: String,
+    /// `main` by default
+    pub component: Option<String>,
+    /// Without a user, the password is used as an access token
+    pub user: Option<String>,
+    /// Name of the environment variable with the password or access token
+    pub password_env: Option<String>,
+}
+
+/// Pushes packages to a packagecloud.io repository
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct PackagecloudUpload {
+    /// `user/repo`
+    pub repo: String,
+    /// Numeric ID of the distribution version, from `/api/v1/distributions.json`
+    pub distro_version_id: u32,
+    /// For packagecloud:enterprise, `https://packagecloud.io` by default
+    pub url: Option<String>,
+    /// Name of the environment variable with the API token, `PACKAGECLOUD_TOKEN` by default
+    pub token_env: Option<String>,
+}
+
+/// Copies all generated files to a directory on a server, using `sftp`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct SftpUpload {
+    /// `[user@]host`, or an alias from `~/.ssh/config`
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+/// Copies all generated files to an S3 bucket, using the `aws` CLI
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct S3Upload {
+    pub bucket: String,
+    /// Directory in the bucket
+    pub prefix: Option<String>,
+    /// Profile from the AWS config
+    pub profile: Option<String>,
+    /// For S3-compatible services
+    pub endpoint_url: Option<String>,
+}
+
+/// Uploads the `.changes` file and the files it lists with `dput`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct DputUpload {
+    /// Host from `dput.cf`, or `ppa:<owner>/<name>` for a Launchpad PPA
+    pub host: Option<String>,
+    /// Defines the host here instead of `dput.cf`
+    pub fqdn: Option<String>,
+    /// Upload directory on `fqdn`
+    pub incoming: Option<String>,
+    /// `ftp` by default
+    pub method: Option<String>,
+    pub login: Option<String>,
+}
+
 /// Struct containing merge configuration
 ///
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -182,14 +806,14 @@ impl MergeByKey {
 
     /// Folds the parent asset into a merge-map preparing to prepare for a merge,
     ///
-    fn prep_parent_item<'a>(&'a self, mut parent: MergeMap<'a>, asset: &'a Vec<String>) -> MergeMap<'_> {
-        if let [src, dest, perm, ..] = &asset[..] {
+    fn prep_parent_item<'a>(&'a self, mut parent: MergeMap<'a>, asset: &'a AssetEntry) -> MergeMap<'a> {
+        if let Some([src, dest, _]) = asset.parts() {
             match &self {
                 MergeByKey::Src(_) => {
-                    parent.insert(src, [dest, perm]);
+                    parent.insert(src, asset);
                 },
                 MergeByKey::Dest(_) => {
-                    parent.insert(dest, [src, perm]);
+                    parent.insert(dest, asset);
                 },
             }
             parent
@@ -202,38 +826,25 @@ impl MergeByKey {
     /// Merges w/ a parent merge map and returns the resulting asset list,
     ///
     fn merge_with(&self, parent: MergeMap<'_>) -> AssetList {
-        match self {
-            MergeByKey::Src(assets) => assets.iter()
-                .fold(parent, |mut acc, asset| {
-                    if let [src, dest, perm, ..] = &asset[..] {
-                        if let Some([replaced_dest, replaced_perm]) = acc.insert(src, [dest, perm]) {
-                            debug!("Replacing {:?} w/ {:?}", [replaced_dest, replaced_perm], [dest, perm]);
-                        }
-                        acc
-                    } else {
-                        warn!("Incomplete asset entry {:?}", asset);
-                        acc
-                    }
-                })
-                .iter()
-                .map(|(src, [dest, perm])| vec![(*src).to_string(), (*dest).to_string(), (*perm).to_string()])
-                .collect(),
-            MergeByKey::Dest(assets) => assets.iter()
-                .fold(parent, |mut acc, asset| {
-                    if let [src, dest, perm, ..] = &asset[..] {
-                        if let Some([replaced_src, replaced_perm]) = acc.insert(dest, [src, perm]) {
-                            debug!("Replacing {:?} w/ {:?}", [replaced_src, replaced_perm], [src, perm]);
-                        }
-                        acc
-                    } else {
-                        warn!("Incomplete asset entry {:?}", asset);
-                        acc
+        let (assets, by_src) = match self {
+            MergeByKey::Src(assets) => (assets, true),
+            MergeByKey::Dest(assets) => (assets, false),
+        };
+        assets.iter()
+            .fold(parent, |mut acc, asset| {
+                if let Some([src, dest, _]) = asset.parts() {
+                    if let Some(replaced) = acc.insert(if by_src { src } else { dest }, asset) {
+                        debug!("Replacing {:?} w/ {:?}", replaced, asset);
                     }
-                })
-                .iter()
-                .map(|(dest, [src, perm])| vec![(*src).to_string(), (*dest).to_string(), (*perm).to_string()])
-                .collect(),
-        }
+                    acc
+                } else {
+                    warn!("Incomplete asset entry {:?}", asset);
+                    acc
+                }
+            })
+            .into_values()
+            .cloned()
+            .collect()
     }
 }
 
@@ -261,8 +872,14 @@ impl CargoDeb {
             copyright: self.copyright.or(parent.copyright),
             license_file: self.license_file.or(parent.license_file),
             changelog: self.changelog.or(parent.changelog),
+            git_changelog: self.git_changelog.or(parent.git_changelog),
+            news: self.news.or(parent.news),
+            upstream_changelog: self.upstream_changelog.or(parent.upstream_changelog),
+            distribution: self.distribution.or(parent.distribution),
+            urgency: self.urgency.or(parent.urgency),
             depends: self.depends.or(parent.depends),
             pre_depends: self.pre_depends.or(parent.pre_depends),
+            build_depends: self.build_depends.or(parent.build_depends),
             recommends: self.recommends.or(parent.recommends),
             suggests: self.suggests.or(parent.suggests),
             enhances: self.enhances.or(parent.enhances),
@@ -270,22 +887,79 @@ impl CargoDeb {
             breaks: self.breaks.or(parent.breaks),
             replaces: self.replaces.or(parent.replaces),
             provides: self.provides.or(parent.provides),
+            built_using: self.built_using.or(parent.built_using),
+            static_built_using: self.static_built_using.or(parent.static_built_using),
+            multiarch: self.multiarch.or(parent.multiarch),
+            vcs_git: self.vcs_git.or(parent.vcs_git),
+            vcs_browser: self.vcs_browser.or(parent.vcs_browser),
+            origin: self.origin.or(parent.origin),
+            bugs: self.bugs.or(parent.bugs),
+            tag: self.tag.or(parent.tag),
+            essential: self.essential.or(parent.essential),
+            protected: self.protected.or(parent.protected),
+            important: self.important.or(parent.important),
+            provenance_fields: self.provenance_fields.or(parent.provenance_fields),
             extended_description: self.extended_description.or(parent.extended_description),
             extended_description_file: self.extended_description_file.or(parent.extended_description_file),
             section: self.section.or(parent.section),
             priority: self.priority.or(parent.priority),
             revision: self.revision.or(parent.revision),
+            version: self.version.or(parent.version),
+            epoch: self.epoch.or(parent.epoch),
+            prerelease_tilde: self.prerelease_tilde.or(parent.prerelease_tilde),
+            output_template: self.output_template.or(parent.output_template),
             conf_files: self.conf_files.or(parent.conf_files),
             assets,
             merge_assets: None,
             triggers_file: self.triggers_file.or(parent.triggers_file),
+            triggers: self.triggers.or(parent.triggers),
+            maintscript: self.maintscript.or(parent.maintscript),
+            init_script: self.init_script.or(parent.init_script),
+            logrotate: self.logrotate.or(parent.logrotate),
+            cron: self.cron.or(parent.cron),
+            udev_rules: self.udev_rules.or(parent.udev_rules),
+            dbus: self.dbus.or(parent.dbus),
+            polkit: self.polkit.or(parent.polkit),
+            apparmor_profiles: self.apparmor_profiles.or(parent.apparmor_profiles),
+            selinux_modules: self.selinux_modules.or(parent.selinux_modules),
+            initramfs_hook: self.initramfs_hook.or(parent.initramfs_hook),
+            dkms: self.dkms.or(parent.dkms),
+            desktop_entries: self.desktop_entries.or(parent.desktop_entries),
+            metainfo: self.metainfo.or(parent.metainfo),
+            generate_man_pages: self.generate_man_pages.or(parent.generate_man_pages),
+            completions: self.completions.or(parent.completions),
+            docs: self.docs.or(parent.docs),
+            examples: self.examples.or(parent.examples),
+            example_binaries: self.example_binaries.or(parent.example_binaries),
+            translations: self.translations.or(parent.translations),
+            dependency_copyright: self.dependency_copyright.or(parent.dependency_copyright),
+            license_texts: self.license_texts.or(parent.license_texts),
+            license_policy: self.license_policy.or(parent.license_policy),
+            sbom: self.sbom.or(parent.sbom),
+            auditable: self.auditable.or(parent.auditable),
+            sysroot: self.sysroot.or(parent.sysroot),
             maintainer_scripts: self.maintainer_scripts.or(parent.maintainer_scripts),
+            debhelper_files: self.debhelper_files.or(parent.debhelper_files),
             features: self.features.or(parent.features),
             default_features: self.default_features.or(parent.default_features),
             separate_debug_symbols: self.separate_debug_symbols.or(parent.separate_debug_symbols),
             compress_debug_symbols: self.compress_debug_symbols.or(parent.compress_debug_symbols),
+            dbgsym: self.dbgsym.or(parent.dbgsym),
+            ddeb: self.ddeb.or(parent.ddeb),
+            dwz: self.dwz.or(parent.dwz),
+            strip_flags: self.strip_flags.or(parent.strip_flags),
+            extra_strip_flags: self.extra_strip_flags.or(parent.extra_strip_flags),
+            strip_keep_sections: self.strip_keep_sections.or(parent.strip_keep_sections),
+            remove_rpath: self.remove_rpath.or(parent.remove_rpath),
+            targets: self.targets.or(parent.targets),
             preserve_symlinks: self.preserve_symlinks.or(parent.preserve_symlinks),
+            compression: self.compression.or(parent.compression),
+            control_compression: self.control_compression.or(parent.control_compression),
+            compression_level: self.compression_level.or(parent.compression_level),
+            control_compression_level: self.control_compression_level.or(parent.control_compression_level),
             systemd_units: self.systemd_units.or(parent.systemd_units),
+            upload: self.upload.or(parent.upload),
+            keyring: self.keyring.or(parent.keyring),
             variants: self.variants.or(parent.variants),
         }
     }
@@ -307,12 +981,39 @@ struct CargoMetadata {
 #[derive(Deserialize)]
 struct CargoMetadataResolve {
     pub root: Option<String>,
+    #[serde(default)]
+    pub nodes: Vec<CargoMetadataNode>,
 }
 
 #[derive(Deserialize)]
-struct CargoMetadataPackage {
+struct CargoMetadataNode {
+    pub id: String,
+    #[serde(default)]
+    pub deps: Vec<CargoMetadataNodeDep>,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadataNodeDep {
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<CargoMetadataDepKind>,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadataDepKind {
+    /// `None` for normal dependencies, `dev` or `build`
+    pub kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CargoMetadataPackage {
     pub id: String,
     pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub license_file: Option<String>,
     pub targets: Vec<CargoMetadataTarget>,
     pub manifest_path: PathBuf,
 }
@@ -330,7 +1031,12 @@ pub(crate) struct ManifestFound {
     pub manifest_path: PathBuf,
     pub root_manifest: Option<cargo_toml::Manifest<CargoPackageMetadata>>,
     pub target_dir: PathBuf,
+    pub lockfile_path: PathBuf,
     pub manifest: cargo_toml::Manifest<CargoPackageMetadata>,
+    /// All other packages, including the dependencies
+    pub other_packages: Vec<CargoMetadataPackage>,
+    /// Names and versions of the packages that are statically linked into the package
+    pub linked_packages: Vec<(String, String)>,
 }
 
 pub(crate) fn cargo_metadata(root_manifest_path: Option<&Path>, selected_package_name: Option<&str>, cargo_locking_flags: CargoLockingFlags) -> Result<ManifestFound, CargoDebError> {
@@ -351,10 +1057,20 @@ pub(crate) fn cargo_metadata(root_manifest_path: Option<&Path>, selected_package
             .and_then(|root_id| metadata.packages.iter().position(move |p| &p.id == root_id))
         .ok_or_else(|| CargoDebError::NoRootFoundInWorkspace(available_package_names()))
     }?;
+    let proc_macros = metadata.packages.iter()
+        .filter(|p| p.targets.iter().any(|t| t.kind.iter().any(|k| k == "proc-macro")))
+        .map(|p| p.id.as_str())
+        .collect::<Vec<_>>();
+    let linked_ids = linked_package_ids(&metadata.resolve.nodes, &metadata.packages[target_package_pos].id, &proc_macros);
+    let linked_packages = metadata.packages.iter()
+        .filter(|p| linked_ids.contains(p.id.as_str()))
+        .map(|p| (p.name.clone(), p.version.clone()))
+        .collect();
     let target_package = metadata.packages.swap_remove(target_package_pos);
     let workspace_root_manifest_path = Path::new(&metadata.workspace_root).join("Cargo.toml");
     let root_manifest = cargo_toml::Manifest::<CargoPackageMetadata>::from_path_with_metadata(workspace_root_manifest_path).ok();
     let target_dir = metadata.target_directory.into();
+    let lockfile_path = Path::new(&metadata.workspace_root).join("Cargo.lock");
     let manifest_path = Path::new(&target_package.manifest_path);
     let manifest_bytes = fs::read(manifest_path).map_err(|e| CargoDebError::IoFile("unable to read manifest", e, manifest_path.to_owned()))?;
     let mut manifest = cargo_toml::Manifest::<CargoPackageMetadata>::from_slice_with_metadata(&manifest_bytes)
@@ -368,10 +1084,32 @@ pub(crate) fn cargo_metadata(root_manifest_path: Option<&Path>, selected_package
         build_targets: target_package.targets,
         root_manifest,
         target_dir,
+        lockfile_path,
         manifest,
+        other_packages: metadata.packages,
+        linked_packages,
     })
 }
 
+/// Ids of the packages that the `root` package links to: the normal dependencies, at every level.
+///
+/// Build-dependencies, dev-dependencies, and proc macros aren't linked into the binaries, and neither are their dependencies, unless something else needs them.
+fn linked_package_ids<'a>(nodes: &'a [CargoMetadataNode], root: &str, proc_macros: &[&str]) -> HashSet<&'a str> {
+    let mut linked = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        let Some(node) = nodes.iter().find(|n| n.id == id) else { continue };
+        for dep in &node.deps {
+            // old versions of Cargo don't have dep_kinds
+            let normal = dep.dep_kinds.is_empty() || dep.dep_kinds.iter().any(|k| k.kind.is_none());
+            if normal && !proc_macros.contains(&dep.pkg.as_str()) && dep.pkg != root && linked.insert(dep.pkg.as_str()) {
+                stack.push(&dep.pkg);
+            }
+        }
+    }
+    linked
+}
+
 /// Returns the path of the `Cargo.toml` that we want to build.
 fn run_cargo_metadata(manifest_path: Option<&Path>, cargo_locking_flags: CargoLockingFlags) -> CDResult<CargoMetadata> {
     let mut cmd = Command::new("cargo");
@@ -408,11 +1146,86 @@ fn run_cargo_metadata(manifest_path: Option<&Path>, cargo_locking_flags: CargoLo
 mod tests {
     use super::*;
 
+    #[test]
+    fn linked_packages() {
+        let resolve: CargoMetadataResolve = serde_json::from_str(r#"{"root": "app", "nodes": [
+            {"id": "app", "deps": [
+                {"pkg": "serde", "dep_kinds": [{"kind": null, "target": null}]},
+                {"pkg": "cc", "dep_kinds": [{"kind": "build", "target": null}]},
+                {"pkg": "tempfile", "dep_kinds": [{"kind": "dev", "target": null}]}
+            ]},
+            {"id": "serde", "deps": [
+                {"pkg": "serde_derive", "dep_kinds": [{"kind": null, "target": null}]},
+                {"pkg": "autocfg", "dep_kinds": [{"kind": "build", "target": null}]},
+                {"pkg": "libc", "dep_kinds": [{"kind": "build", "target": null}, {"kind": null, "target": "cfg(unix)"}]}
+            ]},
+            {"id": "serde_derive", "deps": [{"pkg": "syn", "dep_kinds": [{"kind": null, "target": null}]}]},
+            {"id": "cc", "deps": [{"pkg": "jobserver", "dep_kinds": [{"kind": null, "target": null}]}]},
+            {"id": "libc", "deps": [{"pkg": "app", "dep_kinds": [{"kind": null, "target": null}]}]}
+        ]}"#).unwrap();
+        let mut linked = linked_package_ids(&resolve.nodes, "app", &["serde_derive"]).into_iter().collect::<Vec<_>>();
+        linked.sort_unstable();
+        assert_eq!(linked, ["libc", "serde"]);
+    }
+
+    #[test]
+    fn maintscript_entries() {
+        let deb: CargoDeb = toml::from_str(r#"
+            maintscript = [
+                { rm-conffile = "/etc/foo/old.conf", prior-version = "1.2-1~" },
+                { mv-conffile = "/etc/foo.conf", to = "/etc/foo/foo.conf" },
+                { dir-to-symlink = "/usr/share/doc/foo", target = "foo-common" },
+                { mv-conffile = "/etc/bar.conf" },
+                { rm-conffile = "etc/baz.conf" },
+            ]
+        "#).unwrap();
+        let args = deb.maintscript.unwrap().iter().map(|e| e.helper_args().map_err(|e| e.to_string())).collect::<Vec<_>>();
+        assert_eq!(args[0].as_deref(), Ok("rm_conffile /etc/foo/old.conf 1.2-1~"));
+        assert_eq!(args[1].as_deref(), Ok("mv_conffile /etc/foo.conf /etc/foo/foo.conf"));
+        assert_eq!(args[2].as_deref(), Ok("dir_to_symlink /usr/share/doc/foo foo-common"));
+        assert!(args[3].is_err());
+        assert!(args[4].is_err());
+    }
+
+    #[test]
+    fn relationship_lists() {
+        let deb: CargoDeb = toml::from_str(r#"
+            breaks = ["old-tool (<< 2.0)", "old-tool-data"]
+            replaces = "old-tool (<< 2.0)"
+            [variants.next]
+            breaks = ["old-tool (<< 3.0)"]
+        "#).unwrap();
+        let mut variants = deb.variants.clone().unwrap();
+        let next = variants.remove("next").unwrap().inherit_from(deb);
+        assert_eq!(next.breaks.unwrap().into_depends_string(), "old-tool (<< 3.0)");
+        assert_eq!(next.replaces.unwrap().into_depends_string(), "old-tool (<< 2.0)");
+
+        let deb: CargoDeb = toml::from_str(r#"provides = ["foo-cli (= 2.1)", "foo-compat"]"#).unwrap();
+        assert_eq!(deb.provides.unwrap().into_depends_string(), "foo-cli (= 2.1), foo-compat");
+
+        let deb: CargoDeb = toml::from_str(r#"enhances = ["foo", "foo-gui (>= 2)"]"#).unwrap();
+        assert_eq!(deb.enhances.unwrap().into_depends_string(), "foo, foo-gui (>= 2)");
+    }
+
+    #[test]
+    fn asset_tables() {
+        let deb: CargoDeb = toml::from_str(r#"
+            assets = [
+                ["target/release/small", "usr/bin/", "755"],
+                { source = "target/release/big", dest = "usr/bin/", mode = "755", separate-debug-symbols = true },
+            ]
+        "#).unwrap();
+        let assets = deb.assets.unwrap();
+        assert_eq!(assets[0].parts(), Some(["target/release/small", "usr/bin/", "755"]));
+        assert_eq!(assets[1].parts(), Some(["target/release/big", "usr/bin/", "755"]));
+        assert!(matches!(&assets[1], AssetEntry::Table(t) if t.separate_debug_symbols == Some(true)));
+    }
+
     #[test]
     fn test_merge_assets() {
         // Test merging assets by dest
-        fn create_test_asset(src: impl Into<String>, dest: impl Into<String>, perm: impl Into<String>) -> Vec<String> {
-            vec![src.into(), dest.into(), perm.into()]
+        fn create_test_asset(src: impl Into<String>, dest: impl Into<String>, perm: impl Into<String>) -> AssetEntry {
+            AssetEntry::Array(vec![src.into(), dest.into(), perm.into()])
         }
 
         // Test merging assets by dest
@@ -434,9 +1247,9 @@ mod tests {
         let merged = variant.inherit_from(parent);
         let mut merged = merged.assets.expect("should have assets");
         let merged_asset = merged.pop().expect("should have an asset");
-        assert_eq!("lib/test_variant/empty.txt", merged_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test/empty.txt", merged_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("655", merged_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test_variant/empty.txt", merged_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test/empty.txt", merged_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("655", merged_asset.parts().unwrap()[2], "should have merged the dest location");
 
         // Test merging assets by src
         let original_asset = create_test_asset(
@@ -457,9 +1270,9 @@ mod tests {
         let merged = variant.inherit_from(parent);
         let mut merged = merged.assets.expect("should have assets");
         let merged_asset = merged.pop().expect("should have an asset");
-        assert_eq!("lib/test/empty.txt", merged_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test_variant/empty.txt", merged_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("655", merged_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test/empty.txt", merged_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test_variant/empty.txt", merged_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("655", merged_asset.parts().unwrap()[2], "should have merged the dest location");
 
         // Test merging assets by appending
         let original_asset = create_test_asset(
@@ -481,14 +1294,14 @@ mod tests {
         let mut merged = merged.assets.expect("should have assets");
 
         let merged_asset = merged.pop().expect("should have an asset");
-        assert_eq!("lib/test/empty.txt", merged_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test_variant/empty.txt", merged_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("655", merged_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test/empty.txt", merged_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test_variant/empty.txt", merged_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("655", merged_asset.parts().unwrap()[2], "should have merged the dest location");
 
         let merged_asset = merged.pop().expect("should have an asset");
-        assert_eq!("lib/test/empty.txt", merged_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test/empty.txt", merged_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("777", merged_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test/empty.txt", merged_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test/empty.txt", merged_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("777", merged_asset.parts().unwrap()[2], "should have merged the dest location");
 
         // Test backwards compatibility for variants that have set assets
         let original_asset = create_test_asset(
@@ -515,29 +1328,49 @@ mod tests {
         let merged = variant.inherit_from(parent);
         let mut merged = merged.assets.expect("should have assets");
         let merged_asset = merged.remove(0);
-        assert_eq!("lib/test_variant/empty.txt", merged_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test/empty.txt", merged_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("655", merged_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test_variant/empty.txt", merged_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test/empty.txt", merged_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("655", merged_asset.parts().unwrap()[2], "should have merged the dest location");
 
         let additional_asset = merged.remove(0);
-        assert_eq!("lib/test/other-empty.txt", additional_asset[0].as_str(), "should have merged the source location");
-        assert_eq!("/opt/test/other-empty.txt", additional_asset[1].as_str(), "should preserve dest location");
-        assert_eq!("655", additional_asset[2].as_str(), "should have merged the dest location");
+        assert_eq!("lib/test/other-empty.txt", additional_asset.parts().unwrap()[0], "should have merged the source location");
+        assert_eq!("/opt/test/other-empty.txt", additional_asset.parts().unwrap()[1], "should preserve dest location");
+        assert_eq!("655", additional_asset.parts().unwrap()[2], "should have merged the dest location");
     }
 }
 
 #[test]
 fn deb_ver() {
     let mut c = cargo_toml::Package::new("test", "1.2.3-1");
-    assert_eq!("1.2.3-1-1", manifest_version_string(&c, None));
-    assert_eq!("1.2.3-1-2", manifest_version_string(&c, Some("2")));
-    assert_eq!("1.2.3-1", manifest_version_string(&c, Some("")));
+    assert_eq!("1.2.3-1-1", manifest_version_string(&c, None, None, true, None));
+    assert_eq!("1.2.3-1-2", manifest_version_string(&c, Some("2"), None, true, None));
+    assert_eq!("1.2.3-1", manifest_version_string(&c, Some(""), None, true, None));
     c.version = cargo_toml::Inheritable::Set("1.2.0-beta.3".into());
-    assert_eq!("1.2.0~beta.3-1", manifest_version_string(&c, None));
-    assert_eq!("1.2.0~beta.3-4", manifest_version_string(&c, Some("4")));
-    assert_eq!("1.2.0~beta.3", manifest_version_string(&c, Some("")));
+    assert_eq!("1.2.0~beta.3-1", manifest_version_string(&c, None, None, true, None));
+    assert_eq!("1.2.0~beta.3-4", manifest_version_string(&c, Some("4"), None, true, None));
+    assert_eq!("1.2.0~beta.3", manifest_version_string(&c, Some(""), None, true, None));
     c.version = cargo_toml::Inheritable::Set("1.2.0-new".into());
-    assert_eq!("1.2.0-new-1", manifest_version_string(&c, None));
-    assert_eq!("1.2.0-new-11", manifest_version_string(&c, Some("11")));
-    assert_eq!("1.2.0-new", manifest_version_string(&c, Some("")));
+    assert_eq!("1.2.0-new-1", manifest_version_string(&c, None, None, true, None));
+    assert_eq!("1.2.0-new-11", manifest_version_string(&c, Some("11"), None, true, None));
+    assert_eq!("1.2.0-new", manifest_version_string(&c, Some(""), None, true, None));
+    c.version = cargo_toml::Inheritable::Set("1.2.0-rc".into());
+    assert_eq!("1.2.0~rc-1", manifest_version_string(&c, None, None, true, None));
+    assert_eq!("2:1.2.0~rc-1", manifest_version_string(&c, None, Some(2), true, None));
+    assert_eq!("1.2.0-rc-1", manifest_version_string(&c, None, None, false, None));
+    assert_eq!("1:1.2.0-rc", manifest_version_string(&c, Some(""), Some(1), false, None));
+    assert_eq!("1.2.0~rc+git12-1", manifest_version_string(&c, None, None, true, Some("{crate_version}+git12")));
+}
+
+#[test]
+fn version_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| assert!(Command::new("git").current_dir(dir.path()).args(args).env("GIT_COMMITTER_DATE", "2024-03-01T12:00:00Z").output().unwrap().status.success());
+    git(&["init", "-q"]);
+    for message in ["first", "second"] {
+        git(&["-c", "user.name=a", "-c", "user.email=a@example.com", "commit", "-q", "--allow-empty", "-m", message]);
+    }
+    assert_eq!(expand_git_placeholders("{crate_version}+git{commit_count}.{commits_since_tag}", dir.path()).unwrap(), "{crate_version}+git2.2");
+    assert_eq!(expand_git_placeholders("{commit_date}", dir.path()).unwrap(), "20240301120000");
+    assert_eq!(expand_git_placeholders("{short_sha}", dir.path()).unwrap().len(), 7);
+    assert!(expand_git_placeholders("{nope}", dir.path()).is_err());
 }
@@ -0,0 +1,273 @@
+//! Software bill of materials of the crates that are statically linked into the package,
+//! in [CycloneDX](https://cyclonedx.org/docs/1.5/json/) or [SPDX](https://spdx.github.io/spdx-spec/v2.3/) JSON.
+//!
+//! The crates are from `Cargo.lock`, with their licenses and authors from `cargo metadata`.
+use crate::config::PackageConfig;
+use crate::dependencies::LockedPackage;
+use crate::licenses::CrateLicense;
+use crate::parse::manifest::{SbomConfig, SbomFormat};
+use crate::util::rfc3339_date;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// A crate in the SBOM
+#[derive(Debug, Clone)]
+pub(crate) struct Component {
+    pub name: String,
+    pub version: String,
+    /// Like `registry+https://github.com/rust-lang/crates.io-index`, `None` for path dependencies
+    pub source: Option<String>,
+    /// SHA-256 of the `.crate` file
+    pub checksum: Option<String>,
+    pub license: Option<String>,
+    pub authors: Vec<String>,
+    /// Indices of the crates it depends on
+    pub dependencies: Vec<usize>,
+}
+
+/// The `sbom` setting, and the crates that go in it
+#[derive(Debug, Clone)]
+pub(crate) struct Sbom {
+    pub format: SbomFormat,
+    /// Put it in `/usr/share/doc/<package>/`
+    pub install: bool,
+    /// Write it next to the `.deb` file
+    pub output: bool,
+    pub components: Vec<Component>,
+    /// Indices of the package's own dependencies
+    pub direct: Vec<usize>,
+}
+
+impl Sbom {
+    pub(crate) fn new(config: &SbomConfig) -> Self {
+        Self {
+            format: config.format.unwrap_or_default(),
+            install: config.install.unwrap_or(true),
+            output: config.output.unwrap_or(false),
+            components: Vec::new(),
+            direct: Vec::new(),
+        }
+    }
+
+    /// The `linked` crates, and which of them are in the package's `direct_dependencies`
+    pub(crate) fn set_components(&mut self, linked: &[LockedPackage], licenses: &[CrateLicense], direct_dependencies: &[String]) {
+        let find = |dep: &str| {
+            let mut parts = dep.split(' ');
+            let (name, version) = (parts.next().unwrap_or_default(), parts.next());
+            linked.iter().position(|p| p.name == name && version.map_or(true, |v| p.version == v))
+        };
+        self.components = linked.iter().map(|p| {
+            let license = licenses.iter().find(|l| l.name == p.name && l.version == p.version);
+            let mut dependencies = p.dependencies.iter().filter_map(|d| find(d)).collect::<Vec<_>>();
+            dependencies.sort_unstable();
+            dependencies.dedup();
+            Component {
+                name: p.name.clone(),
+                version: p.version.clone(),
+                source: p.source.clone(),
+                checksum: p.checksum.clone(),
+                license: license.and_then(|l| l.license.clone()),
+                authors: license.map(|l| l.authors.clone()).unwrap_or_default(),
+                dependencies,
+            }
+        }).collect();
+        self.direct = linked.iter().enumerate()
+            .filter(|(_, p)| direct_dependencies.contains(&p.name))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// `sbom.cdx.json` or `sbom.spdx.json`
+    pub(crate) fn file_name(&self) -> &'static str {
+        match self.format {
+            SbomFormat::Cyclonedx => "sbom.cdx.json",
+            SbomFormat::Spdx => "sbom.spdx.json",
+        }
+    }
+
+    /// `<package>_<version>_<arch>.cdx.json` for the `.deb` file
+    pub(crate) fn path_next_to(&self, deb: &Path) -> PathBuf {
+        deb.with_extension(self.file_name().trim_start_matches("sbom."))
+    }
+
+    pub(crate) fn generate(&self, package_deb: &PackageConfig) -> Vec<u8> {
+        let document = match self.format {
+            SbomFormat::Cyclonedx => self.cyclonedx(package_deb),
+            SbomFormat::Spdx => self.spdx(package_deb),
+        };
+        let mut out = serde_json::to_vec_pretty(&document).unwrap_or_default();
+        out.push(b'\n');
+        out
+    }
+
+    fn cyclonedx(&self, package_deb: &PackageConfig) -> Value {
+        let root_ref = format!("{}@{}", package_deb.deb_name, package_deb.deb_version);
+        let mut root = json!({
+            "type": "application",
+            "bom-ref": root_ref,
+            "name": package_deb.deb_name,
+            "version": package_deb.deb_version,
+            "description": package_deb.description,
+        });
+        if let Some(license) = package_deb.license.as_deref() {
+            root["licenses"] = json!([{ "expression": spdx_expression(license) }]);
+        }
+        let components = self.components.iter().map(|c| {
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": purl(c),
+                "name": c.name,
+                "version": c.version,
+                "purl": purl(c),
+            });
+            if !c.authors.is_empty() {
+                component["author"] = c.authors.join(", ").into();
+            }
+            if let Some(license) = c.license.as_deref() {
+                component["licenses"] = json!([{ "expression": spdx_expression(license) }]);
+            }
+            if let Some(checksum) = &c.checksum {
+                component["hashes"] = json!([{ "alg": "SHA-256", "content": checksum }]);
+            }
+            if let Some(url) = c.source.as_deref().and_then(|s| s.strip_prefix("git+")) {
+                component["externalReferences"] = json!([{ "type": "vcs", "url": url }]);
+            }
+            component
+        }).collect::<Vec<_>>();
+        let mut dependencies = vec![json!({ "ref": root_ref, "dependsOn": self.direct.iter().map(|&i| purl(&self.components[i])).collect::<Vec<_>>() })];
+        dependencies.extend(self.components.iter().map(|c| {
+            json!({ "ref": purl(c), "dependsOn": c.dependencies.iter().map(|&i| purl(&self.components[i])).collect::<Vec<_>>() })
+        }));
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "version": 1,
+            "metadata": {
+                "timestamp": rfc3339_date(package_deb.default_timestamp),
+                "tools": { "components": [{ "type": "application", "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") }] },
+                "component": root,
+            },
+            "components": components,
+            "dependencies": dependencies,
+        })
+    }
+
+    fn spdx(&self, package_deb: &PackageConfig) -> Value {
+        let root_id = format!("SPDXRef-Package-{}", spdx_id_chars(&package_deb.deb_name));
+        let component_id = |c: &Component| format!("SPDXRef-Package-crate-{}-{}", spdx_id_chars(&c.name), spdx_id_chars(&c.version));
+        let mut packages = vec![json!({
+            "SPDXID": root_id,
+            "name": package_deb.deb_name,
+            "versionInfo": package_deb.deb_version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": package_deb.license.as_deref().map_or_else(|| "NOASSERTION".into(), spdx_expression),
+            "copyrightText": "NOASSERTION",
+            "summary": package_deb.description,
+            "primaryPackagePurpose": "APPLICATION",
+        })];
+        packages.extend(self.components.iter().map(|c| {
+            let mut package = json!({
+                "SPDXID": component_id(c),
+                "name": c.name,
+                "versionInfo": c.version,
+                "downloadLocation": download_location(c),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": c.license.as_deref().map_or_else(|| "NOASSERTION".into(), spdx_expression),
+                "copyrightText": "NOASSERTION",
+                "primaryPackagePurpose": "LIBRARY",
+                "externalRefs": [{ "referenceCategory": "PACKAGE-MANAGER", "referenceType": "purl", "referenceLocator": purl(c) }],
+            });
+            if let Some(checksum) = &c.checksum {
+                package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": checksum }]);
+            }
+            package
+        }));
+        let mut relationships = vec![json!({ "spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": root_id })];
+        let depends_on = |from: String, to: &[usize]| {
+            to.iter().map(|&i| json!({ "spdxElementId": from, "relationshipType": "DEPENDS_ON", "relatedSpdxElement": component_id(&self.components[i]) })).collect::<Vec<_>>()
+        };
+        relationships.extend(depends_on(root_id.clone(), &self.direct));
+        for c in &self.components {
+            relationships.extend(depends_on(component_id(c), &c.dependencies));
+        }
+        let name = format!("{}_{}_{}", package_deb.deb_name, package_deb.deb_version, package_deb.architecture);
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": name,
+            "documentNamespace": format!("https://spdx.org/spdxdocs/{}", spdx_id_chars(&name)),
+            "creationInfo": {
+                "created": rfc3339_date(package_deb.default_timestamp),
+                "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+}
+
+/// Package URL, like `pkg:cargo/serde@1.0.0`
+fn purl(c: &Component) -> String {
+    format!("pkg:cargo/{}@{}", c.name, c.version.replace('+', "%2B"))
+}
+
+/// The old `MIT/Apache-2.0` syntax of Cargo isn't valid SPDX
+fn spdx_expression(license: &str) -> String {
+    license.split('/').map(str::trim).collect::<Vec<_>>().join(" OR ")
+}
+
+/// SPDX identifiers can only have letters, digits, `.` and `-`
+fn spdx_id_chars(s: &str) -> String {
+    s.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' }).collect()
+}
+
+fn download_location(c: &Component) -> String {
+    match c.source.as_deref() {
+        Some(s) if s.ends_with("crates.io-index") || s == "sparse+https://index.crates.io/" => {
+            format!("https://crates.io/api/v1/crates/{}/{}/download", c.name, c.version)
+        },
+        Some(s) if s.starts_with("git+") => s.to_owned(),
+        _ => "NOASSERTION".into(),
+    }
+}
+
+#[test]
+fn sbom_documents() {
+    let mut listener = crate::listener::MockListener::new();
+    listener.expect_warning().return_const(());
+    listener.expect_info().return_const(());
+    let (_, package_deb) = crate::config::Config::from_manifest(Some(Path::new("Cargo.toml")), None, None, None, None, Default::default(), None, None, None, None, None, None, Default::default(), &listener).unwrap();
+    let locked = |name: &str, version: &str, dependencies: &[&str]| LockedPackage {
+        name: name.into(),
+        version: version.into(),
+        source: Some("registry+https://github.com/rust-lang/crates.io-index".into()),
+        checksum: Some("abc".into()),
+        dependencies: dependencies.iter().map(|&d| d.into()).collect(),
+    };
+    let linked = [locked("a", "1.0.0+x", &["b"]), locked("b", "2.0.0", &["proc-macro-only"])];
+    let licenses = [CrateLicense { name: "a".into(), version: "1.0.0+x".into(), authors: vec!["A".into()], license: Some("MIT/Apache-2.0".into()), texts: Default::default(), files: vec![] }];
+    let mut sbom = Sbom::new(&SbomConfig::default());
+    sbom.set_components(&linked, &licenses, &["a".into()]);
+    assert_eq!(sbom.components[0].dependencies, [1]);
+    assert!(sbom.components[1].dependencies.is_empty());
+    assert_eq!(sbom.path_next_to(Path::new("out/pkg_1.0-1_amd64.deb")), Path::new("out/pkg_1.0-1_amd64.cdx.json"));
+
+    let cdx: Value = serde_json::from_slice(&sbom.generate(&package_deb)).unwrap();
+    assert_eq!(cdx["bomFormat"], "CycloneDX");
+    assert_eq!(cdx["components"][0]["purl"], "pkg:cargo/a@1.0.0%2Bx");
+    assert_eq!(cdx["components"][0]["licenses"][0]["expression"], "MIT OR Apache-2.0");
+    assert_eq!(cdx["components"][1]["hashes"][0]["content"], "abc");
+    assert_eq!(cdx["dependencies"][0]["dependsOn"], json!(["pkg:cargo/a@1.0.0%2Bx"]));
+    assert_eq!(cdx["dependencies"][1]["dependsOn"], json!(["pkg:cargo/b@2.0.0"]));
+
+    sbom.format = SbomFormat::Spdx;
+    let spdx: Value = serde_json::from_slice(&sbom.generate(&package_deb)).unwrap();
+    assert_eq!(spdx["packages"][1]["SPDXID"], "SPDXRef-Package-crate-a-1.0.0-x");
+    assert_eq!(spdx["packages"][2]["licenseDeclared"], "NOASSERTION");
+    assert_eq!(spdx["packages"][2]["downloadLocation"], "https://crates.io/api/v1/crates/b/2.0.0/download");
+    assert_eq!(spdx["relationships"].as_array().unwrap().len(), 3);
+}
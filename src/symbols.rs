@@ -0,0 +1,271 @@
+//! Reads `symbols` files of installed library packages, see [deb-symbols(5)](https://manpages.debian.org/deb-symbols.5),
+//! for the minimum versions of the libraries that have the symbols a binary uses, like `dpkg-shlibdeps` does.
+//!
+//! Libraries without `symbols` files are looked up in the `shlibs` files, see [deb-shlibs(5)](https://manpages.debian.org/deb-shlibs.5),
+//! and in the lists of files of the installed packages.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where dpkg keeps its database, relative to the root of the system. Packages' files are in its `info` dir.
+pub(crate) const DPKG_ADMIN_DIR: &str = "var/lib/dpkg";
+
+/// Library packages installed in the system at a root dir, which is `/` or a sysroot for cross-compilation
+#[derive(Debug, Default)]
+pub(crate) struct InstalledLibraries {
+    info_dir: PathBuf,
+    architecture: String,
+    symbols: Vec<LibrarySymbols>,
+    shlibs: Vec<Shlibs>,
+}
+
+impl InstalledLibraries {
+    /// Libraries for the Debian `architecture` in the dpkg database of the system at `root`
+    pub(crate) fn read(root: &Path, architecture: &str) -> Self {
+        let info_dir = root.join(DPKG_ADMIN_DIR).join("info");
+        Self {
+            symbols: info_files(&info_dir, architecture, "symbols").flat_map(|(_, text)| parse_symbols(&text)).collect(),
+            shlibs: info_files(&info_dir, architecture, "shlibs").flat_map(|(_, text)| parse_shlibs(&text)).collect(),
+            info_dir,
+            architecture: architecture.into(),
+        }
+    }
+
+    /// The package with the library, and the minimum version that has the `used` symbols (`name@version`)
+    pub(crate) fn dependency<'a>(&self, soname: &str, used: impl IntoIterator<Item = &'a str>) -> Option<(String, Option<String>)> {
+        if let Some(library) = self.symbols.iter().find(|lib| lib.soname == soname) {
+            return Some(library.dependency(used));
+        }
+        if let Some(library) = self.shlibs.iter().find(|lib| lib.matches(soname)) {
+            return Some(library.dependency());
+        }
+        // file lists are big, so they're only read for libraries that aren't in the other files
+        info_files(&self.info_dir, &self.architecture, "list")
+            .find(|(_, list)| list.lines().any(|file| file.contains("/lib") && file.rsplit('/').next() == Some(soname)))
+            .map(|(package, _)| (package, None))
+    }
+}
+
+/// A library in a `shlibs` file
+#[derive(Debug)]
+struct Shlibs {
+    name: String,
+    version: String,
+    /// Like `libfoo1 (>= 1.2)`
+    dependency: String,
+}
+
+impl Shlibs {
+    /// `libfoo.so.1` is `libfoo 1`, and `libfoo-1.2.so` is `libfoo 1.2`
+    fn matches(&self, soname: &str) -> bool {
+        soname.strip_prefix(self.name.as_str()).is_some_and(|rest| {
+            rest.strip_prefix(".so.") == Some(self.version.as_str())
+                || rest.strip_prefix('-').and_then(|v| v.strip_suffix(".so")) == Some(self.version.as_str())
+        })
+    }
+
+    /// The version is split off simple dependencies, so it can be merged with other minimum versions
+    fn dependency(&self) -> (String, Option<String>) {
+        match self.dependency.strip_suffix(')').and_then(|dep| dep.split_once(" (>= ")) {
+            Some((package, version)) if !package.contains([' ', ',', '|']) => (package.into(), Some(version.into())),
+            _ => (self.dependency.clone(), None),
+        }
+    }
+}
+
+/// Symbols of one library in a `symbols` file
+#[derive(Debug, Default)]
+pub(crate) struct LibrarySymbols {
+    pub soname: String,
+    /// Like `libc6 #MINVER#`
+    template: String,
+    /// `name@version`, and the version of the package that added it
+    symbols: HashMap<String, String>,
+    /// Minimum versions for all symbols of a version node, from `(symver)` patterns
+    symbol_versions: HashMap<String, String>,
+}
+
+impl LibrarySymbols {
+    /// The package name from the template, and the highest version of the `used` symbols (`name@version`)
+    pub(crate) fn dependency<'a>(&self, used: impl IntoIterator<Item = &'a str>) -> (String, Option<String>) {
+        let Some((name, _)) = self.template.split_once("#MINVER#") else {
+            // a fixed dependency, like `libfoo1 (>= 1.2)`
+            return (self.template.clone(), None);
+        };
+        let min_version = used.into_iter()
+            .filter_map(|symbol| {
+                let version = symbol.rsplit_once('@').map(|(_, v)| v).unwrap_or_default();
+                self.symbols.get(symbol).or_else(|| self.symbol_versions.get(version))
+            })
+            // 0 means the symbol has always been there
+            .filter(|v| *v != "0")
+            .max_by(|a, b| compare_versions(a, b));
+        (name.trim().to_owned(), min_version.cloned())
+    }
+}
+
+/// Package names and contents of `<package>[:<arch>].<extension>` files in the dpkg `info` dir for the Debian `architecture`
+fn info_files<'a>(dir: &Path, architecture: &'a str, extension: &'a str) -> impl Iterator<Item = (String, String)> + 'a {
+    fs::read_dir(dir).into_iter().flatten().flatten().filter_map(move |entry| {
+        let path = entry.path();
+        let stem = path.file_name()?.to_str()?.strip_suffix(extension)?.strip_suffix('.')?;
+        // `Multi-Arch: same` packages have the architecture in the name
+        let package = match stem.split_once(':') {
+            Some((_, arch)) if arch != architecture => return None,
+            Some((package, _)) => package,
+            None => stem,
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Some((package.to_owned(), text)),
+            Err(e) => {
+                log::debug!("can't read {}: {e}", path.display());
+                None
+            },
+        }
+    })
+}
+
+/// `[type:] library version dependencies` lines. Only the default type is for .deb packages, `udeb:` is for the installer.
+fn parse_shlibs(text: &str) -> Vec<Shlibs> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, char::is_whitespace);
+            let (name, version, dependency) = (fields.next()?, fields.next()?, fields.next()?.trim());
+            if name.ends_with(':') || dependency.is_empty() {
+                return None;
+            }
+            Some(Shlibs { name: name.into(), version: version.into(), dependency: dependency.into() })
+        })
+        .collect()
+}
+
+fn parse_symbols(text: &str) -> Vec<LibrarySymbols> {
+    let mut libraries = Vec::<LibrarySymbols>::new();
+    for line in text.lines() {
+        if let Some(symbol_line) = line.strip_prefix(' ') {
+            let Some(library) = libraries.last_mut() else { continue };
+            let mut fields = symbol_line.split_whitespace();
+            let (Some(symbol), Some(min_version)) = (fields.next(), fields.next()) else { continue };
+            // symbols with alternative dependency templates are private
+            if fields.next().is_some_and(|id| id != "0") {
+                continue;
+            }
+            match symbol.strip_prefix('(').and_then(|s| s.split_once(')')) {
+                Some((tags, version)) if tags.split('|').any(|t| t == "symver") => {
+                    library.symbol_versions.insert(version.to_owned(), min_version.to_owned());
+                },
+                // c++ and regex patterns aren't supported
+                Some((tags, _)) if tags.split('|').any(|t| t == "c++" || t == "regex") => {},
+                Some((_, symbol)) => { library.symbols.insert(symbol.to_owned(), min_version.to_owned()); },
+                None => { library.symbols.insert(symbol.to_owned(), min_version.to_owned()); },
+            }
+        } else if line.starts_with(['|', '*', '#']) || line.trim().is_empty() {
+            continue;
+        } else if let Some((soname, template)) = line.split_once(' ') {
+            libraries.push(LibrarySymbols { soname: soname.into(), template: template.trim().into(), ..LibrarySymbols::default() });
+        }
+    }
+    libraries
+}
+
+/// Orders Debian versions like `dpkg --compare-versions`
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    let ((epoch_a, upstream_a, revision_a), (epoch_b, upstream_b, revision_b)) = (split_version(a), split_version(b));
+    epoch_a.cmp(&epoch_b)
+        .then_with(|| compare_version_parts(upstream_a, upstream_b))
+        .then_with(|| compare_version_parts(revision_a, revision_b))
+}
+
+/// Epoch, upstream version, and Debian revision
+pub(crate) fn split_version(version: &str) -> (u32, &str, &str) {
+    let (epoch, rest) = version.split_once(':').map_or((0, version), |(e, rest)| (e.parse().unwrap_or(0), rest));
+    let (upstream, revision) = rest.rsplit_once('-').unwrap_or((rest, ""));
+    (epoch, upstream, revision)
+}
+
+/// `verrevcmp` of dpkg, where `~` sorts before anything, even the end
+fn compare_version_parts(a: &str, b: &str) -> Ordering {
+    let order = |c: Option<&u8>| match c {
+        None => 0,
+        Some(b'~') => -1,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(&c) if c.is_ascii_alphabetic() => i32::from(c),
+        Some(&c) => i32::from(c) + 256,
+    };
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    while !a.is_empty() || !b.is_empty() {
+        while a.first().is_some_and(|c| !c.is_ascii_digit()) || b.first().is_some_and(|c| !c.is_ascii_digit()) {
+            let (order_a, order_b) = (order(a.first()), order(b.first()));
+            if order_a != order_b {
+                return order_a.cmp(&order_b);
+            }
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+        let digits = |s: &[u8]| s.iter().take_while(|c| c.is_ascii_digit()).count();
+        let (digits_a, digits_b) = (digits(a), digits(b));
+        let (number_a, number_b) = (&a[..digits_a], &b[..digits_b]);
+        let (number_a, number_b) = (trim_zeros(number_a), trim_zeros(number_b));
+        let ordering = number_a.len().cmp(&number_b.len()).then_with(|| number_a.cmp(number_b));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = &a[digits_a..];
+        b = &b[digits_b..];
+    }
+    Ordering::Equal
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+#[test]
+fn version_order() {
+    assert_eq!(compare_versions("2.34", "2.4"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0~rc1", "1.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0", "1.0+b1"), Ordering::Less);
+    assert_eq!(compare_versions("1:0.1", "2.0"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0-2", "1.0-10"), Ordering::Less);
+    assert_eq!(compare_versions("1.01", "1.1"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0a", "1.0"), Ordering::Greater);
+}
+
+#[test]
+fn symbols_min_version() {
+    let libraries = parse_symbols("libc.so.6 libc6 #MINVER#\n| libc6 (>> 2.36), libc6 (<< 2.37)\n* Build-Depends-Package: libc-dev\n GLIBC_2.2.5@GLIBC_2.2.5 2.2.5\n malloc@GLIBC_2.2.5 2.2.5\n __libc_start_main@GLIBC_2.34 2.34\n _dl_private@GLIBC_PRIVATE 0 1\nlibfoo.so.1 libfoo1 (>= 1.2)\n foo@Base 1.0\n");
+    assert_eq!(libraries.len(), 2);
+    let libc = &libraries[0];
+    assert_eq!(libc.soname, "libc.so.6");
+    assert!(!libc.symbols.contains_key("_dl_private@GLIBC_PRIVATE"));
+    assert_eq!(libc.dependency(["malloc@GLIBC_2.2.5", "__libc_start_main@GLIBC_2.34"]), ("libc6".into(), Some("2.34".into())));
+    assert_eq!(libc.dependency(["unknown@GLIBC_2.2.5"]), ("libc6".into(), None));
+    assert_eq!(libraries[1].dependency(["foo@Base"]), ("libfoo1 (>= 1.2)".into(), None));
+
+    let symver = parse_symbols("libc.so.6 libc6 #MINVER#\n (symver)GLIBC_2.38 2.38\n");
+    assert_eq!(symver[0].dependency(["strlcpy@GLIBC_2.38"]), ("libc6".into(), Some("2.38".into())));
+}
+
+#[test]
+fn sysroot_libraries() {
+    let root = tempfile::tempdir().unwrap();
+    let info = root.path().join(DPKG_ADMIN_DIR).join("info");
+    fs::create_dir_all(&info).unwrap();
+    fs::write(info.join("libacl1:arm64.shlibs"), "libacl 1 libacl1 (>= 2.3.1)\nudeb: libacl 1 libacl1-udeb (>= 2.3.1)\n").unwrap();
+    fs::write(info.join("libfoo-1.2:arm64.shlibs"), "# comment\nlibfoo 1.2 libfoo-1.2 (>= 1.2) | libfoo-dev\n").unwrap();
+    fs::write(info.join("libbar2:arm64.list"), "/.\n/usr/lib/aarch64-linux-gnu/libbar.so.2\n").unwrap();
+    fs::write(info.join("libbar2:amd64.list"), "/usr/lib/x86_64-linux-gnu/libbar.so.2\n").unwrap();
+    fs::write(info.join("libc6:amd64.symbols"), "libc.so.6 libc6 #MINVER#\n malloc@GLIBC_2.2.5 2.2.5\n").unwrap();
+    fs::write(info.join("libc6:arm64.symbols"), "libc.so.6 libc6 #MINVER#\n malloc@GLIBC_2.17 2.17\n").unwrap();
+
+    let libraries = InstalledLibraries::read(root.path(), "arm64");
+    assert_eq!(libraries.dependency("libc.so.6", ["malloc@GLIBC_2.17"]), Some(("libc6".into(), Some("2.17".into()))));
+    assert_eq!(libraries.dependency("libacl.so.1", []), Some(("libacl1".into(), Some("2.3.1".into()))));
+    assert_eq!(libraries.dependency("libfoo-1.2.so", []), Some(("libfoo-1.2 (>= 1.2) | libfoo-dev".into(), None)));
+    assert_eq!(libraries.dependency("libbar.so.2", []), Some(("libbar2".into(), None)));
+    assert_eq!(libraries.dependency("libbaz.so.1", []), None);
+    assert!(InstalledLibraries::read(root.path(), "amd64").dependency("libc.so.6", ["malloc@GLIBC_2.17"]).is_some_and(|(_, v)| v.is_none()));
+}
@@ -0,0 +1,90 @@
+//! Gettext message catalogs, installed as `/usr/share/locale/<language>/LC_MESSAGES/<domain>.mo`.
+//!
+//! Catalogs are `<language>.po` or `<language>.mo` files in one directory, like `po/` of projects using gettext.
+//! `.po` files are compiled with `msgfmt`.
+//!
+//! See [msgfmt(1)](https://manpages.debian.org/msgfmt.1).
+use crate::error::{CDResult, CargoDebError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) const LOCALE_DIR: &str = "usr/share/locale";
+
+/// Where the catalog of the language is installed
+pub(crate) fn target_path(language: &str, domain: &str) -> PathBuf {
+    Path::new(LOCALE_DIR).join(language).join("LC_MESSAGES").join(format!("{domain}.mo"))
+}
+
+/// Catalogs in the directory by their language, only of the `languages` if given.
+/// Compiled `.mo` files are preferred over `.po` files of the same language.
+pub(crate) fn catalogs(dir: &Path, languages: Option<&[String]>) -> CDResult<BTreeMap<String, PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|e| CargoDebError::IoFile("unable to read translations dir", e, dir.to_owned()))?;
+    let mut catalogs = BTreeMap::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        let Some((language, extension)) = path.file_name().and_then(|f| f.to_str()).and_then(|f| f.rsplit_once('.')) else { continue };
+        if !matches!(extension, "po" | "mo") || languages.is_some_and(|l| !l.iter().any(|l| l == language)) {
+            continue;
+        }
+        if !is_language(language) {
+            return Err(CargoDebError::InvalidField("translations", path.display().to_string(), "the file name must be a language code, like de.po or pt_BR.po"));
+        }
+        let prefer = extension == "mo";
+        catalogs.entry(language.to_owned())
+            .and_modify(|p: &mut PathBuf| if prefer { p.clone_from(&path) })
+            .or_insert(path);
+    }
+    Ok(catalogs)
+}
+
+/// Like `de`, `pt_BR`, `sr@latin`, or `ca_ES.UTF-8`
+fn is_language(name: &str) -> bool {
+    let (name, modifier) = name.split_once('@').unwrap_or((name, "latin"));
+    let (name, _codeset) = name.split_once('.').unwrap_or((name, ""));
+    let (language, territory) = name.split_once('_').unwrap_or((name, "US"));
+    (2..=3).contains(&language.len()) && language.bytes().all(|c| c.is_ascii_lowercase())
+        && (2..=3).contains(&territory.len()) && territory.bytes().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !modifier.is_empty() && modifier.bytes().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Contents of the `.mo` file for the `.po` file
+pub(crate) fn msgfmt(path: &Path) -> CDResult<Vec<u8>> {
+    let output = Command::new("msgfmt")
+        .args(["--check", "--output-file=-"])
+        .arg(path)
+        .output()
+        .map_err(|e| CargoDebError::CommandFailed(e, "msgfmt (from the gettext package)"))?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("msgfmt", path.display().to_string(), output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+#[test]
+fn language_codes() {
+    assert!(is_language("de"));
+    assert!(is_language("pt_BR"));
+    assert!(is_language("sr@latin"));
+    assert!(is_language("ca_ES.UTF-8"));
+    assert!(is_language("es_419"));
+    assert!(!is_language("messages"));
+    assert!(!is_language("de-DE"));
+    assert!(!is_language("DE"));
+    assert_eq!(target_path("pt_BR", "example"), Path::new("usr/share/locale/pt_BR/LC_MESSAGES/example.mo"));
+}
+
+#[test]
+fn finds_catalogs() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["de.po", "fr.po", "fr.mo", "pt_BR.po", "example.pot", "README"] {
+        fs::write(dir.path().join(name), "").unwrap();
+    }
+    let all = catalogs(dir.path(), None).unwrap();
+    assert_eq!(all.keys().collect::<Vec<_>>(), ["de", "fr", "pt_BR"]);
+    assert_eq!(all["fr"], dir.path().join("fr.mo"));
+    let some = catalogs(dir.path(), Some(&["fr".into(), "pt_BR".into()])).unwrap();
+    assert_eq!(some.keys().collect::<Vec<_>>(), ["fr", "pt_BR"]);
+    fs::write(dir.path().join("de-DE.po"), "").unwrap();
+    assert!(catalogs(dir.path(), None).is_err());
+}
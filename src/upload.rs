@@ -0,0 +1,368 @@
+use crate::config::PackageConfig;
+use crate::error::{CDResult, CargoDebError};
+use crate::listener::Listener;
+use crate::parse::manifest::{AptlyUpload, ArtifactoryUpload, DputUpload, PackagecloudUpload, S3Upload, SftpUpload, UploadTarget};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs};
+
+/// Publishes the packages to the repositories selected with `--upload`.
+///
+/// The HTTP APIs are called with `curl`, and the other backends use `sftp` and the `aws` CLI,
+/// so that their usual configuration and credentials apply.
+pub(crate) struct Uploader {
+    targets: Vec<(String, UploadTarget)>,
+    name: String,
+    version: String,
+    architecture: String,
+}
+
+/// Looks for errors that the API reports in a successful response
+type OutputCheck = fn(&[u8]) -> Result<(), String>;
+
+/// One command of an upload
+struct Step {
+    program: &'static str,
+    args: Vec<OsString>,
+    /// Credentials are passed to `curl` as a config file on stdin, so that they're not visible in the process list
+    stdin: String,
+    check_output: Option<OutputCheck>,
+    /// Config file that must exist until the command runs
+    _temp_file: Option<tempfile::TempPath>,
+}
+
+impl Uploader {
+    /// Targets in `config_file` take precedence over the ones in `[package.metadata.deb.upload]`
+    pub(crate) fn new(names: &[String], config_file: Option<&Path>, package_deb: &PackageConfig) -> CDResult<Self> {
+        let mut available = package_deb.upload_targets.clone();
+        if let Some(path) = config_file {
+            let toml = fs::read_to_string(path).map_err(|e| CargoDebError::IoFile("unable to read upload config", e, path.to_owned()))?;
+            let targets = toml::from_str::<HashMap<String, UploadTarget>>(&toml).map_err(|e| CargoDebError::UploadConfigParsing(Box::new(e), path.to_owned()))?;
+            available.extend(targets);
+        }
+        let targets = names.iter().map(|name| {
+            let target = available.get(name).cloned().ok_or_else(|| {
+                let mut available = available.keys().map(String::as_str).collect::<Vec<_>>();
+                available.sort_unstable();
+                CargoDebError::UploadTargetNotFound(name.clone(), if available.is_empty() { "none".into() } else { available.join(", ") })
+            })?;
+            Ok((name.clone(), target))
+        }).collect::<CDResult<_>>()?;
+        Ok(Self {
+            targets,
+            name: package_deb.deb_name.clone(),
+            version: package_deb.deb_version.clone(),
+            architecture: package_deb.architecture.clone(),
+        })
+    }
+
+    /// `dput` uploads the `.changes` file, so it has to be written even without `--changes`
+    pub(crate) fn needs_changes(&self) -> bool {
+        self.targets.iter().any(|(_, t)| matches!(t, UploadTarget::Dput(_)))
+    }
+
+    /// Repository APIs get only the `debs`. SFTP and S3 get `all_files`, which include the `.changes` and `.buildinfo` files.
+    ///
+    /// `signed` is whether the `.changes` file has been signed, which `dput` checks.
+    pub(crate) fn upload(&self, debs: &[PathBuf], all_files: &[PathBuf], signed: bool, listener: &dyn Listener) -> CDResult<()> {
+        for (target_name, target) in &self.targets {
+            let steps = self.steps(target, debs, all_files, signed).map_err(|e| CargoDebError::UploadFailed(target_name.clone(), e))?;
+            for step in steps {
+                step.run(target_name)?;
+            }
+            listener.info(format!("Uploaded {} {} to {target_name}", self.name, self.version));
+        }
+        Ok(())
+    }
+
+    fn steps(&self, target: &UploadTarget, debs: &[PathBuf], all_files: &[PathBuf], signed: bool) -> Result<Vec<Step>, String> {
+        Ok(match target {
+            UploadTarget::Aptly(aptly) => self.aptly_steps(aptly, debs)?,
+            UploadTarget::Artifactory(artifactory) => self.artifactory_steps(artifactory, debs)?,
+            UploadTarget::Packagecloud(packagecloud) => packagecloud_steps(packagecloud, debs)?,
+            UploadTarget::Sftp(sftp) => vec![sftp_step(sftp, all_files)],
+            UploadTarget::S3(s3) => all_files.iter().map(|file| s3_step(s3, file)).collect(),
+            UploadTarget::Dput(dput) => vec![dput_step(dput, all_files, signed)?],
+        })
+    }
+
+    fn aptly_steps(&self, aptly: &AptlyUpload, debs: &[PathBuf]) -> Result<Vec<Step>, String> {
+        let credentials = match &aptly.user {
+            Some(user) => curl_user(user, &secret(aptly.password_env.as_deref().unwrap_or("APTLY_PASSWORD"))?),
+            None => String::new(),
+        };
+        let api = format!("{}/api", aptly.url.trim_end_matches('/'));
+        let dir = url_encode(&format!("cargo-deb-{}_{}", self.name, self.version));
+
+        let mut upload_args = vec!["-X".into(), "POST".into()];
+        for deb in debs {
+            upload_args.push("-F".into());
+            upload_args.push(curl_form_file("file", deb));
+        }
+        upload_args.push(format!("{api}/files/{dir}").into());
+        let mut steps = vec![
+            curl_step(upload_args, credentials.clone()),
+            Step {
+                check_output: Some(check_aptly_report),
+                ..curl_step(vec!["-X".into(), "POST".into(), format!("{api}/repos/{}/file/{dir}", url_encode(&aptly.repo)).into()], credentials.clone())
+            },
+        ];
+        if let Some(distribution) = &aptly.publish_distribution {
+            // aptly's escaping of slashes in the prefix
+            let prefix = match aptly.publish_prefix.as_deref().unwrap_or(".") {
+                "." => ":.".to_owned(),
+                prefix => prefix.replace('_', "__").replace('/', "_"),
+            };
+            steps.push(curl_step(vec![
+                "-X".into(), "PUT".into(), "-H".into(), "Content-Type: application/json".into(), "--data".into(), "{}".into(),
+                format!("{api}/publish/{}/{}", url_encode(&prefix), url_encode(distribution)).into(),
+            ], credentials));
+        }
+        Ok(steps)
+    }
+
+    fn artifactory_steps(&self, artifactory: &ArtifactoryUpload, debs: &[PathBuf]) -> Result<Vec<Step>, String> {
+        let password = secret(artifactory.password_env.as_deref().unwrap_or("ARTIFACTORY_PASSWORD"))?;
+        let credentials = match &artifactory.user {
+            Some(user) => curl_user(user, &password),
+            None => format!("header = {}\n", curl_quote(&format!("Authorization: Bearer {password}"))),
+        };
+        let component = artifactory.component.as_deref().unwrap_or("main");
+        debs.iter().map(|deb| {
+            let file_name = deb.file_name().ok_or("bad path")?.to_string_lossy();
+            let url = format!("{}/pool/{}/{}/{};deb.distribution={};deb.component={};deb.architecture={}",
+                artifactory.url.trim_end_matches('/'), url_encode(component), url_encode(&self.name), url_encode(&file_name),
+                url_encode(&artifactory.distribution), url_encode(component), url_encode(&self.architecture));
+            Ok(curl_step(vec!["-T".into(), deb.into(), url.into()], credentials.clone()))
+        }).collect()
+    }
+}
+
+fn packagecloud_steps(packagecloud: &PackagecloudUpload, debs: &[PathBuf]) -> Result<Vec<Step>, String> {
+    let token = secret(packagecloud.token_env.as_deref().unwrap_or("PACKAGECLOUD_TOKEN"))?;
+    let repo = packagecloud.repo.split('/').map(url_encode).collect::<Vec<_>>().join("/");
+    let url = format!("{}/api/v1/repos/{repo}/packages.json", packagecloud.url.as_deref().unwrap_or("https://packagecloud.io").trim_end_matches('/'));
+    Ok(debs.iter().map(|deb| curl_step(vec![
+        "-X".into(), "POST".into(),
+        "-F".into(), format!("package[distro_version_id]={}", packagecloud.distro_version_id).into(),
+        "-F".into(), curl_form_file("package[package_file]", deb),
+        url.as_str().into(),
+    ], curl_user(&token, ""))).collect())
+}
+
+fn sftp_step(sftp: &SftpUpload, files: &[PathBuf]) -> Step {
+    let mut args = vec!["-b".into(), "-".into()];
+    if let Some(port) = sftp.port {
+        args.push("-P".into());
+        args.push(port.to_string().into());
+    }
+    args.push(sftp.host.as_str().into());
+
+    let dir = sftp.path.trim_end_matches('/');
+    // the leading `-` ignores the error if the directory exists
+    let mut commands = format!("-mkdir {}\n", sftp_quote(dir));
+    for file in files {
+        let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+        commands.push_str(&format!("put {} {}\n", sftp_quote(&file.to_string_lossy()), sftp_quote(&format!("{dir}/{file_name}"))));
+    }
+    Step { program: "sftp", args, stdin: commands, check_output: None, _temp_file: None }
+}
+
+fn s3_step(s3: &S3Upload, file: &Path) -> Step {
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    let key = match s3.prefix.as_deref().map(|p| p.trim_matches('/')) {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}/{file_name}"),
+        _ => file_name.into_owned(),
+    };
+    let mut args = vec!["s3".into(), "cp".into(), "--only-show-errors".into(), file.into(), format!("s3://{}/{key}", s3.bucket).into()];
+    if let Some(profile) = &s3.profile {
+        args.push("--profile".into());
+        args.push(profile.into());
+    }
+    if let Some(endpoint_url) = &s3.endpoint_url {
+        args.push("--endpoint-url".into());
+        args.push(endpoint_url.into());
+    }
+    Step { program: "aws", args, stdin: String::new(), check_output: None, _temp_file: None }
+}
+
+fn dput_step(dput: &DputUpload, files: &[PathBuf], signed: bool) -> Result<Step, String> {
+    let changes = files.iter().find(|f| f.extension().is_some_and(|e| e == "changes")).ok_or("dput needs a .changes file")?;
+    let mut args = Vec::new();
+    let mut temp_file = None;
+    let host = match (&dput.host, &dput.fqdn) {
+        (Some(host), None) => {
+            // Launchpad builds the binaries itself
+            if host.starts_with("ppa:") && !changes.to_string_lossy().ends_with("_source.changes") {
+                return Err(format!("Launchpad PPAs accept only source uploads, and {} is for binary packages", changes.display()));
+            }
+            host.clone()
+        },
+        (None, Some(fqdn)) => {
+            let incoming = dput.incoming.as_deref().ok_or("dput upload with `fqdn` needs `incoming` too")?;
+            let mut config = format!("[cargo-deb]\nfqdn = {fqdn}\nincoming = {incoming}\nmethod = {}\n", dput.method.as_deref().unwrap_or("ftp"));
+            if let Some(login) = &dput.login {
+                config.push_str(&format!("login = {login}\n"));
+            }
+            let mut file = tempfile::NamedTempFile::new().map_err(|e| e.to_string())?;
+            file.write_all(config.as_bytes()).map_err(|e| e.to_string())?;
+            let path = file.into_temp_path();
+            args.push("-c".into());
+            args.push(path.to_path_buf().into());
+            temp_file = Some(path);
+            "cargo-deb".into()
+        },
+        _ => return Err("dput upload needs either `host` or `fqdn`".into()),
+    };
+    if !signed {
+        args.push("--unchecked".into());
+    }
+    args.push(host.into());
+    args.push(changes.into());
+    Ok(Step { program: "dput", args, stdin: String::new(), check_output: None, _temp_file: temp_file })
+}
+
+fn curl_step(args: Vec<OsString>, config: String) -> Step {
+    let mut all_args = vec!["--fail".into(), "--silent".into(), "--show-error".into(), "--config".into(), "-".into()];
+    all_args.extend(args);
+    Step { program: "curl", args: all_args, stdin: config, check_output: None, _temp_file: None }
+}
+
+/// `name=@"path"` for `curl -F`
+fn curl_form_file(name: &str, path: &Path) -> OsString {
+    let path = path.to_string_lossy();
+    format!("{name}=@\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\"")).into()
+}
+
+fn curl_user(user: &str, password: &str) -> String {
+    format!("user = {}\n", curl_quote(&format!("{user}:{password}")))
+}
+
+/// String in a curl config file
+fn curl_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn sftp_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Percent-encodes everything except unreserved characters and `:`, for use in a path segment
+fn url_encode(s: &str) -> String {
+    s.bytes().fold(String::with_capacity(s.len()), |mut out, b| {
+        if b.is_ascii_alphanumeric() || b"-._~:".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+        out
+    })
+}
+
+fn secret(env_name: &str) -> Result<String, String> {
+    env::var(env_name).map_err(|_| format!("the environment variable {env_name} with the credentials is not set"))
+}
+
+/// aptly responds with success even if it rejected the packages
+fn check_aptly_report(output: &[u8]) -> Result<(), String> {
+    #[derive(serde::Deserialize)]
+    struct Report {
+        #[serde(rename = "FailedFiles", default)]
+        failed_files: Vec<String>,
+    }
+    let report: Report = serde_json::from_slice(output).map_err(|e| format!("unexpected response from aptly: {e}"))?;
+    if report.failed_files.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("aptly rejected {}", report.failed_files.join(", ")))
+    }
+}
+
+impl Step {
+    fn run(self, target_name: &str) -> CDResult<()> {
+        log::debug!("{} {:?}", self.program, self.args);
+        let mut child = Command::new(self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| CargoDebError::CommandFailed(e, self.program))?;
+        // dropping stdin closes it
+        child.stdin.take().ok_or("no stdin")?.write_all(self.stdin.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(CargoDebError::UploadFailed(target_name.into(), format!("{} failed: {}", self.program, String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        if let Some(check_output) = self.check_output {
+            check_output(&output.stdout).map_err(|e| CargoDebError::UploadFailed(target_name.into(), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_uploader(target: &str) -> (Uploader, UploadTarget) {
+    let target = toml::from_str::<HashMap<String, UploadTarget>>(target).unwrap().remove("repo").unwrap();
+    (Uploader { targets: vec![], name: "example".into(), version: "1.0-1".into(), architecture: "amd64".into() }, target)
+}
+
+#[test]
+fn parses_targets() {
+    assert!(matches!(test_uploader("[repo]\ntype = 'sftp'\nhost = 'example.com'\npath = '/srv'\n").1, UploadTarget::Sftp(_)));
+    assert!(toml::from_str::<HashMap<String, UploadTarget>>("[repo]\ntype = 'ftp'\nhost = 'example.com'\n").is_err());
+    assert!(toml::from_str::<HashMap<String, UploadTarget>>("[repo]\ntype = 'sftp'\nhost = 'example.com'\npath = '/srv'\npassword = 'x'\n").is_err());
+}
+
+#[test]
+fn aptly_upload_steps() {
+    let (uploader, target) = test_uploader("[repo]\ntype = 'aptly'\nurl = 'http://localhost:8080/'\nrepo = 'my repo'\npublish-distribution = 'stable'\npublish-prefix = 'a/b_c'\n");
+    let steps = uploader.steps(&target, &["/tmp/example_1.0-1_amd64.deb".into()], &[], true).unwrap();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[0].args[5..], ["-X", "POST", "-F", "file=@\"/tmp/example_1.0-1_amd64.deb\"", "http://localhost:8080/api/files/cargo-deb-example_1.0-1"]);
+    assert_eq!(steps[1].args[7], "http://localhost:8080/api/repos/my%20repo/file/cargo-deb-example_1.0-1");
+    assert_eq!(steps[2].args.last().unwrap(), "http://localhost:8080/api/publish/a_b__c/stable");
+    assert!(steps.iter().all(|s| s.program == "curl" && s.stdin.is_empty()));
+    assert_eq!(check_aptly_report(br#"{"FailedFiles":[],"Report":{}}"#), Ok(()));
+    assert!(check_aptly_report(br#"{"FailedFiles":["example_1.0-1_amd64.deb"]}"#).is_err());
+}
+
+#[test]
+fn file_copy_steps() {
+    let files = ["/tmp/a b.deb".into(), "/tmp/x.changes".into()];
+    let (uploader, target) = test_uploader("[repo]\ntype = 'sftp'\nhost = 'me@example.com'\nport = 2222\npath = '/srv/incoming/'\n");
+    let steps = uploader.steps(&target, &[], &files, true).unwrap();
+    assert_eq!(steps[0].args, ["-b", "-", "-P", "2222", "me@example.com"]);
+    assert_eq!(steps[0].stdin, "-mkdir \"/srv/incoming\"\nput \"/tmp/a b.deb\" \"/srv/incoming/a b.deb\"\nput \"/tmp/x.changes\" \"/srv/incoming/x.changes\"\n");
+
+    let (uploader, target) = test_uploader("[repo]\ntype = 's3'\nbucket = 'debs'\nprefix = '/pool/'\n");
+    let steps = uploader.steps(&target, &[], &files, true).unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1].args, ["s3", "cp", "--only-show-errors", "/tmp/x.changes", "s3://debs/pool/x.changes"]);
+}
+
+#[test]
+fn quotes_credentials() {
+    assert_eq!(curl_user("me", "p\"w\\"), "user = \"me:p\\\"w\\\\\"\n");
+    assert_eq!(url_encode("1:2.0~rc/x y"), "1:2.0~rc%2Fx%20y");
+}
+
+#[test]
+fn dput_steps() {
+    let files = ["/tmp/x_1.0_amd64.deb".into(), "/tmp/x_1.0_amd64.changes".into()];
+    let (uploader, target) = test_uploader("[repo]\ntype = 'dput'\nhost = 'mentors'\n");
+    let steps = uploader.steps(&target, &[], &files, false).unwrap();
+    assert_eq!(steps[0].args, ["--unchecked", "mentors", "/tmp/x_1.0_amd64.changes"]);
+
+    let (uploader, target) = test_uploader("[repo]\ntype = 'dput'\nhost = 'ppa:me/stuff'\n");
+    assert!(uploader.steps(&target, &[], &files, true).is_err());
+
+    let (uploader, target) = test_uploader("[repo]\ntype = 'dput'\nfqdn = 'upload.example.com'\nincoming = '/incoming'\nmethod = 'sftp'\n");
+    let steps = uploader.steps(&target, &[], &files, true).unwrap();
+    assert_eq!(steps[0].args[0], "-c");
+    assert_eq!(fs::read_to_string(&steps[0].args[1]).unwrap(), "[cargo-deb]\nfqdn = upload.example.com\nincoming = /incoming\nmethod = sftp\n");
+    assert_eq!(steps[0].args[2..], ["cargo-deb", "/tmp/x_1.0_amd64.changes"]);
+}
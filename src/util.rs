@@ -98,6 +98,49 @@ impl MyJoin for BTreeSet<String> {
     }
 }
 
+/// Formats a UNIX timestamp as in `Date:` fields of Debian files, e.g. `Thu, 01 Jan 1970 00:00:00 +0000`
+pub(crate) fn rfc2822_date(timestamp: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let (year, month, day) = civil_date(timestamp);
+    let secs = timestamp % 86400;
+    format!("{}, {day:02} {} {year} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[(timestamp / 86400 % 7) as usize], MONTHS[(month - 1) as usize], secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats a UNIX timestamp like `1970-01-01T00:00:00Z`, for JSON files
+pub(crate) fn rfc3339_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_date(timestamp);
+    let secs = timestamp % 86400;
+    format!("{year}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Year, month and day of the timestamp
+fn civil_date(timestamp: u64) -> (u64, u64, u64) {
+    let days = timestamp / 86400;
+    // civil_from_days from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn rfc2822_dates() {
+    assert_eq!(rfc2822_date(0), "Thu, 01 Jan 1970 00:00:00 +0000");
+    assert_eq!(rfc2822_date(951_782_400), "Tue, 29 Feb 2000 00:00:00 +0000");
+    assert_eq!(rfc2822_date(1_792_000_000), "Wed, 14 Oct 2026 17:46:40 +0000");
+    assert_eq!(rfc3339_date(951_782_400), "2000-02-29T00:00:00Z");
+    assert_eq!(rfc3339_date(1_792_000_000), "2026-10-14T17:46:40Z");
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use lazy_static::lazy_static;
@@ -1,31 +1,49 @@
 use crate::error::{CDResult, CargoDebError};
-use std::io::{BufWriter, Read};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek};
 use std::num::NonZeroU64;
-#[cfg(feature = "lzma")]
+#[cfg(any(feature = "lzma", feature = "zstd"))]
 use std::num::NonZeroUsize;
 use std::process::{Child, ChildStdin, Command, Stdio};
-use std::{io, ops};
+use std::path::Path;
+use std::{fmt, io};
 use zopfli::{BlockType, GzipEncoder, Options};
 
 pub struct CompressConfig {
     pub fast: bool,
+    /// Format of `data.tar`
     pub compress_type: Format,
+    /// Format of `control.tar`
+    pub control_compress_type: Format,
+    /// Level for `data.tar`. Default depends on `fast`
+    pub compress_level: Option<Level>,
+    /// Level for `control.tar`. Default depends on `fast`
+    pub control_compress_level: Option<Level>,
+    /// Number of threads for xz and zstd. `None` uses all available cores.
+    /// The output is the same regardless of the number of threads.
+    pub compress_threads: Option<u32>,
     pub compress_system: bool,
     pub rsyncable: bool,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Format {
     Xz,
     Gzip,
+    Zstd,
+    /// Uncompressed `.tar`
+    None,
 }
 
 impl Format {
+    /// Empty for uncompressed tarballs
     #[must_use]
     pub fn extension(self) -> &'static str {
         match self {
             Self::Xz => "xz",
             Self::Gzip => "gz",
+            Self::Zstd => "zst",
+            Self::None => "",
         }
     }
 
@@ -33,35 +51,110 @@ impl Format {
         match self {
             Self::Xz => "xz",
             Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::None => "none",
         }
     }
 
-    fn level(self, fast: bool) -> u32 {
-        match self {
+    fn default_level(self, fast: bool) -> Level {
+        let level = match self {
             Self::Xz => if fast { 1 } else { 6 },
             Self::Gzip => if fast { 1 } else { 9 },
+            // dpkg-deb defaults to 3, which is fast, but leaves a lot on the table
+            Self::Zstd => if fast { 1 } else { 12 },
+            Self::None => 0,
+        };
+        Level { level, extreme: false }
+    }
+
+    fn check_level(self, level: Level) -> CDResult<Level> {
+        let valid = match self {
+            Self::Xz => level.level <= 9,
+            // 0 only stores the data
+            Self::Gzip => level.level <= 9 && !level.extreme,
+            Self::Zstd => (1..=22).contains(&level.level) && !level.extreme,
+            Self::None => true,
+        };
+        if !valid {
+            return Err(CargoDebError::InvalidCompressionLevel(level.to_string(), self.program()));
+        }
+        Ok(level)
+    }
+}
+
+/// Compression level as used by the command-line tools, e.g. `6` or `9e`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub level: u32,
+    /// xz's `--extreme` preset modifier
+    pub extreme: bool,
+}
+
+impl Level {
+    fn xz_preset(self) -> u32 {
+        // LZMA_PRESET_EXTREME
+        self.level | if self.extreme { 1 << 31 } else { 0 }
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = CargoDebError;
+
+    fn from_str(s: &str) -> CDResult<Self> {
+        let (num, extreme) = match s.strip_suffix('e') {
+            Some(num) => (num, true),
+            None => (s, false),
+        };
+        Ok(Self {
+            level: num.parse().map_err(|e| CargoDebError::NumParse("compression level must be a number, optionally followed by 'e'", e))?,
+            extreme,
+        })
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.level, if self.extreme { "e" } else { "" })
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = CargoDebError;
+
+    fn from_str(s: &str) -> CDResult<Self> {
+        match s {
+            "gz" | "gzip" => Ok(Self::Gzip),
+            "xz" => Ok(Self::Xz),
+            "zst" | "zstd" => Ok(Self::Zstd),
+            "none" => Ok(Self::None),
+            _ => Err(CargoDebError::Str("unrecognized compression format. Supported: gzip, xz, zstd, none")),
         }
     }
 }
 
 enum Writer {
     #[cfg(feature = "lzma")]
-    Xz(xz2::write::XzEncoder<Vec<u8>>),
-    Gz(flate2::write::GzEncoder<Vec<u8>>),
-    ZopfliGz(BufWriter<GzipEncoder<Vec<u8>>>),
+    Xz(xz2::write::XzEncoder<File>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, File>),
+    Gz(flate2::write::GzEncoder<File>),
+    ZopfliGz(BufWriter<GzipEncoder<File>>),
+    Uncompressed(BufWriter<File>),
     StdIn {
         compress_format: Format,
         child: Child,
-        handle: std::thread::JoinHandle<io::Result<Vec<u8>>>,
+        handle: std::thread::JoinHandle<io::Result<File>>,
         stdin: BufWriter<ChildStdin>,
     },
 }
 
 impl Writer {
     fn finish(self) -> io::Result<Compressed> {
-        match self {
+        let (compress_format, file) = match self {
             #[cfg(feature = "lzma")]
-            Self::Xz(w) => w.finish().map(|data| Compressed { compress_format: Format::Xz, data }),
+            Self::Xz(w) => (Format::Xz, w.finish()?),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => (Format::Zstd, w.finish()?),
             Self::StdIn {
                 compress_format,
                 mut child,
@@ -70,11 +163,13 @@ impl Writer {
             } => {
                 drop(stdin);
                 child.wait()?;
-                handle.join().unwrap().map(|data| Compressed { compress_format, data })
+                (compress_format, handle.join().unwrap()?)
             }
-            Self::Gz(w) => w.finish().map(|data| Compressed { compress_format: Format::Gzip, data }),
-            Self::ZopfliGz(w) => w.into_inner()?.finish().map(|data| Compressed { compress_format: Format::Gzip, data }),
-        }
+            Self::Gz(w) => (Format::Gzip, w.finish()?),
+            Self::ZopfliGz(w) => (Format::Gzip, w.into_inner()?.finish()?),
+            Self::Uncompressed(w) => (Format::None, w.into_inner().map_err(|e| e.into_error())?),
+        };
+        Compressed::new(compress_format, file)
     }
 }
 
@@ -88,8 +183,11 @@ impl io::Write for Compressor {
         match &mut self.writer {
             #[cfg(feature = "lzma")]
             Writer::Xz(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(w) => w.flush(),
             Writer::Gz(w) => w.flush(),
             Writer::ZopfliGz(w) => w.flush(),
+            Writer::Uncompressed(w) => w.flush(),
             Writer::StdIn { stdin, .. } => stdin.flush(),
         }
     }
@@ -98,8 +196,11 @@ impl io::Write for Compressor {
         let len = match &mut self.writer {
             #[cfg(feature = "lzma")]
             Writer::Xz(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(w) => w.write(buf),
             Writer::Gz(w) => w.write(buf),
             Writer::ZopfliGz(w) => w.write(buf),
+            Writer::Uncompressed(w) => w.write(buf),
             Writer::StdIn { stdin, .. } => stdin.write(buf),
         }?;
         self.uncompressed_size += len;
@@ -110,8 +211,11 @@ impl io::Write for Compressor {
         match &mut self.writer {
             #[cfg(feature = "lzma")]
             Writer::Xz(w) => w.write_all(buf),
+            #[cfg(feature = "zstd")]
+            Writer::Zstd(w) => w.write_all(buf),
             Writer::Gz(w) => w.write_all(buf),
             Writer::ZopfliGz(w) => w.write_all(buf),
+            Writer::Uncompressed(w) => w.write_all(buf),
             Writer::StdIn { stdin, .. } => stdin.write_all(buf),
         }?;
         self.uncompressed_size += buf.len();
@@ -132,29 +236,60 @@ impl Compressor {
     }
 }
 
+/// Compressed data in an anonymous temporary file
 pub struct Compressed {
     compress_format: Format,
-    data: Vec<u8>,
+    file: File,
+    len: u64,
 }
 
 impl Compressed {
+    fn new(compress_format: Format, mut file: File) -> io::Result<Self> {
+        let len = file.stream_position()?;
+        file.rewind()?;
+        Ok(Self { compress_format, file, len })
+    }
+
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads the compressed data from the start
+    #[must_use]
+    pub fn into_reader(self) -> File {
+        self.file
+    }
+
     #[must_use]
     pub fn extension(&self) -> &'static str {
         self.compress_format.extension()
     }
-}
-
-impl ops::Deref for Compressed {
-    type Target = Vec<u8>;
 
-    fn deref(&self) -> &Self::Target {
-        &self.data
+    /// `base` with the extension appended, e.g. `data.tar.xz`
+    pub(crate) fn file_name(&self, base: &str) -> String {
+        match self.extension() {
+            "" => base.into(),
+            ext => format!("{base}.{ext}"),
+        }
     }
 }
 
-fn system_compressor(compress_format: Format, fast: bool) -> CDResult<Compressor> {
-    let mut child = Command::new(compress_format.program())
-        .arg(format!("-{}", compress_format.level(fast)))
+fn system_compressor(compress_format: Format, level: Level, threads: Option<u32>, mut out: File) -> CDResult<Compressor> {
+    let mut cmd = Command::new(compress_format.program());
+    if compress_format == Format::Zstd && level.level > 19 {
+        cmd.arg("--ultra");
+    }
+    if let Some(threads) = threads.filter(|_| compress_format != Format::Gzip) {
+        cmd.arg(format!("-T{threads}"));
+    }
+    let mut child = cmd
+        .arg(format!("-{level}"))
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::inherit())
@@ -163,46 +298,64 @@ fn system_compressor(compress_format: Format, fast: bool) -> CDResult<Compressor
     let mut stdout = child.stdout.take().unwrap();
 
     let handle = std::thread::spawn(move || {
-        let mut buf = Vec::new();
-        stdout.read_to_end(&mut buf).map(|_| buf)
+        io::copy(&mut stdout, &mut out).map(|_| out)
     });
 
     let stdin = BufWriter::with_capacity(1<<16, child.stdin.take().unwrap());
     Ok(Compressor::new(Writer::StdIn { compress_format, child, handle, stdin }))
 }
 
-pub fn select_compressor(fast: bool, compress_format: Format, use_system: bool) -> CDResult<Compressor> {
-    if use_system {
-        return system_compressor(compress_format, fast);
+/// The compressed data is written to a temporary file in `temp_dir`, so it doesn't need to fit in memory.
+pub fn select_compressor(fast: bool, compress_format: Format, level: Option<Level>, threads: Option<u32>, use_system: bool, temp_dir: &Path) -> CDResult<Compressor> {
+    let explicit_level = level.map(|l| compress_format.check_level(l)).transpose()?;
+    let level = explicit_level.unwrap_or_else(|| compress_format.default_level(fast));
+    let out = tempfile::tempfile_in(temp_dir)
+        .map_err(|e| CargoDebError::IoFile("unable to create a temporary file", e, temp_dir.to_owned()))?;
+    // gzip has no -0, but flate2 does
+    let system_can_compress = compress_format != Format::None && !(compress_format == Format::Gzip && level.level == 0);
+    if use_system && system_can_compress {
+        return system_compressor(compress_format, level, threads, out);
     }
 
     match compress_format {
         #[cfg(feature = "lzma")]
         Format::Xz => {
-            // Compression level 6 is a good trade off between size and [ridiculously] long compression time
+            // Compression level 6 is a good trade off between size and [ridiculously] long compression time.
+            // Block size depends only on the preset, so the output doesn't depend on the number of threads.
             let encoder = xz2::stream::MtStreamBuilder::new()
-                .threads(std::thread::available_parallelism().unwrap_or(NonZeroUsize::new(1).unwrap()).get() as u32)
-                .preset(compress_format.level(fast))
+                .threads(threads_or_available(threads))
+                .preset(level.xz_preset())
                 .encoder()
                 .map_err(CargoDebError::LzmaCompressionError)?;
 
-            let writer = xz2::write::XzEncoder::new_stream(Vec::new(), encoder);
+            let writer = xz2::write::XzEncoder::new_stream(out, encoder);
             Ok(Compressor::new(Writer::Xz(writer)))
         },
         #[cfg(not(feature = "lzma"))]
-        Format::Xz => system_compressor(compress_format, fast),
+        Format::Xz => system_compressor(compress_format, level, threads, out),
+        #[cfg(feature = "zstd")]
+        Format::Zstd => {
+            let mut encoder = zstd::Encoder::new(out, level.level as i32)?;
+            // zstd's multi-threaded output is the same for any number of workers
+            encoder.multithread(threads_or_available(threads))?;
+            Ok(Compressor::new(Writer::Zstd(encoder)))
+        },
+        #[cfg(not(feature = "zstd"))]
+        Format::Zstd => system_compressor(compress_format, level, threads, out),
+        Format::None => Ok(Compressor::new(Writer::Uncompressed(BufWriter::with_capacity(1<<16, out)))),
         Format::Gzip => {
             use flate2::write::GzEncoder;
             use flate2::Compression;
 
-            let writer = if !fast {
+            // Zopfli is slow, but better than the best gzip level
+            let writer = if !fast && explicit_level.is_none() {
                 let inner_writer = GzipEncoder::new_buffered(Options {
                     iteration_count: NonZeroU64::new(7).unwrap(),
                     ..Options::default()
-                }, BlockType::Dynamic, Vec::new()).unwrap();
+                }, BlockType::Dynamic, out).unwrap();
                 Writer::ZopfliGz(inner_writer)
             } else {
-                let inner_writer = GzEncoder::new(Vec::new(), Compression::new(compress_format.level(fast)));
+                let inner_writer = GzEncoder::new(out, Compression::new(level.level));
                 Writer::Gz(inner_writer)
             };
             Ok(Compressor::new(writer))
@@ -210,6 +363,51 @@ pub fn select_compressor(fast: bool, compress_format: Format, use_system: bool)
     }
 }
 
+/// Decompresses a tarball with the given file extension (empty if uncompressed)
+pub(crate) fn decompress(extension: &str, data: &[u8]) -> CDResult<Vec<u8>> {
+    let format = match extension {
+        "" => Format::None,
+        ext => ext.parse()?,
+    };
+    let mut out = Vec::with_capacity(data.len() * 3);
+    match format {
+        Format::None => out.extend_from_slice(data),
+        Format::Gzip => { flate2::read::MultiGzDecoder::new(data).read_to_end(&mut out)?; },
+        #[cfg(feature = "lzma")]
+        Format::Xz => { xz2::read::XzDecoder::new_multi_decoder(data).read_to_end(&mut out)?; },
+        #[cfg(feature = "zstd")]
+        Format::Zstd => { zstd::Decoder::new(data)?.read_to_end(&mut out)?; },
+        #[allow(unreachable_patterns)]
+        _ => return system_decompress(format, data),
+    }
+    Ok(out)
+}
+
+fn system_decompress(format: Format, data: &[u8]) -> CDResult<Vec<u8>> {
+    let mut child = Command::new(format.program())
+        .arg("-dc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| CargoDebError::CommandFailed(e, format.program()))?;
+    let mut stdin = child.stdin.take().unwrap();
+    let output = std::thread::scope(|s| {
+        s.spawn(move || io::Write::write_all(&mut stdin, data));
+        child.wait_with_output()
+    })?;
+    if !output.status.success() {
+        return Err(CargoDebError::CommandError("decompression failed", format.program().into(), Vec::new()));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(any(feature = "lzma", feature = "zstd"))]
+fn threads_or_available(threads: Option<u32>) -> u32 {
+    threads.filter(|&t| t > 0)
+        .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::new(1).unwrap()).get() as u32)
+}
+
 pub(crate) fn gzipped(mut content: &[u8]) -> io::Result<Vec<u8>> {
     let mut compressed = Vec::with_capacity(content.len() * 2 / 3);
     let mut encoder = GzipEncoder::new(
@@ -224,3 +422,69 @@ pub(crate) fn gzipped(mut content: &[u8]) -> io::Result<Vec<u8>> {
     encoder.finish()?;
     Ok(compressed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn parse_level() {
+        assert_eq!("9e".parse::<Level>().unwrap(), Level { level: 9, extreme: true });
+        assert_eq!("0".parse::<Level>().unwrap(), Level { level: 0, extreme: false });
+        assert_eq!("9e".parse::<Level>().unwrap().to_string(), "9e");
+        assert!("e".parse::<Level>().is_err());
+        assert!("fast".parse::<Level>().is_err());
+    }
+
+    #[test]
+    fn level_ranges() {
+        let l = |s: &str| s.parse::<Level>().unwrap();
+        assert!(Format::Xz.check_level(l("0")).is_ok());
+        assert!(Format::Xz.check_level(l("9e")).is_ok());
+        assert!(Format::Xz.check_level(l("10")).is_err());
+        assert!(Format::Gzip.check_level(l("0")).is_ok());
+        assert!(Format::Gzip.check_level(l("10")).is_err());
+        assert!(Format::Gzip.check_level(l("9e")).is_err());
+        assert!(Format::Zstd.check_level(l("22")).is_ok());
+        assert!(Format::Zstd.check_level(l("23")).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "lzma", feature = "zstd"))]
+    fn same_output_for_any_thread_count() {
+        let data: Vec<u8> = (0..4_000_000u32).map(|n| (n.wrapping_mul(2_654_435_761) >> 27) as u8).collect();
+        let compress = |format, threads| {
+            let mut c = select_compressor(true, format, None, Some(threads), false, &std::env::temp_dir()).unwrap();
+            c.write_all(&data).unwrap();
+            let mut out = Vec::new();
+            c.finish().unwrap().into_reader().read_to_end(&mut out).unwrap();
+            out
+        };
+        for format in [Format::Xz, Format::Zstd, Format::None] {
+            assert_eq!(compress(format, 1), compress(format, 3));
+        }
+    }
+
+    #[test]
+    fn gzip_level_0() {
+        let mut c = select_compressor(false, Format::Gzip, Some(Level { level: 0, extreme: false }), None, true, &std::env::temp_dir()).unwrap();
+        c.write_all(b"hello hello hello").unwrap();
+        let compressed = c.finish().unwrap();
+        let mut out = String::new();
+        flate2::read::GzDecoder::new(compressed.into_reader()).read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello hello hello");
+    }
+
+    #[test]
+    #[cfg(feature = "lzma")]
+    fn xz_extreme() {
+        let mut c = select_compressor(false, Format::Xz, Some(Level { level: 0, extreme: true }), None, false, &std::env::temp_dir()).unwrap();
+        c.write_all(b"hello hello hello").unwrap();
+        let compressed = c.finish().unwrap();
+        assert_eq!("xz", compressed.extension());
+        let mut out = String::new();
+        xz2::read::XzDecoder::new(compressed.into_reader()).read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello hello hello");
+    }
+}
@@ -42,6 +42,68 @@ fn build_with_explicit_compress_type_xz() {
     assert!(ddir.path().join("usr/bin/renamed2").exists());
 }
 
+#[test]
+fn build_with_explicit_compress_type_zstd() {
+    let (_bdir, deb_path) = cargo_deb("tests/test-workspace/test-ws2/Cargo.toml", &["--no-strip", "--compress-type", "zstd"]);
+    check_ar(&deb_path);
+    let ardir = tempfile::tempdir().unwrap();
+    assert!(Command::new("ar")
+        .current_dir(ardir.path())
+        .arg("-x")
+        .arg(&deb_path)
+        .status().unwrap().success());
+
+    // zstd CLI isn't commonly installed, so only check it's a zstd frame
+    for member in ["control.tar.zst", "data.tar.zst"] {
+        let data = fs::read(ardir.path().join(member)).unwrap();
+        assert_eq!(&data[..4], b"\x28\xb5\x2f\xfd", "{member}");
+    }
+}
+
+#[test]
+fn build_with_separate_control_compress_type() {
+    let (_bdir, deb_path) = cargo_deb("tests/test-workspace/test-ws1/Cargo.toml", &["--no-strip", "--fast", "--compress-type", "xz", "--control-compress-type", "gz"]);
+    check_ar(&deb_path);
+    let ardir = tempfile::tempdir().unwrap();
+    assert!(Command::new("ar")
+        .current_dir(ardir.path())
+        .arg("-x")
+        .arg(&deb_path)
+        .status().unwrap().success());
+    assert!(ardir.path().join("data.tar.xz").exists());
+    assert!(ardir.path().join("control.tar.gz").exists());
+    assert!(!ardir.path().join("control.tar.xz").exists());
+}
+
+#[test]
+fn build_uncompressed() {
+    let (_bdir, deb_path) = cargo_deb("tests/test-workspace/test-ws2/Cargo.toml", &["--fast", "--compress-type", "none"]);
+    let ardir = tempfile::tempdir().unwrap();
+    assert!(Command::new("ar")
+        .current_dir(ardir.path())
+        .arg("-x")
+        .arg(&deb_path)
+        .status().unwrap().success());
+
+    let ddir = tempfile::tempdir().unwrap();
+    assert!(Command::new("tar")
+        .arg("xf")
+        .current_dir(ddir.path())
+        .arg(ardir.path().join("data.tar"))
+        .status().unwrap().success());
+    assert!(ardir.path().join("control.tar").exists());
+    assert!(ddir.path().join("usr/bin/renamed2").exists());
+}
+
+#[test]
+fn verify_reproducible() {
+    let (_bdir, deb_path) = cargo_deb("tests/test-workspace/test-ws2/Cargo.toml", &["--verify-reproducible", "--compress-type", "gz"]);
+    let mut first = deb_path.clone().into_os_string();
+    first.push(".first");
+    assert!(!Path::new(&first).exists());
+    extract_package(&deb_path, "gz");
+}
+
 #[test]
 fn build_with_command_line_compress_xz() {
     // ws1 with system xz
@@ -165,6 +227,11 @@ fn extract_package(deb_path: &Path, ext: &str) -> (TempDir, TempDir) {
 /// The `--manifest-path` and `--output` args are automatically set.
 #[track_caller]
 fn cargo_deb(manifest_path: &str, args: &[&str]) -> (TempDir, PathBuf) {
+    cargo_deb_with_env(manifest_path, args, &[])
+}
+
+#[track_caller]
+fn cargo_deb_with_env(manifest_path: &str, args: &[&str], env: &[(&str, &str)]) -> (TempDir, PathBuf) {
     let _ = env_logger::builder().is_test(true).try_init();
 
     let cargo_dir = tempfile::tempdir().unwrap();
@@ -175,6 +242,7 @@ fn cargo_deb(manifest_path: &str, args: &[&str]) -> (TempDir, PathBuf) {
     assert!(cmd_path.exists());
     let output = Command::new(cmd_path)
         .env("CARGO_TARGET_DIR", cargo_dir.path()) // use isolated 'target' directories
+        .envs(env.iter().copied())
         .arg(format!("--manifest-path={}", root.join(manifest_path).display()))
         .arg(format!("--output={}", deb_path.display()))
         .args(args)
@@ -188,7 +256,7 @@ fn cargo_deb(manifest_path: &str, args: &[&str]) -> (TempDir, PathBuf) {
     );
 
     // prints deb path on the last line
-    let last_line = output.stdout[..output.stdout.len() - 1].split(|&c| c == b'\n').last().unwrap();
+    let last_line = output.stdout[..output.stdout.len() - 1].split(|&c| c == b'\n').next_back().unwrap();
     let printed_deb_path = Path::new(::std::str::from_utf8(last_line).unwrap());
     assert_eq!(printed_deb_path, deb_path);
     assert!(deb_path.exists());
@@ -206,6 +274,8 @@ fn run_cargo_deb_command_on_example_dir() {
     assert!(control.contains("Version: 0.1.0-1\n"));
     assert!(control.contains("Section: utils\n"));
     assert!(control.contains("Architecture: "));
+    assert!(control.contains("\nMulti-Arch: foreign\n"));
+    assert!(control.contains("\nTag: role::program, interface::commandline\n"));
     assert!(control.contains("Maintainer: cargo-deb developers <cargo-deb@example.invalid>\n"));
 
     let sha256sums = fs::read_to_string(cdir.path().join("sha256sums")).unwrap();
@@ -221,6 +291,9 @@ fn run_cargo_deb_command_on_example_dir() {
     assert!(ddir.path().join("var/lib/example/3.txt").exists());
     assert!(ddir.path().join("usr/share/doc/example/copyright").exists());
     assert!(ddir.path().join("usr/share/doc/example/changelog.Debian.gz").exists());
+    assert!(ddir.path().join("usr/share/doc/example/NEWS.Debian.gz").exists());
+    // the upstream changelog, from CHANGELOG.md
+    assert!(ddir.path().join("usr/share/doc/example/changelog.gz").exists());
     assert!(ddir.path().join("usr/bin/example").exists());
     // changelog.Debian.gz starts with the gzip magic
     assert_eq!(
@@ -255,6 +328,186 @@ fn run_cargo_deb_command_on_example_dir_with_separate_debug_symbols() {
     );
 }
 
+#[test]
+#[cfg(all(feature = "lzma", target_family = "unix", not(target_os = "macos")))]
+fn run_cargo_deb_command_on_example_dir_with_dbgsym() {
+    let (cargo_dir, deb_path) = cargo_deb("example/Cargo.toml", &["--dbgsym"]);
+    let (_, ddir) = extract_package(&deb_path, DEFAULT_COMPRESSION_EXT);
+    assert!(ddir.path().join("usr/bin/example").exists());
+    assert!(!ddir.path().join("usr/lib/debug").exists());
+
+    let dbgsym_path = glob::glob(cargo_dir.path().join("example-dbgsym_0.1.0-1_*.deb").to_str().unwrap())
+        .unwrap().flatten().next().expect("dbgsym package next to the main package");
+    let (cdir, ddir) = extract_package(&dbgsym_path, DEFAULT_COMPRESSION_EXT);
+    let control = fs::read_to_string(cdir.path().join("control")).unwrap();
+    assert!(control.contains("Package: example-dbgsym\n"), "{control}");
+    assert!(control.contains("Depends: example (= 0.1.0-1)\n"), "{control}");
+    assert!(control.contains("Section: debug\n"), "{control}");
+    assert!(control.contains("Auto-Built-Package: debug-symbols\n"), "{control}");
+    assert!(control.contains("Build-Ids: "), "{control}");
+    assert!(glob::glob(ddir.path().join("usr/lib/debug/.build-id/*/*.debug").to_str().unwrap()).unwrap().flatten().next().is_some());
+    assert!(!ddir.path().join("usr/bin").exists());
+}
+
+#[test]
+#[cfg(all(feature = "lzma", target_family = "unix", not(target_os = "macos")))]
+fn run_cargo_deb_command_on_example_dir_with_ddeb() {
+    let (cargo_dir, _) = cargo_deb("example/Cargo.toml", &["--ddeb"]);
+    let ddeb_path = glob::glob(cargo_dir.path().join("example-dbgsym_0.1.0-1_*.ddeb").to_str().unwrap())
+        .unwrap().flatten().next().expect("ddeb package next to the main package");
+    let (cdir, _) = extract_package(&ddeb_path, DEFAULT_COMPRESSION_EXT);
+    let control = fs::read_to_string(cdir.path().join("control")).unwrap();
+    assert!(control.contains("Package-Type: ddeb\n"), "{control}");
+}
+
+#[test]
+#[cfg(all(feature = "lzma", target_family = "unix", not(target_os = "macos")))]
+fn run_cargo_deb_command_on_example_dir_with_debuginfod_out() {
+    let debuginfod_dir = tempfile::tempdir().unwrap();
+    let arg = format!("--debuginfod-out={}", debuginfod_dir.path().display());
+    let (_cargo_dir, _) = cargo_deb("example/Cargo.toml", &["--separate-debug-symbols", &arg]);
+    let id_dir = fs::read_dir(debuginfod_dir.path()).unwrap().flatten().next().expect("build-id dir").path();
+    assert!(id_dir.file_name().unwrap().len() > 16);
+    let debuginfo_len = id_dir.join("debuginfo").metadata().unwrap().len();
+    let executable_len = id_dir.join("executable").metadata().unwrap().len();
+    assert!(executable_len < debuginfo_len);
+}
+
+#[test]
+#[cfg(all(feature = "lzma", feature = "debug-id", target_os = "linux"))]
+fn run_cargo_deb_command_on_example_dir_with_rpath() {
+    let rustflags = [("RUSTFLAGS", "-C link-arg=-Wl,-rpath,/opt/example/lib")];
+    let (_cargo_dir, deb_path) = cargo_deb_with_env("example/Cargo.toml", &["--variant=norpath"], &rustflags);
+    let (_, ddir) = extract_package(&deb_path, DEFAULT_COMPRESSION_EXT);
+    let output = Command::new("readelf").arg("-d").arg(ddir.path().join("usr/bin/example")).output().unwrap();
+    assert!(output.status.success());
+    let dynamic = String::from_utf8_lossy(&output.stdout);
+    assert!(dynamic.contains("NEEDED"), "{dynamic}");
+    assert!(!dynamic.contains("RPATH") && !dynamic.contains("RUNPATH"), "{dynamic}");
+}
+
+#[test]
+#[cfg(feature = "lzma")]
+fn run_cargo_deb_command_on_example_dir_with_changes() {
+    let (_cargo_dir, deb_path) = cargo_deb("example/Cargo.toml", &["--changes", "--buildinfo"]);
+    let changes_path = fs::read_dir(deb_path.parent().unwrap()).unwrap().flatten()
+        .map(|e| e.path()).find(|p| p.extension().is_some_and(|e| e == "changes")).expect(".changes file");
+    assert!(changes_path.file_name().unwrap().to_str().unwrap().starts_with("example_0.1.0-1_"));
+    let changes = fs::read_to_string(&changes_path).unwrap();
+    assert!(changes.starts_with("Format: 1.8\n"), "{changes}");
+    assert!(changes.contains("\nDistribution: experimental\nUrgency: low\n"), "{changes}");
+    assert!(changes.contains("\nChanges:\n example (0.1.0) experimental; urgency=low\n .\n   * Initial release\n"), "{changes}");
+    let size = fs::metadata(&deb_path).unwrap().len();
+    assert!(changes.contains(&format!(" {size} utils optional test.deb\n")), "{changes}");
+
+    let buildinfo_path = changes_path.with_extension("buildinfo");
+    let buildinfo = fs::read_to_string(&buildinfo_path).unwrap();
+    assert!(buildinfo.starts_with("Format: 1.0\nSource: example\n"), "{buildinfo}");
+    assert!(buildinfo.contains(&format!(" {size} test.deb\n")), "{buildinfo}");
+    assert!(buildinfo.contains("\nRustc-Version: rustc "), "{buildinfo}");
+    assert!(changes.contains(buildinfo_path.file_name().unwrap().to_str().unwrap()), "{changes}");
+}
+
+#[test]
+fn run_cargo_deb_command_on_example_dir_with_checksums() {
+    let (_cargo_dir, deb_path) = cargo_deb("example/Cargo.toml", &["--checksums=sha256,sha512"]);
+    let sha256 = fs::read_to_string(deb_path.with_extension("deb.sha256")).unwrap();
+    assert!(sha256.ends_with("  test.deb\n") && sha256.len() == 64 + 11, "{sha256}");
+    let sha512 = fs::read_to_string(deb_path.with_extension("deb.sha512")).unwrap();
+    assert!(sha512.ends_with("  test.deb\n") && sha512.len() == 128 + 11, "{sha512}");
+    assert!(Command::new("sha256sum").arg("-c").arg(deb_path.with_extension("deb.sha256")).current_dir(deb_path.parent().unwrap()).status().unwrap().success());
+}
+
+#[test]
+fn run_cargo_deb_command_on_example_dir_with_keyring() {
+    let (_cargo_dir, deb_path) = cargo_deb("example/Cargo.toml", &["--keyring"]);
+    let keyring_path = deb_path.with_file_name("example-archive-keyring_0.1.0-1_all.deb");
+    assert!(keyring_path.exists());
+
+    let ardir = tempfile::tempdir().unwrap();
+    assert!(Command::new("dpkg-deb").arg("-x").arg(&keyring_path).arg(ardir.path()).status().unwrap().success());
+    let key = fs::read_to_string(ardir.path().join("usr/share/keyrings/example-archive-keyring.asc")).unwrap();
+    assert!(key.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+    let sources = fs::read_to_string(ardir.path().join("etc/apt/sources.list.d/example-archive-keyring.sources")).unwrap();
+    assert_eq!(sources, "Types: deb\nURIs: https://apt.example.invalid/debian\nSuites: stable testing\nComponents: main\nSigned-By: /usr/share/keyrings/example-archive-keyring.asc\n");
+    assert!(ardir.path().join("usr/share/doc/example-archive-keyring/copyright").exists());
+
+    let output = Command::new("dpkg-deb").arg("-I").arg(&keyring_path).output().unwrap();
+    let info = String::from_utf8_lossy(&output.stdout);
+    assert!(info.contains(" Architecture: all\n"), "{info}");
+    let output = Command::new("dpkg-deb").arg("-I").arg(&keyring_path).arg("conffiles").output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "/etc/apt/sources.list.d/example-archive-keyring.sources\n");
+}
+
+#[test]
+#[cfg(all(feature = "lzma", target_family = "unix"))]
+fn run_cargo_deb_command_on_example_dir_with_librust_dev() {
+    let (_cargo_dir, deb_path) = cargo_deb("example/Cargo.toml", &["--librust-dev"]);
+    let (cdir, ddir) = extract_package(&deb_path, DEFAULT_COMPRESSION_EXT);
+    let control = fs::read_to_string(cdir.path().join("control")).unwrap();
+    assert!(control.starts_with("Package: librust-example-dev\nVersion: 0.1.0-1\n"), "{control}");
+    assert!(control.contains("\nSection: rust\n"), "{control}");
+    assert!(control.contains("\nProvides: librust-example+default-dev (= 0.1.0-1), librust-example+example-debian-build-dev (= 0.1.0-1), "), "{control}");
+    let registry_dir = ddir.path().join("usr/share/cargo/registry/example-0.1.0");
+    assert!(registry_dir.join("src/main.rs").exists());
+    assert!(registry_dir.join("Cargo.toml").exists());
+    let checksum = fs::read_to_string(registry_dir.join(".cargo-checksum.json")).unwrap();
+    assert!(checksum.starts_with("{\"package\":\"") && checksum.ends_with("\",\"files\":{}}"), "{checksum}");
+}
+
+#[test]
+#[cfg(all(feature = "lzma", target_os = "linux"))]
+fn run_cargo_deb_command_on_example_dir_with_source() {
+    let cargo_dir = tempfile::tempdir().unwrap();
+    let root = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
+    let output = Command::new(root.join(env!("CARGO_BIN_EXE_cargo-deb")))
+        .env("CARGO_TARGET_DIR", cargo_dir.path())
+        .arg("--source")
+        .arg("--changes")
+        .arg(format!("--manifest-path={}", root.join("example/Cargo.toml").display()))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "Cmd failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let out_dir = cargo_dir.path().join("debian");
+    let dsc_path = out_dir.join("example_0.1.0-1.dsc");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().last(), Some(dsc_path.to_str().unwrap()));
+    assert!(out_dir.join("example_0.1.0.orig.tar.xz").exists());
+    assert!(out_dir.join("example_0.1.0-1.debian.tar.xz").exists());
+    let changes = fs::read_to_string(out_dir.join("example_0.1.0-1_source.changes")).unwrap();
+    assert!(changes.contains("\nArchitecture: source\n"), "{changes}");
+    assert!(changes.contains(" example_0.1.0-1.dsc\n"), "{changes}");
+
+    let srcdir = tempfile::tempdir().unwrap();
+    let status = Command::new("dpkg-source").arg("-x").arg(&dsc_path).arg(srcdir.path().join("example")).output().unwrap();
+    assert!(status.status.success(), "{}", String::from_utf8_lossy(&status.stderr));
+    let rules = fs::read_to_string(srcdir.path().join("example/debian/rules")).unwrap();
+    assert!(rules.contains("\tcargo deb --deb-version='0.1.0-1'"), "{rules}");
+    assert!(srcdir.path().join("example/src/main.rs").exists());
+}
+
+#[test]
+#[cfg(all(feature = "lzma", target_os = "linux"))]
+fn run_cargo_deb_command_on_example_dir_with_vendored_source() {
+    let cargo_dir = tempfile::tempdir().unwrap();
+    let root = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
+    let output = Command::new(root.join(env!("CARGO_BIN_EXE_cargo-deb")))
+        .env("CARGO_TARGET_DIR", cargo_dir.path())
+        .arg("--source")
+        .arg("--vendor")
+        .arg(format!("--manifest-path={}", root.join("example/Cargo.toml").display()))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "Cmd failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let srcdir = tempfile::tempdir().unwrap();
+    let status = Command::new("dpkg-source").arg("-x").arg(cargo_dir.path().join("debian/example_0.1.0-1.dsc")).arg(srcdir.path().join("example")).output().unwrap();
+    assert!(status.status.success(), "{}", String::from_utf8_lossy(&status.stderr));
+    let rules = fs::read_to_string(srcdir.path().join("example/debian/rules")).unwrap();
+    assert!(rules.contains("\tcargo deb --deb-version='0.1.0-1' --frozen\n"), "{rules}");
+    assert!(srcdir.path().join("example/Cargo.lock").exists());
+}
+
 #[test]
 #[cfg(feature = "lzma")]
 fn run_cargo_deb_command_on_example_dir_with_variant() {
@@ -313,6 +566,19 @@ fn run_cargo_deb_command_on_example_dir_with_variant() {
     assert!(ddir.path().join("usr/bin/example").exists());
 }
 
+#[test]
+#[cfg(all(feature = "lzma", target_family = "unix"))]
+fn run_cargo_deb_command_on_example_dir_with_debhelper_files() {
+    let (_bdir, deb_path) = cargo_deb("example/Cargo.toml", &["--variant=debhelper", "--no-strip"]);
+    let (_cdir, ddir) = extract_package(&deb_path, DEFAULT_COMPRESSION_EXT);
+
+    assert!(ddir.path().join("usr/share/example/4.txt").is_file());
+    assert!(ddir.path().join("var/cache/example").is_dir());
+    assert_eq!(fs::read_link(ddir.path().join("usr/bin/example-link")).unwrap(), Path::new("example"));
+    // the assets from Cargo.toml are kept
+    assert!(ddir.path().join("usr/bin/example").is_file());
+}
+
 #[test]
 #[cfg(all(feature = "lzma", target_family = "unix"))]
 fn run_cargo_deb_command_on_example_dir_with_version() {